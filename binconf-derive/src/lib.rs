@@ -0,0 +1,190 @@
+//! Derive macro of [binconf](https://docs.rs/binconf), enabled with its `derive` feature. See `binconf::BinConf`.
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// The settings of a `#[binconf(...)]` attribute.
+struct Settings {
+    app: String,
+    name: Option<String>,
+    /// Variant of `binconf::ConfigType`, and the `load` and `store` functions of the format.
+    format: (&'static str, &'static str, &'static str),
+    /// Variant of `binconf::ConfigLocation`.
+    location: &'static str,
+}
+
+/// Generates `load()`, `store(&self)` and `path()` functions for a config type, and implements `binconf::ConfigFile` for it.
+#[proc_macro_derive(BinConf, attributes(binconf))]
+pub fn derive_binconf(input: TokenStream) -> TokenStream {
+    expand(input).unwrap_or_else(|message| {
+        format!("::core::compile_error!({message:?});")
+            .parse()
+            .unwrap_or_default()
+    })
+}
+
+fn expand(input: TokenStream) -> Result<TokenStream, String> {
+    let mut attribute = None;
+    let mut type_name = None;
+
+    let mut tokens = input.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket => {
+                let mut inner = group.stream().into_iter();
+                if let (Some(TokenTree::Ident(ident)), Some(TokenTree::Group(args))) =
+                    (inner.next(), inner.next())
+                {
+                    if ident.to_string() == "binconf" {
+                        attribute = Some(args.stream());
+                    }
+                }
+            }
+            TokenTree::Ident(ident) if matches!(ident.to_string().as_str(), "struct" | "enum") => {
+                if let Some(TokenTree::Ident(name)) = tokens.next() {
+                    if matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == '<')
+                    {
+                        return Err(String::from("`BinConf` can't be derived for generic types"));
+                    }
+                    type_name = Some(name.to_string());
+                }
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let type_name = type_name.ok_or("`BinConf` can only be derived for structs and enums")?;
+    let attribute = attribute.ok_or(
+        "`BinConf` needs a `#[binconf(app = \"...\")]` attribute, with optional `format`, `location` and `name`",
+    )?;
+    let Settings {
+        app,
+        name,
+        format: (config_type, load, store),
+        location,
+    } = parse_settings(attribute)?;
+
+    let name = match name {
+        Some(name) => format!("::core::option::Option::Some({name:?})"),
+        None => String::from("::core::option::Option::None"),
+    };
+
+    format!(
+        r#"
+        impl {type_name} {{
+            /// Loads the config, creating it with its defaults if it does not exist.
+            pub fn load() -> ::core::result::Result<Self, ::binconf::ConfigError> {{
+                ::binconf::{load}::<Self>({app:?}, {name}, ::binconf::ConfigLocation::{location}, false)
+            }}
+
+            /// Stores the config.
+            pub fn store(&self) -> ::core::result::Result<(), ::binconf::ConfigError> {{
+                ::binconf::{store}({app:?}, {name}, ::binconf::ConfigLocation::{location}, self)
+            }}
+
+            /// Returns the path of the config file.
+            pub fn path() -> ::core::result::Result<::std::path::PathBuf, ::binconf::ConfigError> {{
+                ::binconf::get_configuration_path(
+                    {app:?},
+                    {name},
+                    ::binconf::ConfigType::{config_type},
+                    ::binconf::ConfigLocation::{location},
+                )
+            }}
+        }}
+
+        impl ::binconf::ConfigFile for {type_name} {{
+            const APP_NAME: &'static str = {app:?};
+            const FILE_NAME: ::core::option::Option<&'static str> = {name};
+            const LOCATION: ::binconf::ConfigLocation = ::binconf::ConfigLocation::{location};
+            const FORMAT: ::binconf::ConfigType = ::binconf::ConfigType::{config_type};
+        }}
+        "#
+    )
+    .parse()
+    .map_err(|err| format!("`BinConf` generated invalid code: {err}"))
+}
+
+/// Parses the `key = "value", ...` settings of the attribute.
+fn parse_settings(attribute: TokenStream) -> Result<Settings, String> {
+    let mut app = None;
+    let mut name = None;
+    let mut format = ("Bin", "load_bin", "store_bin");
+    let mut location = "Config";
+
+    let mut tokens = attribute.into_iter();
+    loop {
+        let key = match tokens.next() {
+            Some(TokenTree::Ident(key)) => key.to_string(),
+            None => break,
+            Some(other) => return Err(format!("Expected a setting name, found `{other}`")),
+        };
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+            _ => return Err(format!("Expected `=` after `{key}`")),
+        }
+        let value = match tokens.next() {
+            Some(TokenTree::Literal(literal)) => string_literal(&literal.to_string())
+                .ok_or_else(|| format!("The value of `{key}` must be a plain string literal"))?,
+            _ => return Err(format!("Expected a string after `{key} =`")),
+        };
+
+        match key.as_str() {
+            "app" => app = Some(value),
+            "name" => name = Some(value),
+            "format" => format = parse_format(&value)?,
+            "location" => location = parse_location(&value)?,
+            other => {
+                return Err(format!(
+                    "Unknown setting `{other}`, expected `app`, `format`, `location` or `name`"
+                ))
+            }
+        }
+
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+            None => break,
+            Some(other) => return Err(format!("Expected `,`, found `{other}`")),
+        }
+    }
+
+    Ok(Settings {
+        app: app.ok_or("The `app` setting is required")?,
+        name,
+        format,
+        location,
+    })
+}
+
+/// Returns the variant of `binconf::ConfigType` of a format, and its `load` and `store` functions.
+fn parse_format(format: &str) -> Result<(&'static str, &'static str, &'static str), String> {
+    match format {
+        "bin" => Ok(("Bin", "load_bin", "store_bin")),
+        "toml" => Ok(("Toml", "load_toml", "store_toml")),
+        "json" => Ok(("Json", "load_json", "store_json")),
+        "yaml" => Ok(("Yaml", "load_yaml", "store_yaml")),
+        "ron" => Ok(("Ron", "load_ron", "store_ron")),
+        other => Err(format!(
+            "Unknown format `{other}`, expected `bin`, `toml`, `json`, `yaml` or `ron`"
+        )),
+    }
+}
+
+/// Returns the variant of `binconf::ConfigLocation` of a location.
+fn parse_location(location: &str) -> Result<&'static str, String> {
+    match location {
+        "config" => Ok("Config"),
+        "cache" => Ok("Cache"),
+        "local_data" => Ok("LocalData"),
+        "cwd" => Ok("Cwd"),
+        other => Err(format!(
+            "Unknown location `{other}`, expected `config`, `cache`, `local_data` or `cwd`"
+        )),
+    }
+}
+
+/// Returns the content of a `"..."` literal without escapes, `None` for any other literal.
+fn string_literal(literal: &str) -> Option<String> {
+    let content = literal.strip_prefix('"')?.strip_suffix('"')?;
+    (!content.contains(['\\', '"'])).then(|| content.to_owned())
+}
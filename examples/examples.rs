@@ -1,4 +1,4 @@
-use binconf::ConfigLocation::{Cache, Config, Cwd, LocalData};
+use binconf::ConfigLocation::Config;
 use serde::{Deserialize, Serialize};
 
 // The struct needs to have all of its fields as owned types
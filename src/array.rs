@@ -0,0 +1,261 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::fs::read_to_string;
+
+/// Appends a value to an array inside a config file, without knowing the full schema of the file. In `json` or `toml` format.
+///
+/// The document is parsed dynamically, the value is pushed to the array found at `key_path` and the file is written back atomically.
+/// Nested keys are separated by dots (e.g. `"network.servers"`).
+///
+/// If the file or any of the keys in the path do not exist, they will be created.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while parsing or serializing the config.
+///
+/// If the value at `key_path` (or one of its parents) exists but is not an array (or a table), a [`ConfigError::NotAnArray`] error is returned.
+///
+/// Formats other than `json` and `toml` return a [`ConfigError::UnsupportedFormat`] error.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Server {
+///    host: String,
+///    port: u16,
+/// }
+///
+/// let server = Server { host: String::from("localhost"), port: 8080 };
+///
+/// binconf::append_to_array("test-binconf-append-array", None, ConfigType::Json, Config, "servers", &server).unwrap();
+/// ```
+pub fn append_to_array<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: impl AsRef<ConfigType>,
+    location: impl AsRef<ConfigLocation>,
+    key_path: &str,
+    value: T,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let config_type = config_type.as_ref();
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        config_type.as_str(),
+        location.as_ref(),
+    )?;
+
+    let document = if config_file_path.try_exists()? {
+        Some(read_to_string(&config_file_path)?)
+    } else {
+        None
+    };
+
+    let document = match config_type {
+        #[cfg(feature = "json-conf")]
+        ConfigType::Json => append_json(document.as_deref(), key_path, value)?,
+
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => append_toml(document.as_deref(), key_path, value)?,
+
+        #[allow(unreachable_patterns)]
+        _ => {
+            return Err(ConfigError::UnsupportedFormat(
+                config_type.as_str().to_owned(),
+            ))
+        }
+    };
+
+    crate::save_config_atomic(&config_file_path, document.as_bytes())
+}
+
+#[cfg(feature = "json-conf")]
+fn append_json<T>(document: Option<&str>, key_path: &str, value: T) -> Result<String, ConfigError>
+where
+    T: serde::Serialize,
+{
+    let mut root = match document {
+        Some(document) if !document.trim().is_empty() => {
+            serde_json::from_str::<serde_json::Value>(document)?
+        }
+        _ => serde_json::Value::Object(serde_json::Map::new()),
+    };
+
+    let (parents, last_key) = match key_path.rsplit_once('.') {
+        Some((parents, last_key)) => (parents.split('.').collect(), last_key),
+        None => (Vec::new(), key_path),
+    };
+
+    let Some(mut table) = root.as_object_mut() else {
+        return Err(ConfigError::NotAnArray(key_path.to_owned()));
+    };
+    for key in parents {
+        let serde_json::Value::Object(child) = table
+            .entry(key)
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+        else {
+            return Err(ConfigError::NotAnArray(key_path.to_owned()));
+        };
+        table = child;
+    }
+
+    let serde_json::Value::Array(array) = table
+        .entry(last_key)
+        .or_insert_with(|| serde_json::Value::Array(Vec::new()))
+    else {
+        return Err(ConfigError::NotAnArray(key_path.to_owned()));
+    };
+    array.push(serde_json::to_value(value)?);
+
+    Ok(serde_json::to_string_pretty(&root)?)
+}
+
+#[cfg(feature = "toml-conf")]
+fn append_toml<T>(document: Option<&str>, key_path: &str, value: T) -> Result<String, ConfigError>
+where
+    T: serde::Serialize,
+{
+    let mut root = match document {
+        Some(document) => toml::from_str::<toml::Table>(document)?,
+        None => toml::Table::new(),
+    };
+
+    let (parents, last_key) = match key_path.rsplit_once('.') {
+        Some((parents, last_key)) => (parents.split('.').collect(), last_key),
+        None => (Vec::new(), key_path),
+    };
+
+    let mut table = &mut root;
+    for key in parents {
+        let toml::Value::Table(child) = table
+            .entry(key)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        else {
+            return Err(ConfigError::NotAnArray(key_path.to_owned()));
+        };
+        table = child;
+    }
+
+    let toml::Value::Array(array) = table
+        .entry(last_key)
+        .or_insert_with(|| toml::Value::Array(Vec::new()))
+    else {
+        return Err(ConfigError::NotAnArray(key_path.to_owned()));
+    };
+    array.push(toml::Value::try_from(value)?);
+
+    Ok(toml::to_string_pretty(&root)?)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use serde::{Deserialize, Serialize};
+    use ConfigLocation::Config;
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+    struct Inventory {
+        name: String,
+        servers: Vec<Server>,
+    }
+
+    #[test]
+    fn append_to_array_keeps_other_keys() {
+        let inventory = Inventory {
+            name: String::from("inventory"),
+            servers: vec![Server {
+                host: String::from("first"),
+                port: 1,
+            }],
+        };
+        let server = Server {
+            host: String::from("second"),
+            port: 2,
+        };
+
+        crate::store_json(
+            "test-binconf-append_to_array-json",
+            None,
+            Config,
+            &inventory,
+        )
+        .unwrap();
+        crate::store_toml(
+            "test-binconf-append_to_array-toml",
+            None,
+            Config,
+            &inventory,
+        )
+        .unwrap();
+
+        append_to_array(
+            "test-binconf-append_to_array-json",
+            None,
+            ConfigType::Json,
+            Config,
+            "servers",
+            &server,
+        )
+        .unwrap();
+        append_to_array(
+            "test-binconf-append_to_array-toml",
+            None,
+            ConfigType::Toml,
+            Config,
+            "servers",
+            &server,
+        )
+        .unwrap();
+
+        let json: Inventory =
+            crate::load_json("test-binconf-append_to_array-json", None, Config, false).unwrap();
+        let toml: Inventory =
+            crate::load_toml("test-binconf-append_to_array-toml", None, Config, false).unwrap();
+
+        assert_eq!(json.name, inventory.name);
+        assert_eq!(
+            json.servers,
+            vec![inventory.servers[0].clone(), server.clone()]
+        );
+        assert_eq!(toml.name, inventory.name);
+        assert_eq!(toml.servers, vec![inventory.servers[0].clone(), server]);
+    }
+
+    #[test]
+    fn append_to_array_rejects_non_array() {
+        let inventory = Inventory::default();
+
+        crate::store_json(
+            "test-binconf-append_to_array_rejects-json",
+            None,
+            Config,
+            &inventory,
+        )
+        .unwrap();
+
+        let result = append_to_array(
+            "test-binconf-append_to_array_rejects-json",
+            None,
+            ConfigType::Json,
+            Config,
+            "name",
+            1,
+        );
+        assert!(matches!(result, Err(ConfigError::NotAnArray(_))));
+    }
+}
@@ -0,0 +1,225 @@
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Attributes set with [`set_file_attributes`], by app name.
+static ATTRIBUTES: RwLock<Vec<(String, FileAttributes)>> = RwLock::new(Vec::new());
+
+/// Platform metadata set on the config files of an app when they are stored. Set with [`set_file_attributes`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileAttributes {
+    /// Hides the files in the file explorer: the hidden attribute on Windows, the `UF_HIDDEN` flag on macOS. Meant for
+    /// machine-managed state files. A no-op on other platforms, where only dot files are hidden.
+    pub hidden: bool,
+    /// Removes the `com.apple.quarantine` extended attribute on macOS, so configs copied from a download or a shared
+    /// folder don't trigger Gatekeeper prompts when they are opened. A no-op on other platforms.
+    pub clear_quarantine: bool,
+}
+
+/// Sets the platform metadata of the config files of `app_name`, or stops setting it with `None`.
+///
+/// The attributes are applied every time a `store` function writes a file in the app directory, in every location.
+/// Failing to apply them is not an error, the config is stored anyway. Files that are already stored keep their
+/// attributes until they are stored again.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::FileAttributes;
+///
+/// binconf::set_file_attributes("test-binconf-file-attributes", Some(FileAttributes {
+///     hidden: true,
+///     clear_quarantine: true,
+/// }));
+///
+/// binconf::store_bin("test-binconf-file-attributes", "state.bin", Cache, vec![1u8, 2, 3]).unwrap();
+/// ```
+pub fn set_file_attributes(app_name: impl AsRef<str>, attributes: Option<FileAttributes>) {
+    let app_name = app_name.as_ref();
+    let mut all_attributes = ATTRIBUTES
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    all_attributes.retain(|(name, _)| name != app_name);
+    if let Some(attributes) = attributes {
+        all_attributes.push((app_name.to_owned(), attributes));
+    }
+}
+
+/// Returns the attributes of the app owning `path`, if any were set.
+fn attributes_for(path: &Path) -> Option<FileAttributes> {
+    let all_attributes = ATTRIBUTES
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    // The same directories as `crate::app_dir`, without creating them
+    all_attributes
+        .iter()
+        .find(|(app_name, _)| {
+            crate::resolution::app_dirs(app_name)
+                .iter()
+                .any(|app_dir| path.starts_with(app_dir))
+        })
+        .map(|(_, attributes)| *attributes)
+}
+
+/// Prepares the config file at `path` to be overwritten.
+///
+/// On Windows, a hidden file can't be truncated by [`std::fs::File::create`], so it is made visible until
+/// [`after_write`] hides it again.
+pub(crate) fn before_write(path: &Path) {
+    #[cfg(windows)]
+    if attributes_for(path).is_some_and(|attributes| attributes.hidden) && path.exists() {
+        let _ = platform::set_hidden(path, false);
+    }
+    #[cfg(not(windows))]
+    let _ = path;
+}
+
+/// Applies the attributes of the app owning the config file at `path`, which was just written.
+pub(crate) fn after_write(path: &Path) {
+    let Some(attributes) = attributes_for(path) else {
+        return;
+    };
+
+    if attributes.hidden {
+        let _ = platform::set_hidden(path, true);
+    }
+    if attributes.clear_quarantine {
+        let _ = platform::clear_quarantine(path);
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::fs::MetadataExt;
+    use std::path::Path;
+
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    const FILE_ATTRIBUTE_NORMAL: u32 = 0x80;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetFileAttributesW(file_name: *const u16, file_attributes: u32) -> i32;
+    }
+
+    pub(super) fn set_hidden(path: &Path, hidden: bool) -> std::io::Result<()> {
+        let attributes = std::fs::metadata(path)?.file_attributes();
+        let updated = if hidden {
+            (attributes & !FILE_ATTRIBUTE_NORMAL) | FILE_ATTRIBUTE_HIDDEN
+        } else {
+            match attributes & !FILE_ATTRIBUTE_HIDDEN {
+                0 => FILE_ATTRIBUTE_NORMAL,
+                attributes => attributes,
+            }
+        };
+        if updated == attributes {
+            return Ok(());
+        }
+
+        let file_name: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+        // SAFETY: the file name is a nul terminated UTF-16 string that outlives the call.
+        if unsafe { SetFileAttributesW(file_name.as_ptr(), updated) } == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn clear_quarantine(_path: &Path) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+mod platform {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    const UF_HIDDEN: u32 = 0x8000;
+
+    pub(super) fn set_hidden(path: &Path, hidden: bool) -> std::io::Result<()> {
+        use std::os::macos::fs::MetadataExt;
+
+        let flags = std::fs::metadata(path)?.st_flags();
+        let updated = if hidden {
+            flags | UF_HIDDEN
+        } else {
+            flags & !UF_HIDDEN
+        };
+        if updated == flags {
+            return Ok(());
+        }
+
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        // SAFETY: the path is a nul terminated string that outlives the call.
+        if unsafe { libc::chflags(path.as_ptr(), updated) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn clear_quarantine(path: &Path) -> std::io::Result<()> {
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        // SAFETY: both strings are nul terminated and outlive the call.
+        let result =
+            unsafe { libc::removexattr(path.as_ptr(), c"com.apple.quarantine".as_ptr(), 0) };
+        if result != 0 {
+            let err = std::io::Error::last_os_error();
+            // The file was never quarantined
+            if err.raw_os_error() != Some(libc::ENOATTR) {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(not(any(windows, target_vendor = "apple")))]
+mod platform {
+    use std::path::Path;
+
+    pub(super) fn set_hidden(_path: &Path, _hidden: bool) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    pub(super) fn clear_quarantine(_path: &Path) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "binary-conf")]
+mod tests {
+    use super::*;
+
+    use crate::ConfigLocation::Cache;
+
+    #[test]
+    fn attributes_apply_to_the_files_of_the_app() {
+        let app = "test-binconf-attributes_apply_to_the_files_of_the_app";
+        let attributes = FileAttributes {
+            hidden: true,
+            clear_quarantine: false,
+        };
+        set_file_attributes(app, Some(attributes));
+
+        let app_dir = crate::app_dir(app, &Cache).unwrap();
+        assert_eq!(attributes_for(&app_dir.join("state.bin")), Some(attributes));
+        assert_eq!(attributes_for(&app_dir.with_file_name("other-app")), None);
+
+        crate::store_bin(app, "state.bin", Cache, vec![1u8, 2, 3]).unwrap();
+        crate::store_bin(app, "state.bin", Cache, vec![4u8]).unwrap();
+        assert_eq!(
+            crate::load_bin::<Vec<u8>>(app, "state.bin", Cache, false).unwrap(),
+            vec![4]
+        );
+
+        set_file_attributes(app, None);
+        assert_eq!(attributes_for(&app_dir.join("state.bin")), None);
+    }
+}
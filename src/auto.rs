@@ -0,0 +1,119 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+
+/// The config types enabled by the features, in the order [`load_auto`] looks for existing files.
+pub(crate) const ENABLED_TYPES: &[ConfigType] = &[
+    #[cfg(feature = "binary-conf")]
+    ConfigType::Bin,
+    #[cfg(feature = "toml-conf")]
+    ConfigType::Toml,
+    #[cfg(feature = "json-conf")]
+    ConfigType::Json,
+    #[cfg(feature = "yaml-conf")]
+    ConfigType::Yaml,
+    #[cfg(feature = "ron-conf")]
+    ConfigType::Ron,
+];
+
+/// Loads a config in the format given by the extension of `config_name`, like `settings.toml`. In any format.
+///
+/// Meant for apps letting their users choose the format of the config. The `load` function of the format is called,
+/// see for example [`crate::load_bin`].
+///
+/// Without a `config_name`, the default file of the app is looked for in every enabled format, binary first, then
+/// `toml`, `json`, `yaml` and `ron`, and the first one that exists is loaded. If there is none, a binary config is
+/// created, or a config in the only enabled format.
+///
+/// # Errors
+///
+/// This function will return the errors of the `load` function of the format, or a [`ConfigError::UnsupportedFormat`]
+/// error if the extension of `config_name` is not the one of an enabled format.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// binconf::store_bin("test-binconf-load-auto", "volume.bin", Config, 7u8).unwrap();
+///
+/// let volume = binconf::load_auto::<u8>("test-binconf-load-auto", "volume.bin", Config, false).unwrap();
+/// assert_eq!(volume, 7);
+/// ```
+pub fn load_auto<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let app_name = app_name.as_ref();
+    let config_name = config_name.into();
+    let location = location.as_ref();
+
+    let config_type = match config_name {
+        Some(config_name) => type_from_name(config_name)?,
+        None => existing_type(app_name, location)?,
+    };
+
+    crate::load_typed(
+        app_name,
+        config_name,
+        &config_type,
+        location,
+        reset_conf_on_err,
+    )
+}
+
+/// Returns the config type of a file name, from its extension.
+pub(crate) fn type_from_name(config_name: &str) -> Result<ConfigType, ConfigError> {
+    let extension = std::path::Path::new(config_name)
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .unwrap_or_default();
+    ConfigType::from_extension(extension)
+        .ok_or_else(|| ConfigError::UnsupportedFormat(extension.to_owned()))
+}
+
+/// Returns the type of the first default file of the app that exists, or the first enabled type if there is none.
+fn existing_type(app_name: &str, location: &ConfigLocation) -> Result<ConfigType, ConfigError> {
+    for config_type in ENABLED_TYPES {
+        let path = crate::config_location(app_name, None, config_type.as_str(), location)?;
+        if path.try_exists()? {
+            return Ok(*config_type);
+        }
+    }
+
+    ENABLED_TYPES
+        .first()
+        .copied()
+        .ok_or_else(|| ConfigError::UnsupportedFormat(String::new()))
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Cache;
+
+    #[test]
+    fn format_follows_the_extension_or_the_existing_file() {
+        let app = "test-binconf-format_follows_the_extension_or_the_existing_file";
+        crate::store_yaml(app, "settings.yml", Cache, vec![1u8, 2]).unwrap();
+        crate::store_ron(app, None, Cache, vec![3u8]).unwrap();
+
+        assert_eq!(
+            load_auto::<Vec<u8>>(app, "settings.yml", Cache, false).unwrap(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            load_auto::<Vec<u8>>(app, None, Cache, false).unwrap(),
+            vec![3]
+        );
+        assert!(matches!(
+            load_auto::<Vec<u8>>(app, "settings.ini", Cache, false),
+            Err(ConfigError::UnsupportedFormat(extension)) if extension == "ini"
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::{
+    path::PathBuf,
+    sync::{mpsc, Mutex},
+    thread::JoinHandle,
+};
+
+/// A job processed by the background writer thread.
+enum Job {
+    Write {
+        config_file_path: PathBuf,
+        full_data: Vec<u8>,
+        /// The written config as `json`, recorded in the change log once the write succeeded. `None` if it is disabled.
+        #[cfg(feature = "json-conf")]
+        logged: Option<serde_json::Value>,
+    },
+    Flush(mpsc::Sender<Option<ConfigError>>),
+}
+
+struct BackgroundWriter {
+    sender: mpsc::Sender<Job>,
+    thread: JoinHandle<()>,
+}
+
+/// The background writer is started lazily by the first [`store_bin_background`] call.
+static WRITER: Mutex<Option<BackgroundWriter>> = Mutex::new(None);
+
+impl BackgroundWriter {
+    fn spawn() -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+
+        let thread = std::thread::spawn(move || {
+            // The first error since the last flush, reported to the caller of `flush_pending`
+            let mut first_error = None;
+
+            for job in receiver {
+                match job {
+                    Job::Write {
+                        config_file_path,
+                        full_data,
+                        #[cfg(feature = "json-conf")]
+                        logged,
+                    } => {
+                        // The process may exit in the middle of a background write, it must never leave a truncated file
+                        let written = crate::save_config_atomic(&config_file_path, &full_data);
+                        #[cfg(feature = "json-conf")]
+                        let written = written.and_then(|()| match logged {
+                            Some(logged) => crate::changelog::record(&config_file_path, &logged),
+                            None => Ok(()),
+                        });
+                        if let Err(err) = written {
+                            first_error.get_or_insert(err);
+                        }
+                    }
+                    Job::Flush(done) => {
+                        let _ = done.send(first_error.take());
+                    }
+                }
+            }
+        });
+
+        BackgroundWriter { sender, thread }
+    }
+}
+
+/// Stores a config file in the config, cache, cwd, or local data directory of the current user, **without waiting for the write**. In `binary` format.
+///
+/// The data is serialized on the calling thread, then handed to a dedicated writer thread and the function returns immediately.
+/// Writes are performed in the order they were enqueued. Use [`flush_pending`] to wait for them and to collect write errors,
+/// and [`shutdown_background_writer`] before the process exits.
+///
+/// Useful for contexts that can't afford synchronous disk writes, like game loops or audio threads.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+///
+/// Errors that happen while writing the file are reported by [`flush_pending`]. The change log, if enabled with
+/// [`crate::set_change_log`], is only updated once the file was written.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///   test: String,
+///   test_vec: Vec<u8>,
+/// }
+///
+/// let test_config = TestConfig {
+///  test: String::from("test-bin"),
+///  test_vec: vec![1, 2, 3, 4, 5],
+/// };
+///
+/// binconf::store_bin_background("test-binconf-store-background-bin", None, Config, &test_config).unwrap();
+/// binconf::flush_pending().unwrap();
+///
+/// let config = binconf::load_bin::<TestConfig>("test-binconf-store-background-bin", None, Config, false).unwrap();
+/// assert_eq!(config, test_config);
+/// ```
+pub fn store_bin_background<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Bin.as_str(),
+        location.as_ref(),
+    )?;
+
+    crate::freeze::ensure_not_frozen(&config_file_path)?;
+    crate::project::ensure_inside_project(&config_file_path)?;
+
+    let full_data = crate::binary_conf::prepare_serialized_data(&data)?;
+    // Kept for the writer thread, which logs the change only once the file was written. Configs `json` can't represent
+    // are not logged, like in the other stores
+    #[cfg(feature = "json-conf")]
+    let logged = crate::changelog::is_enabled()
+        .then(|| serde_json::to_value(&data).ok())
+        .flatten();
+
+    let mut writer = WRITER
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    writer
+        .get_or_insert_with(BackgroundWriter::spawn)
+        .sender
+        .send(Job::Write {
+            config_file_path,
+            full_data,
+            #[cfg(feature = "json-conf")]
+            logged,
+        })
+        .map_err(|_| background_writer_gone())?;
+
+    Ok(())
+}
+
+/// Waits until every write enqueued by [`store_bin_background`] so far has been performed.
+///
+/// # Errors
+///
+/// Returns the first error that happened while writing since the last call to this function.
+pub fn flush_pending() -> Result<(), ConfigError> {
+    let (done_sender, done_receiver) = mpsc::channel();
+
+    {
+        let writer = WRITER
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let Some(writer) = writer.as_ref() else {
+            return Ok(());
+        };
+        writer
+            .sender
+            .send(Job::Flush(done_sender))
+            .map_err(|_| background_writer_gone())?;
+    }
+
+    match done_receiver.recv() {
+        Ok(Some(err)) => Err(err),
+        Ok(None) => Ok(()),
+        Err(_) => Err(background_writer_gone()),
+    }
+}
+
+/// Flushes the pending writes and stops the background writer thread.
+///
+/// Should be called before the process exits, otherwise pending writes may be lost. A later call to [`store_bin_background`]
+/// starts a new writer thread.
+///
+/// # Errors
+///
+/// Returns the first error that happened while writing since the last call to [`flush_pending`].
+pub fn shutdown_background_writer() -> Result<(), ConfigError> {
+    let result = flush_pending();
+
+    let writer = WRITER
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .take();
+    if let Some(BackgroundWriter { sender, thread }) = writer {
+        drop(sender);
+        let _ = thread.join();
+    }
+
+    result
+}
+
+fn background_writer_gone() -> ConfigError {
+    ConfigError::Io(std::io::Error::new(
+        std::io::ErrorKind::BrokenPipe,
+        "Background writer thread is not running",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::{Deserialize, Serialize};
+    use ConfigLocation::Config;
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct TestConfig {
+        test: String,
+        test_vec: Vec<u8>,
+    }
+
+    #[test]
+    fn store_bin_background_keeps_order() {
+        for i in 0..10 {
+            let data = TestConfig {
+                test: format!("test-{i}"),
+                test_vec: vec![i],
+            };
+            store_bin_background("test-binconf-store_bin_background-bin", None, Config, &data)
+                .unwrap();
+        }
+        flush_pending().unwrap();
+
+        let config: TestConfig =
+            crate::load_bin("test-binconf-store_bin_background-bin", None, Config, false).unwrap();
+        assert_eq!(config.test, "test-9");
+        assert_eq!(config.test_vec, vec![9]);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{binary_conf::HASH_BYTE_LENGTH, ConfigError, ConfigLocation, ConfigType};
+use std::{
+    io::{BufReader, Read},
+    marker::PhantomData,
+};
+use xxhash_rust::xxh3::Xxh3;
+
+/// A reader that feeds every byte it reads into a `xxh3_128` hasher.
+struct HashingReader<R> {
+    inner: R,
+    hasher: Xxh3,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Some(read_bytes) = buf.get(..read) {
+            self.hasher.update(read_bytes);
+        }
+        Ok(read)
+    }
+}
+
+/// Iterator over the elements of a binary collection file, returned by [`load_bin_iter`].
+///
+/// Elements are deserialized one at a time while iterating. The `xxh3_128` hash of the file can only be verified after the
+/// last element has been read, so a corrupted file yields its valid elements followed by a final [`ConfigError::HashMismatch`] error.
+pub struct BinIter<T> {
+    state: Option<IterState>,
+    _marker: PhantomData<T>,
+}
+
+struct IterState {
+    reader: HashingReader<BufReader<std::fs::File>>,
+    hash_from_file: [u8; HASH_BYTE_LENGTH],
+    remaining: u64,
+}
+
+impl<T> Iterator for BinIter<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    type Item = Result<T, ConfigError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let state = self.state.as_mut()?;
+
+        if state.remaining == 0 {
+            let state = self.state.take()?;
+            return verify_end(state).err().map(Err);
+        }
+
+        state.remaining -= 1;
+        match bincode::deserialize_from(&mut state.reader) {
+            Ok(element) => Some(Ok(element)),
+            Err(err) => {
+                self.state = None;
+                Some(Err(err.into()))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.state.as_ref().map_or(0, |state| {
+            usize::try_from(state.remaining).unwrap_or(usize::MAX)
+        });
+        (0, remaining.checked_add(1))
+    }
+}
+
+/// Checks that nothing follows the last element and that the hash of the data matches the one stored in the file.
+fn verify_end(mut state: IterState) -> Result<(), ConfigError> {
+    let mut trailing = Vec::new();
+    state.reader.read_to_end(&mut trailing)?;
+
+    if state.reader.hasher.digest128().to_le_bytes() != state.hash_from_file {
+        return Err(ConfigError::HashMismatch);
+    }
+
+    if !trailing.is_empty() {
+        return Err(ConfigError::TrailingData);
+    }
+
+    Ok(())
+}
+
+/// Lazily loads the elements of a collection stored with [`crate::store_bin`], from the config, cache, cwd, or local data directory of the current user. In `binary` format.
+///
+/// Works with files containing a `Vec<T>` (or any other sequence of `T`), reading one element at a time from disk,
+/// so memory stays flat even for very large collections.
+///
+/// If the file does not exist, the iterator is empty and no file is created.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if the file could not be opened.
+///
+/// Deserialization errors are yielded by the iterator. Since the hash covers the whole file, a hash mismatch is only detected
+/// after the last element, see [`BinIter`].
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// let records: Vec<u32> = (0..1000).collect();
+///
+/// binconf::store_bin("test-binconf-load-iter-bin", None, Config, &records).unwrap();
+///
+/// let sum: u32 = binconf::load_bin_iter::<u32>("test-binconf-load-iter-bin", None, Config)
+///     .unwrap()
+///     .map(Result::unwrap)
+///     .sum();
+/// assert_eq!(sum, records.iter().sum::<u32>());
+/// ```
+pub fn load_bin_iter<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<BinIter<T>, ConfigError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Bin.as_str(),
+        location.as_ref(),
+    )?;
+
+    if !config_file_path.try_exists()? {
+        return Ok(BinIter {
+            state: None,
+            _marker: PhantomData,
+        });
+    }
+
+    let mut file = BufReader::new(std::fs::File::open(&config_file_path)?);
+
+    let (hash_from_file, flags) = crate::binary_conf::read_hash(&mut file)?;
+
+    let mut reader = HashingReader {
+        inner: file,
+        hasher: Xxh3::new(),
+    };
+    // The metadata is covered by the hash, so it is read through the hashing reader
+    crate::binary_conf::skip_metadata(flags, &mut reader)?;
+
+    // Sequences are prefixed by their length as a `u64`
+    let remaining: u64 = bincode::deserialize_from(&mut reader)?;
+
+    Ok(BinIter {
+        state: Some(IterState {
+            reader,
+            hash_from_file,
+            remaining,
+        }),
+        _marker: PhantomData,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::{Deserialize, Serialize};
+    use std::io::Write;
+    use ConfigLocation::Config;
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Record {
+        name: String,
+        values: Vec<u8>,
+    }
+
+    #[test]
+    fn load_bin_iter_yields_all_elements() {
+        let records: Vec<Record> = (0..100)
+            .map(|i| Record {
+                name: format!("record-{i}"),
+                values: vec![i; 3],
+            })
+            .collect();
+
+        crate::store_bin("test-binconf-load_bin_iter-bin", None, Config, &records).unwrap();
+
+        let loaded: Vec<Record> = load_bin_iter("test-binconf-load_bin_iter-bin", None, Config)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(loaded, records);
+    }
+
+    #[test]
+    fn load_bin_iter_reports_hash_mismatch() {
+        let records: Vec<u16> = (0..100).collect();
+
+        crate::store_bin(
+            "test-binconf-load_bin_iter_mismatch-bin",
+            None,
+            Config,
+            &records,
+        )
+        .unwrap();
+
+        let path = crate::get_configuration_path(
+            "test-binconf-load_bin_iter_mismatch-bin",
+            None,
+            ConfigType::Bin,
+            Config,
+        )
+        .unwrap();
+        let mut data = std::fs::read(&path).unwrap();
+        if let Some(last) = data.last_mut() {
+            *last = last.wrapping_add(1);
+        }
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(&data)
+            .unwrap();
+
+        let results: Vec<Result<u16, ConfigError>> =
+            load_bin_iter("test-binconf-load_bin_iter_mismatch-bin", None, Config)
+                .unwrap()
+                .collect();
+        assert_eq!(results.len(), 101);
+        assert!(matches!(
+            results.last(),
+            Some(Err(ConfigError::HashMismatch))
+        ));
+    }
+
+    #[test]
+    fn load_bin_iter_missing_file_is_empty() {
+        let mut iter =
+            load_bin_iter::<u8>("test-binconf-load_bin_iter_missing-bin", None, Config).unwrap();
+        assert!(iter.next().is_none());
+    }
+}
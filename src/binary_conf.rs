@@ -1,9 +1,25 @@
-use std::io::{Read, Write};
-use xxhash_rust::xxh3::xxh3_128;
+use std::io::Write;
 
-use crate::{ConfigError, ConfigLocation, ConfigType};
+use crate::{ConfigError, ConfigLocation, ConfigType, Hasher, Xxh3Hasher};
 
-const HASH_BYTE_LENGTH: usize = 16;
+pub(crate) const HASH_BYTE_LENGTH: usize = 16;
+
+/// Identifies the binary files that start with a header. Files stored by older versions start with the hash instead.
+const MAGIC: [u8; 4] = *b"BCNF";
+
+/// Version of the header layout, checked on load.
+const FORMAT_VERSION: u8 = 1;
+
+/// Length of the header: the magic bytes, the format version, the [`Hasher::algorithm_id`], the flags and the payload
+/// length as a little endian `u64`.
+const HEADER_LENGTH: usize = MAGIC.len() + 1 + 1 + 1 + 8;
+
+/// Header flag set when the payload starts with the [`crate::ConfigMetadata`] of [`crate::store_stamped`].
+const FLAG_METADATA: u8 = 1;
+
+/// Header flags known to this version of the crate. Files with other flags are reported as
+/// [`ConfigError::UnsupportedFormatVersion`].
+const KNOWN_FLAGS: u8 = FLAG_METADATA;
 
 /// Loads a config file from the config, cache, cwd, or local data directory of the current user. In `binary` format.
 ///
@@ -16,7 +32,13 @@ const HASH_BYTE_LENGTH: usize = 16;
 ///
 /// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
 ///
-/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config.
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// A damaged file is reported as [`ConfigError::TruncatedFile`] if it is shorter than its header and hash, [`ConfigError::PayloadLengthMismatch`]
+/// if its data is not as long as recorded in its header, [`ConfigError::ZeroedHeader`] if its hash was wiped, and [`ConfigError::HashMismatch`]
+/// if its hash does not match the data, as after editing the file. A file stored by a newer version of the crate is reported as
+/// [`ConfigError::UnsupportedFormatVersion`], and a file stored with another [`Hasher`] as [`ConfigError::HashAlgorithmMismatch`].
+/// These two are not damaged and are never reset, even with `reset_conf_on_err`.
 ///
 /// # Example
 ///
@@ -47,8 +69,12 @@ where
         config_name.into(),
         location.as_ref(),
         reset_conf_on_err,
+        VerifyMode::Strict,
         false,
+        &Xxh3Hasher,
+        &T::default,
     )
+    .map(|(config, _)| config)
 }
 
 /// Loads a config file from the config, cache, cwd, or local data directory of the current user. **Without verifying the hash**. In `binary` format.
@@ -57,6 +83,7 @@ where
 /// other errors **will not be handled** by this function.
 ///
 /// It's **not recommended** to use this function over the [`load_bin`], as it could lead to corrupted data being loaded.
+/// Prefer [`load_bin_verified`] with [`VerifyMode::WarnOnly`], which also tells whether the hash matched.
 ///
 /// If the deserialization fails with the flag `reset_conf_on_err` set to `true`, the config file will be reset to the default config and a new hash will be generated.
 ///
@@ -66,9 +93,9 @@ where
 ///
 /// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
 ///
-/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config.
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
 ///
-/// If the file being read is less than 16 bytes, an error will be returned. It assumes that the first 16 bytes are the hash, even without verifying it, as this could lead to corrupted data being loaded more often.
+/// If the file being read is shorter than its header and hash, a [`ConfigError::TruncatedFile`] error will be returned. It assumes that the bytes after the header are the hash, even without verifying it, as this could lead to corrupted data being loaded more often.
 ///
 /// # Example
 ///
@@ -100,76 +127,341 @@ where
         config_name.into(),
         location.as_ref(),
         reset_conf_on_err,
+        VerifyMode::Skip,
+        false,
+        &Xxh3Hasher,
+        &T::default,
+    )
+    .map(|(config, _)| config)
+}
+
+/// How the hash of a binary config is verified by [`load_bin_verified`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerifyMode {
+    /// A mismatching hash is an error, like in [`load_bin`]. The config is reset if `reset_conf_on_err` is `true`.
+    #[default]
+    Strict,
+    /// The config is loaded despite a mismatching hash, which is reported as [`HashStatus::Mismatch`]. Meant for data recovery.
+    WarnOnly,
+    /// The hash is not verified at all, like in [`load_bin_skip_check`].
+    Skip,
+}
+
+/// What [`load_bin_verified`] found when verifying the hash of the config it returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashStatus {
+    /// The hash matched the data, or the default config was returned and written with a new hash.
+    Valid,
+    /// The hash did not match the data, but the config could still be deserialized. Only returned with [`VerifyMode::WarnOnly`].
+    Mismatch,
+    /// The hash was not verified, with [`VerifyMode::Skip`].
+    Unchecked,
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user, verifying its hash as set by `verify`. In `binary` format.
+///
+/// Works like [`load_bin`], also returning the [`HashStatus`] of the file. With [`VerifyMode::WarnOnly`] a config whose hash does not
+/// match is still loaded, and the mismatch is reported instead of hidden, so an app can recover the data and warn the user about it.
+///
+/// Whatever the mode, the config file is never reset to the default config because of a hash mismatch, except with [`VerifyMode::Strict`].
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// With [`VerifyMode::Strict`], a [`ConfigError::HashMismatch`] or [`ConfigError::ZeroedHeader`] error is returned if the hash does not match and `reset_conf_on_err` is `false`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{HashStatus, VerifyMode};
+///
+/// binconf::store_bin("test-binconf-load-verified-bin", None, Config, vec![1u8, 2, 3]).unwrap();
+///
+/// let (config, status) = binconf::load_bin_verified::<Vec<u8>>("test-binconf-load-verified-bin", None, Config, false, VerifyMode::WarnOnly).unwrap();
+/// if status == HashStatus::Mismatch {
+///     eprintln!("The config is damaged, some settings may be wrong");
+/// }
+/// assert_eq!(config, vec![1, 2, 3]);
+/// ```
+pub fn load_bin_verified<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+    verify: VerifyMode,
+) -> Result<(T, HashStatus), ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_bin_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        reset_conf_on_err,
+        verify,
+        false,
+        &Xxh3Hasher,
+        &T::default,
+    )
+}
+
+/// Recomputes the hash of a binary config file and writes it back, for a file whose data is intact but whose hash was damaged.
+///
+/// Recovers files that [`load_bin`] rejects with a [`ConfigError::ZeroedHeader`] or [`ConfigError::HashMismatch`] error without loading and storing them again
+/// through their config type. The data that follows the hash is kept byte for byte, and is trusted as is: check it first,
+/// for example with [`load_bin_verified`] and [`VerifyMode::WarnOnly`], as a damaged payload gets a valid hash too.
+///
+/// Returns `false` if the hash was already valid and the file was left untouched.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if the
+/// file could not be read or written.
+///
+/// If the file is shorter than the hash, there is no data to rehash and a [`ConfigError::TruncatedFile`] error is returned.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// binconf::store_bin("test-binconf-rehash-bin", None, Config, vec![1u8, 2, 3]).unwrap();
+///
+/// // The hash of a freshly stored config is valid
+/// assert!(!binconf::rehash_bin("test-binconf-rehash-bin", None, Config).unwrap());
+/// ```
+pub fn rehash_bin<'a>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<bool, ConfigError> {
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Bin.as_str(),
+        location.as_ref(),
+    )?;
+
+    let data = crate::progress::read(&config_file_path)?;
+    let frame = split_frame(&Xxh3Hasher, &data)?;
+
+    if frame.hash == Xxh3Hasher.digest(frame.covered) {
+        return Ok(false);
+    }
+
+    let full_data = prepare_framed_data_with(&Xxh3Hasher, frame.flags, &[frame.covered]);
+    crate::save_config_atomic(&config_file_path, &full_data)?;
+
+    Ok(true)
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user. **Rejecting trailing data**. In `binary` format.
+///
+/// Works like [`load_bin`], but if the deserializer does not consume the whole payload, a [`ConfigError::TrailingData`] error is returned.
+/// The regular [`load_bin`] silently ignores any extra bytes after the serialized data.
+///
+/// If the flag `reset_conf_on_err` is set to `true`, the config file will be reset to the default config when trailing data is found.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails or trailing data is found, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///    test: String,
+///    test_vec: Vec<u8>,
+/// }
+///
+/// let config = binconf::load_bin_strict::<TestConfig>("test-binconf-read-strict-bin", None, Config, false).unwrap();
+/// assert_eq!(config, TestConfig::default());
+/// ```
+pub fn load_bin_strict<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_bin_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        reset_conf_on_err,
+        VerifyMode::Strict,
         true,
+        &Xxh3Hasher,
+        &T::default,
+    )
+    .map(|(config, _)| config)
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user, **falling back to a given config**. In `binary` format.
+///
+/// Works like [`load_bin`], but `fallback` takes the place of the `Default` implementation: it is cloned when the file does not exist
+/// or is reset, and left alone when the file is loaded. Meant for configs with big defaults, like lookup tables, that should not be built
+/// on every load just to be thrown away, and for types that can't implement `Default`.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the fallback config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Lookup {
+///    table: Vec<u32>,
+/// }
+///
+/// let fallback = Lookup { table: (0..1024).collect() };
+///
+/// let config = binconf::load_bin_or("test-binconf-read-binary-or", None, Config, false, &fallback).unwrap();
+/// assert_eq!(config, fallback);
+/// ```
+pub fn load_bin_or<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+    fallback: &T,
+) -> Result<T, ConfigError>
+where
+    T: Clone + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_bin_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        reset_conf_on_err,
+        VerifyMode::Strict,
+        false,
+        &Xxh3Hasher,
+        &|| fallback.clone(),
     )
+    .map(|(config, _)| config)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn load_bin_internal<T>(
     app_name: &str,
     config_name: Option<&str>,
     location: &ConfigLocation,
     reset_conf_on_err: bool,
-    skip_hash_check: bool,
-) -> Result<T, ConfigError>
+    verify: VerifyMode,
+    strict: bool,
+    hasher: &dyn Hasher,
+    default: &dyn Fn() -> T,
+) -> Result<(T, HashStatus), ConfigError>
 where
-    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    T: serde::Serialize + serde::de::DeserializeOwned,
 {
     let config_file_path =
         crate::config_location(app_name, config_name, ConfigType::Bin.as_str(), location)?;
 
     let save_default_conf = || {
-        let default_config = T::default();
-        let mut file = std::io::BufWriter::new(std::fs::File::create(&config_file_path)?);
+        let default_config = default();
+        if crate::freeze::is_frozen_path(&config_file_path) {
+            return Ok((default_config, HashStatus::Valid));
+        }
 
-        let full_data = prepare_serialized_data(&default_config)?;
-        file.write_all(&full_data)?;
+        let full_data = prepare_serialized_data_with(hasher, &default_config)?;
+        crate::reset::count();
+        crate::reset::back_up_corrupt(&config_file_path);
+        // Renamed over the corrupt file, a failed write leaves it in place rather than a truncated one
+        crate::save_config_atomic(&config_file_path, &full_data)?;
 
-        Ok(default_config)
+        Ok((default_config, HashStatus::Valid))
     };
 
     if !config_file_path.try_exists()? {
-        return save_default_conf();
-    }
-
-    let file = std::fs::File::open(&config_file_path)?;
-    let mut reader = std::io::BufReader::new(file);
-
-    let mut data = Vec::new();
-    reader.read_to_end(&mut data)?;
-
-    // If the file is empty, or smaller than 16 bytes, we can't have a `xxh3_128` hash
-    if data.len() < HASH_BYTE_LENGTH {
-        if reset_conf_on_err {
-            return save_default_conf();
+        let default_config = default();
+        let full_data = prepare_serialized_data_with(hasher, &default_config)?;
+        if crate::freeze::is_frozen_path(&config_file_path)
+            || crate::save_config_new(&config_file_path, &full_data)?
+        {
+            return Ok((default_config, HashStatus::Valid));
         }
-        return Err(ConfigError::CorruptedHashSector);
+        // Created by another process in the meantime, it is loaded below
     }
 
-    if !skip_hash_check {
-        let (binary_hash_from_file, binary_hash_from_data) = get_hash_from_file_and_data(&data);
+    let started = std::time::Instant::now();
+    let data = crate::progress::read(&config_file_path)?;
+    crate::slow_io::report(
+        crate::IoOperation::Load,
+        &config_file_path,
+        data.len(),
+        started,
+    );
+
+    // If the file is empty, or smaller than the hash length, we can't have a hash. A file stored by a newer version or with
+    // another hasher is not damaged, it is never reset
+    let frame = match split_frame(hasher, &data) {
+        Ok(frame) => frame,
+        Err(ConfigError::TruncatedFile { .. } | ConfigError::PayloadLengthMismatch { .. })
+            if reset_conf_on_err =>
+        {
+            return save_default_conf()
+        }
+        Err(err) => return Err(err),
+    };
 
-        if binary_hash_from_file != binary_hash_from_data {
+    let hash_status = match verify {
+        VerifyMode::Skip => HashStatus::Unchecked,
+        _ if frame.hash == hasher.digest(frame.covered) => HashStatus::Valid,
+        VerifyMode::WarnOnly => HashStatus::Mismatch,
+        VerifyMode::Strict => {
             if reset_conf_on_err {
                 return save_default_conf();
             }
-            return Err(ConfigError::HashMismatch);
+            return Err(hash_mismatch_error(frame.hash));
         }
-    }
+    };
+
+    let mut binary_data_without_hash = match frame.split_metadata() {
+        Ok((_, data)) => data,
+        Err(_) if reset_conf_on_err => return save_default_conf(),
+        Err(err) => return Err(err),
+    };
 
-    // The first 16 bytes are the `xxh3_128` hash, the rest is the serialized data
-    let binary_data_without_hash = &data[HASH_BYTE_LENGTH..];
-    let config: T = match bincode::deserialize_from(binary_data_without_hash) {
+    let config: T = match bincode::deserialize_from(&mut binary_data_without_hash) {
         Ok(config) => config,
         Err(err) => {
             if reset_conf_on_err {
-                save_default_conf()?
+                return save_default_conf();
             } else {
                 return Err(ConfigError::Bincode(err));
             }
         }
     };
 
-    Ok(config)
+    // The reader advances the slice, so anything left over was not consumed by the deserializer
+    if strict && !binary_data_without_hash.is_empty() {
+        if reset_conf_on_err {
+            return save_default_conf();
+        }
+        return Err(ConfigError::TrailingData);
+    }
+
+    Ok((config, hash_status))
 }
 
 /// Stores a config file in the config, cache, cwd, or local data directory of the current user. In `binary` format.
@@ -180,6 +472,8 @@ where
 ///
 /// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while serializing the config.
 ///
+/// If the free space guard is enabled with [`crate::set_free_space_guard`] and there is not enough space, [`ConfigError::InsufficientSpace`] is returned before the file is touched.
+///
 /// # Example
 ///
 /// ```
@@ -208,6 +502,54 @@ pub fn store_bin<'a, T>(
     location: impl AsRef<ConfigLocation>,
     data: T,
 ) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    store_bin_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        data,
+        &Xxh3Hasher,
+    )
+}
+
+/// Stores a config file like [`store_bin`], failing if the file already exists. In `binary` format.
+///
+/// Meant for commands like `myapp init` that generate a config and must never overwrite the one of the user.
+/// When several processes store the same config at once, exactly one of them creates the file.
+///
+/// # Errors
+///
+/// This function will return a [`ConfigError::AlreadyExists`] error if the config file already exists. Other errors are returned if the config,
+/// cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigError, ConfigType};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///   test: String,
+/// }
+///
+/// let path = binconf::get_configuration_path("test-binconf-store-bin-new", None, ConfigType::Bin, Config).unwrap();
+/// # let _ = std::fs::remove_file(&path);
+///
+/// binconf::store_bin_new("test-binconf-store-bin-new", None, Config, TestConfig::default()).unwrap();
+///
+/// let result = binconf::store_bin_new("test-binconf-store-bin-new", None, Config, TestConfig::default());
+/// assert!(matches!(result, Err(ConfigError::AlreadyExists(_))));
+/// ```
+pub fn store_bin_new<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+) -> Result<(), ConfigError>
 where
     T: serde::Serialize,
 {
@@ -218,63 +560,400 @@ where
         location.as_ref(),
     )?;
 
-    let mut file = std::io::BufWriter::new(std::fs::File::create(config_file_path)?);
+    let full_data = prepare_serialized_data_with(&Xxh3Hasher, &data)?;
+    if !crate::save_config_new(&config_file_path, &full_data)? {
+        return Err(ConfigError::AlreadyExists(config_file_path));
+    }
+    #[cfg(feature = "json-conf")]
+    crate::changelog::record(&config_file_path, &data)?;
 
-    let full_data = prepare_serialized_data(data)?;
+    Ok(())
+}
 
-    file.write_all(&full_data[..])?;
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user, verifying it with a custom [`Hasher`]. In `binary` format.
+///
+/// Works like [`load_bin`], for files stored with [`store_bin_with_hasher`] and the same hasher.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::Hasher;
+///
+/// // A toy checksum, use a real hash in practice.
+/// struct Sum;
+///
+/// impl Hasher for Sum {
+///     fn digest_length(&self) -> usize {
+///         8
+///     }
+///
+///     fn digest(&self, data: &[u8]) -> Vec<u8> {
+///         data.iter().map(|byte| u64::from(*byte)).sum::<u64>().to_le_bytes().to_vec()
+///     }
+/// }
+///
+/// binconf::store_bin_with_hasher("test-binconf-hasher", None, Config, vec![1u8, 2, 3], &Sum).unwrap();
+///
+/// let config = binconf::load_bin_with_hasher::<Vec<u8>>("test-binconf-hasher", None, Config, false, &Sum).unwrap();
+/// assert_eq!(config, vec![1, 2, 3]);
+/// ```
+pub fn load_bin_with_hasher<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+    hasher: &impl Hasher,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_bin_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        reset_conf_on_err,
+        VerifyMode::Strict,
+        false,
+        hasher,
+        &T::default,
+    )
+    .map(|(config, _)| config)
+}
+
+/// Stores a config file in the config, cache, cwd, or local data directory of the current user, protected by a custom [`Hasher`]. In `binary` format.
+///
+/// Works like [`store_bin`]. The file must be loaded with [`load_bin_with_hasher`] and the same hasher.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+pub fn store_bin_with_hasher<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+    hasher: &impl Hasher,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    store_bin_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        data,
+        hasher,
+    )
+}
+
+fn store_bin_internal<T>(
+    app_name: &str,
+    config_name: Option<&str>,
+    location: &ConfigLocation,
+    data: T,
+    hasher: &dyn Hasher,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let config_file_path =
+        crate::config_location(app_name, config_name, ConfigType::Bin.as_str(), location)?;
+
+    let full_data = prepare_serialized_data_with(hasher, &data)?;
+    write_bin_file(&config_file_path, &full_data)?;
+
+    #[cfg(feature = "json-conf")]
+    crate::changelog::record(&config_file_path, &data)?;
 
     Ok(())
 }
 
-/// Returns the `xxh3_128` hash of the file and the `xxh3_128` hash of the data.
+/// Writes the framed data of a binary file, with the same checks, hooks and reports as every binary store.
+pub(crate) fn write_bin_file(
+    config_file_path: &std::path::Path,
+    full_data: &[u8],
+) -> Result<(), ConfigError> {
+    crate::freeze::ensure_not_frozen(config_file_path)?;
+    crate::project::ensure_inside_project(config_file_path)?;
+    crate::disk::ensure_free_space(config_file_path, full_data.len())?;
+
+    crate::attributes::before_write(config_file_path);
+    let started = std::time::Instant::now();
+    let mut file = std::io::BufWriter::new(std::fs::File::create(config_file_path)?);
+    crate::progress::write_all(&mut file, config_file_path, full_data)?;
+    file.flush()?;
+    drop(file);
+    crate::attributes::after_write(config_file_path);
+    crate::slow_io::report(
+        crate::IoOperation::Store,
+        config_file_path,
+        full_data.len(),
+        started,
+    );
+
+    Ok(())
+}
+
+/// Verifies the `xxh3_128` hash of the data read from a binary file and deserializes the payload.
+///
+/// Used by the APIs that read binary files outside of the regular `load` path, which never reset the file to defaults.
+pub(crate) fn deserialize_verified<T>(data: &[u8]) -> Result<T, ConfigError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    Ok(bincode::deserialize(verified_payload(data)?)?)
+}
+
+/// Verifies the `xxh3_128` hash of the data read from a binary file and returns the serialized data, after the hash and
+/// the metadata.
+pub(crate) fn verified_payload(data: &[u8]) -> Result<&[u8], ConfigError> {
+    verified_frame(data)?
+        .split_metadata()
+        .map(|(_, payload)| payload)
+}
+
+/// Verifies the `xxh3_128` hash of the data read from a binary file and returns its parts.
+pub(crate) fn verified_frame(data: &[u8]) -> Result<Frame<'_>, ConfigError> {
+    let frame = split_frame(&Xxh3Hasher, data)?;
+
+    if frame.hash != Xxh3Hasher.digest(frame.covered) {
+        return Err(hash_mismatch_error(frame.hash));
+    }
+
+    Ok(frame)
+}
+
+/// The parts of the data read from a binary file, as split by [`split_frame`].
+pub(crate) struct Frame<'a> {
+    /// The hash stored in the file.
+    hash: &'a [u8],
+    /// Everything the hash covers: the metadata, if the header says so, and the serialized data.
+    covered: &'a [u8],
+    /// The flags of the header, `0` for files stored without one.
+    flags: u8,
+}
+
+impl<'a> Frame<'a> {
+    /// Splits the covered data into the metadata, if the header flags it, and the serialized data that follows it.
+    pub(crate) fn split_metadata(
+        &self,
+    ) -> Result<(Option<crate::ConfigMetadata>, &'a [u8]), ConfigError> {
+        if self.flags & FLAG_METADATA == 0 {
+            return Ok((None, self.covered));
+        }
+
+        crate::metadata::ConfigMetadata::from_bytes(self.covered)
+            .map(|(metadata, payload)| (Some(metadata), payload))
+            .ok_or(ConfigError::CorruptedHashSector)
+    }
+}
+
+/// Splits the data read from a binary file into the hash stored in the file and the data it covers.
 ///
-/// The first element of the tuple is the `xxh3_128` hash of the file, the second element is the `xxh3_128` hash of the data.
+/// The header is validated first. Files without the magic bytes were stored before the header was introduced and
+/// start with the hash directly, they are upgraded the next time they are stored.
 ///
-/// If the data is corrupted, the `xxh3_128` hash of the file and the `xxh3_128` hash of the data will not match.
-fn get_hash_from_file_and_data(data: &[u8]) -> (&[u8], Vec<u8>) {
-    // The first 64 bits (16 bytes) of the data will be the xxh3_128 hash of the data.
-    let binary_hash_from_file = &data[..HASH_BYTE_LENGTH];
+/// Returns [`ConfigError::TruncatedFile`] if the data is too short to contain the header and the hash.
+fn split_frame<'a>(hasher: &dyn Hasher, data: &'a [u8]) -> Result<Frame<'a>, ConfigError> {
+    let Some(framed) = data.strip_prefix(&MAGIC) else {
+        let (hash, covered) = split_hash(hasher, data)?;
+        return Ok(Frame {
+            hash,
+            covered,
+            flags: 0,
+        });
+    };
 
-    // The rest of the data will be the serialized data.
-    let binary_data_without_hash = &data[HASH_BYTE_LENGTH..];
+    let truncated = || ConfigError::TruncatedFile {
+        len: data.len(),
+        expected: HEADER_LENGTH + hasher.digest_length(),
+    };
+    let (header, framed) = framed
+        .split_at_checked(HEADER_LENGTH - MAGIC.len())
+        .ok_or_else(truncated)?;
+    let (flags, expected) = parse_header(hasher, header)?;
+    let (hash, covered) = framed
+        .split_at_checked(hasher.digest_length())
+        .ok_or_else(truncated)?;
+
+    let found = covered.len() as u64;
+    if found != expected {
+        return Err(ConfigError::PayloadLengthMismatch { expected, found });
+    }
 
-    let binary_hash_from_data = &xxh3_128(binary_data_without_hash).to_le_bytes()[..];
+    Ok(Frame {
+        hash,
+        covered,
+        flags,
+    })
+}
 
-    // The `xxh3_128` hash should be 64 bits (16 bytes) long. If it's not, something went wrong.
-    // This prevents a vec allocation with incorrect size.
-    assert!(binary_hash_from_data.len() == HASH_BYTE_LENGTH);
+/// Splits the data of a file stored without a header into the hash and the serialized data that follows it.
+fn split_hash<'a>(
+    hasher: &dyn Hasher,
+    data: &'a [u8],
+) -> Result<(&'a [u8], &'a [u8]), ConfigError> {
+    // The first bytes of the data will be the hash of the data, the rest will be the serialized data.
+    data.split_at_checked(hasher.digest_length())
+        .ok_or(ConfigError::TruncatedFile {
+            len: data.len(),
+            expected: hasher.digest_length(),
+        })
+}
 
-    (binary_hash_from_file, binary_hash_from_data.to_vec())
+/// Validates the header that follows the magic bytes, and returns the flags and the payload length it records.
+fn parse_header(hasher: &dyn Hasher, header: &[u8]) -> Result<(u8, u64), ConfigError> {
+    let header: [u8; HEADER_LENGTH - MAGIC.len()] =
+        header.try_into().map_err(|_| ConfigError::TruncatedFile {
+            len: header.len() + MAGIC.len(),
+            expected: HEADER_LENGTH,
+        })?;
+    let [version, algorithm_id, flags, payload_length @ ..] = header;
+
+    if version != FORMAT_VERSION || flags & !KNOWN_FLAGS != 0 {
+        return Err(ConfigError::UnsupportedFormatVersion(version));
+    }
+    if algorithm_id != hasher.algorithm_id() {
+        return Err(ConfigError::HashAlgorithmMismatch {
+            expected: hasher.algorithm_id(),
+            found: algorithm_id,
+        });
+    }
+
+    Ok((flags, u64::from_le_bytes(payload_length)))
+}
+
+/// Reads the header and the `xxh3_128` hash at the start of a binary file, leaving `reader` at the start of the data
+/// covered by the hash. Returns the hash and the header flags, to give to [`skip_metadata`].
+///
+/// Used by the APIs that stream a file instead of reading it whole. The payload length is not checked, as the data is
+/// not read here.
+pub(crate) fn read_hash(
+    reader: &mut impl std::io::Read,
+) -> Result<([u8; HASH_BYTE_LENGTH], u8), ConfigError> {
+    let mut magic = [0; MAGIC.len()];
+    reader
+        .read_exact(&mut magic)
+        .map_err(|_| ConfigError::CorruptedHashSector)?;
+
+    let mut hash = [0; HASH_BYTE_LENGTH];
+    let mut flags = 0;
+    if magic == MAGIC {
+        let mut header = [0; HEADER_LENGTH - MAGIC.len()];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| ConfigError::CorruptedHashSector)?;
+        (flags, _) = parse_header(&Xxh3Hasher, &header)?;
+        reader
+            .read_exact(&mut hash)
+            .map_err(|_| ConfigError::CorruptedHashSector)?;
+    } else {
+        // Stored without a header, the magic bytes were the start of the hash
+        let (start, rest) = hash.split_at_mut(MAGIC.len());
+        start.copy_from_slice(&magic);
+        reader
+            .read_exact(rest)
+            .map_err(|_| ConfigError::CorruptedHashSector)?;
+    }
+
+    Ok((hash, flags))
+}
+
+/// Skips the metadata at the start of the data covered by the hash, if the header `flags` returned by [`read_hash`]
+/// say there is one, leaving `reader` at the start of the serialized data.
+pub(crate) fn skip_metadata(flags: u8, reader: &mut impl std::io::Read) -> Result<(), ConfigError> {
+    if flags & FLAG_METADATA == 0 {
+        return Ok(());
+    }
+
+    let mut metadata = [0; crate::metadata::METADATA_LENGTH];
+    reader
+        .read_exact(&mut metadata)
+        .map_err(|_| ConfigError::CorruptedHashSector)?;
+    crate::metadata::ConfigMetadata::from_bytes(&metadata)
+        .ok_or(ConfigError::CorruptedHashSector)?;
+
+    Ok(())
+}
+
+/// Returns the error for a hash that does not match the data: [`ConfigError::ZeroedHeader`] if the hash sector
+/// was wiped, [`ConfigError::HashMismatch`] if it holds another hash, as after editing the file.
+fn hash_mismatch_error(binary_hash_from_file: &[u8]) -> ConfigError {
+    if binary_hash_from_file.iter().all(|byte| *byte == 0) {
+        ConfigError::ZeroedHeader {
+            len: binary_hash_from_file.len(),
+        }
+    } else {
+        ConfigError::HashMismatch
+    }
 }
 
 /// Prepares the data to be stored in a file.
 ///
-/// It will calculate the `xxh3_128` hash of the data and prepend it to the data.
+/// It will calculate the `xxh3_128` hash of the data and prepend it to the data, after a header.
 ///
-/// Returns the binary data with the hash prepended.
+/// Returns the binary data with the header and the hash prepended.
 ///
-/// The first `64 bits (16 bytes)` of the data will be the `xxh3_128` hash of the data, the rest of the data will be the serialized data.
-fn prepare_serialized_data<T>(data: T) -> Result<Vec<u8>, ConfigError>
+/// The header is the magic bytes `BCNF`, the format version, the id of the hash algorithm, the flags and the length of the
+/// serialized data as a little endian `u64`. It is followed by the `128 bits (16 bytes)` `xxh3_128` hash of the serialized data, and the serialized data.
+pub(crate) fn prepare_serialized_data<T>(data: T) -> Result<Vec<u8>, ConfigError>
+where
+    T: serde::Serialize,
+{
+    prepare_serialized_data_with(&Xxh3Hasher, data)
+}
+
+fn prepare_serialized_data_with<T>(hasher: &dyn Hasher, data: T) -> Result<Vec<u8>, ConfigError>
 where
     T: serde::Serialize,
 {
-    // Create a buffer with 16 bytes zeroed out, and append the serialized data to it.
-    let mut full_data = [vec![0; HASH_BYTE_LENGTH], bincode::serialize(&data)?].concat();
-    // Calculate the `xxh3_128` hash of the serialized data.
+    Ok(prepare_framed_data_with(
+        hasher,
+        0,
+        &[&bincode::serialize(&data)?],
+    ))
+}
 
-    let hash = &xxh3_128(&full_data[HASH_BYTE_LENGTH..]).to_le_bytes()[..];
+/// Prepares already serialized sections to be stored in a file.
+///
+/// Works like [`prepare_serialized_data`], concatenating the sections and prepending the header and the `xxh3_128` hash of all of them.
+pub(crate) fn prepare_framed_data(sections: &[&[u8]]) -> Vec<u8> {
+    prepare_framed_data_with(&Xxh3Hasher, 0, sections)
+}
 
-    // Prepend the `xxh3_128` hash to the binary data. If the hash length is not 16 bytes, this will panic. This should never happen as the `xxh3_128` hash is always 16 bytes.
-    // This function will panic if the two slices have different lengths.
-    full_data[..HASH_BYTE_LENGTH].clone_from_slice(hash);
+/// Prepares the metadata of [`crate::store_stamped`] and the serialized data to be stored in a file.
+///
+/// Works like [`prepare_framed_data`], flagging the metadata in the header so every reader can skip it.
+pub(crate) fn prepare_stamped_data(metadata: &[u8], data: &[u8]) -> Vec<u8> {
+    prepare_framed_data_with(&Xxh3Hasher, FLAG_METADATA, &[metadata, data])
+}
+
+fn prepare_framed_data_with(hasher: &dyn Hasher, flags: u8, sections: &[&[u8]]) -> Vec<u8> {
+    let payload = sections.concat();
 
-    Ok(full_data)
+    let mut full_data = Vec::with_capacity(HEADER_LENGTH + hasher.digest_length() + payload.len());
+    full_data.extend_from_slice(&MAGIC);
+    full_data.push(FORMAT_VERSION);
+    full_data.push(hasher.algorithm_id());
+    full_data.push(flags);
+    full_data.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    full_data.extend_from_slice(&hasher.digest(&payload));
+    full_data.extend_from_slice(&payload);
+
+    full_data
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::Seek;
+    use std::io::{Read, Seek};
+    use xxhash_rust::xxh3::xxh3_128;
 
     use super::*;
 
@@ -490,6 +1169,152 @@ mod tests {
         assert_eq!(config, data);
     }
 
+    #[test]
+    fn strict_rejects_trailing_data_bin() {
+        let data = TestConfig {
+            test: String::from("test"),
+            test_vec: vec![1, 2, 3, 4, 5],
+        };
+
+        // A payload with extra bytes after the serialized data, but with a valid hash
+        let mut payload = bincode::serialize(&data).unwrap();
+        payload.extend_from_slice(b"garbage");
+        let hash = xxh3_128(&payload).to_le_bytes();
+
+        let path = get_configuration_path(
+            "test-binconf-strict_rejects_trailing_data-bin",
+            None,
+            ConfigType::Bin,
+            Config,
+        )
+        .unwrap();
+        std::fs::write(&path, [&hash[..], &payload[..]].concat()).unwrap();
+
+        let config: TestConfig = load_bin(
+            "test-binconf-strict_rejects_trailing_data-bin",
+            None,
+            Config,
+            false,
+        )
+        .unwrap();
+        assert_eq!(config, data);
+
+        let config = load_bin_strict::<TestConfig>(
+            "test-binconf-strict_rejects_trailing_data-bin",
+            None,
+            Config,
+            false,
+        );
+        assert!(matches!(config, Err(ConfigError::TrailingData)));
+    }
+
+    #[test]
+    fn malformed_data_returns_errors_bin() {
+        let full_data = prepare_serialized_data(String::from("test")).unwrap();
+        assert_eq!(
+            verified_payload(&full_data).unwrap(),
+            &full_data[HEADER_LENGTH + HASH_BYTE_LENGTH..]
+        );
+
+        for length in 0..full_data.len() {
+            let truncated = &full_data[..length];
+            let err = deserialize_verified::<String>(truncated).unwrap_err();
+            assert!(err.is_corruption(), "length {length}: {err}");
+        }
+
+        // A valid hash over data that claims a huge string length
+        let payload = u64::MAX.to_le_bytes();
+        let malformed = prepare_framed_data(&[&payload]);
+        assert!(deserialize_verified::<String>(&malformed).is_err());
+    }
+
+    #[test]
+    fn custom_hasher_bin() {
+        struct Reversed;
+
+        impl Hasher for Reversed {
+            fn digest_length(&self) -> usize {
+                16
+            }
+
+            fn digest(&self, data: &[u8]) -> Vec<u8> {
+                let mut digest = Xxh3Hasher.digest(data);
+                digest.reverse();
+                digest
+            }
+        }
+
+        let data = TestConfig {
+            test: String::from("test"),
+            test_vec: vec![1, 2, 3, 4, 5],
+        };
+
+        store_bin_with_hasher(
+            "test-binconf-custom_hasher-bin",
+            None,
+            Config,
+            &data,
+            &Reversed,
+        )
+        .unwrap();
+
+        let config: TestConfig = load_bin_with_hasher(
+            "test-binconf-custom_hasher-bin",
+            None,
+            Config,
+            false,
+            &Reversed,
+        )
+        .unwrap();
+        assert_eq!(config, data);
+
+        let config = load_bin::<TestConfig>("test-binconf-custom_hasher-bin", None, Config, false);
+        assert!(matches!(
+            config,
+            Err(ConfigError::HashAlgorithmMismatch {
+                expected: 1,
+                found: 255
+            })
+        ));
+    }
+
+    #[test]
+    fn no_hasher_stores_the_header_and_payload_bin() {
+        let data = TestConfig {
+            test: String::from("test"),
+            test_vec: vec![1, 2, 3],
+        };
+
+        store_bin_with_hasher(
+            "test-binconf-no_hasher-bin",
+            None,
+            Config,
+            &data,
+            &crate::NoHasher,
+        )
+        .unwrap();
+
+        let path = crate::get_configuration_path(
+            "test-binconf-no_hasher-bin",
+            None,
+            ConfigType::Bin,
+            Config,
+        )
+        .unwrap();
+        let stored = std::fs::read(path).unwrap();
+        assert_eq!(&stored[HEADER_LENGTH..], bincode::serialize(&data).unwrap());
+
+        let config: TestConfig = load_bin_with_hasher(
+            "test-binconf-no_hasher-bin",
+            None,
+            Config,
+            false,
+            &crate::NoHasher,
+        )
+        .unwrap();
+        assert_eq!(config, data);
+    }
+
     #[test]
     fn load_config_fallback() {
         let data = String::from("test of corrupted data");
@@ -543,6 +1368,288 @@ mod tests {
         )
         .unwrap();
 
-        assert_eq!(corrupted_data, String::from_utf8_lossy(&new_data[24..]));
+        assert_eq!(
+            corrupted_data,
+            String::from_utf8_lossy(&new_data[HEADER_LENGTH + 24..])
+        );
+    }
+
+    #[test]
+    fn verify_modes_bin() {
+        let app = "test-binconf-verify_modes_bin";
+        let data = vec![1u8, 2, 3];
+        store_bin(app, None, Cache, &data).unwrap();
+
+        let load = |verify| load_bin_verified::<Vec<u8>>(app, None, Cache, false, verify);
+        assert_eq!(
+            load(VerifyMode::Strict).unwrap(),
+            (data.clone(), HashStatus::Valid)
+        );
+        assert_eq!(
+            load(VerifyMode::WarnOnly).unwrap(),
+            (data.clone(), HashStatus::Valid)
+        );
+        assert_eq!(
+            load(VerifyMode::Skip).unwrap(),
+            (data.clone(), HashStatus::Unchecked)
+        );
+
+        // Damage the hash, the data itself is intact
+        let path = get_configuration_path(app, None, ConfigType::Bin, Cache).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[HEADER_LENGTH] = !bytes[HEADER_LENGTH];
+        std::fs::write(&path, &bytes).unwrap();
+
+        assert!(matches!(
+            load(VerifyMode::Strict),
+            Err(ConfigError::HashMismatch)
+        ));
+        assert_eq!(
+            load(VerifyMode::WarnOnly).unwrap(),
+            (data.clone(), HashStatus::Mismatch)
+        );
+        assert_eq!(
+            load(VerifyMode::Skip).unwrap(),
+            (data, HashStatus::Unchecked)
+        );
+
+        // The file is not reset, even with `reset_conf_on_err`
+        let (_, status) =
+            load_bin_verified::<Vec<u8>>(app, None, Cache, true, VerifyMode::WarnOnly).unwrap();
+        assert_eq!(status, HashStatus::Mismatch);
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+    }
+
+    #[test]
+    fn rehash_bin_repairs_hash() {
+        let app = "test-binconf-rehash_bin_repairs_hash";
+        let data = TestConfig {
+            test: String::from("rehash"),
+            test_vec: vec![1, 2, 3],
+        };
+        store_bin(app, None, Cache, &data).unwrap();
+        assert!(!rehash_bin(app, None, Cache).unwrap());
+
+        let path = get_configuration_path(app, None, ConfigType::Bin, Cache).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[HEADER_LENGTH..HEADER_LENGTH + HASH_BYTE_LENGTH].fill(0);
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            load_bin::<TestConfig>(app, None, Cache, false),
+            Err(ConfigError::ZeroedHeader {
+                len: HASH_BYTE_LENGTH
+            })
+        ));
+
+        assert!(rehash_bin(app, None, Cache).unwrap());
+        assert_eq!(
+            load_bin::<TestConfig>(app, None, Cache, false).unwrap(),
+            data
+        );
+
+        std::fs::write(&path, [0u8; 4]).unwrap();
+        assert!(matches!(
+            rehash_bin(app, None, Cache),
+            Err(ConfigError::TruncatedFile {
+                len: 4,
+                expected: HASH_BYTE_LENGTH
+            })
+        ));
+    }
+
+    #[test]
+    fn header_corruption_is_diagnosed_bin() {
+        let app = "test-binconf-header_corruption_is_diagnosed_bin";
+        store_bin(app, None, Cache, vec![1u8, 2, 3]).unwrap();
+        let path = get_configuration_path(app, None, ConfigType::Bin, Cache).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        let load = || load_bin::<Vec<u8>>(app, None, Cache, false);
+
+        // Disk ran out mid-write
+        std::fs::write(&path, &bytes[..10]).unwrap();
+        assert!(matches!(
+            load(),
+            Err(ConfigError::TruncatedFile { len: 10, expected })
+                if expected == HEADER_LENGTH + HASH_BYTE_LENGTH
+        ));
+
+        // File edited
+        let mut edited = bytes.clone();
+        if let Some(last) = edited.last_mut() {
+            *last = 9;
+        }
+        std::fs::write(&path, &edited).unwrap();
+        assert!(matches!(load(), Err(ConfigError::HashMismatch)));
+
+        // Hash sector damaged
+        let mut zeroed = bytes;
+        zeroed[HEADER_LENGTH..HEADER_LENGTH + HASH_BYTE_LENGTH].fill(0);
+        std::fs::write(&path, &zeroed).unwrap();
+        assert!(matches!(
+            load(),
+            Err(ConfigError::ZeroedHeader {
+                len: HASH_BYTE_LENGTH
+            })
+        ));
+    }
+
+    #[test]
+    fn header_is_validated_bin() {
+        let app = "test-binconf-header_is_validated_bin";
+        store_bin(app, None, Cache, vec![1u8, 2, 3]).unwrap();
+        let path = get_configuration_path(app, None, ConfigType::Bin, Cache).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..MAGIC.len()], &MAGIC);
+        let load = || load_bin::<Vec<u8>>(app, None, Cache, false);
+
+        // Stored by a newer version
+        let mut newer = bytes.clone();
+        newer[MAGIC.len()] = FORMAT_VERSION + 1;
+        std::fs::write(&path, &newer).unwrap();
+        assert!(matches!(
+            load(),
+            Err(ConfigError::UnsupportedFormatVersion(version)) if version == FORMAT_VERSION + 1
+        ));
+
+        // Data appended to the file
+        let mut appended = bytes.clone();
+        appended.push(4);
+        std::fs::write(&path, &appended).unwrap();
+        let payload_length = (bytes.len() - HEADER_LENGTH - HASH_BYTE_LENGTH) as u64;
+        assert!(matches!(
+            load(),
+            Err(ConfigError::PayloadLengthMismatch { expected, found })
+                if expected == payload_length && found == payload_length + 1
+        ));
+
+        // Stored without a header by an older version
+        let legacy = bytes[HEADER_LENGTH..].to_vec();
+        std::fs::write(&path, &legacy).unwrap();
+        assert_eq!(load().unwrap(), vec![1, 2, 3]);
+        assert_eq!(
+            read_hash(&mut legacy.as_slice()).unwrap(),
+            read_hash(&mut bytes.as_slice()).unwrap()
+        );
+    }
+
+    #[test]
+    fn load_or_clones_the_fallback_only_when_needed_bin() {
+        static CLONES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+        // No `Default` implementation
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Lookup {
+            table: Vec<u32>,
+        }
+
+        impl Clone for Lookup {
+            fn clone(&self) -> Self {
+                CLONES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Lookup {
+                    table: self.table.clone(),
+                }
+            }
+        }
+
+        let app = "test-binconf-load_or_clones_the_fallback_only_when_needed_bin";
+        let path = get_configuration_path(app, None, ConfigType::Bin, Cache).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let fallback = Lookup {
+            table: (0..1024).collect(),
+        };
+
+        assert_eq!(
+            load_bin_or(app, None, Cache, false, &fallback).unwrap(),
+            fallback
+        );
+        assert_eq!(CLONES.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let stored = Lookup { table: vec![7] };
+        store_bin(app, None, Cache, &stored).unwrap();
+        assert_eq!(
+            load_bin_or(app, None, Cache, false, &fallback).unwrap(),
+            stored
+        );
+        assert_eq!(CLONES.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        std::fs::write(&path, [1u8, 2, 3]).unwrap();
+        assert_eq!(
+            load_bin_or(app, None, Cache, true, &fallback).unwrap(),
+            fallback
+        );
+        assert_eq!(CLONES.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn foreign_files_are_never_reset_bin() {
+        let app = "test-binconf-foreign_files_are_never_reset_bin";
+        store_bin(app, None, Cache, vec![1u8, 2, 3]).unwrap();
+        let path = get_configuration_path(app, None, ConfigType::Bin, Cache).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        let load = || load_bin::<Vec<u8>>(app, None, Cache, true);
+
+        // Stored by a newer version
+        let mut newer = bytes.clone();
+        newer[MAGIC.len()] = FORMAT_VERSION + 1;
+        std::fs::write(&path, &newer).unwrap();
+        assert!(matches!(
+            load(),
+            Err(ConfigError::UnsupportedFormatVersion(_))
+        ));
+        assert_eq!(std::fs::read(&path).unwrap(), newer);
+
+        // Stored with another hasher
+        let mut other_hasher = bytes.clone();
+        other_hasher[MAGIC.len() + 1] = 255;
+        std::fs::write(&path, &other_hasher).unwrap();
+        assert!(matches!(
+            load(),
+            Err(ConfigError::HashAlgorithmMismatch {
+                expected: 1,
+                found: 255
+            })
+        ));
+        assert_eq!(std::fs::read(&path).unwrap(), other_hasher);
+
+        // Truly damaged files are still reset
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(load().unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn payloads_that_look_like_metadata_are_kept_bin() {
+        #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+        struct Tagged {
+            tag: [u8; 8],
+            values: [u64; 3],
+            rest: Vec<u8>,
+        }
+
+        let data = Tagged {
+            tag: *b"BCNFMETA",
+            values: [1, 2, 3],
+            rest: vec![4, 5],
+        };
+
+        let app = "test-binconf-payloads_that_look_like_metadata_are_kept_bin";
+        store_bin(app, None, Cache, &data).unwrap();
+        assert_eq!(load_bin::<Tagged>(app, None, Cache, false).unwrap(), data);
+    }
+
+    #[test]
+    fn unknown_header_flags_are_rejected_bin() {
+        let app = "test-binconf-unknown_header_flags_are_rejected_bin";
+        store_bin(app, None, Cache, 1u32).unwrap();
+        let path = get_configuration_path(app, None, ConfigType::Bin, Cache).unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[MAGIC.len() + 2] |= 0x80;
+        std::fs::write(&path, &bytes).unwrap();
+
+        assert!(matches!(
+            load_bin::<u32>(app, None, Cache, true),
+            Err(ConfigError::UnsupportedFormatVersion(FORMAT_VERSION))
+        ));
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
     }
 }
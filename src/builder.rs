@@ -0,0 +1,190 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::PathBuf;
+
+/// Starts a [`ConfigBuilder`] for `app_name`, an alternative to the positional arguments of the `load` and `store` functions.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+///
+/// let builder = binconf::builder("test-binconf-builder")
+///     .name("settings.bin")
+///     .location(Config)
+///     .format(ConfigType::Bin)
+///     .reset_on_error(true);
+///
+/// builder.store(vec![1u8, 2, 3]).unwrap();
+/// assert_eq!(builder.load::<Vec<u8>>().unwrap(), vec![1, 2, 3]);
+/// ```
+pub fn builder(app_name: impl Into<String>) -> ConfigBuilder {
+    ConfigBuilder {
+        app_name: app_name.into(),
+        config_name: None,
+        location: ConfigLocation::Config,
+        format: None,
+        reset_on_error: false,
+    }
+}
+
+/// Where and how a config is loaded and stored, built with [`builder`].
+///
+/// Loading and storing call the `load` and `store` functions of the format, like [`crate::load_bin`] and [`crate::store_bin`].
+/// The builder can be kept and reused, every call reads the file again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigBuilder {
+    app_name: String,
+    config_name: Option<String>,
+    location: ConfigLocation,
+    format: Option<ConfigType>,
+    reset_on_error: bool,
+}
+
+impl ConfigBuilder {
+    /// Sets the name of the config file, `<app>.<extension>` by default.
+    #[must_use]
+    pub fn name(mut self, config_name: impl Into<String>) -> Self {
+        self.config_name = Some(config_name.into());
+        self
+    }
+
+    /// Sets the location of the config file, [`ConfigLocation::Config`] by default.
+    #[must_use]
+    pub fn location(mut self, location: ConfigLocation) -> Self {
+        self.location = location;
+        self
+    }
+
+    /// Sets the format of the config file.
+    ///
+    /// By default it is guessed from the extension of the [`name`](ConfigBuilder::name), and is
+    /// [`ConfigType::Bin`] for configs without a name.
+    #[must_use]
+    pub fn format(mut self, format: ConfigType) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Resets the config file to the default config when it can't be deserialized, instead of returning an error.
+    /// See the `reset_conf_on_err` flag of the `load` functions. Disabled by default.
+    #[must_use]
+    pub fn reset_on_error(mut self, reset_on_error: bool) -> Self {
+        self.reset_on_error = reset_on_error;
+        self
+    }
+
+    /// Loads the config, with the `load` function of its format.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the errors of the `load` function, or a [`ConfigError::UnsupportedFormat`] error if
+    /// no format was set and none could be guessed.
+    pub fn load<T>(&self) -> Result<T, ConfigError>
+    where
+        T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        crate::load_typed(
+            &self.app_name,
+            self.config_name.as_deref(),
+            &self.config_type()?,
+            &self.location,
+            self.reset_on_error,
+        )
+    }
+
+    /// Stores the config, with the `store` function of its format.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the errors of the `store` function, or a [`ConfigError::UnsupportedFormat`] error if
+    /// no format was set and none could be guessed.
+    pub fn store<T>(&self, data: T) -> Result<(), ConfigError>
+    where
+        T: serde::Serialize,
+    {
+        crate::store_typed(
+            &self.app_name,
+            self.config_name.as_deref(),
+            &self.config_type()?,
+            &self.location,
+            data,
+        )
+    }
+
+    /// Returns the path of the config file. See [`crate::get_configuration_path`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config directory could not be found or created, or a
+    /// [`ConfigError::UnsupportedFormat`] error if no format was set and none could be guessed.
+    pub fn path(&self) -> Result<PathBuf, ConfigError> {
+        crate::get_configuration_path(
+            &self.app_name,
+            self.config_name.as_deref(),
+            self.config_type()?,
+            &self.location,
+        )
+    }
+
+    fn config_type(&self) -> Result<ConfigType, ConfigError> {
+        if let Some(format) = self.format {
+            return Ok(format);
+        }
+
+        match &self.config_name {
+            Some(config_name) => crate::auto::type_from_name(config_name),
+
+            #[cfg(feature = "binary-conf")]
+            None => Ok(ConfigType::Bin),
+
+            #[cfg(not(feature = "binary-conf"))]
+            None => Err(ConfigError::UnsupportedFormat(String::new())),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Cache;
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Settings {
+        name: String,
+    }
+
+    #[test]
+    fn builder_guesses_the_format_from_the_name() {
+        let app = "test-binconf-builder_guesses_the_format_from_the_name";
+        let settings = Settings {
+            name: String::from("builder"),
+        };
+
+        let builder = builder(app).name("settings.yaml").location(Cache);
+        builder.store(&settings).unwrap();
+        assert_eq!(builder.load::<Settings>().unwrap(), settings);
+        assert_eq!(
+            builder.path().unwrap(),
+            crate::get_configuration_path(app, "settings.yaml", ConfigType::Yaml, Cache).unwrap()
+        );
+        assert_eq!(
+            crate::load_yaml::<Settings>(app, "settings.yaml", Cache, false).unwrap(),
+            settings
+        );
+
+        let unknown = self::builder(app).name("settings.ini").location(Cache);
+        assert!(matches!(
+            unknown.load::<Settings>(),
+            Err(ConfigError::UnsupportedFormat(extension)) if extension == "ini"
+        ));
+
+        let default = self::builder(app).location(Cache);
+        assert_eq!(
+            default.path().unwrap(),
+            crate::get_configuration_path(app, None, ConfigType::Bin, Cache).unwrap()
+        );
+    }
+}
@@ -0,0 +1,362 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use xxhash_rust::xxh3::xxh3_128;
+
+/// Describes the content of a config bundle created by [`create_bundle`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Manifest {
+    pub app_name: String,
+    pub app_version: Option<String>,
+    pub files: Vec<BundleFile>,
+}
+
+/// A config file stored inside a bundle.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BundleFile {
+    pub file_name: String,
+    /// The extension of the config type of the file, as returned by [`ConfigType::as_str`].
+    pub format: String,
+    /// The `xxh3_128` hash of the file content.
+    pub hash: u128,
+}
+
+/// On-disk layout of a bundle. `contents` is parallel to `manifest.files`.
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    manifest: Manifest,
+    contents: Vec<Vec<u8>>,
+}
+
+/// What to do when a file from a bundle already exists in the app directory.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ConflictPolicy {
+    /// Keep the existing file.
+    Skip,
+    /// Replace the existing file with the one from the bundle.
+    Overwrite,
+    /// Deep merge the file from the bundle over the existing one. Binary files can't be merged and are skipped.
+    Merge,
+}
+
+/// The outcome of [`apply_bundle`], listing the file names by what happened to them.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct BundleReport {
+    pub restored: Vec<String>,
+    pub merged: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+/// Creates a bundle with every config file of an app, from the config, cache, cwd, or local data directory of the current user.
+///
+/// The bundle is a single binary file holding a [`Manifest`] (app name, app version, file names, formats and hashes) and the
+/// content of every file. Each file is verified before being added, like [`crate::export_raw`] does. The bundle itself is protected by a `xxh3_128` hash.
+///
+/// Returns the manifest of the created bundle.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if the bundle could not be written.
+///
+/// If config files are corrupted, a [`ConfigError::Multiple`] error listing every one of them is returned and no bundle is created.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConflictPolicy;
+///
+/// binconf::store_bin("test-binconf-bundle", None, Config, &String::from("bundled")).unwrap();
+///
+/// let destination = std::env::temp_dir().join("test-binconf-bundle.bundle");
+/// let manifest = binconf::create_bundle("test-binconf-bundle", Config, Some("1.0.0"), &destination).unwrap();
+/// assert_eq!(manifest.files.len(), 1);
+///
+/// let report = binconf::apply_bundle("test-binconf-bundle-restored", Config, &destination, ConflictPolicy::Overwrite, None).unwrap();
+/// assert_eq!(report.restored, vec![String::from("test-binconf-bundle.bin")]);
+/// ```
+pub fn create_bundle(
+    app_name: impl AsRef<str>,
+    location: impl AsRef<ConfigLocation>,
+    app_version: Option<&str>,
+    destination: impl AsRef<Path>,
+) -> Result<Manifest, ConfigError> {
+    let app_dir = crate::app_dir(app_name.as_ref(), location.as_ref())?;
+
+    let mut entries = std::fs::read_dir(&app_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    let mut files = Vec::new();
+    let mut contents = Vec::new();
+    let mut failures = Vec::new();
+
+    for path in entries {
+        crate::cancel::check()?;
+        let Some(config_type) = path
+            .extension()
+            .and_then(|extension| ConfigType::from_extension(&extension.to_string_lossy()))
+        else {
+            continue;
+        };
+        if !path.is_file() {
+            continue;
+        }
+
+        let data = match crate::progress::read(&path).and_then(|data| {
+            crate::transfer::validate_raw(&data, &config_type)?;
+            Ok(data)
+        }) {
+            Ok(data) => data,
+            Err(err) => {
+                failures.push((path, err));
+                continue;
+            }
+        };
+
+        files.push(BundleFile {
+            file_name: path
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            format: config_type.as_str().to_owned(),
+            hash: xxh3_128(&data),
+        });
+        contents.push(data);
+    }
+    ConfigError::from_failures(failures)?;
+
+    let manifest = Manifest {
+        app_name: app_name.as_ref().to_owned(),
+        app_version: app_version.map(str::to_owned),
+        files,
+    };
+
+    let bundle = Bundle { manifest, contents };
+    let full_data = crate::binary_conf::prepare_serialized_data(&bundle)?;
+    crate::save_config_atomic(destination.as_ref(), &full_data)?;
+
+    Ok(bundle.manifest)
+}
+
+/// Restores the files of a bundle created by [`create_bundle`] into the app directory, in the config, cache, cwd, or local data directory of the current user.
+///
+/// If `only` is set, just the files with these names are restored. Files that already exist are handled according to `policy`.
+/// Every file is verified against the hash recorded in the manifest before any of them is written.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if the bundle could not be read,
+/// or if a file could not be written.
+///
+/// If the bundle is corrupted a [`ConfigError::HashMismatch`] error is returned. If files inside it are corrupted, a [`ConfigError::Multiple`]
+/// error listing every one of them is returned and nothing is written.
+pub fn apply_bundle(
+    app_name: impl AsRef<str>,
+    location: impl AsRef<ConfigLocation>,
+    source: impl AsRef<Path>,
+    policy: ConflictPolicy,
+    only: Option<&[&str]>,
+) -> Result<BundleReport, ConfigError> {
+    let app_dir = crate::app_dir(app_name.as_ref(), location.as_ref())?;
+
+    let data = crate::progress::read(source)?;
+    let bundle: Bundle = crate::binary_conf::deserialize_verified(&data)?;
+
+    let mut report = BundleReport::default();
+    let mut verified = Vec::new();
+    let mut failures = Vec::new();
+
+    for (file, content) in bundle.manifest.files.into_iter().zip(bundle.contents) {
+        if only.is_some_and(|only| !only.contains(&file.file_name.as_str())) {
+            continue;
+        }
+
+        // Never let a bundle write outside of the app directory
+        if Path::new(&file.file_name).file_name() != Some(file.file_name.as_ref()) {
+            report.skipped.push(file.file_name);
+            continue;
+        }
+
+        let Some(config_type) = ConfigType::from_extension(&file.format) else {
+            report.skipped.push(file.file_name);
+            continue;
+        };
+
+        let config_file_path = app_dir.join(&file.file_name);
+        let verification = if xxh3_128(&content) == file.hash {
+            crate::transfer::validate_raw(&content, &config_type)
+        } else {
+            Err(ConfigError::HashMismatch)
+        };
+        match verification {
+            Ok(()) => verified.push((file.file_name, config_type, config_file_path, content)),
+            Err(err) => failures.push((config_file_path, err)),
+        }
+    }
+    ConfigError::from_failures(failures)?;
+
+    for (file_name, config_type, config_file_path, content) in verified {
+        crate::cancel::check()?;
+
+        if !config_file_path.try_exists()? {
+            crate::save_config_atomic(&config_file_path, &content)?;
+            report.restored.push(file_name);
+            continue;
+        }
+
+        match policy {
+            ConflictPolicy::Skip => report.skipped.push(file_name),
+            ConflictPolicy::Overwrite => {
+                crate::save_config_atomic(&config_file_path, &content)?;
+                report.restored.push(file_name);
+            }
+            ConflictPolicy::Merge => {
+                #[cfg(any(
+                    feature = "toml-conf",
+                    feature = "json-conf",
+                    feature = "yaml-conf",
+                    feature = "ron-conf"
+                ))]
+                if !matches!(config_type, ConfigType::Bin) {
+                    let existing = std::fs::read_to_string(&config_file_path)?;
+                    let overlay = String::from_utf8_lossy(&content);
+                    let merged = crate::merge::merge_documents(&existing, &overlay, &config_type)?;
+                    crate::save_config_atomic(&config_file_path, merged.as_bytes())?;
+                    report.merged.push(file_name);
+                    continue;
+                }
+                #[cfg(not(any(
+                    feature = "toml-conf",
+                    feature = "json-conf",
+                    feature = "yaml-conf",
+                    feature = "ron-conf"
+                )))]
+                let _ = config_type;
+
+                report.skipped.push(file_name);
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[test]
+    fn apply_bundle_policies() {
+        crate::store_bin("test-binconf-bundle_policies-source", None, Config, 1u8).unwrap();
+        std::fs::write(
+            crate::get_configuration_path(
+                "test-binconf-bundle_policies-source",
+                None,
+                ConfigType::Toml,
+                Config,
+            )
+            .unwrap(),
+            "[ui]\ntheme = \"dark\"\n",
+        )
+        .unwrap();
+
+        let bundle_path = std::env::temp_dir().join("test-binconf-bundle_policies.bundle");
+        let manifest = create_bundle(
+            "test-binconf-bundle_policies-source",
+            Config,
+            None,
+            &bundle_path,
+        )
+        .unwrap();
+        assert_eq!(manifest.files.len(), 2);
+
+        let target = "test-binconf-bundle_policies-target";
+        crate::store_bin(
+            target,
+            Some("test-binconf-bundle_policies-source.bin"),
+            Config,
+            2u8,
+        )
+        .unwrap();
+        std::fs::write(
+            crate::get_configuration_path(
+                target,
+                Some("test-binconf-bundle_policies-source.toml"),
+                ConfigType::Toml,
+                Config,
+            )
+            .unwrap(),
+            "[ui]\ntheme = \"light\"\nsize = 3\n",
+        )
+        .unwrap();
+
+        let report =
+            apply_bundle(target, Config, &bundle_path, ConflictPolicy::Skip, None).unwrap();
+        assert_eq!(report.skipped.len(), 2);
+
+        let report =
+            apply_bundle(target, Config, &bundle_path, ConflictPolicy::Merge, None).unwrap();
+        assert_eq!(
+            report.merged,
+            vec![String::from("test-binconf-bundle_policies-source.toml")]
+        );
+        assert_eq!(
+            report.skipped,
+            vec![String::from("test-binconf-bundle_policies-source.bin")]
+        );
+
+        let merged: toml::Table = crate::load_toml(
+            target,
+            Some("test-binconf-bundle_policies-source.toml"),
+            Config,
+            false,
+        )
+        .unwrap();
+        assert_eq!(merged["ui"]["theme"].as_str(), Some("dark"));
+        assert_eq!(merged["ui"]["size"].as_integer(), Some(3));
+
+        let report = apply_bundle(
+            target,
+            Config,
+            &bundle_path,
+            ConflictPolicy::Overwrite,
+            Some(&["test-binconf-bundle_policies-source.bin"]),
+        )
+        .unwrap();
+        assert_eq!(report.restored.len(), 1);
+        let restored: u8 = crate::load_bin(
+            target,
+            Some("test-binconf-bundle_policies-source.bin"),
+            Config,
+            false,
+        )
+        .unwrap();
+        assert_eq!(restored, 1);
+    }
+
+    #[test]
+    fn corrupted_files_are_all_reported() {
+        let app = "test-binconf-corrupted_files_are_all_reported";
+        let first =
+            crate::get_configuration_path(app, Some("first.toml"), ConfigType::Toml, Config)
+                .unwrap();
+        let second =
+            crate::get_configuration_path(app, Some("second.toml"), ConfigType::Toml, Config)
+                .unwrap();
+        std::fs::write(&first, "a = ").unwrap();
+        std::fs::write(&second, "[b").unwrap();
+
+        let bundle_path = std::env::temp_dir().join("test-binconf-corrupted_files.bundle");
+        let Err(ConfigError::Multiple(failures)) = create_bundle(app, Config, None, &bundle_path)
+        else {
+            panic!("both files should be reported");
+        };
+        let paths: Vec<_> = failures.into_iter().map(|(path, _)| path).collect();
+        assert_eq!(paths, vec![first, second]);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::ConfigError;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+thread_local! {
+    /// The tokens set with [`with_cancellation`] on this thread.
+    static TOKENS: RefCell<Vec<CancellationToken>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Cancels the config operations running inside [`with_cancellation`], from any thread.
+///
+/// Clones share the same state: keep one to call [`cancel`](CancellationToken::cancel), for example when the user
+/// quits, and pass another one to the thread doing the operations.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the operations watching this token. There is no way to undo it.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Removes the token pushed by [`with_cancellation`], even if its closure panics.
+struct TokenGuard;
+
+impl Drop for TokenGuard {
+    fn drop(&mut self) {
+        TOKENS.with(|tokens| tokens.borrow_mut().pop());
+    }
+}
+
+/// Runs `f` with the config operations of the current thread watching `token`.
+///
+/// Once the token is cancelled, the `load` functions and the exports and bundles return a [`ConfigError::Cancelled`]
+/// error instead of starting to read a file, and stop reading big files between two chunks of 1 MiB. Writes are never
+/// interrupted halfway, a config file is either written completely or not at all. Cancelling a `load` never resets
+/// the config.
+///
+/// Tokens nest, the operations stop when any of them is cancelled. They are only watched on the current thread.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{CancellationToken, ConfigError};
+///
+/// binconf::store_bin("test-binconf-with-cancellation", None, Cache, vec![0u8; 1024]).unwrap();
+///
+/// let token = CancellationToken::new();
+/// let on_quit = token.clone();
+///
+/// binconf::with_cancellation(&token, || {
+///     // The user quits while the state is loading
+///     on_quit.cancel();
+///     let state = binconf::load_bin::<Vec<u8>>("test-binconf-with-cancellation", None, Cache, true);
+///     assert!(matches!(state, Err(ConfigError::Cancelled)));
+/// });
+/// ```
+pub fn with_cancellation<R>(token: &CancellationToken, f: impl FnOnce() -> R) -> R {
+    TOKENS.with(|tokens| tokens.borrow_mut().push(token.clone()));
+    let _guard = TokenGuard;
+
+    f()
+}
+
+/// Returns a [`ConfigError::Cancelled`] error if a token watched by the current thread was cancelled.
+pub(crate) fn check() -> Result<(), ConfigError> {
+    let cancelled =
+        TOKENS.with(|tokens| tokens.borrow().iter().any(CancellationToken::is_cancelled));
+    if cancelled {
+        return Err(ConfigError::Cancelled);
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if the current thread watches a token, so big files are read in chunks.
+pub(crate) fn is_watched() -> bool {
+    TOKENS.with(|tokens| !tokens.borrow().is_empty())
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use crate::ConfigLocation::Cache;
+
+    #[test]
+    fn cancelled_loads_leave_the_config_untouched() {
+        let app = "test-binconf-cancelled_loads_leave_the_config_untouched";
+        crate::store_toml(
+            app,
+            None,
+            Cache,
+            std::collections::HashMap::from([("a", 1)]),
+        )
+        .unwrap();
+        let path =
+            crate::get_configuration_path(app, None, crate::ConfigType::Toml, Cache).unwrap();
+        let stored = std::fs::read(&path).unwrap();
+
+        let outer = CancellationToken::new();
+        let inner = CancellationToken::new();
+        with_cancellation(&outer, || {
+            with_cancellation(&inner, || assert!(check().is_ok()));
+
+            outer.cancel();
+            let loaded =
+                crate::load_toml::<std::collections::HashMap<String, u32>>(app, None, Cache, true);
+            assert!(matches!(loaded, Err(ConfigError::Cancelled)));
+        });
+
+        assert!(!is_watched());
+        assert_eq!(std::fs::read(&path).unwrap(), stored);
+        assert!(crate::load_toml::<std::collections::HashMap<String, u32>>(
+            app, None, Cache, false
+        )
+        .is_ok());
+    }
+}
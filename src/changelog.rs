@@ -0,0 +1,317 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Maximum number of changes kept per config. `None` disables the change log.
+static MAX_ENTRIES: Mutex<Option<usize>> = Mutex::new(None);
+
+/// Extension appended to the file name of a config to get its change log file.
+const CHANGELOG_EXTENSION: &str = "changes";
+
+/// A key of a config whose value changed in a store, returned by [`change_history`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Dotted path of the key, empty if the config is not a struct or a map.
+    pub key: String,
+    /// The previous value, as `json`. `None` if the key was added.
+    pub old: Option<String>,
+    /// The new value, as `json`. `None` if the key was removed.
+    pub new: Option<String>,
+    pub changed_at: SystemTime,
+    /// Id of the process that stored the config.
+    pub pid: u32,
+}
+
+/// The change log file: the values of the last stored config, to diff the next one against, and the changes so far.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ChangeLog {
+    snapshot: BTreeMap<String, String>,
+    entries: Vec<Entry>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Entry {
+    key: String,
+    old: Option<String>,
+    new: Option<String>,
+    changed_at: u64,
+    pid: u32,
+}
+
+/// Enables or disables the change log, keeping up to `max_entries` changes per config.
+///
+/// When enabled, every `store` function compares the stored config with the previous one, key by key, and appends the
+/// differences to a `<config file>.changes` file next to it. Read them with [`change_history`]. The first store of a config
+/// only records its values, to compare the next stores against. Once `max_entries` is reached the oldest changes are dropped.
+///
+/// The change log is disabled by default.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// binconf::set_change_log(Some(100));
+///
+/// binconf::store_bin("test-binconf-set-change-log", None, Config, 1u32).unwrap();
+///
+/// binconf::set_change_log(None);
+/// ```
+pub fn set_change_log(max_entries: Option<usize>) {
+    *MAX_ENTRIES
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = max_entries;
+}
+
+/// Returns `true` if the change log is enabled, for the stores that need to keep the config around to record it later.
+#[cfg(feature = "binary-conf")]
+pub(crate) fn is_enabled() -> bool {
+    MAX_ENTRIES
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .is_some()
+}
+
+/// Loads the changes recorded by the change log for a config, oldest first.
+///
+/// Returns an empty list if the change log was never enabled while storing the config.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created,
+/// or if the change log file could not be read or deserialized.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+/// use std::collections::HashMap;
+///
+/// binconf::set_change_log(Some(100));
+///
+/// binconf::store_json("test-binconf-change-history", None, Config, HashMap::from([("theme", "light")])).unwrap();
+/// binconf::store_json("test-binconf-change-history", None, Config, HashMap::from([("theme", "dark")])).unwrap();
+///
+/// binconf::set_change_log(None);
+///
+/// let changes = binconf::change_history("test-binconf-change-history", None, ConfigType::Json, Config).unwrap();
+/// let change = changes.last().unwrap();
+/// assert_eq!(change.key, "theme");
+/// assert_eq!(change.old.as_deref(), Some("\"light\""));
+/// assert_eq!(change.new.as_deref(), Some("\"dark\""));
+/// ```
+pub fn change_history<'a>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: impl AsRef<ConfigType>,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<Vec<Change>, ConfigError> {
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        config_type.as_ref().as_str(),
+        location.as_ref(),
+    )?;
+
+    let log = match std::fs::read(changelog_path(&config_file_path)) {
+        Ok(data) => serde_json::from_slice::<ChangeLog>(&data)?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(log
+        .entries
+        .into_iter()
+        .map(|entry| Change {
+            key: entry.key,
+            old: entry.old,
+            new: entry.new,
+            changed_at: UNIX_EPOCH + Duration::from_millis(entry.changed_at),
+            pid: entry.pid,
+        })
+        .collect())
+}
+
+/// Records the differences between `data`, just stored at `config_file_path`, and the previously stored config.
+pub(crate) fn record<T>(config_file_path: &Path, data: &T) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let Some(max_entries) = *MAX_ENTRIES
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+    else {
+        return Ok(());
+    };
+
+    let log_path = changelog_path(config_file_path);
+    // A damaged log only means the history is lost, a new one is started from this store
+    let previous = std::fs::read(&log_path)
+        .ok()
+        .and_then(|data| serde_json::from_slice::<ChangeLog>(&data).ok());
+
+    // Configs `json` can't represent, like maps with non string keys, are not logged
+    let Ok(value) = serde_json::to_value(data) else {
+        return Ok(());
+    };
+    let mut snapshot = BTreeMap::new();
+    flatten(value, String::new(), &mut snapshot);
+
+    let log = match previous {
+        Some(mut log) => {
+            let changed_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis() as u64);
+            let entry = |key: &String, old: Option<&String>, new: Option<&String>| Entry {
+                key: key.clone(),
+                old: old.cloned(),
+                new: new.cloned(),
+                changed_at,
+                pid: std::process::id(),
+            };
+
+            for (key, new) in &snapshot {
+                match log.snapshot.get(key) {
+                    Some(old) if old == new => {}
+                    old => log.entries.push(entry(key, old, Some(new))),
+                }
+            }
+            for (key, old) in &log.snapshot {
+                if !snapshot.contains_key(key) {
+                    log.entries.push(entry(key, Some(old), None));
+                }
+            }
+
+            let excess = log.entries.len().saturating_sub(max_entries);
+            log.entries.drain(..excess);
+            log.snapshot = snapshot;
+            log
+        }
+        None => ChangeLog {
+            snapshot,
+            entries: Vec::new(),
+        },
+    };
+
+    crate::save_config_atomic(&log_path, &serde_json::to_vec(&log)?)
+}
+
+/// Flattens the objects of `value` into dotted paths, anything else is a single value.
+pub(crate) fn flatten(
+    value: serde_json::Value,
+    path: String,
+    values: &mut BTreeMap<String, String>,
+) {
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let path = if path.is_empty() {
+                    key
+                } else {
+                    format!("{path}.{key}")
+                };
+                flatten(value, path, values);
+            }
+        }
+        value => {
+            values.insert(path, value.to_string());
+        }
+    }
+}
+
+fn changelog_path(config_file_path: &Path) -> PathBuf {
+    let mut file_name = config_file_path
+        .file_name()
+        .map(std::ffi::OsStr::to_os_string)
+        .unwrap_or_default();
+    file_name.push(".");
+    file_name.push(CHANGELOG_EXTENSION);
+    config_file_path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[derive(serde::Serialize)]
+    struct Settings {
+        name: String,
+        ui: Ui,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Ui {
+        theme: String,
+        size: Option<u8>,
+    }
+
+    #[test]
+    fn records_key_level_changes() {
+        let app = "test-binconf-records_key_level_changes";
+        let path = crate::get_configuration_path(app, None, ConfigType::Toml, Config).unwrap();
+        let settings = |theme: &str, size| Settings {
+            name: String::from("app"),
+            ui: Ui {
+                theme: String::from(theme),
+                size,
+            },
+        };
+
+        // Other tests store configs concurrently, record directly instead of enabling the change log for everyone
+        let record_with = |settings: &Settings| {
+            set_change_log(Some(3));
+            let result = record(&path, settings);
+            set_change_log(None);
+            result.unwrap();
+        };
+
+        record_with(&settings("light", None));
+        assert!(change_history(app, None, ConfigType::Toml, Config)
+            .unwrap()
+            .is_empty());
+
+        record_with(&settings("dark", Some(12)));
+        record_with(&settings("dark", Some(14)));
+
+        let changes: Vec<_> = change_history(app, None, ConfigType::Toml, Config)
+            .unwrap()
+            .into_iter()
+            .map(|change| (change.key, change.old, change.new))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (
+                    String::from("ui.size"),
+                    Some(String::from("null")),
+                    Some(String::from("12"))
+                ),
+                (
+                    String::from("ui.theme"),
+                    Some(String::from("\"light\"")),
+                    Some(String::from("\"dark\""))
+                ),
+                (
+                    String::from("ui.size"),
+                    Some(String::from("12")),
+                    Some(String::from("14"))
+                ),
+            ]
+        );
+
+        // Only the 3 most recent changes are kept
+        record_with(&settings("light", Some(14)));
+        let changes = change_history(app, None, ConfigType::Toml, Config).unwrap();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(
+            changes.first().map(|change| change.key.as_str()),
+            Some("ui.theme")
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use crate::{ConfigError, ConfigLocation};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// Marker in the name of the copies of corrupted config files.
+pub(crate) const CORRUPT_MARKER: &str = ".corrupt-";
+
+/// Retention rules used by [`cleanup`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CleanupPolicy {
+    /// Temporary files and staging directories younger than this are left alone, as they may belong to a write in progress.
+    pub temp_max_age: Duration,
+    /// How many copies of corrupted files (`<name>.corrupt-<timestamp>`) to keep per config file.
+    pub keep_corrupt: usize,
+}
+
+impl Default for CleanupPolicy {
+    fn default() -> Self {
+        CleanupPolicy {
+            temp_max_age: Duration::from_secs(60 * 60),
+            keep_corrupt: 3,
+        }
+    }
+}
+
+/// The files and directories removed by [`cleanup`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CleanupReport {
+    pub removed: Vec<PathBuf>,
+}
+
+/// Removes leftovers of crashed writes and trims old copies in the app directory, in the config, cache, cwd, or local data directory of the current user.
+///
+/// The subdirectories of the [`crate::LayoutPolicy`] of the app are cleaned as well, and in the current directory the leftovers of its dot files.
+///
+/// - Temporary files (`*.tmp`, and `*.<pid>-<n>.new` of the `store_*_new` functions) and profile staging directories (`.<profile>.staging`, `.<profile>.old`) older than [`CleanupPolicy::temp_max_age`] are removed.
+///   A `.<profile>.old` directory is the only copy of its profile when a swap of [`crate::replace_profile`] was interrupted, it is put back in
+///   place instead if the profile is missing.
+/// - Copies of corrupted files are grouped by the config file they belong to, only the newest ones are kept.
+///
+/// Config files themselves are never touched, so it is safe to run at app startup.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if a file could not be removed.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::CleanupPolicy;
+///
+/// let report = binconf::cleanup("test-binconf-cleanup", Config, &CleanupPolicy::default()).unwrap();
+/// println!("Removed {} stale files", report.removed.len());
+/// ```
+pub fn cleanup(
+    app_name: impl AsRef<str>,
+    location: impl AsRef<ConfigLocation>,
+    policy: &CleanupPolicy,
+) -> Result<CleanupReport, ConfigError> {
+    let app_name = app_name.as_ref();
+    let location = location.as_ref();
+    let app_dir = crate::app_dir(app_name, location)?;
+    let now = SystemTime::now();
+
+    let mut report = CleanupReport::default();
+    // Copies grouped by the path of their config file, with their timestamp suffix
+    let mut copies: BTreeMap<PathBuf, Vec<(String, PathBuf)>> = BTreeMap::new();
+
+    for (dir, owned_files) in scanned_dirs(app_name, location, app_dir)? {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let is_dir = entry.file_type()?.is_dir();
+
+            // A directory shared with other files, only the leftovers of the files of the app are touched
+            if let Some(owned_files) = &owned_files {
+                let is_owned = !is_dir
+                    && owned_files.iter().any(|owned| {
+                        file_name
+                            .strip_prefix(owned.as_str())
+                            .is_some_and(|rest| rest.starts_with('.'))
+                    });
+                if !is_owned {
+                    continue;
+                }
+            }
+
+            if is_dir {
+                if let Some(profile) = file_name
+                    .strip_prefix('.')
+                    .and_then(|file_name| file_name.strip_suffix(".old"))
+                {
+                    let profile_dir = dir.join(profile);
+                    if !profile_dir.try_exists()? {
+                        crate::profile::restore_interrupted_swap(&profile_dir, &path)?;
+                        continue;
+                    }
+                }
+            }
+
+            let is_temp = if is_dir {
+                file_name.starts_with('.')
+                    && (file_name.ends_with(".staging") || file_name.ends_with(".old"))
+            } else {
+                file_name.ends_with(".tmp") || is_new_temp(&file_name)
+            };
+
+            if is_temp {
+                let age = entry
+                    .metadata()?
+                    .modified()
+                    .ok()
+                    .and_then(|modified| now.duration_since(modified).ok())
+                    .unwrap_or_default();
+
+                if age >= policy.temp_max_age {
+                    if is_dir {
+                        std::fs::remove_dir_all(&path)?;
+                    } else {
+                        std::fs::remove_file(&path)?;
+                    }
+                    report.removed.push(path);
+                }
+                continue;
+            }
+
+            if is_dir {
+                continue;
+            }
+
+            if let Some((config_file_name, suffix)) = file_name.split_once(CORRUPT_MARKER) {
+                copies
+                    .entry(dir.join(config_file_name))
+                    .or_default()
+                    .push((suffix.to_owned(), path));
+            }
+        }
+    }
+
+    for mut group in copies.into_values() {
+        // Timestamps are zero padded, so the newest copies sort last
+        group.sort();
+        let remove_count = group.len().saturating_sub(policy.keep_corrupt);
+
+        for (_, path) in group.into_iter().take(remove_count) {
+            std::fs::remove_file(&path)?;
+            report.removed.push(path);
+        }
+    }
+
+    report.removed.sort();
+
+    Ok(report)
+}
+
+/// A directory to clean, with the names of the files of the app when the directory is shared with other files.
+type ScannedDir = (PathBuf, Option<Vec<String>>);
+
+/// Returns the directories holding the files of the app: the app directory and the subdirectories of its
+/// [`crate::LayoutPolicy`], along with the directories of its dot files (see [`crate::CwdPolicy::dot_files`]).
+///
+/// Dot files share their directory with other files, the names of the files of the app are returned with it.
+fn scanned_dirs(
+    app_name: &str,
+    location: &ConfigLocation,
+    app_dir: PathBuf,
+) -> Result<Vec<ScannedDir>, ConfigError> {
+    let mut dirs: Vec<ScannedDir> = vec![(app_dir.clone(), None)];
+
+    for subdirectory in crate::layout::subdirectories(app_name) {
+        let dir = app_dir.join(subdirectory);
+        if dir.is_dir() && !dirs.iter().any(|(scanned, _)| *scanned == dir) {
+            dirs.push((dir, None));
+        }
+    }
+
+    if *location == ConfigLocation::Cwd && crate::project::uses_dot_files(app_name) {
+        for config_type in crate::auto::ENABLED_TYPES {
+            let path = crate::config_location(app_name, None, config_type.as_str(), location)?;
+            let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+                continue;
+            };
+            let file_name = file_name.to_string_lossy().into_owned();
+
+            match dirs.iter_mut().find(|(scanned, _)| scanned == dir) {
+                Some((_, Some(owned_files))) => owned_files.push(file_name),
+                Some((_, None)) => {}
+                None => dirs.push((dir.to_path_buf(), Some(vec![file_name]))),
+            }
+        }
+    }
+
+    Ok(dirs)
+}
+
+/// Returns `true` for the temporary files of the `store_*_new` functions, named `<name>.<pid>-<n>.new`.
+fn is_new_temp(file_name: &str) -> bool {
+    let is_number = |part: &str| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit());
+
+    file_name
+        .strip_suffix(".new")
+        .and_then(|file_name| file_name.rsplit_once('.'))
+        .and_then(|(_, unique)| unique.split_once('-'))
+        .is_some_and(|(pid, n)| is_number(pid) && is_number(n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[test]
+    fn cleanup_trims_copies_and_temp_files() {
+        let app_dir = crate::app_dir("test-binconf-cleanup_trims", &Config).unwrap();
+
+        let files = [
+            "settings.toml",
+            "settings.toml.tmp",
+            "settings.toml.corrupt-0000000001",
+            "settings.toml.corrupt-0000000002",
+            "settings.toml.corrupt-0000000003",
+            "settings.toml.1234-0.new",
+            "settings.new",
+        ];
+        for file in files {
+            std::fs::write(app_dir.join(file), "").unwrap();
+        }
+        std::fs::create_dir_all(app_dir.join(".work.staging")).unwrap();
+
+        let policy = CleanupPolicy {
+            temp_max_age: Duration::ZERO,
+            keep_corrupt: 1,
+        };
+        let report = cleanup("test-binconf-cleanup_trims", Config, &policy).unwrap();
+
+        let mut expected = vec![
+            app_dir.join(".work.staging"),
+            app_dir.join("settings.toml.corrupt-0000000001"),
+            app_dir.join("settings.toml.corrupt-0000000002"),
+            app_dir.join("settings.toml.1234-0.new"),
+            app_dir.join("settings.toml.tmp"),
+        ];
+        expected.sort();
+        assert_eq!(report.removed, expected);
+
+        assert!(app_dir.join("settings.toml").exists());
+        assert!(app_dir.join("settings.toml.corrupt-0000000003").exists());
+        assert!(app_dir.join("settings.new").exists());
+    }
+
+    #[test]
+    fn cleanup_restores_interrupted_profile_swaps() {
+        let app_dir = crate::app_dir("test-binconf-cleanup_restores_swaps", &Config).unwrap();
+        let old_dir = app_dir.join(".work.old");
+        let _ = std::fs::remove_dir_all(app_dir.join("work"));
+        std::fs::create_dir_all(&old_dir).unwrap();
+        std::fs::write(old_dir.join("a.toml"), "a = 1\n").unwrap();
+
+        let policy = CleanupPolicy {
+            temp_max_age: Duration::ZERO,
+            ..CleanupPolicy::default()
+        };
+        let report = cleanup("test-binconf-cleanup_restores_swaps", Config, &policy).unwrap();
+
+        assert!(report.removed.is_empty());
+        assert!(app_dir.join("work").join("a.toml").exists());
+        assert!(!old_dir.exists());
+    }
+
+    #[test]
+    #[cfg(feature = "toml-conf")]
+    fn cleanup_trims_copies_in_layout_subdirectories() {
+        let app = "test-binconf-cleanup_trims_subdirectories";
+        crate::set_layout_policy(
+            app,
+            crate::LayoutPolicy {
+                subdirectories: vec![(crate::ConfigType::Toml, PathBuf::from("state"))],
+                ..crate::LayoutPolicy::default()
+            },
+        );
+        let state_dir = crate::app_dir(app, &Config).unwrap().join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+        for file in [
+            "settings.toml.corrupt-0000000001",
+            "settings.toml.corrupt-0000000002",
+        ] {
+            std::fs::write(state_dir.join(file), "").unwrap();
+        }
+
+        let policy = CleanupPolicy {
+            keep_corrupt: 1,
+            ..CleanupPolicy::default()
+        };
+        let report = cleanup(app, Config, &policy).unwrap();
+
+        assert_eq!(
+            report.removed,
+            vec![state_dir.join("settings.toml.corrupt-0000000001")]
+        );
+        assert!(state_dir.join("settings.toml.corrupt-0000000002").exists());
+    }
+}
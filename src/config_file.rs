@@ -0,0 +1,86 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::PathBuf;
+
+/// A config type that knows where it is stored, the single source of truth for its app name, file name, location and format.
+///
+/// Implement it by hand with the associated consts, or derive it with `BinConf` when the `derive` feature is
+/// enabled. The provided functions call the `load` and `store` functions of [`FORMAT`](ConfigFile::FORMAT).
+///
+/// # Example
+///
+/// ```
+/// use binconf::{ConfigFile, ConfigLocation, ConfigType};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Window {
+///     width: u32,
+///     height: u32,
+/// }
+///
+/// impl ConfigFile for Window {
+///     const APP_NAME: &'static str = "test-binconf-config-file";
+///     const FILE_NAME: Option<&'static str> = Some("window.bin");
+///     const LOCATION: ConfigLocation = ConfigLocation::Cache;
+///     const FORMAT: ConfigType = ConfigType::Bin;
+/// }
+///
+/// Window { width: 800, height: 600 }.save().unwrap();
+///
+/// assert_eq!(Window::load().unwrap(), Window { width: 800, height: 600 });
+/// assert!(Window::path().unwrap().ends_with("test-binconf-config-file/window.bin"));
+/// ```
+pub trait ConfigFile: Default + serde::Serialize + serde::de::DeserializeOwned {
+    /// The app name.
+    const APP_NAME: &'static str;
+    /// The name of the config file, `None` for the default file name of the app.
+    const FILE_NAME: Option<&'static str> = None;
+    /// The location of the config file.
+    const LOCATION: ConfigLocation = ConfigLocation::Config;
+    /// The format of the config file.
+    const FORMAT: ConfigType;
+
+    /// Loads the config, creating it with its defaults if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the errors of the `load` function of the format.
+    fn load() -> Result<Self, ConfigError> {
+        crate::load_typed(
+            Self::APP_NAME,
+            Self::FILE_NAME,
+            &Self::FORMAT,
+            &Self::LOCATION,
+            false,
+        )
+    }
+
+    /// Stores the config.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the errors of the `store` function of the format.
+    fn save(&self) -> Result<(), ConfigError> {
+        crate::store_typed(
+            Self::APP_NAME,
+            Self::FILE_NAME,
+            &Self::FORMAT,
+            &Self::LOCATION,
+            self,
+        )
+    }
+
+    /// Returns the path of the config file. See [`crate::get_configuration_path`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config directory could not be found or created.
+    fn path() -> Result<PathBuf, ConfigError> {
+        crate::get_configuration_path(
+            Self::APP_NAME,
+            Self::FILE_NAME,
+            Self::FORMAT,
+            Self::LOCATION,
+        )
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{ConfigBuilder, ConfigError};
+use std::cell::RefCell;
+
+thread_local! {
+    /// The contexts set with [`with_context`] on this thread, the innermost last.
+    static CONTEXTS: RefCell<Vec<ConfigBuilder>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Removes the context pushed by [`with_context`], even if its closure panics.
+struct ContextGuard;
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        CONTEXTS.with(|contexts| contexts.borrow_mut().pop());
+    }
+}
+
+/// Runs `f` with `context` as the config context of the current thread, used by [`load`] and [`store`].
+///
+/// Code deep in the call stack can then load and store the config without receiving the app name, location and
+/// options from every caller. Contexts nest: the innermost one is used until its closure returns. The context is only
+/// visible from the current thread, threads spawned by `f` start without one.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// fn bump_launches() -> Result<u32, binconf::ConfigError> {
+///     let launches = binconf::load::<u32>()? + 1;
+///     binconf::store(launches)?;
+///     Ok(launches)
+/// }
+///
+/// let context = binconf::builder("test-binconf-with-context").location(Cache);
+/// let launches = binconf::with_context(context, || bump_launches().unwrap());
+///
+/// assert_eq!(binconf::load_bin::<u32>("test-binconf-with-context", None, Cache, false).unwrap(), launches);
+/// ```
+pub fn with_context<R>(context: ConfigBuilder, f: impl FnOnce() -> R) -> R {
+    CONTEXTS.with(|contexts| contexts.borrow_mut().push(context));
+    let _guard = ContextGuard;
+
+    f()
+}
+
+/// Returns the innermost config context of the current thread, set with [`with_context`].
+pub fn current_context() -> Option<ConfigBuilder> {
+    CONTEXTS.with(|contexts| contexts.borrow().last().cloned())
+}
+
+/// Loads the config of the current context. See [`with_context`] and [`ConfigBuilder::load`].
+///
+/// # Errors
+///
+/// This function will return the errors of [`ConfigBuilder::load`], or a [`ConfigError::NoContext`] error if it is
+/// called outside of [`with_context`].
+pub fn load<T>() -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    current_context().ok_or(ConfigError::NoContext)?.load()
+}
+
+/// Stores the config of the current context. See [`with_context`] and [`ConfigBuilder::store`].
+///
+/// # Errors
+///
+/// This function will return the errors of [`ConfigBuilder::store`], or a [`ConfigError::NoContext`] error if it is
+/// called outside of [`with_context`].
+pub fn store<T>(data: T) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    current_context().ok_or(ConfigError::NoContext)?.store(data)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use crate::ConfigLocation::Cache;
+
+    #[test]
+    fn contexts_nest_per_thread() {
+        let outer = crate::builder("test-binconf-contexts_nest_per_thread").location(Cache);
+        let inner = outer.clone().name("inner.json");
+
+        assert!(matches!(load::<u32>(), Err(ConfigError::NoContext)));
+
+        with_context(outer.clone(), || {
+            store(1u32).unwrap();
+            with_context(inner.clone(), || store(2u32).unwrap());
+            assert_eq!(load::<u32>().unwrap(), 1);
+
+            let spawned = std::thread::spawn(current_context).join().unwrap();
+            assert_eq!(spawned, None);
+        });
+
+        // Popped even when the closure panics
+        let _ =
+            std::panic::catch_unwind(|| with_context(inner.clone(), || panic!("while loading")));
+        assert_eq!(current_context(), None);
+
+        assert_eq!(inner.load::<u32>().unwrap(), 2);
+    }
+}
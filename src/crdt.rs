@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A value that can merge a diverged copy of itself without losing updates.
+///
+/// Merging is commutative, associative and idempotent, so copies of a config synced between machines converge to the
+/// same value whatever the order in which they are merged.
+pub trait Merge {
+    /// Merges `other` into `self`.
+    fn merge(&mut self, other: &Self);
+}
+
+/// A grow-only counter. Every replica only increments its own count, the value is the sum of all counts.
+///
+/// # Example
+///
+/// ```
+/// use binconf::{GCounter, Merge};
+///
+/// let mut laptop = GCounter::default();
+/// let mut desktop = GCounter::default();
+///
+/// laptop.increment("laptop", 2);
+/// desktop.increment("desktop", 3);
+///
+/// laptop.merge(&desktop);
+/// assert_eq!(laptop.value(), 5);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GCounter {
+    counts: BTreeMap<String, u64>,
+}
+
+impl GCounter {
+    /// Increments the count of `replica` by `amount`.
+    pub fn increment(&mut self, replica: &str, amount: u64) {
+        let count = self.counts.entry(replica.to_owned()).or_default();
+        *count = count.saturating_add(amount);
+    }
+
+    /// Returns the sum of the counts of all replicas.
+    pub fn value(&self) -> u64 {
+        self.counts
+            .values()
+            .fold(0, |total, count| total.saturating_add(*count))
+    }
+}
+
+impl Merge for GCounter {
+    fn merge(&mut self, other: &Self) {
+        for (replica, count) in &other.counts {
+            let own = self.counts.entry(replica.clone()).or_default();
+            *own = (*own).max(*count);
+        }
+    }
+}
+
+/// A last-writer-wins register. The value with the highest timestamp wins, ties are broken by the replica name.
+///
+/// The timestamp can be any monotonically increasing number, like the [`crate::ConfigMetadata::write_count`] of the
+/// config or its modification time in milliseconds.
+///
+/// # Example
+///
+/// ```
+/// use binconf::{LwwRegister, Merge};
+///
+/// let mut laptop = LwwRegister::new(String::from("light"), 1, "laptop");
+/// let desktop = LwwRegister::new(String::from("dark"), 2, "desktop");
+///
+/// laptop.merge(&desktop);
+/// assert_eq!(laptop.value(), "dark");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LwwRegister<T> {
+    value: T,
+    timestamp: u64,
+    replica: String,
+}
+
+impl<T> LwwRegister<T> {
+    pub fn new(value: T, timestamp: u64, replica: &str) -> Self {
+        LwwRegister {
+            value,
+            timestamp,
+            replica: replica.to_owned(),
+        }
+    }
+
+    /// Sets the value, unless the register already holds a newer one.
+    pub fn set(&mut self, value: T, timestamp: u64, replica: &str) {
+        if (timestamp, replica) > (self.timestamp, self.replica.as_str()) {
+            *self = LwwRegister::new(value, timestamp, replica);
+        }
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
+impl<T: Clone> Merge for LwwRegister<T> {
+    fn merge(&mut self, other: &Self) {
+        self.set(other.value.clone(), other.timestamp, &other.replica);
+    }
+}
+
+/// Unique identity of an addition to an [`OrSet`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct Tag {
+    replica: String,
+    counter: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry<T> {
+    value: T,
+    tag: Tag,
+}
+
+/// An observed-remove set. A removal only cancels the additions it has seen, so an element added concurrently on
+/// another replica survives the merge.
+///
+/// # Example
+///
+/// ```
+/// use binconf::{Merge, OrSet};
+///
+/// let mut laptop = OrSet::default();
+/// laptop.insert(String::from("vim"), "laptop");
+///
+/// let mut desktop = laptop.clone();
+/// desktop.remove(&String::from("vim"));
+/// laptop.insert(String::from("vim"), "laptop");
+///
+/// desktop.merge(&laptop);
+/// assert!(desktop.contains(&String::from("vim")));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrSet<T> {
+    entries: Vec<Entry<T>>,
+    removed: Vec<Tag>,
+    clocks: BTreeMap<String, u64>,
+}
+
+impl<T> Default for OrSet<T> {
+    fn default() -> Self {
+        OrSet {
+            entries: Vec::new(),
+            removed: Vec::new(),
+            clocks: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: PartialEq> OrSet<T> {
+    /// Adds `value` to the set, as seen by `replica`.
+    pub fn insert(&mut self, value: T, replica: &str) {
+        let clock = self.clocks.entry(replica.to_owned()).or_default();
+        *clock = clock.saturating_add(1);
+
+        self.entries.push(Entry {
+            value,
+            tag: Tag {
+                replica: replica.to_owned(),
+                counter: *clock,
+            },
+        });
+    }
+
+    /// Removes every addition of `value` known by this copy of the set.
+    pub fn remove(&mut self, value: &T) {
+        let (removed, entries) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| entry.value == *value);
+        self.entries = entries;
+        self.removed
+            .extend(removed.into_iter().map(|entry: Entry<T>| entry.tag));
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.entries.iter().any(|entry| entry.value == *value)
+    }
+
+    /// Returns the elements of the set, each one once.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(index, entry)| {
+                !self
+                    .entries
+                    .iter()
+                    .take(*index)
+                    .any(|previous| previous.value == entry.value)
+            })
+            .map(|(_, entry)| &entry.value)
+    }
+}
+
+impl<T: PartialEq + Clone> Merge for OrSet<T> {
+    fn merge(&mut self, other: &Self) {
+        for tag in &other.removed {
+            if !self.removed.contains(tag) {
+                self.removed.push(tag.clone());
+            }
+        }
+
+        for entry in &other.entries {
+            if !self.entries.iter().any(|own| own.tag == entry.tag) {
+                self.entries.push(entry.clone());
+            }
+        }
+
+        let removed = &self.removed;
+        self.entries.retain(|entry| !removed.contains(&entry.tag));
+
+        for (replica, clock) in &other.clocks {
+            let own = self.clocks.entry(replica.clone()).or_default();
+            *own = (*own).max(*clock);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_is_commutative_and_idempotent() {
+        let mut a = GCounter::default();
+        let mut b = GCounter::default();
+        a.increment("a", 1);
+        b.increment("b", 2);
+        b.increment("a", 0);
+
+        let mut ab = a.clone();
+        ab.merge(&b);
+        let mut ba = b.clone();
+        ba.merge(&a);
+        assert_eq!(ab, ba);
+
+        ab.merge(&b);
+        assert_eq!(ab.value(), 3);
+
+        let mut a = LwwRegister::new(1, 5, "a");
+        let b = LwwRegister::new(2, 5, "b");
+        a.merge(&b);
+        assert_eq!(*a.value(), 2);
+        a.set(3, 4, "z");
+        assert_eq!(*a.value(), 2);
+    }
+
+    #[test]
+    fn or_set_add_wins_over_concurrent_remove() {
+        let mut a = OrSet::default();
+        a.insert("x", "a");
+        a.insert("y", "a");
+
+        let mut b = a.clone();
+        b.remove(&"x");
+        b.remove(&"y");
+        a.insert("x", "a");
+
+        let mut ab = a.clone();
+        ab.merge(&b);
+        let mut ba = b.clone();
+        ba.merge(&a);
+
+        assert!(ab.contains(&"x"));
+        assert!(!ab.contains(&"y"));
+        assert_eq!(ab.iter().collect::<Vec<_>>(), vec![&"x"]);
+        assert_eq!(ab.iter().collect::<Vec<_>>(), ba.iter().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::{ffi::OsString, path::PathBuf};
+
+/// Environment variable set by systemd to the directory of the credentials passed with `LoadCredential=`.
+const CREDENTIALS_DIRECTORY: &str = "CREDENTIALS_DIRECTORY";
+
+/// Loads a config passed to a systemd service as a credential, falling back to the config file in `location`.
+///
+/// When the service is started with `LoadCredential=<name>:<path>`, systemd places the file in the private directory given
+/// by `$CREDENTIALS_DIRECTORY`, only readable by the service. The credential is looked up there under `config_name`,
+/// `<app>.<extension>` by default. Outside of systemd, or when the service has no such credential, the config is loaded
+/// like the `load` function of its [`ConfigType`] does, without resetting it on errors.
+///
+/// Credentials are read-only: they are never written, and no default config is created in the credentials directory.
+///
+/// # Errors
+///
+/// This function will return an error if the credential could not be read or deserialized, or the errors of the `load`
+/// function of the format when falling back to the config file.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Secrets {
+///     token: String,
+/// }
+///
+/// // With `LoadCredential=test-binconf-credential.bin:/etc/my-app/secrets.bin` in the unit file
+/// let secrets = binconf::load_credential::<Secrets>("test-binconf-credential", None, ConfigType::Bin, Config).unwrap();
+/// # assert_eq!(secrets, Secrets::default());
+/// ```
+pub fn load_credential<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: impl AsRef<ConfigType>,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_credential_with(
+        app_name.as_ref(),
+        config_name.into(),
+        config_type.as_ref(),
+        location.as_ref(),
+        &|name| std::env::var_os(name),
+    )
+}
+
+/// Loads a credential like [`load_credential`], with the environment variables looked up with `env`.
+fn load_credential_with<T>(
+    app_name: &str,
+    config_name: Option<&str>,
+    config_type: &ConfigType,
+    location: &ConfigLocation,
+    env: &dyn Fn(&str) -> Option<OsString>,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    if let Some(credential_path) = credential_path(app_name, config_name, config_type, env) {
+        match std::fs::read(&credential_path) {
+            Ok(data) => return crate::deserialize_typed(&data, config_type),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    crate::load_typed(app_name, config_name, config_type, location, false)
+}
+
+/// Returns the path of the credential holding the config, if systemd passed credentials to the process.
+fn credential_path(
+    app_name: &str,
+    config_name: Option<&str>,
+    config_type: &ConfigType,
+    env: &dyn Fn(&str) -> Option<OsString>,
+) -> Option<PathBuf> {
+    let directory = env(CREDENTIALS_DIRECTORY).filter(|dir| !dir.is_empty())?;
+    let file_name = match config_name {
+        Some(config_name) => config_name.to_owned(),
+        None => format!("{app_name}.{}", config_type.as_str()),
+    };
+
+    Some(PathBuf::from(directory).join(file_name))
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Cache;
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Secrets {
+        token: String,
+    }
+
+    #[test]
+    fn credentials_are_preferred_to_config_files() {
+        let app = "test-binconf-credentials_are_preferred_to_config_files";
+        let directory = std::env::temp_dir().join(app);
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join(format!("{app}.toml")), "token = \"secret\"").unwrap();
+
+        crate::store_toml(
+            app,
+            None,
+            Cache,
+            Secrets {
+                token: String::from("from-file"),
+            },
+        )
+        .unwrap();
+
+        let env = |name: &str| (name == CREDENTIALS_DIRECTORY).then(|| directory.clone().into());
+
+        let secrets: Secrets =
+            load_credential_with(app, None, &ConfigType::Toml, &Cache, &env).unwrap();
+        assert_eq!(secrets.token, "secret");
+
+        // No such credential, the config file is loaded
+        let secrets: Secrets =
+            load_credential_with(app, Some("other.toml"), &ConfigType::Toml, &Cache, &env).unwrap();
+        assert_eq!(secrets, Secrets::default());
+        let secrets: Secrets =
+            load_credential_with(app, None, &ConfigType::Json, &Cache, &env).unwrap();
+        assert_eq!(secrets, Secrets::default());
+        assert!(!directory.join(format!("{app}.json")).exists());
+
+        // Outside of systemd, or with an empty variable
+        let secrets: Secrets =
+            load_credential_with(app, None, &ConfigType::Toml, &Cache, &|_| None).unwrap();
+        assert_eq!(secrets.token, "from-file");
+        let empty = |_: &str| Some(OsString::new());
+        let secrets: Secrets =
+            load_credential_with(app, None, &ConfigType::Toml, &Cache, &empty).unwrap();
+        assert_eq!(secrets.token, "from-file");
+    }
+}
@@ -0,0 +1,125 @@
+use crate::ConfigError;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Free space, in bytes, that must remain after a store. `None` disables the guard.
+static FREE_SPACE_MARGIN: Mutex<Option<u64>> = Mutex::new(None);
+
+/// Enables or disables the free space guard used by every `store` function.
+///
+/// When enabled, the available space on the filesystem holding the config file is checked before the file is touched,
+/// and the store fails with [`ConfigError::InsufficientSpace`] unless the serialized config plus `margin` bytes fit.
+/// This avoids leaving a truncated config behind when the disk fills up in the middle of a write.
+///
+/// The guard is disabled by default and is a no-op on platforms where the free space can not be determined.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// // Keep at least 1 MiB free after every store.
+/// binconf::set_free_space_guard(Some(1024 * 1024));
+///
+/// binconf::store_bin("test-binconf-free-space-guard", None, Config, 42u32).unwrap();
+///
+/// binconf::set_free_space_guard(None);
+/// ```
+pub fn set_free_space_guard(margin: Option<u64>) {
+    *FREE_SPACE_MARGIN
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = margin;
+}
+
+/// Checks that `length` bytes can be written to `config_file_path`, if the free space guard is enabled.
+pub(crate) fn ensure_free_space(config_file_path: &Path, length: usize) -> Result<(), ConfigError> {
+    let margin = *FREE_SPACE_MARGIN
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    match margin {
+        Some(margin) => check_free_space(config_file_path, (length as u64).saturating_add(margin)),
+        None => Ok(()),
+    }
+}
+
+fn check_free_space(config_file_path: &Path, required: u64) -> Result<(), ConfigError> {
+    let dir = config_file_path.parent().unwrap_or(config_file_path);
+
+    match available_space(dir) {
+        Some(available) if available < required => Err(ConfigError::InsufficientSpace {
+            available,
+            required,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the space available to the current user on the filesystem holding `path`, in bytes.
+///
+/// Returns `None` if it could not be determined, which is always the case on non-unix platforms.
+#[cfg(unix)]
+pub(crate) fn available_space(path: &Path) -> Option<u64> {
+    let stat = statvfs(path)?;
+
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn available_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Returns `true` if the filesystem holding `path` is mounted read-only.
+///
+/// Returns `None` if it could not be determined, which is always the case on platforms other than linux.
+#[cfg(target_os = "linux")]
+pub(crate) fn is_read_only(path: &Path) -> Option<bool> {
+    Some(statvfs(path)?.f_flag & libc::ST_RDONLY != 0)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn is_read_only(_path: &Path) -> Option<bool> {
+    None
+}
+
+#[cfg(unix)]
+fn statvfs(path: &Path) -> Option<libc::statvfs> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: `path` is a valid nul terminated string and `stat` is only read after `statvfs` reported success.
+    unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        Some(stat.assume_init())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_free_space_rejects_oversized_writes() {
+        let dir = std::env::temp_dir();
+        let config_file_path = dir.join("test-binconf-check_free_space.bin");
+
+        if available_space(&dir).is_none() {
+            return;
+        }
+
+        assert!(check_free_space(&config_file_path, 0).is_ok());
+        assert!(matches!(
+            check_free_space(&config_file_path, u64::MAX),
+            Err(ConfigError::InsufficientSpace {
+                required: u64::MAX,
+                ..
+            })
+        ));
+    }
+}
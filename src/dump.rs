@@ -0,0 +1,222 @@
+use crate::{ConfigError, ConfigType};
+use std::path::Path;
+
+/// Written instead of the value of a secret setting.
+const REDACTED: &str = "<redacted>";
+
+/// Parts of key names holding secrets, compared in lower case with `-` and `_` removed.
+const SECRET_KEYS: &[&str] = &[
+    "password",
+    "passwd",
+    "passphrase",
+    "secret",
+    "token",
+    "apikey",
+    "privatekey",
+    "credential",
+];
+
+/// Serializes the effective config of the app, as it was resolved after layering and overrides, with its secrets redacted.
+///
+/// The "print effective config" of a `--print-config` flag or a support bundle: pass the config the app actually runs with.
+/// Settings whose key name contains `password`, `passphrase`, `secret`, `token`, `api_key`, `private_key` or `credential`,
+/// in any case and with or without `-` and `_`, are written as `"<redacted>"`. Unset settings are written as is.
+///
+/// # Errors
+///
+/// This function will return an error if the config could not be serialized, or a [`ConfigError::UnsupportedFormat`]
+/// error for binary configs, which are not readable.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigType;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Database {
+///     url: String,
+///     password: String,
+/// }
+///
+/// let database = Database {
+///     url: String::from("postgres://db:5432"),
+///     password: String::from("hunter2"),
+/// };
+///
+/// let effective = binconf::effective_config_string(&database, ConfigType::Json).unwrap();
+/// assert!(effective.contains("postgres://db:5432"));
+/// assert!(!effective.contains("hunter2"));
+/// ```
+pub fn effective_config_string<T>(
+    value: &T,
+    config_type: impl AsRef<ConfigType>,
+) -> Result<String, ConfigError>
+where
+    T: serde::Serialize,
+{
+    let config_type = config_type.as_ref();
+    let mut value = serde_json::to_value(value)?;
+    redact(&mut value);
+
+    match config_type {
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => {
+            // `toml` has no null value, unset settings are left out like when storing the config
+            remove_nulls(&mut value);
+            crate::output::to_toml_string(&value, &crate::output::output_options())
+        }
+
+        ConfigType::Json => crate::output::to_json_string(&value, &crate::output::output_options()),
+
+        #[cfg(feature = "yaml-conf")]
+        ConfigType::Yaml => crate::output::to_yaml_string(&value, &crate::output::output_options()),
+
+        #[cfg(feature = "ron-conf")]
+        ConfigType::Ron => crate::output::to_ron_string(&value, &crate::output::output_options()),
+
+        #[allow(unreachable_patterns)]
+        _ => Err(ConfigError::UnsupportedFormat(
+            config_type.as_str().to_owned(),
+        )),
+    }
+}
+
+/// Writes the effective config of the app to `destination`, with its secrets redacted. See [`effective_config_string`].
+///
+/// The format is chosen from the extension of `destination`, like `effective.toml`.
+///
+/// # Errors
+///
+/// This function will return an error if the config could not be serialized or written, or a [`ConfigError::UnsupportedFormat`]
+/// error if the extension of `destination` is not a text format.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let settings = HashMap::from([("api_token", "abc123"), ("region", "eu-west-1")]);
+///
+/// let destination = std::env::temp_dir().join("test-binconf-dump-effective-config.json");
+/// binconf::dump_effective_config(&settings, &destination).unwrap();
+///
+/// let dumped = std::fs::read_to_string(&destination).unwrap();
+/// assert!(dumped.contains("eu-west-1"));
+/// assert!(!dumped.contains("abc123"));
+/// ```
+pub fn dump_effective_config<T>(value: &T, destination: impl AsRef<Path>) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let destination = destination.as_ref();
+    let extension = destination
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .unwrap_or_default();
+    let config_type = ConfigType::from_extension(extension)
+        .ok_or_else(|| ConfigError::UnsupportedFormat(extension.to_owned()))?;
+
+    let text = effective_config_string(value, config_type)?;
+    std::fs::write(destination, text)?;
+
+    Ok(())
+}
+
+fn is_secret(key: &str) -> bool {
+    let key: String = key
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    SECRET_KEYS.iter().any(|secret| key.contains(secret))
+}
+
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret(key) && !value.is_null() {
+                    *value = serde_json::Value::String(REDACTED.to_owned());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+#[cfg(feature = "toml-conf")]
+fn remove_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Serialize)]
+    struct Settings {
+        name: String,
+        proxy: Option<String>,
+        accounts: Vec<Account>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Account {
+        user: String,
+        #[serde(rename = "Api-Key")]
+        api_key: String,
+        private_key: Option<String>,
+    }
+
+    #[test]
+    fn secrets_are_redacted_in_every_format() {
+        let settings = Settings {
+            name: String::from("effective"),
+            proxy: None,
+            accounts: vec![Account {
+                user: String::from("admin"),
+                api_key: String::from("abc123"),
+                private_key: None,
+            }],
+        };
+
+        for config_type in [
+            ConfigType::Toml,
+            ConfigType::Json,
+            ConfigType::Yaml,
+            ConfigType::Ron,
+        ] {
+            let text = effective_config_string(&settings, config_type).unwrap();
+            assert!(text.contains("admin"), "{text}");
+            assert!(text.contains(REDACTED), "{text}");
+            assert!(!text.contains("abc123"), "{text}");
+        }
+
+        let value: serde_json::Value =
+            serde_json::from_str(&effective_config_string(&settings, ConfigType::Json).unwrap())
+                .unwrap();
+        assert_eq!(value["accounts"][0]["private_key"], serde_json::Value::Null);
+
+        assert!(matches!(
+            effective_config_string(&settings, ConfigType::Bin),
+            Err(ConfigError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            dump_effective_config(&settings, std::env::temp_dir().join("effective.ini")),
+            Err(ConfigError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,309 @@
+use crate::ConfigError;
+
+#[cfg(any(feature = "json-conf", feature = "yaml-conf"))]
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, Visitor};
+#[cfg(any(feature = "json-conf", feature = "yaml-conf"))]
+use std::{cell::RefCell, fmt};
+
+/// Walks a self describing document, failing on the first mapping holding the same key twice.
+///
+/// The path and the name of the duplicated key are written to `found`, the error itself is raised through the deserializer
+/// so it carries the position of the key.
+#[cfg(any(feature = "json-conf", feature = "yaml-conf"))]
+struct DuplicateCheck<'a> {
+    path: String,
+    found: &'a RefCell<Option<(String, String)>>,
+}
+
+#[cfg(any(feature = "json-conf", feature = "yaml-conf"))]
+impl<'de> DeserializeSeed<'de> for DuplicateCheck<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+#[cfg(any(feature = "json-conf", feature = "yaml-conf"))]
+impl<'de> Visitor<'de> for DuplicateCheck<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_none<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        while seq
+            .next_element_seed(DuplicateCheck {
+                path: self.path.clone(),
+                found: self.found,
+            })?
+            .is_some()
+        {}
+        Ok(())
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut keys = std::collections::HashSet::new();
+        while let Some(KeyName(key)) = map.next_key()? {
+            let path = match &key {
+                Some(key) if self.path.is_empty() => key.clone(),
+                Some(key) => format!("{}.{key}", self.path),
+                None => self.path.clone(),
+            };
+
+            if let Some(key) = key {
+                if keys.contains(&key) {
+                    let err = de::Error::custom(format_args!("duplicate key `{path}`"));
+                    *self.found.borrow_mut() = Some((path, key));
+                    return Err(err);
+                }
+                keys.insert(key);
+            }
+
+            map.next_value_seed(DuplicateCheck {
+                path,
+                found: self.found,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<(), A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        let (IgnoredAny, variant) = data.variant()?;
+        de::VariantAccess::newtype_variant_seed(variant, self)
+    }
+}
+
+/// The name of a mapping key, `None` for keys that are sequences or mappings themselves, which are never reported.
+#[cfg(any(feature = "json-conf", feature = "yaml-conf"))]
+struct KeyName(Option<String>);
+
+#[cfg(any(feature = "json-conf", feature = "yaml-conf"))]
+impl<'de> de::Deserialize<'de> for KeyName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct KeyVisitor;
+
+        impl<'de> Visitor<'de> for KeyVisitor {
+            type Value = KeyName;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a mapping key")
+            }
+
+            fn visit_bool<E>(self, value: bool) -> Result<KeyName, E> {
+                Ok(KeyName(Some(value.to_string())))
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<KeyName, E> {
+                Ok(KeyName(Some(value.to_string())))
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<KeyName, E> {
+                Ok(KeyName(Some(value.to_string())))
+            }
+
+            fn visit_f64<E>(self, value: f64) -> Result<KeyName, E> {
+                Ok(KeyName(Some(value.to_string())))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<KeyName, E> {
+                Ok(KeyName(Some(value.to_owned())))
+            }
+
+            fn visit_unit<E>(self) -> Result<KeyName, E> {
+                Ok(KeyName(Some(String::from("null"))))
+            }
+
+            fn visit_seq<A>(self, seq: A) -> Result<KeyName, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                IgnoredAny.visit_seq(seq)?;
+                Ok(KeyName(None))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<KeyName, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                IgnoredAny.visit_map(map)?;
+                Ok(KeyName(None))
+            }
+
+            fn visit_enum<A>(self, data: A) -> Result<KeyName, A::Error>
+            where
+                A: de::EnumAccess<'de>,
+            {
+                IgnoredAny.visit_enum(data)?;
+                Ok(KeyName(None))
+            }
+        }
+
+        deserializer.deserialize_any(KeyVisitor)
+    }
+}
+
+/// Fails with [`ConfigError::DuplicateKey`] if a `json` object holds the same key twice.
+#[cfg(feature = "json-conf")]
+pub(crate) fn check_json(json_str: &str) -> Result<(), ConfigError> {
+    let found = RefCell::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(json_str);
+    let check = DuplicateCheck {
+        path: String::new(),
+        found: &found,
+    };
+
+    match check.deserialize(&mut deserializer) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(match found.into_inner() {
+            Some((path, _)) => ConfigError::DuplicateKey {
+                path,
+                line: err.line(),
+            },
+            None => err.into(),
+        }),
+    }
+}
+
+/// Fails with [`ConfigError::DuplicateKey`] if a `yaml` mapping of the first document holds the same key twice.
+#[cfg(feature = "yaml-conf")]
+pub(crate) fn check_yaml(yaml_str: &str) -> Result<(), ConfigError> {
+    let Some(document) = serde_yaml::Deserializer::from_str(yaml_str).next() else {
+        return Ok(());
+    };
+
+    let found = RefCell::new(None);
+    let check = DuplicateCheck {
+        path: String::new(),
+        found: &found,
+    };
+
+    match check.deserialize(document) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(match found.into_inner() {
+            Some((path, key)) => {
+                let mapping_line = err.location().map_or(0, |location| location.line());
+                ConfigError::DuplicateKey {
+                    line: yaml_duplicate_line(yaml_str, mapping_line, &key).unwrap_or(mapping_line),
+                    path,
+                }
+            }
+            None => err.into(),
+        }),
+    }
+}
+
+/// Finds the line of the second `key` of the block mapping starting at `mapping_line`.
+///
+/// Errors raised while visiting a mapping point at its start, so the entries are matched by their indentation.
+#[cfg(feature = "yaml-conf")]
+fn yaml_duplicate_line(yaml_str: &str, mapping_line: usize, key: &str) -> Option<usize> {
+    let mut indent = None;
+    for (index, line) in yaml_str
+        .lines()
+        .enumerate()
+        .skip(mapping_line.saturating_sub(1))
+    {
+        let content = line.trim_start();
+        let entry = content.strip_prefix("- ").unwrap_or(content).trim_start();
+        let is_key = [key.to_owned(), format!("\"{key}\""), format!("'{key}'")]
+            .iter()
+            .any(|name| {
+                entry
+                    .strip_prefix(name.as_str())
+                    .is_some_and(|rest| rest.trim_start().starts_with(':'))
+            });
+        if !is_key {
+            continue;
+        }
+
+        let column = line.len() - entry.len();
+        match indent {
+            None => indent = Some(column),
+            Some(indent) if indent == column => return Some(index + 1),
+            Some(_) => {}
+        }
+    }
+    None
+}
+
+/// Turns the duplicate key errors of the `toml` parser, which always rejects them, into [`ConfigError::DuplicateKey`].
+#[cfg(feature = "toml-conf")]
+pub(crate) fn toml_error(err: toml::de::Error, toml_str: &str) -> ConfigError {
+    // The parser reports them as "duplicate key `key` in table `table`" or "... in document root"
+    let duplicate = err.message().lines().find_map(|line| {
+        let (key, table) = line.strip_prefix("duplicate key `")?.split_once("` in ")?;
+        Some(match table.strip_prefix("table `") {
+            Some(table) => format!("{}.{key}", table.strip_suffix('`')?),
+            None => key.to_owned(),
+        })
+    });
+
+    match (duplicate, err.span()) {
+        (Some(path), Some(span)) => ConfigError::DuplicateKey {
+            path,
+            line: toml_str
+                .get(..span.start)
+                .map_or(0, |before| before.matches('\n').count() + 1),
+        },
+        _ => err.into(),
+    }
+}
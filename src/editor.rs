@@ -0,0 +1,195 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::Path;
+use std::process::Command;
+
+/// Opens a config in the editor of the user, waits for it to exit and loads the edited config.
+///
+/// The editor is taken from the `VISUAL` environment variable, then `EDITOR`, which may contain arguments (e.g. `code --wait`).
+/// When neither is set, the default text editor of the OS is used: `open -W -t` on macOS, `notepad` on Windows and `vi` elsewhere.
+/// The config file is created with the default config first if it does not exist.
+///
+/// If the edited file can't be deserialized, the previous version of the file is restored and the error is returned,
+/// so the app never runs with a config it can't read. Works like `kubectl edit`.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if the current config
+/// could not be loaded, if the editor could not be started or exited with an error, or if the edited config could not be deserialized.
+///
+/// Binary configs can't be edited and return a [`ConfigError::UnsupportedFormat`] error.
+///
+/// # Example
+///
+/// ```no_run
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///    test: String,
+///    test_vec: Vec<u8>,
+/// }
+///
+/// match binconf::edit_in_editor::<TestConfig>("test-binconf-edit-in-editor", None, ConfigType::Toml, Config) {
+///     Ok(config) => println!("New config: {config:?}"),
+///     Err(err) => eprintln!("The config was not changed: {err}"),
+/// }
+/// ```
+pub fn edit_in_editor<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    edit_with(
+        &editor_command(),
+        app_name.as_ref(),
+        config_name.into(),
+        config_type,
+        location.as_ref(),
+    )
+}
+
+fn edit_with<T>(
+    editor: &[String],
+    app_name: &str,
+    config_name: Option<&str>,
+    config_type: ConfigType,
+    location: &ConfigLocation,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    #[cfg(feature = "binary-conf")]
+    if config_type == ConfigType::Bin {
+        return Err(ConfigError::UnsupportedFormat(
+            config_type.as_str().to_owned(),
+        ));
+    }
+
+    // Also writes the default config if the file does not exist yet
+    crate::load_typed::<T>(app_name, config_name, &config_type, location, false)?;
+
+    let config_file_path =
+        crate::config_location(app_name, config_name, config_type.as_str(), location)?;
+    let previous = std::fs::read(&config_file_path)?;
+
+    if let Err(err) = run_editor(editor, &config_file_path) {
+        crate::save_config_atomic(&config_file_path, &previous)?;
+        return Err(err);
+    }
+
+    match crate::load_typed(app_name, config_name, &config_type, location, false) {
+        Ok(config) => Ok(config),
+        Err(err) => {
+            crate::save_config_atomic(&config_file_path, &previous)?;
+            Err(err)
+        }
+    }
+}
+
+/// Returns the editor command of the user, the program followed by its arguments.
+fn editor_command() -> Vec<String> {
+    let from_env = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|variable| std::env::var(variable).ok())
+        .map(|editor| {
+            editor
+                .split_whitespace()
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        })
+        .find(|editor| !editor.is_empty());
+
+    from_env.unwrap_or_else(|| {
+        let default: &[&str] = if cfg!(target_os = "macos") {
+            &["open", "-W", "-t"]
+        } else if cfg!(windows) {
+            &["notepad"]
+        } else {
+            &["vi"]
+        };
+        default.iter().map(|arg| (*arg).to_owned()).collect()
+    })
+}
+
+fn run_editor(editor: &[String], path: &Path) -> Result<(), ConfigError> {
+    let Some((program, args)) = editor.split_first() else {
+        return Err(ConfigError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "No editor configured",
+        )));
+    };
+
+    let status = Command::new(program).args(args).arg(path).status()?;
+    if !status.success() {
+        return Err(ConfigError::Io(std::io::Error::other(format!(
+            "Editor `{program}` exited with {status}"
+        ))));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg(all(unix, feature = "full"))]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use ConfigLocation::Config;
+
+    /// An "editor" that replaces the file with `content`.
+    fn writing(content: &str) -> Vec<String> {
+        vec![
+            String::from("sh"),
+            String::from("-c"),
+            format!("printf '{content}' > \"$0\""),
+        ]
+    }
+
+    #[test]
+    fn edited_config_is_validated() {
+        let app = "test-binconf-edited_config_is_validated";
+        crate::store_toml(app, None, Config, HashMap::from([("theme", "light")])).unwrap();
+
+        let config: HashMap<String, String> = edit_with(
+            &writing("theme = \"dark\""),
+            app,
+            None,
+            ConfigType::Toml,
+            &Config,
+        )
+        .unwrap();
+        assert_eq!(config.get("theme").map(String::as_str), Some("dark"));
+
+        let config = edit_with::<HashMap<String, String>>(
+            &writing("theme = "),
+            app,
+            None,
+            ConfigType::Toml,
+            &Config,
+        );
+        assert!(matches!(config, Err(ConfigError::TomlDe(_))));
+
+        // The invalid edit was rolled back
+        let config: HashMap<String, String> = crate::load_toml(app, None, Config, false).unwrap();
+        assert_eq!(config.get("theme").map(String::as_str), Some("dark"));
+
+        let config = edit_with::<HashMap<String, String>>(
+            &[String::from("false")],
+            app,
+            None,
+            ConfigType::Toml,
+            &Config,
+        );
+        assert!(matches!(config, Err(ConfigError::Io(_))));
+
+        let config = edit_with::<u32>(&writing(""), app, None, ConfigType::Bin, &Config);
+        assert!(matches!(config, Err(ConfigError::UnsupportedFormat(_))));
+    }
+}
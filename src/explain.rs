@@ -0,0 +1,372 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// The layers stacked by [`load_toml_layered`] and [`explain`], from lowest to highest precedence after the defaults and the user file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LayerSources {
+    /// A system wide config file, applied over the defaults and under the user file. A missing file is skipped.
+    pub system: Option<PathBuf>,
+    /// Prefix of the environment variables overriding the config. `MYAPP` reads `MYAPP_LEVEL` as `level`
+    /// and `MYAPP_UI__THEME` as `ui.theme`. Values are parsed as TOML values, falling back to strings.
+    pub env_prefix: Option<String>,
+    /// Dotted key paths and raw values given on the command line, applied last. Values are parsed like the environment variables.
+    pub cli: Vec<(String, String)>,
+}
+
+/// The layer that supplied the effective value of a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The `Default` implementation of the config.
+    Default,
+    /// The system config file at the given path.
+    System(PathBuf),
+    /// The config file of the user at the given path.
+    UserFile(PathBuf),
+    /// The environment variable with the given name.
+    Env(String),
+    /// The command line.
+    Cli,
+}
+
+/// The [`Source`] of every effective value of a config, by dotted key path. Returned by [`explain`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProvenanceMap {
+    sources: BTreeMap<String, Source>,
+}
+
+impl ProvenanceMap {
+    /// Returns the source of the value at the dotted `key_path`, if it exists.
+    pub fn get(&self, key_path: &str) -> Option<&Source> {
+        self.sources.get(key_path)
+    }
+
+    /// Iterates over the dotted key paths and their source, in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Source)> {
+        self.sources
+            .iter()
+            .map(|(key_path, source)| (key_path.as_str(), source))
+    }
+
+    fn set(&mut self, key_path: String, value: &toml::Value, source: &Source) {
+        // The previous value may have been a table with a different shape
+        let nested = format!("{key_path}.");
+        self.sources
+            .retain(|existing, _| *existing != key_path && !existing.starts_with(&nested));
+
+        match value {
+            toml::Value::Table(table) => {
+                for (key, value) in table {
+                    self.set(format!("{key_path}.{key}"), value, source);
+                }
+            }
+            _ => {
+                self.sources.insert(key_path, source.clone());
+            }
+        }
+    }
+}
+
+/// Deep merges `overlay` into `base`, recording `source` for every value it sets.
+fn apply(
+    base: &mut toml::Table,
+    overlay: toml::Table,
+    source: &Source,
+    provenance: &mut ProvenanceMap,
+    prefix: &str,
+) {
+    for (key, value) in overlay {
+        let key_path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(table)) => {
+                apply(base_table, table, source, provenance, &key_path);
+            }
+            (_, value) => {
+                provenance.set(key_path, &value, source);
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Builds a table holding `raw` at the dotted `key_path`.
+fn override_table(key_path: &str, raw: &str) -> toml::Table {
+    let value = toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_owned()));
+
+    key_path
+        .rsplit('.')
+        .fold(value, |value, key| {
+            toml::Value::Table(toml::Table::from_iter([(key.to_owned(), value)]))
+        })
+        .as_table()
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Merges the layers of a config, with the environment variables taken from `env` rather than the process environment.
+fn layer<T>(
+    app_name: &str,
+    config_name: Option<&str>,
+    location: &ConfigLocation,
+    sources: &LayerSources,
+    env: impl Iterator<Item = (String, String)>,
+) -> Result<(toml::Table, ProvenanceMap), ConfigError>
+where
+    T: Default + serde::Serialize,
+{
+    let mut document = toml::Table::new();
+    let mut provenance = ProvenanceMap::default();
+
+    apply(
+        &mut document,
+        toml::Table::try_from(T::default())?,
+        &Source::Default,
+        &mut provenance,
+        "",
+    );
+
+    let user_file =
+        crate::config_location(app_name, config_name, ConfigType::Toml.as_str(), location)?;
+    let files = sources
+        .system
+        .iter()
+        .map(|path| (path, Source::System(path.clone())))
+        .chain([(&user_file, Source::UserFile(user_file.clone()))]);
+
+    let mut failures = Vec::new();
+    for (path, source) in files {
+        let table = match std::fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content).map_err(ConfigError::from),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => Err(err.into()),
+        };
+        match table {
+            Ok(table) => apply(&mut document, table, &source, &mut provenance, ""),
+            Err(err) => failures.push((path.clone(), err)),
+        }
+    }
+    ConfigError::from_failures(failures)?;
+
+    if let Some(env_prefix) = &sources.env_prefix {
+        let env_prefix = format!("{env_prefix}_");
+        // Sorted, so the result does not depend on the order of the environment
+        let variables: BTreeMap<String, String> = env
+            .filter(|(name, _)| name.starts_with(&env_prefix))
+            .collect();
+
+        for (name, raw) in variables {
+            let key_path = name
+                .trim_start_matches(&env_prefix)
+                .to_lowercase()
+                .replace("__", ".");
+            apply(
+                &mut document,
+                override_table(&key_path, &raw),
+                &Source::Env(name),
+                &mut provenance,
+                "",
+            );
+        }
+    }
+
+    for (key_path, raw) in &sources.cli {
+        apply(
+            &mut document,
+            override_table(key_path, raw),
+            &Source::Cli,
+            &mut provenance,
+            "",
+        );
+    }
+
+    Ok((document, provenance))
+}
+
+/// Loads a config from stacked layers: the defaults, a system file, the user file, environment variables and command line values. In `toml` format.
+///
+/// Each layer is deep merged over the previous ones, see [`LayerSources`]. The user file is the one [`crate::load_toml`] reads, it is never created or modified.
+/// Use [`explain`] to find out which layer supplied each value.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if the merged config
+/// could not be deserialized. If files of the layers are invalid, a [`ConfigError::Multiple`] error listing every one of them is returned.
+pub fn load_toml_layered<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    sources: &LayerSources,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let (document, _) = layer::<T>(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        sources,
+        std::env::vars(),
+    )?;
+
+    Ok(document.try_into()?)
+}
+
+/// Reports which layer supplied the effective value of every key of a config loaded with [`load_toml_layered`].
+///
+/// Answers "why is this setting X?" questions, by telling whether a value comes from the defaults, the system file, the user file,
+/// an environment variable or the command line.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created. If files of the layers
+/// are invalid, a [`ConfigError::Multiple`] error listing every one of them is returned.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{LayerSources, Source};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct TestConfig {
+///    level: u32,
+///    verbose: bool,
+/// }
+///
+/// let sources = LayerSources {
+///     cli: vec![(String::from("verbose"), String::from("true"))],
+///     ..LayerSources::default()
+/// };
+///
+/// let provenance = binconf::explain::<TestConfig>("test-binconf-explain", None, Config, &sources).unwrap();
+///
+/// assert_eq!(provenance.get("level"), Some(&Source::Default));
+/// assert_eq!(provenance.get("verbose"), Some(&Source::Cli));
+/// ```
+pub fn explain<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    sources: &LayerSources,
+) -> Result<ProvenanceMap, ConfigError>
+where
+    T: Default + serde::Serialize,
+{
+    let (_, provenance) = layer::<T>(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        sources,
+        std::env::vars(),
+    )?;
+
+    Ok(provenance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::{Deserialize, Serialize};
+    use ConfigLocation::Config;
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+    struct Ui {
+        theme: String,
+        size: u32,
+    }
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+    struct TestConfig {
+        name: String,
+        level: u32,
+        ui: Ui,
+    }
+
+    #[test]
+    fn explain_reports_each_layer() {
+        let app_name = "test-binconf-explain_reports_each_layer";
+
+        let system = std::env::temp_dir().join("test-binconf-explain_reports_each_layer.toml");
+        std::fs::write(&system, "name = \"system\"\n[ui]\ntheme = \"dark\"\n").unwrap();
+
+        let user_file =
+            crate::get_configuration_path(app_name, None, ConfigType::Toml, Config).unwrap();
+        std::fs::write(&user_file, "[ui]\nsize = 14\n").unwrap();
+
+        // Only the variables with the prefix are layers
+        let env = [
+            ("TEST_BINCONF_EXPLAIN_LEVEL", "3"),
+            ("OTHER_APP_LEVEL", "4"),
+        ]
+        .map(|(name, value)| (name.to_owned(), value.to_owned()));
+
+        let sources = LayerSources {
+            system: Some(system.clone()),
+            env_prefix: Some(String::from("TEST_BINCONF_EXPLAIN")),
+            cli: vec![(String::from("ui.theme"), String::from("light"))],
+        };
+
+        let (document, provenance) =
+            layer::<TestConfig>(app_name, None, &Config, &sources, env.into_iter()).unwrap();
+        assert_eq!(provenance.get("name"), Some(&Source::System(system)));
+        assert_eq!(
+            provenance.get("level"),
+            Some(&Source::Env(String::from("TEST_BINCONF_EXPLAIN_LEVEL")))
+        );
+        assert_eq!(
+            provenance.get("ui.size"),
+            Some(&Source::UserFile(user_file))
+        );
+        assert_eq!(provenance.get("ui.theme"), Some(&Source::Cli));
+        assert_eq!(provenance.iter().count(), 4);
+
+        let config: TestConfig = document.try_into().unwrap();
+        assert_eq!(
+            config,
+            TestConfig {
+                name: String::from("system"),
+                level: 3,
+                ui: Ui {
+                    theme: String::from("light"),
+                    size: 14,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_layers_are_all_reported() {
+        let app_name = "test-binconf-invalid_layers_are_all_reported";
+
+        let system = std::env::temp_dir().join("test-binconf-invalid_layers_are_all_reported.toml");
+        std::fs::write(&system, "name = \n").unwrap();
+
+        let user_file =
+            crate::get_configuration_path(app_name, None, ConfigType::Toml, Config).unwrap();
+        std::fs::write(&user_file, "[ui\n").unwrap();
+
+        let sources = LayerSources {
+            system: Some(system.clone()),
+            ..LayerSources::default()
+        };
+
+        let Err(ConfigError::Multiple(failures)) =
+            load_toml_layered::<TestConfig>(app_name, None, Config, &sources)
+        else {
+            panic!("both layers should be reported");
+        };
+        let paths: Vec<_> = failures.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(paths, vec![system, user_file]);
+        assert!(failures.iter().all(|(_, err)| err.is_corruption()));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::Path;
+
+/// The key holding the name of the config to inherit from.
+const EXTENDS_KEY: &str = "extends";
+
+/// Loads a config file that can inherit from other configs of the same app, from the config, cache, cwd, or local data directory of the current user. In `toml` format.
+///
+/// If the document has a top level `extends = "<name>"` key, the config with that name in the same app directory is loaded first
+/// and the document is deep merged over it. The parent can extend another config too, the chain is resolved recursively.
+/// A name without extension refers to a `toml` file (`"base"` loads `base.toml`).
+///
+/// The `extends` key itself is removed before deserializing, so `T` does not need to declare it.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if a parent config does not exist,
+/// or if something went wrong while deserializing the config.
+///
+/// If the configs extend each other in a loop, a [`ConfigError::InheritanceCycle`] error is returned with the chain of names.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Profile {
+///    host: String,
+///    verbose: bool,
+/// }
+///
+/// let base = binconf::get_configuration_path("test-binconf-read-extends", Some("base.toml"), ConfigType::Toml, Config).unwrap();
+/// std::fs::write(base, "host = \"example.com\"\nverbose = false\n").unwrap();
+///
+/// let debug = binconf::get_configuration_path("test-binconf-read-extends", Some("debug.toml"), ConfigType::Toml, Config).unwrap();
+/// std::fs::write(debug, "extends = \"base\"\nverbose = true\n").unwrap();
+///
+/// let profile = binconf::load_toml_extends::<Profile>("test-binconf-read-extends", Some("debug.toml"), Config, false).unwrap();
+/// assert_eq!(profile.host, "example.com");
+/// assert!(profile.verbose);
+/// ```
+pub fn load_toml_extends<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Toml.as_str(),
+        location.as_ref(),
+    )?;
+
+    let save_default_conf = || {
+        let default_config = T::default();
+        let toml_str = toml::to_string_pretty(&default_config)?;
+        if !crate::freeze::is_frozen_path(&config_file_path) {
+            crate::reset::back_up_corrupt(&config_file_path);
+            crate::save_config_str(&config_file_path, &toml_str)?;
+        }
+        Ok(default_config)
+    };
+
+    if !config_file_path.try_exists()? {
+        return save_default_conf();
+    }
+
+    let mut chain = Vec::new();
+    let config = match resolve(&config_file_path, &mut chain) {
+        Ok(config) => config,
+        Err(err @ ConfigError::TomlDe(_)) => {
+            if reset_conf_on_err {
+                return save_default_conf();
+            }
+            return Err(err);
+        }
+        Err(err) => return Err(err),
+    };
+
+    match config.try_into() {
+        Ok(config) => Ok(config),
+        Err(err) => {
+            if reset_conf_on_err {
+                return save_default_conf();
+            }
+            Err(err.into())
+        }
+    }
+}
+
+/// Loads the document at `config_file_path` merged over the chain of configs it extends.
+///
+/// `chain` holds the file names already visited, to detect cycles.
+fn resolve(config_file_path: &Path, chain: &mut Vec<String>) -> Result<toml::Value, ConfigError> {
+    let file_name = config_file_path
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    if chain.contains(&file_name) {
+        chain.push(file_name);
+        return Err(ConfigError::InheritanceCycle(std::mem::take(chain)));
+    }
+    chain.push(file_name);
+
+    let mut document = toml::from_str::<toml::Table>(&std::fs::read_to_string(config_file_path)?)?;
+
+    let Some(parent) = document.remove(EXTENDS_KEY) else {
+        return Ok(toml::Value::Table(document));
+    };
+    let Some(parent) = parent.as_str() else {
+        return Err(ConfigError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("`{EXTENDS_KEY}` must be a string"),
+        )));
+    };
+
+    let parent_file_name = if Path::new(parent).extension().is_some() {
+        parent.to_owned()
+    } else {
+        format!("{parent}.{}", ConfigType::Toml.as_str())
+    };
+
+    let mut config = resolve(&config_file_path.with_file_name(parent_file_name), chain)?;
+    crate::merge::merge_toml(&mut config, toml::Value::Table(document));
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::{Deserialize, Serialize};
+    use ConfigLocation::Config;
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Profile {
+        host: String,
+        port: u16,
+        verbose: bool,
+    }
+
+    fn write_profile(name: &str, content: &str) {
+        let path = crate::get_configuration_path(
+            "test-binconf-extends-toml",
+            Some(name),
+            ConfigType::Toml,
+            Config,
+        )
+        .unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn extends_is_resolved_recursively() {
+        write_profile("base.toml", "host = \"base\"\nport = 80\nverbose = false\n");
+        write_profile("staging.toml", "extends = \"base\"\nport = 8080\n");
+        write_profile("debug.toml", "extends = \"staging.toml\"\nverbose = true\n");
+
+        let profile: Profile = load_toml_extends(
+            "test-binconf-extends-toml",
+            Some("debug.toml"),
+            Config,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            profile,
+            Profile {
+                host: String::from("base"),
+                port: 8080,
+                verbose: true,
+            }
+        );
+    }
+
+    #[test]
+    fn extends_detects_cycles() {
+        write_profile("cycle-a.toml", "extends = \"cycle-b\"\n");
+        write_profile("cycle-b.toml", "extends = \"cycle-a\"\n");
+
+        let profile = load_toml_extends::<Profile>(
+            "test-binconf-extends-toml",
+            Some("cycle-a.toml"),
+            Config,
+            false,
+        );
+        assert!(matches!(
+            profile,
+            Err(ConfigError::InheritanceCycle(chain)) if chain.len() == 3
+        ));
+    }
+}
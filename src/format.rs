@@ -0,0 +1,263 @@
+use crate::{ConfigError, ConfigLocation};
+
+/// A config file format, to load and store configs in a format this crate does not provide.
+///
+/// Implement it for your format and use [`load_with`] and [`store_with`], which resolve the path of the config file and reset it on errors
+/// like the `load` and `store` functions of the built-in formats.
+///
+/// Errors of the underlying serializer are returned as [`ConfigError::FormatSer`] and [`ConfigError::FormatDe`].
+pub trait ConfigFormat {
+    /// Extension of the config files, without the leading dot, used for the default file name `<app>.<extension>`.
+    fn extension(&self) -> &str;
+
+    /// Serializes `data` into the content of a config file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` can't be represented in the format.
+    fn serialize<T>(&self, data: &T) -> Result<Vec<u8>, ConfigError>
+    where
+        T: serde::Serialize;
+
+    /// Deserializes the content of a config file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` is not a valid config of type `T`.
+    fn deserialize<T>(&self, bytes: &[u8]) -> Result<T, ConfigError>
+    where
+        T: serde::de::DeserializeOwned;
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user. In a custom [`ConfigFormat`].
+///
+/// It will load a config file, deserialize it with `format` and return it. If the file does not exist, the default config is stored and returned.
+///
+/// If the flag `reset_conf_on_err` is set to `true`, the config file will be reset to the default config if
+/// the deserialization fails, if set to `false` an error will be returned. An empty file holds no config, the default config is returned.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigError, ConfigFormat};
+///
+/// /// `bincode` behind a magic number.
+/// struct Tagged;
+///
+/// impl ConfigFormat for Tagged {
+///     fn extension(&self) -> &str {
+///         "tag"
+///     }
+///
+///     fn serialize<T: serde::Serialize>(&self, data: &T) -> Result<Vec<u8>, ConfigError> {
+///         let mut bytes = b"TAG1".to_vec();
+///         bincode::serialize_into(&mut bytes, data).map_err(|err| ConfigError::FormatSer(err))?;
+///         Ok(bytes)
+///     }
+///
+///     fn deserialize<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, ConfigError> {
+///         let payload = bytes.strip_prefix(b"TAG1").ok_or_else(|| ConfigError::FormatDe("bad magic number".into()))?;
+///         bincode::deserialize(payload).map_err(|err| ConfigError::FormatDe(err))
+///     }
+/// }
+///
+/// binconf::store_with("test-binconf-load-with", None, Config, vec![1u8, 2, 3], &Tagged).unwrap();
+///
+/// let config = binconf::load_with::<Vec<u8>>("test-binconf-load-with", None, Config, false, &Tagged).unwrap();
+/// assert_eq!(config, vec![1, 2, 3]);
+/// ```
+pub fn load_with<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+    format: &impl ConfigFormat,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        format.extension(),
+        location.as_ref(),
+    )?;
+
+    let save_default_conf = || {
+        let default_config = T::default();
+        let bytes = format.serialize(&default_config)?;
+        if !crate::freeze::is_frozen_path(&config_file_path) {
+            crate::reset::back_up_corrupt(&config_file_path);
+            crate::save_config_atomic(&config_file_path, &bytes)?;
+        }
+        Ok(default_config)
+    };
+
+    if !config_file_path.try_exists()? {
+        let default_config = T::default();
+        let bytes = format.serialize(&default_config)?;
+        if crate::freeze::is_frozen_path(&config_file_path)
+            || crate::save_config_new(&config_file_path, &bytes)?
+        {
+            return Ok(default_config);
+        }
+        // Created by another process in the meantime, it is loaded below
+    }
+
+    let started = std::time::Instant::now();
+    let bytes = std::fs::read(&config_file_path)?;
+    crate::slow_io::report(
+        crate::IoOperation::Load,
+        &config_file_path,
+        bytes.len(),
+        started,
+    );
+    // An empty file is left by `touch` or an interrupted write, it holds no config rather than a damaged one
+    if bytes.is_empty() {
+        return if reset_conf_on_err {
+            save_default_conf()
+        } else {
+            Ok(Default::default())
+        };
+    }
+
+    match format.deserialize(&bytes) {
+        Ok(config) => Ok(config),
+        Err(err) if reset_conf_on_err => {
+            crate::reset::report(&config_file_path, &String::from_utf8_lossy(&bytes), err);
+            save_default_conf()
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Stores a config file in the config, cache, cwd, or local data directory of the current user. In a custom [`ConfigFormat`].
+///
+/// It will store a config file, serializing it with `format`. See [`load_with`].
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+pub fn store_with<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+    format: &impl ConfigFormat,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        format.extension(),
+        location.as_ref(),
+    )?;
+
+    let bytes = format.serialize(&data)?;
+    crate::save_config_atomic(&config_file_path, &bytes)?;
+
+    #[cfg(feature = "json-conf")]
+    crate::changelog::record(&config_file_path, &data)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use crate::ConfigLocation::Cache;
+
+    /// One `key=value` pair per line, through `json` values.
+    struct Lines;
+
+    impl ConfigFormat for Lines {
+        fn extension(&self) -> &str {
+            "lines"
+        }
+
+        fn serialize<T: serde::Serialize>(&self, data: &T) -> Result<Vec<u8>, ConfigError> {
+            let serde_json::Value::Object(map) = serde_json::to_value(data)? else {
+                return Err(ConfigError::FormatSer("not a map".into()));
+            };
+            Ok(map
+                .iter()
+                .map(|(key, value)| format!("{key}={value}\n"))
+                .collect::<String>()
+                .into_bytes())
+        }
+
+        fn deserialize<T: serde::de::DeserializeOwned>(
+            &self,
+            bytes: &[u8],
+        ) -> Result<T, ConfigError> {
+            let text =
+                std::str::from_utf8(bytes).map_err(|err| ConfigError::FormatDe(err.into()))?;
+            let mut map = serde_json::Map::new();
+            for line in text.lines() {
+                let (key, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| ConfigError::FormatDe(format!("no `=` in {line:?}").into()))?;
+                map.insert(key.to_owned(), serde_json::from_str(value)?);
+            }
+            serde_json::from_value(serde_json::Value::Object(map))
+                .map_err(|err| ConfigError::FormatDe(err.into()))
+        }
+    }
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Settings {
+        name: String,
+        retries: u32,
+    }
+
+    #[test]
+    fn custom_format_round_trip_and_reset() {
+        let app = "test-binconf-custom_format_round_trip_and_reset";
+        let path = crate::config_location(app, None, "lines", &Cache).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            load_with::<Settings>(app, None, Cache, false, &Lines).unwrap(),
+            Settings::default()
+        );
+
+        let settings = Settings {
+            name: String::from("lines"),
+            retries: 3,
+        };
+        store_with(app, None, Cache, &settings, &Lines).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "name=\"lines\"\nretries=3\n"
+        );
+        assert_eq!(
+            load_with::<Settings>(app, None, Cache, false, &Lines).unwrap(),
+            settings
+        );
+
+        std::fs::write(&path, "garbage").unwrap();
+        let err = load_with::<Settings>(app, None, Cache, false, &Lines).unwrap_err();
+        assert!(matches!(err, ConfigError::FormatDe(_)));
+        assert!(err.is_corruption());
+
+        assert_eq!(
+            load_with::<Settings>(app, None, Cache, true, &Lines).unwrap(),
+            Settings::default()
+        );
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "name=\"\"\nretries=0\n"
+        );
+    }
+}
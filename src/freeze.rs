@@ -0,0 +1,113 @@
+use crate::ConfigError;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Names of the apps frozen with [`freeze`].
+static FROZEN: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Makes the configs of `app_name` read-only for the rest of the process.
+///
+/// Every `store` function writing to the app directory, in any [`ConfigLocation`](crate::ConfigLocation), then fails with [`ConfigError::Frozen`],
+/// and the `load` functions return the default config without writing it when the config file is missing or reset.
+/// Useful for "--no-save" CLI modes, and for tests that must not leave anything on disk.
+///
+/// There is no way to unfreeze an app.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigError;
+///
+/// binconf::freeze("test-binconf-freeze");
+///
+/// let config = binconf::load_bin::<u32>("test-binconf-freeze", None, Config, false).unwrap();
+/// assert_eq!(config, 0);
+///
+/// let result = binconf::store_bin("test-binconf-freeze", None, Config, 42u32);
+/// assert!(matches!(result, Err(ConfigError::Frozen)));
+/// ```
+pub fn freeze(app_name: impl AsRef<str>) {
+    let mut frozen = FROZEN
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    if !is_frozen_in(&frozen, app_name.as_ref()) {
+        frozen.push(app_name.as_ref().to_owned());
+    }
+}
+
+/// Returns `true` if [`freeze`] was called for `app_name`.
+pub fn is_frozen(app_name: impl AsRef<str>) -> bool {
+    is_frozen_in(
+        &FROZEN
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner),
+        app_name.as_ref(),
+    )
+}
+
+fn is_frozen_in(frozen: &[String], app_name: &str) -> bool {
+    frozen.iter().any(|frozen| frozen == app_name)
+}
+
+/// Returns `true` if `path` is inside the directory of a frozen app, in any location.
+pub(crate) fn is_frozen_path(path: &Path) -> bool {
+    let frozen = FROZEN
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    if frozen.is_empty() {
+        return false;
+    }
+
+    // The same directories as `crate::app_dir`, without creating them
+    frozen.iter().any(|app_name| {
+        crate::resolution::app_dirs(app_name)
+            .iter()
+            .any(|app_dir| path.starts_with(app_dir))
+    })
+}
+
+/// Fails with [`ConfigError::Frozen`] if `path` belongs to a frozen app.
+pub(crate) fn ensure_not_frozen(path: &Path) -> Result<(), ConfigError> {
+    if is_frozen_path(path) {
+        return Err(ConfigError::Frozen);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use crate::ConfigLocation::{Cache, Config};
+
+    #[test]
+    fn frozen_app_is_never_written() {
+        let app = "test-binconf-frozen_app_is_never_written";
+        freeze(app);
+        assert!(is_frozen(app));
+        assert!(!is_frozen("test-binconf-not-frozen"));
+
+        let config: std::collections::HashMap<String, u8> =
+            crate::load_toml(app, None, Config, false).unwrap();
+        assert!(config.is_empty());
+        let config: Vec<u8> = crate::load_json(app, None, Cache, false).unwrap();
+        assert!(config.is_empty());
+
+        assert!(matches!(
+            crate::store_yaml(app, None, Config, 1),
+            Err(ConfigError::Frozen)
+        ));
+        assert!(matches!(
+            crate::store_bin(app, None, Cache, 1),
+            Err(ConfigError::Frozen)
+        ));
+
+        let app_dir = dirs::config_dir().unwrap().join(app);
+        assert!(!app_dir.exists());
+    }
+}
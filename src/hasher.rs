@@ -0,0 +1,63 @@
+use xxhash_rust::xxh3::xxh3_128;
+
+/// Integrity hash protecting binary config files.
+///
+/// The digest is stored in front of the serialized data and verified on load. The default is [`Xxh3Hasher`],
+/// implement this trait to use another algorithm, like a cryptographic hash, a hardware accelerated checksum or a MAC,
+/// with [`crate::store_bin_with_hasher`] and [`crate::load_bin_with_hasher`]. [`NoHasher`] disables the check.
+///
+/// A file must be loaded with the same hasher it was stored with, otherwise a [`crate::ConfigError::HashAlgorithmMismatch`]
+/// error is returned, or a [`crate::ConfigError::HashMismatch`] error if both hashers have the same [`Hasher::algorithm_id`].
+pub trait Hasher: Send + Sync {
+    /// Length of the digests returned by [`Hasher::digest`], in bytes.
+    fn digest_length(&self) -> usize;
+
+    /// Identifies the algorithm in the header of the files, so a file stored with another hasher is reported as such.
+    ///
+    /// `0` is [`NoHasher`] and `1` is [`Xxh3Hasher`], the default of `255` is shared by all the custom hashers.
+    fn algorithm_id(&self) -> u8 {
+        255
+    }
+
+    /// Returns the digest of `data`. It must always be [`Hasher::digest_length`] bytes long.
+    fn digest(&self, data: &[u8]) -> Vec<u8>;
+}
+
+/// The default [`Hasher`], a little endian `xxh3_128` hash.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Xxh3Hasher;
+
+impl Hasher for Xxh3Hasher {
+    fn digest_length(&self) -> usize {
+        crate::binary_conf::HASH_BYTE_LENGTH
+    }
+
+    fn algorithm_id(&self) -> u8 {
+        1
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        xxh3_128(data).to_le_bytes().to_vec()
+    }
+}
+
+/// A [`Hasher`] that disables the integrity check, for targets where hashing the config costs too much.
+///
+/// No digest is stored in front of the data, so the file is the header followed by the bare `bincode` payload, and a
+/// damaged file is only detected if its length changed or it can't be deserialized anymore.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NoHasher;
+
+impl Hasher for NoHasher {
+    fn digest_length(&self) -> usize {
+        0
+    }
+
+    fn algorithm_id(&self) -> u8 {
+        0
+    }
+
+    fn digest(&self, _data: &[u8]) -> Vec<u8> {
+        Vec::new()
+    }
+}
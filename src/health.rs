@@ -0,0 +1,288 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::PathBuf;
+
+/// What [`health_check`] should verify.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthCheckOptions {
+    /// Minimum free space required on the filesystem holding the app directory, in bytes.
+    pub min_free_space: u64,
+    /// File names of the config files that must exist and be intact. Their type is taken from the extension.
+    pub configs: Vec<String>,
+}
+
+impl Default for HealthCheckOptions {
+    fn default() -> Self {
+        HealthCheckOptions {
+            min_free_space: 10 * 1024 * 1024,
+            configs: Vec::new(),
+        }
+    }
+}
+
+/// A problem found by [`health_check`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthIssue {
+    /// The app directory could not be created or read.
+    DirectoryInaccessible(String),
+    /// A file could not be created in the app directory.
+    NotWritable(String),
+    /// The free space is below [`HealthCheckOptions::min_free_space`].
+    LowDiskSpace { available: u64, required: u64 },
+    /// A config file could not be read.
+    Unreadable { file_name: String, error: String },
+    /// A config file is missing.
+    Missing(String),
+    /// A config file failed its integrity check.
+    Corrupted { file_name: String, error: String },
+    /// A config file has an extension that does not match any enabled config type.
+    UnknownFormat(String),
+}
+
+impl std::fmt::Display for HealthIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            HealthIssue::DirectoryInaccessible(err) => {
+                write!(f, "App directory is not accessible: {err}")
+            }
+            HealthIssue::NotWritable(err) => write!(f, "App directory is not writable: {err}"),
+            HealthIssue::LowDiskSpace {
+                available,
+                required,
+            } => write!(
+                f,
+                "Low disk space: {available} bytes available, {required} bytes required"
+            ),
+            HealthIssue::Unreadable { file_name, error } => {
+                write!(f, "`{file_name}` is not readable: {error}")
+            }
+            HealthIssue::Missing(file_name) => write!(f, "`{file_name}` is missing"),
+            HealthIssue::Corrupted { file_name, error } => {
+                write!(f, "`{file_name}` is corrupted: {error}")
+            }
+            HealthIssue::UnknownFormat(file_name) => {
+                write!(f, "`{file_name}` has an unknown config format")
+            }
+        }
+    }
+}
+
+/// The result of [`health_check`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    pub app_dir: PathBuf,
+    /// Free space on the filesystem holding the app directory, if it could be determined.
+    pub available_space: Option<u64>,
+    pub issues: Vec<HealthIssue>,
+}
+
+impl HealthReport {
+    /// Returns `true` if no issue was found.
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Checks that an app can load and store its configs, in the config, cache, cwd, or local data directory of the current user.
+///
+/// Verifies that the app directory is accessible and writable, and not frozen with [`crate::freeze`], that there is enough free space, and that every config listed in
+/// [`HealthCheckOptions::configs`] exists and passes its integrity check (hash for binary files, parsing for text files).
+///
+/// Meant to be called at startup, so daemons can fail fast with actionable diagnostics instead of failing later in the middle of a store.
+///
+/// # Errors
+///
+/// This function only returns an error if the config, cache or local data directory itself could not be found. Every other problem is reported in [`HealthReport::issues`].
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::HealthCheckOptions;
+///
+/// let report = binconf::health_check("test-binconf-health", Config, &HealthCheckOptions::default()).unwrap();
+///
+/// for issue in &report.issues {
+///     eprintln!("{issue}");
+/// }
+/// ```
+pub fn health_check(
+    app_name: impl AsRef<str>,
+    location: impl AsRef<ConfigLocation>,
+    options: &HealthCheckOptions,
+) -> Result<HealthReport, ConfigError> {
+    let app_name = app_name.as_ref();
+    let location = location.as_ref();
+    let mut issues = Vec::new();
+
+    let app_dir = match crate::app_dir(app_name, location) {
+        Ok(app_dir) => app_dir,
+        Err(ConfigError::Io(err)) if err.kind() != std::io::ErrorKind::NotFound => {
+            return Ok(HealthReport {
+                app_dir: PathBuf::new(),
+                available_space: None,
+                issues: vec![HealthIssue::DirectoryInaccessible(err.to_string())],
+            });
+        }
+        Err(err) => return Err(err),
+    };
+
+    if let Err(err) = std::fs::read_dir(&app_dir) {
+        issues.push(HealthIssue::DirectoryInaccessible(err.to_string()));
+    }
+
+    let probe_path = app_dir.join(".binconf-health-probe.tmp");
+    // A frozen app must not be written to, not even to probe it
+    match crate::freeze::ensure_not_frozen(&probe_path)
+        .and_then(|()| Ok(std::fs::write(&probe_path, b"")?))
+    {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe_path);
+        }
+        Err(err) => issues.push(HealthIssue::NotWritable(err.to_string())),
+    }
+
+    let available_space = crate::disk::available_space(&app_dir);
+    if let Some(available) = available_space {
+        if available < options.min_free_space {
+            issues.push(HealthIssue::LowDiskSpace {
+                available,
+                required: options.min_free_space,
+            });
+        }
+    }
+
+    for file_name in &options.configs {
+        let Some(config_type) = std::path::Path::new(file_name)
+            .extension()
+            .and_then(|extension| ConfigType::from_extension(&extension.to_string_lossy()))
+        else {
+            issues.push(HealthIssue::UnknownFormat(file_name.clone()));
+            continue;
+        };
+
+        // Resolved like `load` does, the layout may route the file to a subdirectory
+        let config_file_path =
+            match crate::config_location(app_name, Some(file_name), config_type.as_str(), location)
+            {
+                Ok(config_file_path) => config_file_path,
+                Err(err) => {
+                    issues.push(HealthIssue::Unreadable {
+                        file_name: file_name.clone(),
+                        error: err.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+        let data = match std::fs::read(config_file_path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                issues.push(HealthIssue::Missing(file_name.clone()));
+                continue;
+            }
+            Err(err) => {
+                issues.push(HealthIssue::Unreadable {
+                    file_name: file_name.clone(),
+                    error: err.to_string(),
+                });
+                continue;
+            }
+        };
+
+        if let Err(err) = crate::transfer::validate_raw(&data, &config_type) {
+            issues.push(HealthIssue::Corrupted {
+                file_name: file_name.clone(),
+                error: err.to_string(),
+            });
+        }
+    }
+
+    Ok(HealthReport {
+        app_dir,
+        available_space,
+        issues,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[test]
+    #[cfg(feature = "binary-conf")]
+    fn health_check_reports_config_issues() {
+        crate::store_bin("test-binconf-health_check-bin", None, Config, 1u8).unwrap();
+        let app_dir = crate::app_dir("test-binconf-health_check-bin", &Config).unwrap();
+        std::fs::write(app_dir.join("broken.bin"), b"not a binconf file").unwrap();
+
+        let options = HealthCheckOptions {
+            min_free_space: 0,
+            configs: vec![
+                String::from("test-binconf-health_check-bin.bin"),
+                String::from("broken.bin"),
+                String::from("missing.bin"),
+                String::from("unknown.ext"),
+            ],
+        };
+        let report = health_check("test-binconf-health_check-bin", Config, &options).unwrap();
+
+        assert_eq!(report.issues.len(), 3);
+        assert!(matches!(
+            &report.issues[0],
+            HealthIssue::Corrupted { file_name, .. } if file_name == "broken.bin"
+        ));
+        assert_eq!(
+            report.issues[1],
+            HealthIssue::Missing(String::from("missing.bin"))
+        );
+        assert_eq!(
+            report.issues[2],
+            HealthIssue::UnknownFormat(String::from("unknown.ext"))
+        );
+    }
+
+    #[test]
+    fn health_check_reports_low_disk_space() {
+        let options = HealthCheckOptions {
+            min_free_space: u64::MAX,
+            configs: Vec::new(),
+        };
+        let report = health_check("test-binconf-health_check-space", Config, &options).unwrap();
+
+        if report.available_space.is_some() {
+            assert!(matches!(
+                report.issues.as_slice(),
+                [HealthIssue::LowDiskSpace { .. }]
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "binary-conf")]
+    fn health_check_follows_the_layout_and_respects_freeze() {
+        let app = "test-binconf-health_check-frozen";
+        crate::set_layout_policy(
+            app,
+            crate::LayoutPolicy {
+                subdirectories: vec![(ConfigType::Bin, PathBuf::from("state"))],
+                ..crate::LayoutPolicy::default()
+            },
+        );
+        crate::store_bin(app, "state.bin", Config, 1u8).unwrap();
+        crate::freeze(app);
+
+        let options = HealthCheckOptions {
+            min_free_space: 0,
+            configs: vec![String::from("state.bin")],
+        };
+        let report = health_check(app, Config, &options).unwrap();
+
+        assert_eq!(
+            report.issues,
+            vec![HealthIssue::NotWritable(ConfigError::Frozen.to_string())]
+        );
+        assert!(!report.app_dir.join(".binconf-health-probe.tmp").exists());
+    }
+}
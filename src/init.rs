@@ -0,0 +1,154 @@
+/// Generates typed `load()`, `save()` and `path()` functions for a config struct, wired to an app name, a format and a location.
+///
+/// The format is one of `Bin`, `Toml`, `Json`, `Yaml` or `Ron`, and its feature must be enabled. The location is one of the [`crate::ConfigLocation`] variants.
+/// With the `watch` feature, a `watch()` function calling back with the reloaded config is generated too.
+///
+/// The functions are generated in the module the macro is called from, so it is usually called from a dedicated module.
+///
+/// - `load() -> Result<Config, ConfigError>` loads the config, creating it with its defaults if it does not exist.
+/// - `save(&Config) -> Result<(), ConfigError>` stores the config.
+/// - `path() -> Result<PathBuf, ConfigError>` returns the path of the config file.
+/// - `watch(FnMut(Config)) -> Result<WatchHandle, ConfigError>` watches the config file, see [`crate::watch_config`].
+///
+/// # Example
+///
+/// ```
+/// mod settings {
+///     use serde::{Deserialize, Serialize};
+///
+///     #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+///     pub struct Settings {
+///         pub volume: u8,
+///     }
+///
+///     binconf::init!(Settings, "test-binconf-init", Bin, Config);
+/// }
+///
+/// settings::save(&settings::Settings { volume: 7 }).unwrap();
+///
+/// assert_eq!(settings::load().unwrap(), settings::Settings { volume: 7 });
+/// assert!(settings::path().unwrap().ends_with("test-binconf-init/test-binconf-init.bin"));
+/// ```
+#[macro_export]
+macro_rules! init {
+    ($config:ty, $app_name:expr, Bin, $location:ident) => {
+        $crate::__init_functions!($config, $app_name, Bin, $location, load_bin, store_bin);
+    };
+    ($config:ty, $app_name:expr, Toml, $location:ident) => {
+        $crate::__init_functions!($config, $app_name, Toml, $location, load_toml, store_toml);
+    };
+    ($config:ty, $app_name:expr, Json, $location:ident) => {
+        $crate::__init_functions!($config, $app_name, Json, $location, load_json, store_json);
+    };
+    ($config:ty, $app_name:expr, Yaml, $location:ident) => {
+        $crate::__init_functions!($config, $app_name, Yaml, $location, load_yaml, store_yaml);
+    };
+    ($config:ty, $app_name:expr, Ron, $location:ident) => {
+        $crate::__init_functions!($config, $app_name, Ron, $location, load_ron, store_ron);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __init_functions {
+    ($config:ty, $app_name:expr, $config_type:ident, $location:ident, $load:ident, $store:ident) => {
+        /// Loads the config, creating it with its defaults if it does not exist.
+        #[allow(dead_code)]
+        pub fn load() -> ::std::result::Result<$config, $crate::ConfigError> {
+            $crate::$load::<$config>($app_name, None, $crate::ConfigLocation::$location, false)
+        }
+
+        /// Stores the config.
+        #[allow(dead_code)]
+        pub fn save(config: &$config) -> ::std::result::Result<(), $crate::ConfigError> {
+            $crate::$store($app_name, None, $crate::ConfigLocation::$location, config)
+        }
+
+        /// Returns the path of the config file.
+        #[allow(dead_code)]
+        pub fn path() -> ::std::result::Result<::std::path::PathBuf, $crate::ConfigError> {
+            $crate::get_configuration_path(
+                $app_name,
+                None,
+                $crate::ConfigType::$config_type,
+                $crate::ConfigLocation::$location,
+            )
+        }
+
+        $crate::__init_watch!($config, $app_name, $config_type, $location);
+    };
+}
+
+#[cfg(feature = "watch")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __init_watch {
+    ($config:ty, $app_name:expr, $config_type:ident, $location:ident) => {
+        /// Watches the config file, calling `callback` with the reloaded config every time it is modified.
+        #[allow(dead_code)]
+        pub fn watch(
+            callback: impl FnMut($config) + Send + 'static,
+        ) -> ::std::result::Result<$crate::WatchHandle, $crate::ConfigError> {
+            $crate::watch_config::<$config, _>(
+                $app_name,
+                None,
+                $crate::ConfigType::$config_type,
+                $crate::ConfigLocation::$location,
+                callback,
+            )
+        }
+    };
+}
+
+#[cfg(not(feature = "watch"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __init_watch {
+    ($config:ty, $app_name:expr, $config_type:ident, $location:ident) => {};
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Settings {
+        theme: String,
+    }
+
+    mod settings {
+        crate::init!(
+            super::Settings,
+            "test-binconf-init_generates_functions",
+            Toml,
+            Config
+        );
+    }
+
+    #[test]
+    fn init_generates_functions() {
+        assert_eq!(settings::load().unwrap(), Settings::default());
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let handle = settings::watch(move |config| sender.send(config).unwrap()).unwrap();
+
+        let config = Settings {
+            theme: String::from("dark"),
+        };
+        settings::save(&config).unwrap();
+
+        assert_eq!(settings::load().unwrap(), config);
+        assert!(settings::path()
+            .unwrap()
+            .ends_with("test-binconf-init_generates_functions.toml"));
+        assert_eq!(
+            receiver
+                .recv_timeout(std::time::Duration::from_secs(5))
+                .unwrap(),
+            config
+        );
+
+        handle.stop();
+    }
+}
@@ -1,5 +1,5 @@
+use crate::progress::read_to_string;
 use crate::{ConfigError, ConfigLocation, ConfigType};
-use std::{fs::read_to_string, io::Write};
 
 /// Loads a config file from the config, cache, cwd, or local data directory of the current user. In `json` format.
 ///
@@ -8,11 +8,14 @@ use std::{fs::read_to_string, io::Write};
 /// If the flag `reset_conf_on_err` is set to `true`, the config file will be reset to the default config if
 /// the deserialization fails, if set to `false` an error will be returned.
 ///
+/// An empty file, as left by `touch` or an interrupted write, is not an error: the default config is returned,
+/// and written to the file if `reset_conf_on_err` is `true`.
+///
 /// # Errors
 ///
 /// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
 ///
-/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config.
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
 ///
 /// # Example
 ///
@@ -38,35 +41,214 @@ pub fn load_json<'a, T>(
 where
     T: Default + serde::Serialize + serde::de::DeserializeOwned,
 {
-    let config_file_path = crate::config_location(
+    load_json_internal(
         app_name.as_ref(),
         config_name.into(),
-        ConfigType::Json.as_str(),
         location.as_ref(),
-    )?;
+        reset_conf_on_err,
+        false,
+        &T::default,
+    )
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user. **Rejecting trailing data and duplicate keys**. In `json` format.
+///
+/// Works like [`load_json`], but if anything other than whitespace follows the JSON document (for example a second concatenated document),
+/// a [`ConfigError::TrailingData`] error is returned instead of a generic parse error.
+///
+/// If the flag `reset_conf_on_err` is set to `true`, the config file will be reset to the default config when trailing data or a duplicate key is found.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, trailing data or a duplicate key is found, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///    test: String,
+///    test_vec: Vec<u8>,
+/// }
+///
+/// let config = binconf::load_json_strict::<TestConfig>("test-binconf-read-strict-json", None, Config, false).unwrap();
+/// assert_eq!(config, TestConfig::default());
+/// ```
+pub fn load_json_strict<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_json_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        reset_conf_on_err,
+        true,
+        &T::default,
+    )
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user, **falling back to a given config**. In `json` format.
+///
+/// Works like [`load_json`], but `fallback` takes the place of the `Default` implementation: it is cloned when the file does not exist
+/// or is reset, and left alone when the file is loaded. Meant for configs with big defaults, like lookup tables, that should not be built
+/// on every load just to be thrown away, and for types that can't implement `Default`.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the fallback config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Lookup {
+///    table: Vec<u32>,
+/// }
+///
+/// let fallback = Lookup { table: (0..1024).collect() };
+///
+/// let config = binconf::load_json_or("test-binconf-read-json-or", None, Config, false, &fallback).unwrap();
+/// assert_eq!(config, fallback);
+/// ```
+pub fn load_json_or<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+    fallback: &T,
+) -> Result<T, ConfigError>
+where
+    T: Clone + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_json_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        reset_conf_on_err,
+        false,
+        &|| fallback.clone(),
+    )
+}
+
+fn load_json_internal<T>(
+    app_name: &str,
+    config_name: Option<&str>,
+    location: &ConfigLocation,
+    reset_conf_on_err: bool,
+    strict: bool,
+    default: &dyn Fn() -> T,
+) -> Result<T, ConfigError>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let config_file_path =
+        crate::config_location(app_name, config_name, ConfigType::Json.as_str(), location)?;
 
     let save_default_conf = || {
-        let default_config = T::default();
-        let json_str = serde_json::to_string_pretty(&default_config)?;
-        crate::save_config_str(&config_file_path, &json_str)?;
+        let default_config = default();
+        let json_str = crate::output::to_json_string(
+            &default_config,
+            &crate::output::output_options_for(&config_file_path),
+        )?;
+        if !crate::freeze::is_frozen_path(&config_file_path) {
+            crate::reset::back_up_corrupt(&config_file_path);
+            crate::save_config_str(&config_file_path, &json_str)?;
+        }
         Ok(default_config)
     };
 
     if !config_file_path.try_exists()? {
-        return save_default_conf();
+        let default_config = default();
+        let json_str = crate::output::to_json_string(
+            &default_config,
+            &crate::output::output_options_for(&config_file_path),
+        )?;
+        if crate::freeze::is_frozen_path(&config_file_path)
+            || crate::save_config_new(&config_file_path, json_str.as_bytes())?
+        {
+            return Ok(default_config);
+        }
+        // Created by another process in the meantime, it is loaded below
     }
 
+    let started = std::time::Instant::now();
     let json_str = read_to_string(&config_file_path)?;
-    let config = match serde_json::from_str::<T>(&json_str) {
+    crate::slow_io::report(
+        crate::IoOperation::Load,
+        &config_file_path,
+        json_str.len(),
+        started,
+    );
+    // An empty file is left by `touch` or an interrupted write, it holds no config rather than a damaged one
+    if json_str.is_empty() {
+        return if reset_conf_on_err {
+            save_default_conf()
+        } else {
+            Ok(default())
+        };
+    }
+    #[cfg(feature = "binary-conf")]
+    if !strict {
+        if let Some(config) = crate::parse_cache::lookup(app_name, &config_file_path, &json_str) {
+            return Ok(config);
+        }
+    }
+    let config = match deserialize_json::<T>(&json_str, strict) {
         Ok(config) => config,
         Err(err) => {
             if reset_conf_on_err {
+                crate::reset::report(&config_file_path, &json_str, err);
                 return save_default_conf();
             }
-            return Err(err.into());
+            return Err(err);
         }
     };
 
+    #[cfg(feature = "binary-conf")]
+    if !strict {
+        crate::parse_cache::store(app_name, &config_file_path, &json_str, &config);
+    }
+
+    Ok(config)
+}
+
+/// Deserializes a `json` string.
+///
+/// In strict mode the input must be fully consumed by the deserializer, otherwise a [`ConfigError::TrailingData`] error is returned.
+/// A key appearing twice in the same mapping returns a [`ConfigError::DuplicateKey`] error.
+fn deserialize_json<T>(json_str: &str, strict: bool) -> Result<T, ConfigError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    if !strict {
+        return Ok(serde_json::from_str::<T>(json_str)?);
+    }
+
+    crate::duplicates::check_json(json_str)?;
+
+    let mut deserializer = serde_json::Deserializer::from_str(json_str);
+    let config = T::deserialize(&mut deserializer)?;
+
+    if deserializer.end().is_err() {
+        return Err(ConfigError::TrailingData);
+    }
+
     Ok(config)
 }
 
@@ -116,17 +298,238 @@ where
         location.as_ref(),
     )?;
 
-    let mut file = std::io::BufWriter::new(std::fs::File::create(config_file_path)?);
+    let json_str = crate::output::to_json_string(
+        &data,
+        &crate::output::output_options_for(&config_file_path),
+    )?;
+    crate::save_config_str(&config_file_path, &json_str)?;
+    crate::changelog::record(&config_file_path, &data)?;
 
-    let json_str = serde_json::to_string_pretty(&data)?;
+    Ok(())
+}
 
-    file.write_all(json_str.as_bytes())?;
+/// Stores a config file like [`store_json`], failing if the file already exists. In `json` format.
+///
+/// Meant for commands like `myapp init` that generate a config and must never overwrite the one of the user.
+/// When several processes store the same config at once, exactly one of them creates the file.
+///
+/// # Errors
+///
+/// This function will return a [`ConfigError::AlreadyExists`] error if the config file already exists. Other errors are returned if the config,
+/// cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigError, ConfigType};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///   test: String,
+/// }
+///
+/// let path = binconf::get_configuration_path("test-binconf-store-json-new", None, ConfigType::Json, Config).unwrap();
+/// # let _ = std::fs::remove_file(&path);
+///
+/// binconf::store_json_new("test-binconf-store-json-new", None, Config, TestConfig::default()).unwrap();
+///
+/// let result = binconf::store_json_new("test-binconf-store-json-new", None, Config, TestConfig::default());
+/// assert!(matches!(result, Err(ConfigError::AlreadyExists(_))));
+/// ```
+pub fn store_json_new<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Json.as_str(),
+        location.as_ref(),
+    )?;
+
+    let json_str = crate::output::to_json_string(
+        &data,
+        &crate::output::output_options_for(&config_file_path),
+    )?;
+    if !crate::save_config_new(&config_file_path, json_str.as_bytes())? {
+        return Err(ConfigError::AlreadyExists(config_file_path));
+    }
+    crate::changelog::record(&config_file_path, &data)?;
 
     Ok(())
 }
 
+/// Loads a list-shaped config file from the config, cache, cwd, or local data directory of the current user. In `json` format.
+///
+/// The file must contain a JSON array. Every element is deserialized on its own, so if one of them is invalid
+/// the returned [`ConfigError::JsonElement`] error tells which index failed instead of a generic parse error for the whole file.
+///
+/// If the file does not exist, an empty array is stored and an empty `Vec` is returned.
+///
+/// If the flag `reset_conf_on_err` is set to `true`, the config file will be reset to an empty array if
+/// the deserialization fails, if set to `false` an error will be returned.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to an empty array.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Server {
+///    host: String,
+///    port: u16,
+/// }
+///
+/// let servers = binconf::load_json_vec::<Server>("test-binconf-read-vec-json", None, Config, false).unwrap();
+/// assert!(servers.is_empty());
+/// ```
+pub fn load_json_vec<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+) -> Result<Vec<T>, ConfigError>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Json.as_str(),
+        location.as_ref(),
+    )?;
+
+    let save_default_conf = || {
+        let default_config = Vec::new();
+        let json_str = crate::output::to_json_string(
+            &default_config,
+            &crate::output::output_options_for(&config_file_path),
+        )?;
+        if !crate::freeze::is_frozen_path(&config_file_path) {
+            crate::reset::back_up_corrupt(&config_file_path);
+            crate::save_config_str(&config_file_path, &json_str)?;
+        }
+        Ok(default_config)
+    };
+
+    if !config_file_path.try_exists()? {
+        let default_config = Vec::new();
+        let json_str = crate::output::to_json_string(
+            &default_config,
+            &crate::output::output_options_for(&config_file_path),
+        )?;
+        if crate::freeze::is_frozen_path(&config_file_path)
+            || crate::save_config_new(&config_file_path, json_str.as_bytes())?
+        {
+            return Ok(default_config);
+        }
+        // Created by another process in the meantime, it is loaded below
+    }
+
+    let started = std::time::Instant::now();
+    let json_str = read_to_string(&config_file_path)?;
+    crate::slow_io::report(
+        crate::IoOperation::Load,
+        &config_file_path,
+        json_str.len(),
+        started,
+    );
+    // An empty file is left by `touch` or an interrupted write, it holds no config rather than a damaged one
+    if json_str.is_empty() {
+        return if reset_conf_on_err {
+            save_default_conf()
+        } else {
+            Ok(Default::default())
+        };
+    }
+    let config = match deserialize_json_vec::<T>(&json_str) {
+        Ok(config) => config,
+        Err(err) => {
+            if reset_conf_on_err {
+                crate::reset::report(&config_file_path, &json_str, err);
+                return save_default_conf();
+            }
+            return Err(err);
+        }
+    };
+
+    Ok(config)
+}
+
+/// Deserializes a `json` array element by element, reporting the index of the first invalid element.
+fn deserialize_json_vec<T>(json_str: &str) -> Result<Vec<T>, ConfigError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let elements = serde_json::from_str::<Vec<serde_json::Value>>(json_str)?;
+
+    elements
+        .into_iter()
+        .enumerate()
+        .map(|(index, element)| {
+            serde_json::from_value::<T>(element)
+                .map_err(|err| ConfigError::JsonElement { index, err })
+        })
+        .collect()
+}
+
+/// Stores a list-shaped config file in the config, cache, cwd, or local data directory of the current user. In `json` format.
+///
+/// The elements are stored as a JSON array, that can be loaded back with [`load_json_vec`].
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Server {
+///    host: String,
+///    port: u16,
+/// }
+///
+/// let servers = vec![Server { host: String::from("localhost"), port: 8080 }];
+///
+/// binconf::store_json_vec("test-binconf-store-vec-json", None, Config, &servers).unwrap();
+///
+/// let stored = binconf::load_json_vec::<Server>("test-binconf-store-vec-json", None, Config, false).unwrap();
+/// assert_eq!(stored, servers);
+/// ```
+pub fn store_json_vec<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    data: &[T],
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    store_json(app_name, config_name, location, data)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+
     use super::*;
 
     use serde::Deserialize;
@@ -318,6 +721,102 @@ mod tests {
         assert_eq!(config, data);
     }
 
+    #[test]
+    fn strict_rejects_trailing_data_json() {
+        let data = TestConfig {
+            test: String::from("test"),
+            test_vec: vec![1, 2, 3, 4, 5],
+        };
+
+        store_json(
+            "test-binconf-strict_rejects_trailing_data-json",
+            None,
+            Config,
+            &data,
+        )
+        .unwrap();
+        let config: TestConfig = load_json_strict(
+            "test-binconf-strict_rejects_trailing_data-json",
+            None,
+            Config,
+            false,
+        )
+        .unwrap();
+        assert_eq!(config, data);
+
+        let path = crate::get_configuration_path(
+            "test-binconf-strict_rejects_trailing_data-json",
+            None,
+            ConfigType::Json,
+            Config,
+        )
+        .unwrap();
+        let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(br#"{"test": "", "test_vec": []}"#).unwrap();
+
+        let config = load_json_strict::<TestConfig>(
+            "test-binconf-strict_rejects_trailing_data-json",
+            None,
+            Config,
+            false,
+        );
+        assert!(matches!(config, Err(ConfigError::TrailingData)));
+    }
+
+    #[test]
+    fn vec_reports_invalid_element_index_json() {
+        let data = vec![
+            TestConfig {
+                test: String::from("first"),
+                test_vec: vec![1, 2],
+            },
+            TestConfig {
+                test: String::from("second"),
+                test_vec: vec![3, 4],
+            },
+        ];
+
+        store_json_vec(
+            "test-binconf-vec_reports_invalid_element_index-json",
+            None,
+            Config,
+            &data,
+        )
+        .unwrap();
+        let config: Vec<TestConfig> = load_json_vec(
+            "test-binconf-vec_reports_invalid_element_index-json",
+            None,
+            Config,
+            false,
+        )
+        .unwrap();
+        assert_eq!(config, data);
+
+        let path = crate::get_configuration_path(
+            "test-binconf-vec_reports_invalid_element_index-json",
+            None,
+            ConfigType::Json,
+            Config,
+        )
+        .unwrap();
+        std::fs::write(
+            path,
+            r#"[{"test": "ok", "test_vec": []}, {"test": 1, "test_vec": []}]"#,
+        )
+        .unwrap();
+
+        let config = load_json_vec::<TestConfig>(
+            "test-binconf-vec_reports_invalid_element_index-json",
+            None,
+            Config,
+            false,
+        );
+        assert!(matches!(
+            config,
+            Err(ConfigError::JsonElement { index: 1, .. })
+        ));
+    }
+
     #[test]
     fn save_config_user_cwd_json() {
         let data = TestConfig {
@@ -330,4 +829,39 @@ mod tests {
             load_json("test-binconf-save_config_user_cwd-json", None, Cwd, false).unwrap();
         assert_eq!(config, data);
     }
+
+    #[test]
+    fn rejects_duplicate_keys_json() {
+        let path = crate::get_configuration_path(
+            "test-binconf-rejects_duplicate_keys-json",
+            None,
+            ConfigType::Json,
+            Config,
+        )
+        .unwrap();
+        std::fs::write(
+            path,
+            r#"{
+  "ui": {
+    "theme": 1,
+    "size": 2,
+    "theme": 3
+  }
+}"#,
+        )
+        .unwrap();
+
+        let config = load_json_strict::<
+            std::collections::HashMap<String, std::collections::HashMap<String, u8>>,
+        >(
+            "test-binconf-rejects_duplicate_keys-json",
+            None,
+            Config,
+            false,
+        );
+        assert!(matches!(
+            config,
+            Err(ConfigError::DuplicateKey { path, line: 5 }) if path == "ui.theme"
+        ));
+    }
 }
@@ -0,0 +1,84 @@
+use crate::{ConfigError, ConfigType};
+
+/// Reads the value at `key_path` from a text config document, without deserializing the whole document into a typed struct.
+///
+/// Nested keys are separated by dots (e.g. `"ui.theme"`). Returns `None` if any of the keys in the path does not exist.
+///
+/// The binary format does not carry key names, so it returns a [`ConfigError::UnsupportedFormat`] error.
+pub(crate) fn get_key<V>(
+    document: &str,
+    config_type: &ConfigType,
+    key_path: &str,
+) -> Result<Option<V>, ConfigError>
+where
+    V: serde::de::DeserializeOwned,
+{
+    let keys = key_path.split('.');
+
+    match config_type {
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => {
+            let mut value = toml::Value::Table(toml::from_str::<toml::Table>(document)?);
+            for key in keys {
+                let toml::Value::Table(mut table) = value else {
+                    return Ok(None);
+                };
+                let Some(child) = table.remove(key) else {
+                    return Ok(None);
+                };
+                value = child;
+            }
+            Ok(Some(value.try_into()?))
+        }
+
+        #[cfg(feature = "json-conf")]
+        ConfigType::Json => {
+            let mut value = serde_json::from_str::<serde_json::Value>(document)?;
+            for key in keys {
+                let serde_json::Value::Object(mut table) = value else {
+                    return Ok(None);
+                };
+                let Some(child) = table.remove(key) else {
+                    return Ok(None);
+                };
+                value = child;
+            }
+            Ok(Some(serde_json::from_value(value)?))
+        }
+
+        #[cfg(feature = "yaml-conf")]
+        ConfigType::Yaml => {
+            let mut value = serde_yaml::from_str::<serde_yaml::Value>(document)?;
+            for key in keys {
+                let serde_yaml::Value::Mapping(mut table) = value else {
+                    return Ok(None);
+                };
+                let Some(child) = table.remove(key) else {
+                    return Ok(None);
+                };
+                value = child;
+            }
+            Ok(Some(serde_yaml::from_value(value)?))
+        }
+
+        #[cfg(feature = "ron-conf")]
+        ConfigType::Ron => {
+            let mut value = ron::from_str::<ron::Value>(document)?;
+            for key in keys {
+                let ron::Value::Map(mut table) = value else {
+                    return Ok(None);
+                };
+                let Some(child) = table.remove(&ron::Value::String(key.to_owned())) else {
+                    return Ok(None);
+                };
+                value = child;
+            }
+            Ok(Some(value.into_rust()?))
+        }
+
+        #[allow(unreachable_patterns)]
+        _ => Err(ConfigError::UnsupportedFormat(
+            config_type.as_str().to_owned(),
+        )),
+    }
+}
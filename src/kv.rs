@@ -0,0 +1,235 @@
+//! Building blocks for `config get` / `config set` subcommands.
+//!
+//! Keys are dotted paths as listed by [`keys`] (e.g. `"ui.theme"`), values are read and written as strings, like they
+//! are typed on a command line. [`set`] converts the string to the type of the setting and stores the config only if it
+//! is still valid.
+//!
+//! ```
+//! use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+//! use binconf::{kv, ConfigType};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Default, Serialize, Deserialize)]
+//! struct Settings {
+//!     ui: Ui,
+//! }
+//!
+//! #[derive(Default, Serialize, Deserialize)]
+//! struct Ui {
+//!     theme: String,
+//!     size: u8,
+//! }
+//!
+//! // myapp config set ui.size 12
+//! kv::set::<Settings>("test-binconf-kv", None, ConfigType::Json, Config, "ui.size", "12").unwrap();
+//!
+//! // myapp config get ui.size
+//! let size = kv::get::<Settings>("test-binconf-kv", None, ConfigType::Json, Config, "ui.size").unwrap();
+//! assert_eq!(size, "12");
+//!
+//! // myapp config set ui.size big
+//! assert!(kv::set::<Settings>("test-binconf-kv", None, ConfigType::Json, Config, "ui.size", "big").is_err());
+//! ```
+
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use serde_json::Value;
+
+/// Returns the keys of `T` that can be read and written, for shell completion. Same as [`config_keys`](crate::config_keys).
+pub fn keys<T>() -> Vec<String>
+where
+    T: serde::de::DeserializeOwned,
+{
+    crate::config_keys::<T>()
+}
+
+/// Returns the value at `key` of a config, as typed on a command line: strings as is, anything else as `json`.
+///
+/// The config is loaded with the `load` function of its [`ConfigType`], so the default config is written if the file does not exist.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created,
+/// if the config could not be loaded, or a [`ConfigError::UnknownKey`] error if the config has no value at `key`.
+pub fn get<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+    key: &str,
+) -> Result<String, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let config: T = crate::load_typed(
+        app_name.as_ref(),
+        config_name.into(),
+        &config_type,
+        location.as_ref(),
+        false,
+    )?;
+    let config = serde_json::to_value(config)?;
+
+    let mut value = &config;
+    for part in key.split('.') {
+        value = value
+            .get(part)
+            .ok_or_else(|| ConfigError::UnknownKey(key.to_owned()))?;
+    }
+
+    Ok(match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    })
+}
+
+/// Sets the value at `key` of a config from a string, and stores the config. Returns the new config.
+///
+/// The string is used as is for string settings, anything else is parsed as `json` (e.g. `12`, `true`, `[1, 2]` or `null`),
+/// falling back to a string, which lets unit enum variants be written without quotes. The config is then deserialized
+/// into `T` again, so a value of the wrong type is rejected and nothing is stored.
+///
+/// Besides the keys listed by [`keys`], entries of map settings can be set (e.g. `colors.red` for a `colors` map).
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created,
+/// if the config could not be loaded or stored, a [`ConfigError::UnknownKey`] error if `T` has no setting at `key`,
+/// or a [`ConfigError::InvalidValue`] error if `value` does not fit the type of the setting.
+pub fn set<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+    key: &str,
+    value: &str,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let app_name = app_name.as_ref();
+    let config_name = config_name.into();
+    let location = location.as_ref();
+
+    let known = keys::<T>();
+    // Fields that are not known are silently dropped when deserializing, only keys of `T` or entries of its maps are accepted
+    let is_known = known.is_empty()
+        || known.iter().any(|known| {
+            key == known
+                || key
+                    .strip_prefix(known.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        });
+    if !is_known {
+        return Err(ConfigError::UnknownKey(key.to_owned()));
+    }
+
+    let config: T = crate::load_typed(app_name, config_name, &config_type, location, false)?;
+    let mut config = serde_json::to_value(config)?;
+
+    let (parents, leaf) = key
+        .rsplit_once('.')
+        .map_or((None, key), |(parents, leaf)| (Some(parents), leaf));
+    let mut table = &mut config;
+    for part in parents.into_iter().flat_map(|parents| parents.split('.')) {
+        table = table
+            .get_mut(part)
+            .ok_or_else(|| ConfigError::UnknownKey(key.to_owned()))?;
+    }
+    let Value::Object(table) = table else {
+        return Err(ConfigError::UnknownKey(key.to_owned()));
+    };
+
+    let coerced = match table.get(leaf) {
+        Some(Value::String(_)) => Value::String(value.to_owned()),
+        _ => serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned())),
+    };
+    table.insert(leaf.to_owned(), coerced);
+
+    let config: T = serde_json::from_value(config).map_err(|err| ConfigError::InvalidValue {
+        key: key.to_owned(),
+        reason: err.to_string(),
+    })?;
+
+    crate::store_typed(app_name, config_name, &config_type, location, &config)?;
+    Ok(config)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use ConfigLocation::Config;
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Settings {
+        name: String,
+        ui: Ui,
+        colors: HashMap<String, String>,
+    }
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Ui {
+        size: u8,
+        fullscreen: bool,
+        scale: Option<f32>,
+        mode: Mode,
+    }
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    enum Mode {
+        #[default]
+        Light,
+        Dark,
+    }
+
+    #[test]
+    fn get_and_set_coerce_values() {
+        let app = "test-binconf-get_and_set_coerce_values";
+        let set = |key, value| set::<Settings>(app, None, ConfigType::Toml, Config, key, value);
+        let get = |key| get::<Settings>(app, None, ConfigType::Toml, Config, key);
+
+        set("name", "12").unwrap();
+        set("ui.size", "12").unwrap();
+        set("ui.fullscreen", "true").unwrap();
+        set("ui.scale", "1.5").unwrap();
+        set("ui.mode", "Dark").unwrap();
+        let config = set("colors.red", "#f00").unwrap();
+
+        assert_eq!(config.name, "12");
+        assert_eq!(
+            config.ui,
+            Ui {
+                size: 12,
+                fullscreen: true,
+                scale: Some(1.5),
+                mode: Mode::Dark,
+            }
+        );
+        assert_eq!(get("name").unwrap(), "12");
+        assert_eq!(get("ui.size").unwrap(), "12");
+        assert_eq!(get("ui.mode").unwrap(), "Dark");
+        assert_eq!(get("colors.red").unwrap(), "#f00");
+
+        assert!(matches!(
+            set("ui.size", "300"),
+            Err(ConfigError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            set("ui.mode", "Blue"),
+            Err(ConfigError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            set("ui.theme", "dark"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            get("colors.blue"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+
+        // Rejected values are not stored
+        assert_eq!(get("ui.size").unwrap(), "12");
+    }
+}
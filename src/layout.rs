@@ -0,0 +1,320 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+
+/// Pattern of the default file names, see [`LayoutPolicy::file_name`].
+const DEFAULT_FILE_NAME: &str = "{app}.{ext}";
+
+/// Layout policies set with [`set_layout_policy`], by app name.
+static LAYOUTS: RwLock<Vec<(String, LayoutPolicy)>> = RwLock::new(Vec::new());
+
+/// Where the files of an app are placed inside its directory. Set with [`set_layout_policy`].
+///
+/// By default every file is written at the root of the app directory. Routing the machine-managed formats to their own
+/// subdirectory keeps them apart from the configs the user edits.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LayoutPolicy {
+    /// Subdirectory of the app directory holding the files of a config type, e.g. `(ConfigType::Bin, "state")` for
+    /// `<app>/state/<app>.bin`. Config types that are not listed stay at the root. The paths must be relative and made of plain
+    /// directory names, without `..`: the `load` and `store` functions fail with an [`std::io::ErrorKind::InvalidInput`] error otherwise.
+    pub subdirectories: Vec<(ConfigType, PathBuf)>,
+    /// Suffix added to the default file name in a location, e.g. `(ConfigLocation::Cache, "cache")` for `<app>.cache.bin`
+    /// in the cache directory. Files named explicitly with a `config_name` are not affected.
+    ///
+    /// Tells apart files of different locations that would otherwise share the same name, so a cache restored from a
+    /// manual backup can't be mistaken for the settings.
+    pub name_suffixes: Vec<(ConfigLocation, String)>,
+    /// Pattern of the default file name, used when no `config_name` is given. `{app}` is replaced with the app name and
+    /// `{ext}` with the extension of the config type, e.g. `settings.{ext}` for `settings.toml`. Defaults to `{app}.{ext}`.
+    ///
+    /// The suffix of the location, if any, is added before the extension: `settings.cache.toml`. The pattern and the suffixes must
+    /// give a plain file name, without path separators: the `load` and `store` functions fail with an
+    /// [`std::io::ErrorKind::InvalidInput`] error otherwise.
+    pub file_name: Option<String>,
+}
+
+impl LayoutPolicy {
+    /// Returns the subdirectory of the files with the given extension, if they are routed to one.
+    fn subdirectory(&self, extension: &str) -> Option<&Path> {
+        let config_type = ConfigType::from_extension(extension)?;
+        self.subdirectories
+            .iter()
+            .find(|(routed, _)| *routed == config_type)
+            .map(|(_, subdirectory)| subdirectory.as_path())
+    }
+
+    /// Returns the suffix of the default file names in `location`, if any.
+    fn name_suffix(&self, location: &ConfigLocation) -> Option<&str> {
+        self.name_suffixes
+            .iter()
+            .find(|(suffixed, _)| suffixed == location)
+            .map(|(_, suffix)| suffix.as_str())
+    }
+}
+
+/// Sets the layout of the files of `app_name`, used by every `load` and `store` function for the rest of the process.
+///
+/// The policy applies in every [`ConfigLocation`](crate::ConfigLocation). Files already written at another place are not moved.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigType, LayoutPolicy};
+///
+/// binconf::set_layout_policy("test-binconf-layout", LayoutPolicy {
+///     subdirectories: vec![(ConfigType::Bin, "state".into())],
+///     name_suffixes: vec![(Cache, String::from("cache"))],
+///     file_name: None,
+/// });
+///
+/// let path = binconf::get_configuration_path("test-binconf-layout", None, ConfigType::Bin, Config).unwrap();
+/// assert!(path.ends_with("test-binconf-layout/state/test-binconf-layout.bin"));
+///
+/// let path = binconf::get_configuration_path("test-binconf-layout", None, ConfigType::Bin, Cache).unwrap();
+/// assert!(path.ends_with("test-binconf-layout/state/test-binconf-layout.cache.bin"));
+///
+/// binconf::set_layout_policy("test-binconf-layout-file-name", LayoutPolicy {
+///     file_name: Some(String::from("settings-v2.{ext}")),
+///     ..LayoutPolicy::default()
+/// });
+///
+/// let path = binconf::get_configuration_path("test-binconf-layout-file-name", None, ConfigType::Bin, Config).unwrap();
+/// assert!(path.ends_with("test-binconf-layout-file-name/settings-v2.bin"));
+/// ```
+pub fn set_layout_policy(app_name: impl AsRef<str>, policy: LayoutPolicy) {
+    let app_name = app_name.as_ref();
+    let mut layouts = LAYOUTS
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    match layouts.iter_mut().find(|(name, _)| name == app_name) {
+        Some((_, layout)) => *layout = policy,
+        None => layouts.push((app_name.to_owned(), policy)),
+    }
+}
+
+/// Returns `true` if `path` is only made of plain names, so joining it to the app directory stays inside of it.
+fn is_plain_relative(path: &Path) -> bool {
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Returns the error of a layout path that would leave the app directory.
+fn invalid_layout(what: &str, path: &Path) -> ConfigError {
+    ConfigError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Invalid layout {what} `{}`", path.display()),
+    ))
+}
+
+/// Returns the subdirectory of the app directory holding the files of `app_name` with the given extension, if any.
+///
+/// Fails if the subdirectory of the layout is not a plain relative path.
+pub(crate) fn subdirectory(
+    app_name: &str,
+    extension: &str,
+) -> Result<Option<PathBuf>, ConfigError> {
+    let subdirectory = LAYOUTS
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .iter()
+        .find(|(name, _)| name == app_name)
+        .and_then(|(_, layout)| layout.subdirectory(extension))
+        .map(Path::to_path_buf);
+
+    match subdirectory {
+        Some(subdirectory) if !is_plain_relative(&subdirectory) => {
+            Err(invalid_layout("subdirectory", &subdirectory))
+        }
+        subdirectory => Ok(subdirectory),
+    }
+}
+
+/// Returns every subdirectory the layout of `app_name` routes files to. Subdirectories that would leave the app directory are left out.
+pub(crate) fn subdirectories(app_name: &str) -> Vec<PathBuf> {
+    LAYOUTS
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .iter()
+        .find(|(name, _)| name == app_name)
+        .map(|(_, layout)| {
+            layout
+                .subdirectories
+                .iter()
+                .map(|(_, subdirectory)| subdirectory.clone())
+                .filter(|subdirectory| is_plain_relative(subdirectory))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the name of the config file of `app_name` with the given extension when no `config_name` is given.
+///
+/// Fails if the pattern or the suffix of the layout would not give a plain file name.
+pub(crate) fn default_file_name(
+    app_name: &str,
+    extension: &str,
+    location: &ConfigLocation,
+) -> Result<String, ConfigError> {
+    let layouts = LAYOUTS
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let layout = layouts.iter().find(|(name, _)| name == app_name);
+
+    let pattern = layout
+        .and_then(|(_, layout)| layout.file_name.as_deref())
+        .unwrap_or(DEFAULT_FILE_NAME);
+    let extension = match layout.and_then(|(_, layout)| layout.name_suffix(location)) {
+        Some(suffix) => format!("{suffix}.{extension}"),
+        None => extension.to_owned(),
+    };
+
+    // Checked before the app name is put in, only the layout is untrusted
+    let pattern = pattern.replace("{ext}", &extension);
+    let mut components = Path::new(&pattern).components();
+    if !matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) {
+        return Err(invalid_layout("file name", Path::new(&pattern)));
+    }
+    let file_name = pattern.replace("{app}", app_name);
+
+    if *location == ConfigLocation::Cwd && crate::project::uses_dot_files(app_name) {
+        return Ok(format!(".{file_name}"));
+    }
+
+    Ok(file_name)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use crate::ConfigLocation::{Cache, Config};
+
+    #[test]
+    fn formats_are_routed_to_subdirectories() {
+        let app = "test-binconf-formats_are_routed_to_subdirectories";
+        set_layout_policy(
+            app,
+            LayoutPolicy {
+                subdirectories: vec![(ConfigType::Bin, PathBuf::from("state"))],
+                ..LayoutPolicy::default()
+            },
+        );
+
+        let app_dir = crate::app_dir(app, &Cache).unwrap();
+        crate::store_bin(app, None, Cache, 42u32).unwrap();
+        crate::store_toml(
+            app,
+            None,
+            Cache,
+            std::collections::HashMap::from([("theme", "dark")]),
+        )
+        .unwrap();
+
+        assert!(app_dir.join("state").join(format!("{app}.bin")).is_file());
+        assert!(app_dir.join(format!("{app}.toml")).is_file());
+        assert_eq!(crate::load_bin::<u32>(app, None, Cache, false).unwrap(), 42);
+    }
+
+    #[test]
+    fn default_names_are_suffixed_per_location() {
+        let app = "test-binconf-default_names_are_suffixed_per_location";
+        set_layout_policy(
+            app,
+            LayoutPolicy {
+                name_suffixes: vec![(Cache, String::from("cache"))],
+                ..LayoutPolicy::default()
+            },
+        );
+
+        let path = crate::get_configuration_path(app, None, ConfigType::Bin, Cache).unwrap();
+        assert!(path.ends_with(format!("{app}.cache.bin")));
+        let path = crate::get_configuration_path(app, None, ConfigType::Bin, Config).unwrap();
+        assert!(path.ends_with(format!("{app}.bin")));
+
+        // Explicit names are kept
+        let path = crate::get_configuration_path(app, "index.bin", ConfigType::Bin, Cache).unwrap();
+        assert!(path.ends_with("index.bin"));
+    }
+
+    #[test]
+    fn default_names_follow_the_pattern() {
+        let app = "test-binconf-default_names_follow_the_pattern";
+        set_layout_policy(
+            app,
+            LayoutPolicy {
+                name_suffixes: vec![(Cache, String::from("cache"))],
+                file_name: Some(String::from("{app}-v1.{ext}")),
+                ..LayoutPolicy::default()
+            },
+        );
+
+        let path = crate::get_configuration_path(app, None, ConfigType::Toml, Config).unwrap();
+        assert!(path.ends_with(format!("{app}-v1.toml")));
+        let path = crate::get_configuration_path(app, None, ConfigType::Bin, Cache).unwrap();
+        assert!(path.ends_with(format!("{app}-v1.cache.bin")));
+
+        crate::store_toml(
+            app,
+            None,
+            Config,
+            std::collections::HashMap::from([("v", 1)]),
+        )
+        .unwrap();
+        let loaded: std::collections::HashMap<String, u32> =
+            crate::load_toml(app, None, Config, false).unwrap();
+        assert_eq!(loaded.get("v"), Some(&1));
+    }
+
+    #[test]
+    fn layouts_leaving_the_app_directory_are_rejected() {
+        let app = "test-binconf-layouts_leaving_the_app_directory_are_rejected";
+        let outside = crate::app_dir(app, &Cache)
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join(format!("{app}-escaped.bin"));
+
+        for policy in [
+            LayoutPolicy {
+                subdirectories: vec![(ConfigType::Bin, PathBuf::from("../escaped"))],
+                ..LayoutPolicy::default()
+            },
+            LayoutPolicy {
+                file_name: Some(String::from("../{app}-escaped.{ext}")),
+                ..LayoutPolicy::default()
+            },
+            LayoutPolicy {
+                name_suffixes: vec![(Cache, String::from("/../../escaped"))],
+                ..LayoutPolicy::default()
+            },
+        ] {
+            set_layout_policy(app, policy);
+
+            let Err(ConfigError::Io(err)) = crate::store_bin(app, None, Cache, 42u32) else {
+                panic!("the layout was not rejected");
+            };
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        }
+        assert!(!outside.exists());
+
+        #[cfg(unix)]
+        {
+            set_layout_policy(
+                app,
+                LayoutPolicy {
+                    subdirectories: vec![(ConfigType::Bin, PathBuf::from("/tmp"))],
+                    ..LayoutPolicy::default()
+                },
+            );
+            assert!(crate::get_configuration_path(app, None, ConfigType::Bin, Cache).is_err());
+        }
+    }
+}
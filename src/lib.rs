@@ -1,7 +1,194 @@
+// Library code must never panic on bad input files, every failure is reported as a `ConfigError`.
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::indexing_slicing
+    )
+)]
+
+// Lets the code generated by the derive macro, which names the crate `::binconf`, be tested inside it
+#[cfg(all(test, feature = "derive"))]
+extern crate self as binconf;
+
 #[cfg(feature = "binary-conf")]
 mod binary_conf;
+
+#[cfg(feature = "binary-conf")]
+mod hasher;
+
+#[cfg(feature = "binary-conf")]
+mod background;
+
+#[cfg(feature = "binary-conf")]
+mod sharded;
+
+#[cfg(feature = "binary-conf")]
+mod bin_iter;
+
+#[cfg(feature = "binary-conf")]
+mod summary;
+
 #[cfg(feature = "toml-conf")]
 mod toml_conf;
+mod transfer;
+
+mod profile;
+
+mod cleanup;
+
+mod disk;
+
+mod health;
+
+mod permissions;
+
+mod messages;
+
+mod metadata;
+
+mod crdt;
+
+mod lock;
+
+mod update;
+
+mod reloadable;
+
+mod slow_io;
+
+mod progress;
+
+mod cancel;
+
+mod ownership;
+
+mod reset;
+
+mod freeze;
+
+mod attributes;
+
+mod resolution;
+
+mod project;
+
+mod stream;
+
+mod builder;
+
+mod context;
+
+mod config_file;
+
+mod auto;
+
+mod credentials;
+
+mod format;
+
+mod layout;
+
+mod reflect;
+
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+mod editor;
+
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+mod outcome;
+
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+mod scaffold;
+
+mod init;
+
+mod output;
+
+pub mod prelude;
+
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+#[cfg_attr(not(feature = "binary-conf"), allow(dead_code))]
+mod merge;
+
+#[cfg(feature = "toml-conf")]
+mod policy;
+
+#[cfg(feature = "toml-conf")]
+mod overrides;
+
+#[cfg(feature = "toml-conf")]
+mod local;
+
+#[cfg(feature = "toml-conf")]
+mod extends;
+
+#[cfg(feature = "toml-conf")]
+mod explain;
+
+#[cfg(feature = "toml-conf")]
+mod state;
+
+#[cfg(feature = "toml-conf")]
+mod shared;
+
+#[cfg(feature = "json-conf")]
+mod changelog;
+
+#[cfg(feature = "json-conf")]
+mod dump;
+
+#[cfg(all(
+    feature = "binary-conf",
+    any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )
+))]
+mod mirror;
+
+#[cfg(all(
+    feature = "binary-conf",
+    any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )
+))]
+mod parse_cache;
+
+#[cfg(feature = "json-conf")]
+mod undo;
+
+#[cfg(feature = "json-conf")]
+pub mod kv;
+
+#[cfg(feature = "binary-conf")]
+mod bundle;
 
 #[cfg(feature = "json-conf")]
 mod json_conf;
@@ -12,20 +199,164 @@ mod yaml_conf;
 #[cfg(feature = "ron-conf")]
 mod ron_conf;
 
+#[cfg(any(feature = "toml-conf", feature = "json-conf", feature = "yaml-conf"))]
+mod duplicates;
+
+#[cfg(any(feature = "toml-conf", feature = "json-conf"))]
+mod array;
+
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+mod keys;
+
+#[cfg(feature = "watch")]
+mod watch;
+
+#[cfg(feature = "binary-conf")]
+pub use binary_conf::{
+    load_bin, load_bin_or, load_bin_skip_check, load_bin_strict, load_bin_verified,
+    load_bin_with_hasher, rehash_bin, store_bin, store_bin_new, store_bin_with_hasher, HashStatus,
+    VerifyMode,
+};
+
+#[cfg(feature = "binary-conf")]
+pub use hasher::{Hasher, NoHasher, Xxh3Hasher};
+
+#[cfg(feature = "binary-conf")]
+pub use background::{flush_pending, shutdown_background_writer, store_bin_background};
+
+#[cfg(feature = "binary-conf")]
+pub use sharded::{load_shard, store_sharded};
+
+#[cfg(feature = "binary-conf")]
+pub use bin_iter::{load_bin_iter, BinIter};
+
 #[cfg(feature = "binary-conf")]
-pub use binary_conf::{load_bin, load_bin_skip_check, store_bin};
+pub use summary::{load_bin_summary, load_bin_with_summary, store_bin_with_summary};
+
+pub use transfer::{export_raw, import_raw};
+
+pub use profile::{profile_dir, replace_profile};
+
+pub use cleanup::{cleanup, CleanupPolicy, CleanupReport};
+
+pub use disk::set_free_space_guard;
+
+pub use health::{health_check, HealthCheckOptions, HealthIssue, HealthReport};
+
+pub use permissions::{fix_permissions, PermissionFix, PermissionsReport};
+
+pub use messages::ErrorMessages;
+
+pub use metadata::{load_metadata, store_stamped, ConfigMetadata};
+
+pub use crdt::{GCounter, LwwRegister, Merge, OrSet};
+
+pub use lock::{lock_session, SessionLock, StaleLockPolicy};
+
+pub use reloadable::ReloadableConfig;
+
+/// Derives `load()`, `store(&self)` and `path()` functions for a config type, from a `#[binconf(...)]` attribute. Needs the `derive` feature.
+///
+/// Replaces the app name, location and format repeated at every call. The attribute takes string settings:
+///
+/// - `app`: the app name, required.
+/// - `format`: `bin`, `toml`, `json`, `yaml` or `ron`, defaults to `bin`. The feature of the format must be enabled.
+/// - `location`: `config`, `cache`, `local_data` or `cwd`, defaults to `config`.
+/// - `name`: the config name, defaults to the default file name of the app.
+///
+/// The generated functions call the regular `load` and `store` functions of the format, the config is created with its
+/// defaults if it does not exist. [`ConfigFile`] is implemented with the same settings. Generic types are not supported.
+///
+/// # Example
+///
+/// ```
+/// use binconf::BinConf;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, BinConf, PartialEq, Debug)]
+/// #[binconf(app = "test-binconf-derive", location = "cache")]
+/// struct Settings {
+///     volume: u8,
+/// }
+///
+/// Settings { volume: 7 }.store().unwrap();
+///
+/// assert_eq!(Settings::load().unwrap(), Settings { volume: 7 });
+/// assert!(Settings::path().unwrap().ends_with("test-binconf-derive/test-binconf-derive.bin"));
+/// ```
+#[cfg(feature = "derive")]
+pub use binconf_derive::BinConf;
+
+#[cfg(feature = "binary-conf")]
+pub use update::update_bin;
 
 #[cfg(feature = "toml-conf")]
-pub use toml_conf::{load_toml, store_toml};
+pub use update::update_toml;
 
 #[cfg(feature = "json-conf")]
-pub use json_conf::{load_json, store_json};
+pub use update::update_json;
 
 #[cfg(feature = "yaml-conf")]
-pub use yaml_conf::{load_yaml, store_yaml};
+pub use update::update_yaml;
 
 #[cfg(feature = "ron-conf")]
-pub use ron_conf::{load_ron, store_ron};
+pub use update::update_ron;
+
+pub use output::{set_app_output_options, set_output_options, OutputOptions};
+
+pub use slow_io::{clear_slow_io_hook, set_slow_io_hook, IoOperation, SlowIoEvent};
+
+pub use progress::{clear_progress_hook, set_progress_hook, ProgressEvent};
+
+pub use ownership::{set_ownership_guard, OwnershipGuard, OwnershipMismatch};
+
+pub use cancel::{with_cancellation, CancellationToken};
+
+pub use freeze::{freeze, is_frozen};
+
+pub use attributes::{set_file_attributes, FileAttributes};
+
+pub use resolution::{
+    is_container, resolve_app_dir, resolve_config_path, resolve_location, set_base_dir_override,
+    set_container_resolution, set_path_canonicalization, ResolutionSource, ResolvedLocation,
+    ResolvedPath,
+};
+
+pub use project::{find_project_root, set_cwd_policy, CwdPolicy};
+
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+pub use project::find_project_config;
+
+pub use builder::{builder, ConfigBuilder};
+
+pub use context::{current_context, load, store, with_context};
+
+pub use config_file::ConfigFile;
+
+pub use auto::load_auto;
+
+pub use credentials::load_credential;
+
+pub use stream::{load_from_reader, load_from_stdin};
+
+#[cfg(unix)]
+pub use stream::load_from_fd;
+
+pub use format::{load_with, store_with, ConfigFormat};
+
+pub use layout::{set_layout_policy, LayoutPolicy};
+
+pub use reflect::config_keys;
 
 #[cfg(any(
     feature = "toml-conf",
@@ -33,7 +364,120 @@ pub use ron_conf::{load_ron, store_ron};
     feature = "yaml-conf",
     feature = "ron-conf"
 ))]
-use std::io::Write;
+pub use editor::edit_in_editor;
+
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+pub use outcome::{
+    load_with_outcome, load_with_stats, store_with_stats, LoadOutcome, LoadStats, StoreStats,
+};
+
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+pub use scaffold::scaffold;
+
+#[cfg(feature = "json-conf")]
+pub use changelog::{change_history, set_change_log, Change};
+
+#[cfg(feature = "json-conf")]
+pub use dump::{dump_effective_config, effective_config_string};
+
+#[cfg(all(
+    feature = "binary-conf",
+    any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )
+))]
+pub use mirror::{load_mirrored, store_mirrored};
+
+#[cfg(all(
+    feature = "binary-conf",
+    any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )
+))]
+pub use parse_cache::set_parse_cache;
+
+#[cfg(feature = "json-conf")]
+pub use undo::UndoableConfig;
+
+pub use reset::{clear_reset_hook, set_reset_hook, ErrorLocation, ResetEvent};
+
+#[cfg(feature = "binary-conf")]
+pub use bundle::{apply_bundle, create_bundle, BundleFile, BundleReport, ConflictPolicy, Manifest};
+
+#[cfg(feature = "toml-conf")]
+pub use policy::{load_toml_with_policy, system_policy_path, PolicyConfig};
+
+#[cfg(feature = "toml-conf")]
+pub use overrides::{load_toml_with_base, store_toml_overrides};
+
+#[cfg(feature = "toml-conf")]
+pub use local::{load_toml_local, store_toml_local};
+
+#[cfg(feature = "toml-conf")]
+pub use extends::load_toml_extends;
+
+#[cfg(feature = "toml-conf")]
+pub use explain::{explain, load_toml_layered, LayerSources, ProvenanceMap, Source};
+
+#[cfg(feature = "toml-conf")]
+pub use state::StateFile;
+
+#[cfg(feature = "toml-conf")]
+pub use shared::{load_shared, update_shared};
+
+#[cfg(all(feature = "toml-conf", feature = "watch"))]
+pub use shared::watch_shared;
+
+#[cfg(feature = "toml-conf")]
+pub use toml_conf::{load_toml, load_toml_or, store_toml, store_toml_new};
+
+#[cfg(feature = "json-conf")]
+pub use json_conf::{
+    load_json, load_json_or, load_json_strict, load_json_vec, store_json, store_json_new,
+    store_json_vec,
+};
+
+#[cfg(feature = "yaml-conf")]
+pub use yaml_conf::{load_yaml, load_yaml_or, load_yaml_strict, store_yaml, store_yaml_new};
+
+#[cfg(feature = "ron-conf")]
+pub use ron_conf::{load_ron, load_ron_or, load_ron_strict, store_ron, store_ron_new};
+
+#[cfg(any(feature = "toml-conf", feature = "json-conf"))]
+pub use array::append_to_array;
+
+#[cfg(feature = "watch")]
+pub use watch::{set_watch_options, watch_config, WatchHandle, WatchOptions};
+
+#[cfg(all(
+    feature = "watch",
+    any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )
+))]
+pub use watch::watch_key;
+
+#[cfg(all(feature = "watch", feature = "json-conf"))]
+pub use watch::{watch_config_changes, ChangedKeys};
 
 use std::path::PathBuf;
 
@@ -56,8 +500,6 @@ use std::path::PathBuf;
 ///
 /// println!("The configuration file is located at: {}", config_path.display());
 /// ```
-///
-
 pub fn get_configuration_path<'a>(
     app_name: impl AsRef<str>,
     config_name: impl Into<Option<&'a str>>,
@@ -81,41 +523,108 @@ pub fn get_configuration_path<'a>(
 /// Returns the path to the config file with the given extension.
 ///
 /// **The function does not guarantee that the file exists. Just that the path to the file exists.**
+///
+/// The path is canonical if [`set_path_canonicalization`] is enabled.
 fn config_location(
     app_name: &str,
     config_name: Option<&str>,
     extension: &str,
     location: &ConfigLocation,
 ) -> Result<PathBuf, ConfigError> {
-    let conf_dir = match location {
-        ConfigLocation::Config => dirs::config_dir().ok_or(ConfigError::Io(
-            std::io::Error::new(std::io::ErrorKind::NotFound, "Config directory not found"),
-        ))?,
-        ConfigLocation::Cache => dirs::cache_dir().ok_or(ConfigError::Io(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "Cache directory not found",
-        )))?,
-        ConfigLocation::LocalData => {
-            dirs::data_local_dir().ok_or(ConfigError::Io(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Local data directory not found",
-            )))?
+    let conf_file = raw_config_location(app_name, config_name, extension, location)?;
+
+    Ok(resolution::canonicalize_if_enabled(conf_file))
+}
+
+/// Returns the path to the config file like [`config_location`], without creating any directory.
+///
+/// For reads that must not leave an empty app directory behind when the config does not exist.
+#[cfg(any(
+    feature = "binary-conf",
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+fn existing_config_location(
+    app_name: &str,
+    config_name: Option<&str>,
+    extension: &str,
+    location: &ConfigLocation,
+) -> Result<PathBuf, ConfigError> {
+    let conf_file = config_location_with(app_name, config_name, extension, location, false)?;
+
+    Ok(resolution::canonicalize_if_enabled(conf_file))
+}
+
+/// Prepares the path to the config file like [`config_location`], never canonicalizing it.
+fn raw_config_location(
+    app_name: &str,
+    config_name: Option<&str>,
+    extension: &str,
+    location: &ConfigLocation,
+) -> Result<PathBuf, ConfigError> {
+    config_location_with(app_name, config_name, extension, location, true)
+}
+
+/// Builds the path to the config file, creating the directories leading to it if `create` is `true`.
+fn config_location_with(
+    app_name: &str,
+    config_name: Option<&str>,
+    extension: &str,
+    location: &ConfigLocation,
+    create: bool,
+) -> Result<PathBuf, ConfigError> {
+    // Dot files are stored directly in the current directory
+    let dot_files = *location == ConfigLocation::Cwd && project::uses_dot_files(app_name);
+    let mut conf_dir = if dot_files {
+        resolution::resolve_location(location)?.base_dir
+    } else if create {
+        app_dir(app_name, location)?
+    } else {
+        resolution::resolve_app_dir(app_name, location)?.base_dir
+    };
+
+    if let Some(subdirectory) = layout::subdirectory(app_name, extension)?.filter(|_| !dot_files) {
+        conf_dir.push(subdirectory);
+        if create
+            && !conf_dir.try_exists()?
+            && !freeze::is_frozen(app_name)
+            && !project::is_outside_project(app_name, location)
+        {
+            std::fs::create_dir_all(&conf_dir)?;
         }
-        ConfigLocation::Cwd => std::env::current_dir()?,
+    }
+
+    let conf_file = match config_name {
+        Some(config_name) => conf_dir.join(config_name),
+        None => conf_dir.join(layout::default_file_name(app_name, extension, location)?),
     };
 
-    let conf_dir = conf_dir.join(app_name);
+    if *location == ConfigLocation::Cwd {
+        return project::search_parents(app_name, conf_file);
+    }
+
+    Ok(conf_file)
+}
 
-    if !conf_dir.try_exists()? {
+/// Returns the directory holding the config files of an app, creating it if it does not exist.
+fn app_dir(app_name: &str, location: &ConfigLocation) -> Result<PathBuf, ConfigError> {
+    let conf_dir = resolution::resolve_app_dir(app_name, location)?.base_dir;
+
+    if !conf_dir.try_exists()?
+        && !freeze::is_frozen(app_name)
+        && !project::is_outside_project(app_name, location)
+    {
         std::fs::create_dir_all(&conf_dir)?;
     }
+    ownership::ensure_owned(&conf_dir)?;
 
-    let conf_file = conf_dir.join(config_name.unwrap_or(&format!("{app_name}.{extension}")));
-
-    Ok(conf_file)
+    Ok(conf_dir)
 }
 
 #[non_exhaustive]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum ConfigType {
     #[cfg(feature = "toml-conf")]
     Toml,
@@ -152,6 +661,30 @@ impl ConfigType {
             ConfigType::Bin => "bin",
         }
     }
+
+    /// Returns the config type matching a file extension, if its feature is enabled.
+    ///
+    /// Both `yml` and `yaml` are recognized as `yaml`.
+    pub fn from_extension(extension: &str) -> Option<ConfigType> {
+        match extension {
+            #[cfg(feature = "toml-conf")]
+            "toml" => Some(ConfigType::Toml),
+
+            #[cfg(feature = "json-conf")]
+            "json" => Some(ConfigType::Json),
+
+            #[cfg(feature = "yaml-conf")]
+            "yml" | "yaml" => Some(ConfigType::Yaml),
+
+            #[cfg(feature = "ron-conf")]
+            "ron" => Some(ConfigType::Ron),
+
+            #[cfg(feature = "binary-conf")]
+            "bin" => Some(ConfigType::Bin),
+
+            _ => None,
+        }
+    }
 }
 
 impl AsRef<ConfigType> for ConfigType {
@@ -183,26 +716,351 @@ impl AsRef<ConfigLocation> for ConfigLocation {
 ))]
 #[inline]
 fn save_config_str(config_file_path: &PathBuf, config_as_str: &str) -> Result<(), ConfigError> {
+    use std::io::Write;
+
+    freeze::ensure_not_frozen(config_file_path)?;
+    project::ensure_inside_project(config_file_path)?;
+    disk::ensure_free_space(config_file_path, config_as_str.len())?;
+
+    attributes::before_write(config_file_path);
+    let started = std::time::Instant::now();
     let mut file = std::io::BufWriter::new(std::fs::File::create(config_file_path)?);
-    file.write_all(config_as_str.as_bytes())?;
+    progress::write_all(&mut file, config_file_path, config_as_str.as_bytes())?;
+    file.flush()?;
+    drop(file);
+    attributes::after_write(config_file_path);
+    slow_io::report(
+        slow_io::IoOperation::Store,
+        config_file_path,
+        config_as_str.len(),
+        started,
+    );
 
     Ok(())
 }
 
+/// Saves the config bytes to the given path atomically.
+///
+/// The data is written to a temporary file next to the config file, which is then renamed over it,
+/// so readers never see a partially written file.
+fn save_config_atomic(
+    config_file_path: &std::path::Path,
+    config_bytes: &[u8],
+) -> Result<(), ConfigError> {
+    let mut tmp_file_name = config_file_path
+        .file_name()
+        .map(std::ffi::OsStr::to_os_string)
+        .unwrap_or_default();
+    tmp_file_name.push(".tmp");
+    let tmp_file_path = config_file_path.with_file_name(tmp_file_name);
+
+    freeze::ensure_not_frozen(config_file_path)?;
+    project::ensure_inside_project(config_file_path)?;
+    disk::ensure_free_space(config_file_path, config_bytes.len())?;
+
+    let started = std::time::Instant::now();
+    let mut file = std::fs::File::create(&tmp_file_path)?;
+    progress::write_all(&mut file, config_file_path, config_bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(&tmp_file_path, config_file_path)?;
+    attributes::after_write(config_file_path);
+    slow_io::report(
+        slow_io::IoOperation::Store,
+        config_file_path,
+        config_bytes.len(),
+        started,
+    );
+
+    Ok(())
+}
+
+/// Saves the config bytes to the given path only if no file exists there yet. Returns `false` if one does.
+///
+/// The data is written to a temporary file which is then hard linked to the config path, an operation that fails if the
+/// path exists. When several processes create the same config at once, exactly one of them writes it and the others
+/// never see a partially written file. File systems without hard links fall back to an exclusive create of the file.
+fn save_config_new(
+    config_file_path: &std::path::Path,
+    config_bytes: &[u8],
+) -> Result<bool, ConfigError> {
+    static NEXT_TMP: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    freeze::ensure_not_frozen(config_file_path)?;
+    project::ensure_inside_project(config_file_path)?;
+    disk::ensure_free_space(config_file_path, config_bytes.len())?;
+
+    // Unique per process and call, concurrent creators must not share their temporary file
+    let mut tmp_file_name = config_file_path
+        .file_name()
+        .map(std::ffi::OsStr::to_os_string)
+        .unwrap_or_default();
+    tmp_file_name.push(format!(
+        ".{}-{}.new",
+        std::process::id(),
+        NEXT_TMP.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    let tmp_file_path = config_file_path.with_file_name(tmp_file_name);
+
+    let started = std::time::Instant::now();
+    let write = |path: &std::path::Path| -> std::io::Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        progress::write_all(&mut file, config_file_path, config_bytes)?;
+        file.sync_all()
+    };
+
+    write(&tmp_file_path)?;
+    let linked = std::fs::hard_link(&tmp_file_path, config_file_path);
+    let _ = std::fs::remove_file(&tmp_file_path);
+
+    let created = match linked {
+        Ok(()) => true,
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => false,
+        Err(_) => match write(config_file_path) {
+            Ok(()) => true,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => false,
+            Err(err) => return Err(err.into()),
+        },
+    };
+
+    if created {
+        attributes::after_write(config_file_path);
+        slow_io::report(
+            slow_io::IoOperation::Store,
+            config_file_path,
+            config_bytes.len(),
+            started,
+        );
+    }
+
+    Ok(created)
+}
+
+/// Loads a config of any [`ConfigType`] with its `load` function.
+fn load_typed<T>(
+    app_name: &str,
+    config_name: Option<&str>,
+    config_type: &ConfigType,
+    location: &ConfigLocation,
+    reset_conf_on_err: bool,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    match config_type {
+        #[cfg(feature = "binary-conf")]
+        ConfigType::Bin => load_bin(app_name, config_name, location, reset_conf_on_err),
+
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => load_toml(app_name, config_name, location, reset_conf_on_err),
+
+        #[cfg(feature = "json-conf")]
+        ConfigType::Json => load_json(app_name, config_name, location, reset_conf_on_err),
+
+        #[cfg(feature = "yaml-conf")]
+        ConfigType::Yaml => load_yaml(app_name, config_name, location, reset_conf_on_err),
+
+        #[cfg(feature = "ron-conf")]
+        ConfigType::Ron => load_ron(app_name, config_name, location, reset_conf_on_err),
+    }
+}
+
+/// Stores a config of any [`ConfigType`] with its `store` function.
+fn store_typed<T>(
+    app_name: &str,
+    config_name: Option<&str>,
+    config_type: &ConfigType,
+    location: &ConfigLocation,
+    data: T,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    match config_type {
+        #[cfg(feature = "binary-conf")]
+        ConfigType::Bin => store_bin(app_name, config_name, location, data),
+
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => store_toml(app_name, config_name, location, data),
+
+        #[cfg(feature = "json-conf")]
+        ConfigType::Json => store_json(app_name, config_name, location, data),
+
+        #[cfg(feature = "yaml-conf")]
+        ConfigType::Yaml => store_yaml(app_name, config_name, location, data),
+
+        #[cfg(feature = "ron-conf")]
+        ConfigType::Ron => store_ron(app_name, config_name, location, data),
+    }
+}
+
+/// Serializes a config to the content of the config file at `path`, of any [`ConfigType`], as its `store` function writes it.
+fn serialize_typed<T>(
+    data: &T,
+    config_type: &ConfigType,
+    path: &std::path::Path,
+) -> Result<Vec<u8>, ConfigError>
+where
+    T: serde::Serialize,
+{
+    #[cfg(any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    ))]
+    let options = output::output_options_for(path);
+    #[cfg(not(any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )))]
+    let _ = path;
+
+    match config_type {
+        #[cfg(feature = "binary-conf")]
+        ConfigType::Bin => binary_conf::prepare_serialized_data(data),
+
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => Ok(output::to_toml_string(data, &options)?.into_bytes()),
+
+        #[cfg(feature = "json-conf")]
+        ConfigType::Json => Ok(output::to_json_string(data, &options)?.into_bytes()),
+
+        #[cfg(feature = "yaml-conf")]
+        ConfigType::Yaml => Ok(output::to_yaml_string(data, &options)?.into_bytes()),
+
+        #[cfg(feature = "ron-conf")]
+        ConfigType::Ron => Ok(output::to_ron_string(data, &options)?.into_bytes()),
+    }
+}
+
+/// Deserializes the content of a config file of any [`ConfigType`], read from somewhere else than its `load` function.
+///
+/// An empty document holds no config, the default config is returned like the `load` functions do.
+fn deserialize_typed<T>(data: &[u8], config_type: &ConfigType) -> Result<T, ConfigError>
+where
+    T: Default + serde::de::DeserializeOwned,
+{
+    if data.is_empty() {
+        return Ok(T::default());
+    }
+
+    #[cfg(any(feature = "toml-conf", feature = "ron-conf"))]
+    let text = || {
+        std::str::from_utf8(data)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    };
+
+    match config_type {
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => Ok(toml::from_str(text()?)?),
+
+        #[cfg(feature = "json-conf")]
+        ConfigType::Json => Ok(serde_json::from_slice(data)?),
+
+        #[cfg(feature = "yaml-conf")]
+        ConfigType::Yaml => Ok(serde_yaml::from_slice(data)?),
+
+        #[cfg(feature = "ron-conf")]
+        ConfigType::Ron => Ok(ron::from_str(text()?)?),
+
+        #[cfg(feature = "binary-conf")]
+        ConfigType::Bin => Ok(bincode::deserialize(binary_conf::verified_payload(data)?)?),
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug)]
 pub enum ConfigError {
     Io(std::io::Error),
 
+    /// The deserializer did not consume the whole input. Only returned by the `strict` load functions.
+    TrailingData,
+
+    /// The same key appears twice in a mapping, at the given line of the file. Returned by the `strict` load functions,
+    /// and always when loading `toml` configs, whose parser rejects duplicate keys.
+    DuplicateKey {
+        path: String,
+        line: usize,
+    },
+
+    /// The value at the given key path exists but is not an array.
+    NotAnArray(String),
+
+    /// The config has no setting at the given key path. Returned by the [`kv`] functions.
+    UnknownKey(String),
+
+    /// The value given for a key does not fit the type of the setting, with the reason. Returned by [`kv::set`].
+    InvalidValue {
+        key: String,
+        reason: String,
+    },
+
+    /// The operation is not supported for the given config type.
+    UnsupportedFormat(String),
+
+    /// Not enough free space to store the config. Only returned when [`set_free_space_guard`] is enabled.
+    InsufficientSpace {
+        available: u64,
+        required: u64,
+    },
+
+    /// The app was frozen with [`freeze`], its configs can't be written anymore.
+    Frozen,
+
+    /// [`load`] or [`store`] was called outside of [`with_context`].
+    NoContext,
+
+    /// The operation was cancelled with the [`CancellationToken`] given to [`with_cancellation`].
+    Cancelled,
+
+    /// The app directory belongs to another user. Only returned when [`set_ownership_guard`] is set to refuse.
+    ForeignOwner(OwnershipMismatch),
+
+    /// The current directory, given here, is not inside a project. Only returned when creating or writing configs in
+    /// [`ConfigLocation::Cwd`] with [`CwdPolicy::refuse_outside_project`] set.
+    OutsideProject(PathBuf),
+
+    /// The config file already exists at the given path. Returned by the `store_*_new` functions.
+    AlreadyExists(PathBuf),
+
+    /// Several files failed in an operation that touches many of them, like creating or applying a bundle, or loading
+    /// layered configs. Every failure is listed with the path of its file, so they can all be fixed in one pass.
+    Multiple(Vec<(PathBuf, ConfigError)>),
+
+    /// The session lock is held by another process, since the given time. Returned by [`lock_session`]. The pid is `0`
+    /// while the holder is still writing the lock file.
+    LockHeldBy {
+        pid: u64,
+        since: std::time::SystemTime,
+    },
+
     #[cfg(feature = "toml-conf")]
     TomlSer(toml::ser::Error),
 
     #[cfg(feature = "toml-conf")]
     TomlDe(toml::de::Error),
 
+    /// The configs extend each other in a loop. Holds the chain of file names, ending with the repeated one.
+    #[cfg(feature = "toml-conf")]
+    InheritanceCycle(Vec<String>),
+
     #[cfg(feature = "json-conf")]
     Json(serde_json::Error),
 
+    /// An element of a JSON array could not be deserialized. Returned by [`load_json_vec`].
+    #[cfg(feature = "json-conf")]
+    JsonElement {
+        index: usize,
+        err: serde_json::Error,
+    },
+
     #[cfg(feature = "yaml-conf")]
     Yaml(serde_yaml::Error),
 
@@ -220,6 +1078,47 @@ pub enum ConfigError {
 
     #[cfg(feature = "binary-conf")]
     CorruptedHashSector,
+
+    /// The binary config file is shorter than its hash header, as left by a write interrupted when the disk ran out.
+    #[cfg(feature = "binary-conf")]
+    TruncatedFile {
+        len: usize,
+        expected: usize,
+    },
+
+    /// The hash header of the binary config file is all zeros, the hash sector was damaged but the data may be intact.
+    /// See [`rehash_bin`].
+    #[cfg(feature = "binary-conf")]
+    ZeroedHeader {
+        len: usize,
+    },
+
+    /// The header of the binary config file has a format version or flags this version of the crate can't read, the
+    /// file was stored by a newer version.
+    #[cfg(feature = "binary-conf")]
+    UnsupportedFormatVersion(u8),
+
+    /// The binary config file was stored with another [`Hasher`] than the one used to load it, identified by
+    /// [`Hasher::algorithm_id`].
+    #[cfg(feature = "binary-conf")]
+    HashAlgorithmMismatch {
+        expected: u8,
+        found: u8,
+    },
+
+    /// The length of the data of the binary config file does not match the length recorded in its header, as after a
+    /// truncated write or appending to the file.
+    #[cfg(feature = "binary-conf")]
+    PayloadLengthMismatch {
+        expected: u64,
+        found: u64,
+    },
+
+    /// A [`ConfigFormat`] could not serialize the config.
+    FormatSer(Box<dyn std::error::Error + Send + Sync>),
+
+    /// A [`ConfigFormat`] could not deserialize the config file.
+    FormatDe(Box<dyn std::error::Error + Send + Sync>),
 }
 
 #[cfg(feature = "yaml-conf")]
@@ -277,20 +1176,232 @@ impl From<std::io::Error> for ConfigError {
     }
 }
 
+/// Stable category of a [`ConfigError`], returned by [`ConfigError::kind`].
+///
+/// Unlike the variants of [`ConfigError`], which wrap the errors of the underlying crates and depend on the enabled features,
+/// the kinds do not change when those crates do, so applications can branch on them.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// A file or directory was not found.
+    NotFound,
+    /// The current user is not allowed to access a file or directory.
+    PermissionDenied,
+    /// There is not enough free space to store the config.
+    InsufficientSpace,
+    /// The config file is damaged: bad hash, truncated file, invalid syntax or trailing data.
+    Corrupt,
+    /// The config is well formed but its content does not match the expected type or structure.
+    FormatMismatch,
+    /// The configs involved contradict each other, like an inheritance cycle, or another process holds the lock.
+    Conflict,
+    /// The config could not be serialized.
+    Serialize,
+    /// The operation is not supported for the config type.
+    Unsupported,
+    /// The operation was cancelled by the application.
+    Cancelled,
+    /// Any other I/O error.
+    Io,
+}
+
+impl ErrorKind {
+    fn from_io(err: &std::io::Error) -> ErrorKind {
+        match err.kind() {
+            std::io::ErrorKind::NotFound => ErrorKind::NotFound,
+            std::io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            std::io::ErrorKind::StorageFull => ErrorKind::InsufficientSpace,
+            std::io::ErrorKind::UnexpectedEof => ErrorKind::Corrupt,
+            _ => ErrorKind::Io,
+        }
+    }
+}
+
+impl ConfigError {
+    /// Returns the stable category of the error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+    /// use binconf::ErrorKind;
+    ///
+    /// match binconf::load_bin::<u32>("test-binconf-error-kind", None, Config, false) {
+    ///     Ok(config) => println!("{config}"),
+    ///     Err(err) if err.kind() == ErrorKind::Corrupt => eprintln!("The config is corrupted: {err}"),
+    ///     Err(err) => eprintln!("{err}"),
+    /// }
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ConfigError::Io(err) => ErrorKind::from_io(err),
+
+            ConfigError::TrailingData => ErrorKind::Corrupt,
+            ConfigError::DuplicateKey { .. } => ErrorKind::Corrupt,
+            ConfigError::Frozen => ErrorKind::PermissionDenied,
+            ConfigError::NoContext => ErrorKind::Unsupported,
+            ConfigError::Cancelled => ErrorKind::Cancelled,
+            ConfigError::ForeignOwner(_) => ErrorKind::PermissionDenied,
+            ConfigError::OutsideProject(_) => ErrorKind::PermissionDenied,
+
+            ConfigError::NotAnArray(_) => ErrorKind::FormatMismatch,
+            ConfigError::UnknownKey(_) => ErrorKind::FormatMismatch,
+            ConfigError::InvalidValue { .. } => ErrorKind::FormatMismatch,
+
+            ConfigError::UnsupportedFormat(_) => ErrorKind::Unsupported,
+
+            ConfigError::InsufficientSpace { .. } => ErrorKind::InsufficientSpace,
+
+            ConfigError::LockHeldBy { .. } => ErrorKind::Conflict,
+            ConfigError::AlreadyExists(_) => ErrorKind::Conflict,
+            ConfigError::Multiple(failures) => failures
+                .first()
+                .map_or(ErrorKind::Io, |(_, err)| err.kind()),
+
+            #[cfg(feature = "toml-conf")]
+            ConfigError::TomlSer(_) => ErrorKind::Serialize,
+
+            #[cfg(feature = "toml-conf")]
+            ConfigError::TomlDe(_) => ErrorKind::Corrupt,
+
+            #[cfg(feature = "toml-conf")]
+            ConfigError::InheritanceCycle(_) => ErrorKind::Conflict,
+
+            #[cfg(feature = "json-conf")]
+            ConfigError::Json(err) | ConfigError::JsonElement { err, .. } => match err.classify() {
+                serde_json::error::Category::Io => ErrorKind::Io,
+                serde_json::error::Category::Data => ErrorKind::FormatMismatch,
+                serde_json::error::Category::Syntax | serde_json::error::Category::Eof => {
+                    ErrorKind::Corrupt
+                }
+            },
+
+            #[cfg(feature = "yaml-conf")]
+            ConfigError::Yaml(_) => ErrorKind::Corrupt,
+
+            #[cfg(feature = "ron-conf")]
+            ConfigError::RonSer(_) => ErrorKind::Serialize,
+
+            #[cfg(feature = "ron-conf")]
+            ConfigError::RonDe(_) => ErrorKind::Corrupt,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::Bincode(err) => match err.as_ref() {
+                bincode::ErrorKind::Io(err) => ErrorKind::from_io(err),
+                bincode::ErrorKind::SequenceMustHaveLength => ErrorKind::Serialize,
+                _ => ErrorKind::Corrupt,
+            },
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::HashMismatch => ErrorKind::Corrupt,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::CorruptedHashSector => ErrorKind::Corrupt,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::TruncatedFile { .. } => ErrorKind::Corrupt,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::ZeroedHeader { .. } => ErrorKind::Corrupt,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::UnsupportedFormatVersion(_) => ErrorKind::Unsupported,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::HashAlgorithmMismatch { .. } => ErrorKind::FormatMismatch,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::PayloadLengthMismatch { .. } => ErrorKind::Corrupt,
+
+            ConfigError::FormatSer(_) => ErrorKind::Serialize,
+
+            ConfigError::FormatDe(_) => ErrorKind::Corrupt,
+        }
+    }
+
+    /// Returns `Ok` if there are no failures, otherwise a [`ConfigError::Multiple`] error listing them.
+    #[cfg(any(feature = "binary-conf", feature = "toml-conf"))]
+    pub(crate) fn from_failures(failures: Vec<(PathBuf, ConfigError)>) -> Result<(), ConfigError> {
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        Err(ConfigError::Multiple(failures))
+    }
+
+    /// Returns `true` if the stored config itself is bad: hash mismatch, truncated file, parse error or content that does not match the expected type.
+    ///
+    /// Environmental errors, like a missing permission or a full disk, are never classified as corruption.
+    pub fn is_corruption(&self) -> bool {
+        matches!(self.kind(), ErrorKind::Corrupt | ErrorKind::FormatMismatch)
+    }
+
+    /// Returns `true` if replacing the config file, by resetting it to its default or restoring a backup, is an appropriate way to recover.
+    ///
+    /// That is the case for corrupted and missing configs. Environmental errors are not recoverable this way, as writing a new file
+    /// would fail for the same reason or hide the actual problem.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+    ///
+    /// let config = match binconf::load_bin::<u32>("test-binconf-is-recoverable", None, Config, false) {
+    ///     Ok(config) => config,
+    ///     Err(err) if err.is_recoverable() => {
+    ///         binconf::store_bin("test-binconf-is-recoverable", None, Config, 0u32).unwrap();
+    ///         0
+    ///     }
+    ///     Err(err) => panic!("{err}"),
+    /// };
+    /// ```
+    pub fn is_recoverable(&self) -> bool {
+        self.is_corruption() || self.kind() == ErrorKind::NotFound
+    }
+}
+
 impl std::error::Error for ConfigError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             ConfigError::Io(err) => Some(err),
 
+            ConfigError::TrailingData => None,
+            ConfigError::DuplicateKey { .. } => None,
+            ConfigError::Frozen => None,
+            ConfigError::NoContext => None,
+            ConfigError::Cancelled => None,
+            ConfigError::ForeignOwner(_) => None,
+            ConfigError::OutsideProject(_) => None,
+            ConfigError::AlreadyExists(_) => None,
+            ConfigError::Multiple(failures) => failures
+                .first()
+                .map(|(_, err)| err as &(dyn std::error::Error + 'static)),
+
+            ConfigError::NotAnArray(_) => None,
+            ConfigError::UnknownKey(_) => None,
+            ConfigError::InvalidValue { .. } => None,
+
+            ConfigError::UnsupportedFormat(_) => None,
+
+            ConfigError::InsufficientSpace { .. } => None,
+
+            ConfigError::LockHeldBy { .. } => None,
+
             #[cfg(feature = "toml-conf")]
             ConfigError::TomlSer(err) => Some(err),
 
             #[cfg(feature = "toml-conf")]
             ConfigError::TomlDe(err) => Some(err),
 
+            #[cfg(feature = "toml-conf")]
+            ConfigError::InheritanceCycle(_) => None,
+
             #[cfg(feature = "json-conf")]
             ConfigError::Json(err) => Some(err),
 
+            #[cfg(feature = "json-conf")]
+            ConfigError::JsonElement { err, .. } => Some(err),
+
             #[cfg(feature = "yaml-conf")]
             ConfigError::Yaml(err) => Some(err),
 
@@ -308,6 +1419,25 @@ impl std::error::Error for ConfigError {
 
             #[cfg(feature = "binary-conf")]
             ConfigError::CorruptedHashSector => None,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::TruncatedFile { .. } => None,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::ZeroedHeader { .. } => None,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::UnsupportedFormatVersion(_) => None,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::HashAlgorithmMismatch { .. } => None,
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::PayloadLengthMismatch { .. } => None,
+
+            ConfigError::FormatSer(err) => Some(err.as_ref()),
+
+            ConfigError::FormatDe(err) => Some(err.as_ref()),
         }
     }
 }
@@ -317,6 +1447,59 @@ impl std::fmt::Display for ConfigError {
         match self {
             ConfigError::Io(err) => write!(f, "{err}"),
 
+            ConfigError::TrailingData => write!(f, "Trailing data after the config payload"),
+            ConfigError::Frozen => write!(f, "The configs of this app are frozen"),
+            ConfigError::NoContext => write!(f, "No config context is set on this thread"),
+            ConfigError::Cancelled => write!(f, "The operation was cancelled"),
+            ConfigError::OutsideProject(dir) => write!(
+                f,
+                "{} is not inside a project, configs are not stored there",
+                dir.display()
+            ),
+            ConfigError::ForeignOwner(mismatch) => write!(
+                f,
+                "{} belongs to uid {}, not to the current uid {}",
+                mismatch.path.display(),
+                mismatch.owner_uid,
+                mismatch.current_uid
+            ),
+            ConfigError::AlreadyExists(path) => {
+                write!(f, "The config file {} already exists", path.display())
+            }
+            ConfigError::Multiple(failures) => {
+                write!(f, "{} files failed", failures.len())?;
+                for (path, err) in failures {
+                    write!(f, "\n  {}: {err}", path.display())?;
+                }
+                Ok(())
+            }
+            ConfigError::DuplicateKey { path, line } => {
+                write!(f, "Duplicate key `{path}` at line {line}")
+            }
+
+            ConfigError::NotAnArray(key) => write!(f, "Value at `{key}` is not an array"),
+            ConfigError::UnknownKey(key) => write!(f, "Unknown key `{key}`"),
+            ConfigError::InvalidValue { key, reason } => {
+                write!(f, "Invalid value for `{key}`: {reason}")
+            }
+
+            ConfigError::UnsupportedFormat(format) => {
+                write!(f, "Operation not supported for `{format}` configs")
+            }
+
+            ConfigError::InsufficientSpace {
+                available,
+                required,
+            } => write!(
+                f,
+                "Insufficient space: {available} bytes available, {required} bytes required"
+            ),
+
+            ConfigError::LockHeldBy { pid, since } => {
+                let held_for = since.elapsed().unwrap_or_default().as_secs();
+                write!(f, "Locked by process {pid} for {held_for} seconds")
+            }
+
             #[cfg(feature = "binary-conf")]
             ConfigError::Bincode(err) => write!(f, "{err}"),
 
@@ -326,9 +1509,17 @@ impl std::fmt::Display for ConfigError {
             #[cfg(feature = "toml-conf")]
             ConfigError::TomlDe(err) => write!(f, "{err}"),
 
+            #[cfg(feature = "toml-conf")]
+            ConfigError::InheritanceCycle(chain) => {
+                write!(f, "Config inheritance cycle: {}", chain.join(" -> "))
+            }
+
             #[cfg(feature = "json-conf")]
             ConfigError::Json(err) => write!(f, "{err}"),
 
+            #[cfg(feature = "json-conf")]
+            ConfigError::JsonElement { index, err } => write!(f, "Element {index}: {err}"),
+
             #[cfg(feature = "yaml-conf")]
             ConfigError::Yaml(err) => write!(f, "{err}"),
 
@@ -343,6 +1534,38 @@ impl std::fmt::Display for ConfigError {
 
             #[cfg(feature = "binary-conf")]
             ConfigError::CorruptedHashSector => write!(f, "Corrupted hash sector"),
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::TruncatedFile { len, expected } => write!(
+                f,
+                "Truncated file: {len} bytes, the hash header alone is {expected} bytes"
+            ),
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::ZeroedHeader { len } => {
+                write!(f, "Zeroed hash header: all {len} bytes are zero")
+            }
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::UnsupportedFormatVersion(version) => {
+                write!(f, "Unsupported binary format version: {version}")
+            }
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::HashAlgorithmMismatch { expected, found } => write!(
+                f,
+                "Hash algorithm mismatch: expected algorithm {expected}, the file was stored with algorithm {found}"
+            ),
+
+            #[cfg(feature = "binary-conf")]
+            ConfigError::PayloadLengthMismatch { expected, found } => write!(
+                f,
+                "Payload length mismatch: the header records {expected} bytes, the file holds {found} bytes"
+            ),
+
+            ConfigError::FormatSer(err) => write!(f, "{err}"),
+
+            ConfigError::FormatDe(err) => write!(f, "{err}"),
         }
     }
 }
@@ -351,7 +1574,6 @@ impl std::fmt::Display for ConfigError {
 #[cfg(feature = "full")]
 mod tests {
     use super::*;
-    use dirs;
 
     #[test]
     fn test_get_configuration_path_config() {
@@ -633,4 +1855,124 @@ mod tests {
         assert_eq!(ron_config, cwd_location.join("test/custom.ron"));
         assert_eq!(bin_config, cwd_location.join("test/custom.bin"));
     }
+
+    #[test]
+    fn error_kind_categorizes_errors() {
+        let not_found = ConfigError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(not_found.kind(), ErrorKind::NotFound);
+
+        let path = get_configuration_path(
+            "test-binconf-error_kind",
+            None,
+            ConfigType::Bin,
+            ConfigLocation::Config,
+        )
+        .unwrap();
+        std::fs::write(&path, b"too short").unwrap();
+        let corrupt = load_bin::<u32>(
+            "test-binconf-error_kind",
+            None,
+            ConfigLocation::Config,
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(corrupt.kind(), ErrorKind::Corrupt);
+
+        let mismatch = ConfigError::from(serde_json::from_str::<u32>("\"text\"").unwrap_err());
+        assert_eq!(mismatch.kind(), ErrorKind::FormatMismatch);
+
+        let syntax = ConfigError::from(serde_json::from_str::<u32>("").unwrap_err());
+        assert_eq!(syntax.kind(), ErrorKind::Corrupt);
+    }
+
+    #[test]
+    fn error_classification_predicates() {
+        let corrupt = ConfigError::HashMismatch;
+        assert!(corrupt.is_corruption());
+        assert!(corrupt.is_recoverable());
+
+        let truncated = ConfigError::from(bincode::deserialize::<u64>(&[0u8; 4]).unwrap_err());
+        assert!(truncated.is_corruption());
+
+        let not_found = ConfigError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(!not_found.is_corruption());
+        assert!(not_found.is_recoverable());
+
+        let denied = ConfigError::from(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+        assert!(!denied.is_corruption());
+        assert!(!denied.is_recoverable());
+
+        let full = ConfigError::InsufficientSpace {
+            available: 0,
+            required: 1,
+        };
+        assert!(!full.is_recoverable());
+    }
+
+    #[test]
+    fn save_config_new_creates_once() {
+        let path = get_configuration_path(
+            "test-binconf-save_config_new_creates_once",
+            None,
+            ConfigType::Bin,
+            ConfigLocation::Config,
+        )
+        .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let threads: Vec<_> = (0..8u8)
+            .map(|id| {
+                let path = path.clone();
+                std::thread::spawn(move || (id, save_config_new(&path, &[id; 64]).unwrap()))
+            })
+            .collect();
+        let winners: Vec<_> = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .filter(|(_, created)| *created)
+            .collect();
+
+        // Only one write went through, complete, and no temporary file is left behind
+        let [(id, _)] = winners.as_slice() else {
+            panic!("{} threads created the file", winners.len());
+        };
+        assert_eq!(std::fs::read(&path).unwrap(), [*id; 64]);
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+    }
+
+    #[test]
+    fn derive_generates_typed_functions() {
+        #[derive(Default, serde::Serialize, serde::Deserialize, BinConf, PartialEq, Debug)]
+        #[binconf(
+            app = "test-binconf-derive_generates_typed_functions",
+            format = "toml",
+            location = "cache",
+            name = "settings.toml"
+        )]
+        struct Settings {
+            theme: String,
+        }
+
+        let settings = Settings {
+            theme: String::from("dark"),
+        };
+        settings.store().unwrap();
+
+        assert_eq!(Settings::load().unwrap(), settings);
+        assert_eq!(<Settings as ConfigFile>::load().unwrap(), settings);
+        assert_eq!(<Settings as ConfigFile>::LOCATION, ConfigLocation::Cache);
+        assert_eq!(
+            Settings::path().unwrap(),
+            get_configuration_path(
+                "test-binconf-derive_generates_typed_functions",
+                "settings.toml",
+                ConfigType::Toml,
+                ConfigLocation::Cache
+            )
+            .unwrap()
+        );
+    }
 }
@@ -0,0 +1,205 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::{Path, PathBuf};
+
+/// Loads a shared config with the machine-local overrides of the current checkout, from the config, cache, cwd, or local data directory of the current user. In `toml` format.
+///
+/// Meant for project configs in [`ConfigLocation::Cwd`] that are committed with the project. The shared file, like
+/// `config.toml`, holds the settings of the team. The local file next to it, like `config.local.toml`, holds the
+/// settings of this machine, and is deep merged over the shared file. Store the shared file with [`crate::store_toml`],
+/// and the local file with [`store_toml_local`]. Missing files are skipped, and the default config is returned if there
+/// are none.
+///
+/// No file is ever created.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or
+/// if a file could not be read or deserialized.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Project {
+///     registry: String,
+///     jobs: u32,
+/// }
+///
+/// let shared = Project { registry: String::from("https://registry.example.com"), jobs: 4 };
+/// binconf::store_toml("test-binconf-load-toml-local", "config.toml", Config, &shared).unwrap();
+///
+/// // Only `jobs = 16` is written to `config.local.toml`
+/// let local = Project { jobs: 16, ..shared };
+/// binconf::store_toml_local("test-binconf-load-toml-local", "config.toml", Config, &local).unwrap();
+///
+/// let project: Project = binconf::load_toml_local("test-binconf-load-toml-local", "config.toml", Config).unwrap();
+/// assert_eq!(project, local);
+/// ```
+pub fn load_toml_local<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::de::DeserializeOwned,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Toml.as_str(),
+        location.as_ref(),
+    )?;
+
+    let shared = read_table(&config_file_path)?;
+    let local = read_table(&local_path(&config_file_path))?;
+    if shared.is_none() && local.is_none() {
+        return Ok(T::default());
+    }
+
+    let mut config = toml::Value::Table(shared.unwrap_or_default());
+    if let Some(local) = local {
+        crate::merge::merge_toml(&mut config, toml::Value::Table(local));
+    }
+
+    Ok(config.try_into()?)
+}
+
+/// Stores the values of a config that differ from the shared config into its machine-local file, in the config, cache, cwd, or local data directory of the current user. In `toml` format.
+///
+/// The counterpart of [`load_toml_local`]. The local file is named after the shared file, `config.local.toml` for
+/// `config.toml`. When it is inside a git repository, it is added to `.git/info/exclude`, which ignores it in this
+/// checkout only, without touching the `.gitignore` of the project. The shared file is never written.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if
+/// the shared file could not be read, or if something went wrong while serializing the config. Failing to update the
+/// git exclude file is not an error.
+pub fn store_toml_local<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Toml.as_str(),
+        location.as_ref(),
+    )?;
+    let local_file_path = local_path(&config_file_path);
+
+    let shared = read_table(&config_file_path)?.unwrap_or_default();
+    let config = toml::Table::try_from(data)?;
+    let local = crate::merge::diff_toml(&shared, &config);
+
+    crate::save_config_atomic(&local_file_path, toml::to_string_pretty(&local)?.as_bytes())?;
+    let _ = exclude_from_git(&local_file_path);
+
+    Ok(())
+}
+
+/// Returns the path of the local file of the shared config at `config_file_path`: `config.local.toml` for `config.toml`.
+fn local_path(config_file_path: &Path) -> PathBuf {
+    let stem = config_file_path
+        .file_stem()
+        .map(std::ffi::OsStr::to_os_string)
+        .unwrap_or_default();
+
+    let mut file_name = stem;
+    file_name.push(".local");
+    if let Some(extension) = config_file_path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+
+    config_file_path.with_file_name(file_name)
+}
+
+fn read_table(path: &Path) -> Result<Option<toml::Table>, ConfigError> {
+    if !path.try_exists()? {
+        return Ok(None);
+    }
+
+    Ok(Some(toml::from_str(&crate::progress::read_to_string(
+        path,
+    )?)?))
+}
+
+/// Adds `path` to the exclude file of the git repository holding it, if any and if it is not listed yet.
+fn exclude_from_git(path: &Path) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let Some(repository) = path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join(".git").is_dir())
+    else {
+        return Ok(());
+    };
+    let Ok(relative) = path.strip_prefix(repository) else {
+        return Ok(());
+    };
+
+    // Anchored to the root of the repository, with `/` separators on every platform
+    let pattern = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .fold(String::new(), |pattern, component| {
+            format!("{pattern}/{component}")
+        });
+
+    let exclude_path = repository.join(".git").join("info").join("exclude");
+    let excluded = match std::fs::read_to_string(&exclude_path) {
+        Ok(excluded) => excluded,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    if excluded.lines().any(|line| line.trim() == pattern) {
+        return Ok(());
+    }
+
+    if let Some(info_dir) = exclude_path.parent() {
+        std::fs::create_dir_all(info_dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&exclude_path)?;
+    if !excluded.is_empty() && !excluded.ends_with('\n') {
+        writeln!(file)?;
+    }
+    writeln!(file, "{pattern}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_files_are_excluded_from_git() {
+        let repository =
+            std::env::temp_dir().join("test-binconf-local_files_are_excluded_from_git");
+        let _ = std::fs::remove_dir_all(&repository);
+        std::fs::create_dir_all(repository.join(".git")).unwrap();
+        std::fs::create_dir_all(repository.join("tool")).unwrap();
+
+        let local_file_path = local_path(&repository.join("tool").join(".tool.toml"));
+        assert_eq!(
+            local_file_path,
+            repository.join("tool").join(".tool.local.toml")
+        );
+
+        exclude_from_git(&local_file_path).unwrap();
+        exclude_from_git(&local_file_path).unwrap();
+        let excluded =
+            std::fs::read_to_string(repository.join(".git").join("info").join("exclude")).unwrap();
+        assert_eq!(excluded, "/tool/.tool.local.toml\n");
+    }
+}
@@ -0,0 +1,366 @@
+use crate::{ConfigError, ConfigLocation};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How often a lock held by another process is tried again while waiting for it.
+const LOCK_RETRY: Duration = Duration::from_millis(10);
+
+/// How long a lock file without a pid is left to its holder to write it, before it is considered left by a crash.
+const EMPTY_LOCK_GRACE: Duration = Duration::from_secs(2);
+
+/// When an existing session lock is considered stale and broken by [`lock_session`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaleLockPolicy {
+    /// Break the lock if the process that holds it is no longer running. Only detected on unix platforms.
+    pub break_dead_pid: bool,
+    /// Break the lock if it is older than this, whether its process is running or not.
+    pub max_age: Option<Duration>,
+}
+
+impl Default for StaleLockPolicy {
+    fn default() -> Self {
+        StaleLockPolicy {
+            break_dead_pid: true,
+            max_age: None,
+        }
+    }
+}
+
+/// A session lock acquired with [`lock_session`]. The lock is released when it is dropped.
+#[derive(Debug)]
+pub struct SessionLock {
+    path: PathBuf,
+    /// What this lock wrote in the lock file, to tell it apart from a lock acquired by another process after breaking it.
+    content: String,
+}
+
+impl SessionLock {
+    /// Releases the lock. Same as dropping it, but reports errors.
+    ///
+    /// The lock file is only removed if it is still the one of this lock. If it was broken as stale and acquired by
+    /// another process since, the lock of that process is left alone.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lock file could not be removed.
+    pub fn release(mut self) -> Result<(), ConfigError> {
+        let path = std::mem::take(&mut self.path);
+        let content = std::mem::take(&mut self.content);
+        std::mem::forget(self);
+
+        remove_lock_holding(&path, &content)
+    }
+}
+
+impl Drop for SessionLock {
+    fn drop(&mut self) {
+        let _ = remove_lock_holding(&self.path, &self.content);
+    }
+}
+
+/// Acquires the session lock of an app, in the config, cache, cwd, or local data directory of the current user.
+///
+/// Only one [`SessionLock`] per app and location can exist at a time, across processes. The lock file records the pid of its holder and
+/// when it was acquired. A lock left behind by a crashed process is broken according to `policy`.
+///
+/// # Errors
+///
+/// This function will return a [`ConfigError::LockHeldBy`] error if another live session holds the lock, so apps can show
+/// an "another instance is running" message. Other errors are returned if the lock file could not be created or read.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigError, StaleLockPolicy};
+///
+/// let lock = binconf::lock_session("test-binconf-lock-session", Config, &StaleLockPolicy::default()).unwrap();
+///
+/// match binconf::lock_session("test-binconf-lock-session", Config, &StaleLockPolicy::default()) {
+///     Err(ConfigError::LockHeldBy { pid, .. }) => println!("Another instance is running with pid {pid}"),
+///     _ => unreachable!(),
+/// }
+///
+/// lock.release().unwrap();
+/// ```
+pub fn lock_session(
+    app_name: impl AsRef<str>,
+    location: impl AsRef<ConfigLocation>,
+    policy: &StaleLockPolicy,
+) -> Result<SessionLock, ConfigError> {
+    let app_name = app_name.as_ref();
+    let path = crate::app_dir(app_name, location.as_ref())?.join(format!("{app_name}.lock"));
+
+    lock_file(path, policy)
+}
+
+/// Acquires the lock file at `path`, breaking it according to `policy` if it is stale.
+pub(crate) fn lock_file(
+    path: PathBuf,
+    policy: &StaleLockPolicy,
+) -> Result<SessionLock, ConfigError> {
+    static NEXT_LOCK: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    loop {
+        let since = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        // The counter tells apart the locks of the same process acquired within the same second
+        let content = format!(
+            "{}\n{since}\n{}\n",
+            std::process::id(),
+            NEXT_LOCK.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        );
+        if create_lock(&path, &content)? {
+            return Ok(SessionLock { path, content });
+        }
+
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            // Released in the meantime
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut lines = content.lines().map(str::parse::<u64>);
+        let (Some(Ok(pid)), Some(Ok(since))) = (lines.next(), lines.next()) else {
+            // Empty until the holder writes it, unless it crashed between creating and writing the file
+            let modified = std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or_else(|_| SystemTime::now());
+            let written_soon = SystemTime::now()
+                .duration_since(modified)
+                .map_or(true, |age| age < EMPTY_LOCK_GRACE);
+            if written_soon {
+                return Err(ConfigError::LockHeldBy {
+                    pid: 0,
+                    since: modified,
+                });
+            }
+            remove_lock_holding(&path, &content)?;
+            continue;
+        };
+        let since = UNIX_EPOCH + Duration::from_secs(since);
+
+        let expired = policy.max_age.is_some_and(|max_age| {
+            SystemTime::now()
+                .duration_since(since)
+                .is_ok_and(|age| age > max_age)
+        });
+        let dead = policy.break_dead_pid && !process_is_running(pid);
+
+        if !(expired || dead) {
+            return Err(ConfigError::LockHeldBy { pid, since });
+        }
+
+        remove_lock_holding(&path, &content)?;
+    }
+}
+
+/// Acquires the lock file at `path` like [`lock_file`], waiting up to `timeout` for another process to release it.
+pub(crate) fn lock_file_waiting(
+    path: PathBuf,
+    policy: &StaleLockPolicy,
+    timeout: Duration,
+) -> Result<SessionLock, ConfigError> {
+    let started = Instant::now();
+    loop {
+        match lock_file(path.clone(), policy) {
+            Err(ConfigError::LockHeldBy { .. }) if started.elapsed() < timeout => {
+                std::thread::sleep(LOCK_RETRY);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Creates the lock file at `path` holding `content`, returns `false` if it already exists.
+///
+/// The content is written to a temporary file which is then hard linked in place, so other processes never read a lock
+/// file that is still empty. File systems without hard links fall back to an exclusive create of the file.
+fn create_lock(path: &std::path::Path, content: &str) -> Result<bool, ConfigError> {
+    static NEXT_TMP: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    let write = |path: &std::path::Path| -> std::io::Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    };
+
+    let mut tmp_file_name = path
+        .file_name()
+        .map(std::ffi::OsStr::to_os_string)
+        .unwrap_or_default();
+    tmp_file_name.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        NEXT_TMP.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    let tmp_file_path = path.with_file_name(tmp_file_name);
+
+    write(&tmp_file_path)?;
+    let linked = std::fs::hard_link(&tmp_file_path, path);
+    let _ = std::fs::remove_file(&tmp_file_path);
+
+    match linked {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        Err(_) => match write(path) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(err) => Err(err.into()),
+        },
+    }
+}
+
+/// Removes the lock file at `path`, if it still holds `expected`: the content a stale lock was judged on, or the content
+/// written by the [`SessionLock`] being released.
+///
+/// Another process may have broken the same lock and acquired a new one since it was read. The lock is moved to a unique
+/// name first, which only one process can do, and put back if it turns out to be a different lock. A different lock is
+/// never removed.
+fn remove_lock_holding(path: &std::path::Path, expected: &str) -> Result<(), ConfigError> {
+    static NEXT_STALE: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    let mut stale_file_name = path
+        .file_name()
+        .map(std::ffi::OsStr::to_os_string)
+        .unwrap_or_default();
+    stale_file_name.push(format!(
+        ".{}-{}.stale",
+        std::process::id(),
+        NEXT_STALE.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    let stale_file_path = path.with_file_name(stale_file_name);
+
+    match std::fs::rename(path, &stale_file_path) {
+        // Broken by another process in the meantime
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+        Ok(()) => {}
+    }
+
+    if std::fs::read_to_string(&stale_file_path)? == expected {
+        std::fs::remove_file(&stale_file_path)?;
+        return Ok(());
+    }
+
+    // A live lock, put it back without replacing one created since
+    match std::fs::hard_link(&stale_file_path, path) {
+        Ok(()) => std::fs::remove_file(&stale_file_path)?,
+        // Another lock was acquired in the meantime, both are live and neither is removed
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(_) => {
+            let _ = std::fs::rename(&stale_file_path, path);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn process_is_running(pid: u64) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+
+    // SAFETY: signal 0 only checks whether the process exists, nothing is sent.
+    let result = unsafe { libc::kill(pid, 0) };
+
+    result == 0 || std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+#[cfg(not(unix))]
+fn process_is_running(_pid: u64) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[test]
+    fn lock_session_breaks_stale_locks() {
+        let app_name = "test-binconf-lock_session_breaks_stale_locks";
+
+        let lock = lock_session(app_name, Config, &StaleLockPolicy::default()).unwrap();
+        assert!(matches!(
+            lock_session(app_name, Config, &StaleLockPolicy::default()),
+            Err(ConfigError::LockHeldBy { pid, .. }) if pid == u64::from(std::process::id())
+        ));
+
+        // Pretend the holder crashed, leaving an old lock of a pid that does not exist
+        let path = lock.path.clone();
+        std::mem::forget(lock);
+        std::fs::write(&path, format!("{}\n0\n", i32::MAX)).unwrap();
+
+        let policy = StaleLockPolicy {
+            break_dead_pid: false,
+            max_age: None,
+        };
+        assert!(lock_session(app_name, Config, &policy).is_err());
+
+        let policy = StaleLockPolicy {
+            break_dead_pid: false,
+            max_age: Some(Duration::from_secs(60)),
+        };
+        let lock = lock_session(app_name, Config, &policy).unwrap();
+        lock.release().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn breaking_a_lock_keeps_a_newer_one() {
+        let path = std::env::temp_dir().join("test-binconf-breaking_a_lock_keeps_a_newer_one.lock");
+        let _ = std::fs::remove_file(&path);
+
+        // Another waiter broke the stale lock and acquired a new one since it was read
+        let stale = format!("{}\n0\n", i32::MAX);
+        std::fs::write(&path, format!("{}\n1\n", std::process::id())).unwrap();
+        remove_lock_holding(&path, &stale).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\n1\n", std::process::id())
+        );
+
+        std::fs::write(&path, &stale).unwrap();
+        remove_lock_holding(&path, &stale).unwrap();
+        assert!(!path.exists());
+
+        // A lock that is still being written is held
+        std::fs::write(&path, "").unwrap();
+        assert!(matches!(
+            lock_file(path.clone(), &StaleLockPolicy::default()),
+            Err(ConfigError::LockHeldBy { pid: 0, .. })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn releasing_a_broken_lock_keeps_the_new_one() {
+        let app_name = "test-binconf-releasing_a_broken_lock_keeps_the_new_one";
+        let old = lock_session(app_name, Config, &StaleLockPolicy::default()).unwrap();
+
+        // The lock looks too old and is broken by another instance
+        std::fs::write(&old.path, format!("{}\n0\n", std::process::id())).unwrap();
+        let policy = StaleLockPolicy {
+            break_dead_pid: false,
+            max_age: Some(Duration::from_secs(60)),
+        };
+        let new = lock_session(app_name, Config, &policy).unwrap();
+        let content = std::fs::read_to_string(&new.path).unwrap();
+
+        drop(old);
+        assert_eq!(std::fs::read_to_string(&new.path).unwrap(), content);
+
+        let path = new.path.clone();
+        new.release().unwrap();
+        assert!(!path.exists());
+    }
+}
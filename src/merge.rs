@@ -0,0 +1,198 @@
+use crate::{ConfigError, ConfigType};
+
+/// Deep merges two text config documents of the same type, returning the merged document.
+///
+/// Tables (objects, mappings, maps) are merged recursively, any other value from `overlay` replaces the one in `base`.
+/// Keys only present in `base` are kept.
+///
+/// The binary format does not carry key names, so it returns a [`ConfigError::UnsupportedFormat`] error.
+pub(crate) fn merge_documents(
+    base: &str,
+    overlay: &str,
+    config_type: &ConfigType,
+) -> Result<String, ConfigError> {
+    match config_type {
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => {
+            let mut base = toml::Value::Table(toml::from_str(base)?);
+            merge_toml(&mut base, toml::Value::Table(toml::from_str(overlay)?));
+            Ok(toml::to_string_pretty(&base)?)
+        }
+
+        #[cfg(feature = "json-conf")]
+        ConfigType::Json => {
+            let mut base = serde_json::from_str(base)?;
+            merge_json(&mut base, serde_json::from_str(overlay)?);
+            Ok(serde_json::to_string_pretty(&base)?)
+        }
+
+        #[cfg(feature = "yaml-conf")]
+        ConfigType::Yaml => {
+            let mut base = serde_yaml::from_str(base)?;
+            merge_yaml(&mut base, serde_yaml::from_str(overlay)?);
+            Ok(serde_yaml::to_string(&base)?)
+        }
+
+        #[cfg(feature = "ron-conf")]
+        ConfigType::Ron => {
+            let mut base = ron::from_str(base)?;
+            merge_ron(&mut base, ron::from_str(overlay)?);
+            let ser_config = ron::ser::PrettyConfig::new()
+                .depth_limit(4)
+                .indentor("\t".to_owned());
+            Ok(ron::ser::to_string_pretty(&base, ser_config)?)
+        }
+
+        #[allow(unreachable_patterns)]
+        _ => Err(ConfigError::UnsupportedFormat(
+            config_type.as_str().to_owned(),
+        )),
+    }
+}
+
+/// Deserializes a text config document of the given type.
+///
+/// The binary format returns a [`ConfigError::UnsupportedFormat`] error.
+pub(crate) fn deserialize_document<T>(
+    document: &str,
+    config_type: &ConfigType,
+) -> Result<T, ConfigError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    match config_type {
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => Ok(toml::from_str(document)?),
+
+        #[cfg(feature = "json-conf")]
+        ConfigType::Json => Ok(serde_json::from_str(document)?),
+
+        #[cfg(feature = "yaml-conf")]
+        ConfigType::Yaml => Ok(serde_yaml::from_str(document)?),
+
+        #[cfg(feature = "ron-conf")]
+        ConfigType::Ron => Ok(ron::from_str(document)?),
+
+        #[allow(unreachable_patterns)]
+        _ => Err(ConfigError::UnsupportedFormat(
+            config_type.as_str().to_owned(),
+        )),
+    }
+}
+
+#[cfg(feature = "toml-conf")]
+pub(crate) fn merge_toml(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_toml(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Returns the values of `value` that differ from `base`, recursing into tables.
+///
+/// Merging the result over `base` with [`merge_toml`] gives back `value`, as long as `value` does not remove keys from `base`.
+#[cfg(feature = "toml-conf")]
+pub(crate) fn diff_toml(base: &toml::Table, value: &toml::Table) -> toml::Table {
+    let mut diff = toml::Table::new();
+
+    for (key, value) in value {
+        match (base.get(key), value) {
+            (Some(base_value), value) if base_value == value => {}
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(table)) => {
+                let child = diff_toml(base_table, table);
+                if !child.is_empty() {
+                    diff.insert(key.clone(), toml::Value::Table(child));
+                }
+            }
+            (_, value) => {
+                diff.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    diff
+}
+
+#[cfg(feature = "json-conf")]
+fn merge_json(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_json(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(feature = "yaml-conf")]
+fn merge_yaml(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_yaml(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(feature = "ron-conf")]
+fn merge_ron(base: &mut ron::Value, overlay: ron::Value) {
+    match (base, overlay) {
+        (ron::Value::Map(base), ron::Value::Map(overlay)) => {
+            for (key, value) in overlay {
+                match base.remove(&key) {
+                    Some(mut base_value) => {
+                        merge_ron(&mut base_value, value);
+                        base.insert(key, base_value);
+                    }
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_documents_is_deep() {
+        let merged = merge_documents(
+            "name = \"base\"\n[ui]\ntheme = \"light\"\nsize = 12\n",
+            "[ui]\ntheme = \"dark\"\n",
+            &ConfigType::Toml,
+        )
+        .unwrap();
+        let merged: toml::Table = toml::from_str(&merged).unwrap();
+
+        assert_eq!(merged["name"].as_str(), Some("base"));
+        assert_eq!(merged["ui"]["theme"].as_str(), Some("dark"));
+        assert_eq!(merged["ui"]["size"].as_integer(), Some(12));
+    }
+}
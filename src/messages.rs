@@ -0,0 +1,86 @@
+use crate::{ConfigError, ErrorKind};
+use std::collections::HashMap;
+
+/// Source of user facing error messages, used by [`ConfigError::localized_message`].
+///
+/// The messages of the underlying serde and bincode errors are meant for developers and are always in English.
+/// Implement this trait to show translated or friendlier messages in end user dialogs.
+///
+/// It is implemented for `HashMap<ErrorKind, String>`, acting as a simple message catalog, and for closures.
+pub trait ErrorMessages {
+    /// Returns the message to show for `err`, or `None` to fall back to its `Display` output.
+    fn message(&self, err: &ConfigError) -> Option<String>;
+}
+
+impl ErrorMessages for HashMap<ErrorKind, String> {
+    fn message(&self, err: &ConfigError) -> Option<String> {
+        self.get(&err.kind()).cloned()
+    }
+}
+
+impl<F> ErrorMessages for F
+where
+    F: Fn(&ConfigError) -> Option<String>,
+{
+    fn message(&self, err: &ConfigError) -> Option<String> {
+        self(err)
+    }
+}
+
+impl ConfigError {
+    /// Returns the message provided by `messages` for this error, or its `Display` output if there is none.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use binconf::{ConfigError, ErrorKind};
+    /// use std::collections::HashMap;
+    ///
+    /// let catalog = HashMap::from([
+    ///     (ErrorKind::Corrupt, String::from("La configuración está dañada.")),
+    ///     (ErrorKind::PermissionDenied, String::from("No tienes permiso para acceder a la configuración.")),
+    /// ]);
+    ///
+    /// let err = ConfigError::HashMismatch;
+    ///
+    /// assert_eq!(err.localized_message(&catalog), "La configuración está dañada.");
+    /// ```
+    pub fn localized_message(&self, messages: &impl ErrorMessages) -> String {
+        messages.message(self).unwrap_or_else(|| self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn localized_message_falls_back_to_display() {
+        let catalog = HashMap::from([(ErrorKind::NotFound, String::from("Introuvable"))]);
+
+        let not_found = ConfigError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(not_found.localized_message(&catalog), "Introuvable");
+
+        let unsupported = ConfigError::UnsupportedFormat(String::from("bin"));
+        assert_eq!(
+            unsupported.localized_message(&catalog),
+            unsupported.to_string()
+        );
+    }
+
+    #[test]
+    fn localized_message_from_closure() {
+        let messages = |err: &ConfigError| match err {
+            ConfigError::InsufficientSpace { required, .. } => {
+                Some(format!("Se necesitan {required} bytes"))
+            }
+            _ => None,
+        };
+
+        let err = ConfigError::InsufficientSpace {
+            available: 1,
+            required: 2,
+        };
+        assert_eq!(err.localized_message(&messages), "Se necesitan 2 bytes");
+    }
+}
@@ -0,0 +1,332 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Marks the start of a metadata header, both in binary files and in sidecar files.
+const METADATA_MAGIC: [u8; 8] = *b"BCNFMETA";
+
+/// Length of a metadata header: the magic bytes, the creation and modification times and the write counter.
+#[cfg(feature = "binary-conf")]
+pub(crate) const METADATA_LENGTH: usize = METADATA_MAGIC.len() + 8 + 8 + 8;
+
+/// Extension appended to the file name of text configs to get their metadata sidecar file.
+const SIDECAR_EXTENSION: &str = "meta";
+
+/// Creation and modification times and write counter of a config stored with [`store_stamped`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigMetadata {
+    /// When the config was first stored with [`store_stamped`].
+    pub created_at: SystemTime,
+    /// When the config was last stored with [`store_stamped`]. Never goes backwards, even if the system clock does.
+    pub modified_at: SystemTime,
+    /// Number of times the config was stored with [`store_stamped`].
+    pub write_count: u64,
+}
+
+impl ConfigMetadata {
+    /// Returns `true` if `self` describes a newer copy of the config than `other`.
+    ///
+    /// The copy with the most writes wins, ties are broken by the modification time. Useful for sync tools that must pick
+    /// one of two copies deterministically.
+    pub fn is_newer_than(&self, other: &ConfigMetadata) -> bool {
+        (self.write_count, self.modified_at) > (other.write_count, other.modified_at)
+    }
+
+    fn next(previous: Option<ConfigMetadata>) -> ConfigMetadata {
+        // Times are stored with millisecond precision, truncate now so the returned metadata matches the stored one
+        let now = UNIX_EPOCH + Duration::from_millis(to_millis(SystemTime::now()));
+
+        match previous {
+            Some(previous) => ConfigMetadata {
+                created_at: previous.created_at,
+                modified_at: now.max(previous.modified_at),
+                write_count: previous.write_count.saturating_add(1),
+            },
+            None => ConfigMetadata {
+                created_at: now,
+                modified_at: now,
+                write_count: 1,
+            },
+        }
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        [
+            &METADATA_MAGIC[..],
+            &to_millis(self.created_at).to_le_bytes(),
+            &to_millis(self.modified_at).to_le_bytes(),
+            &self.write_count.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    /// Parses a metadata header at the start of `bytes`, returning it with the bytes that follow it.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<(ConfigMetadata, &[u8])> {
+        let (magic, bytes) = bytes.split_first_chunk::<8>()?;
+        if *magic != METADATA_MAGIC {
+            return None;
+        }
+
+        let (created_at, bytes) = bytes.split_first_chunk::<8>()?;
+        let (modified_at, bytes) = bytes.split_first_chunk::<8>()?;
+        let (write_count, bytes) = bytes.split_first_chunk::<8>()?;
+
+        let time =
+            |millis: &[u8; 8]| UNIX_EPOCH + Duration::from_millis(u64::from_le_bytes(*millis));
+
+        Some((
+            ConfigMetadata {
+                created_at: time(created_at),
+                modified_at: time(modified_at),
+                write_count: u64::from_le_bytes(*write_count),
+            },
+            bytes,
+        ))
+    }
+}
+
+fn to_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64)
+}
+
+fn sidecar_path(config_file_path: &Path) -> PathBuf {
+    let mut file_name = config_file_path
+        .file_name()
+        .map(std::ffi::OsStr::to_os_string)
+        .unwrap_or_default();
+    file_name.push(".");
+    file_name.push(SIDECAR_EXTENSION);
+    config_file_path.with_file_name(file_name)
+}
+
+/// Stores a config file in the config, cache, cwd, or local data directory of the current user, along with its [`ConfigMetadata`]. In any format.
+///
+/// The creation time is kept from the previous metadata, the modification time is updated and the write counter is incremented.
+/// For binary files the metadata is stored before the data, covered by the hash and flagged in the header, and the file can still be read
+/// with [`crate::load_bin`] and the other binary readers.
+/// For text files it is stored in a `<file name>.meta` sidecar file, so the config itself is left untouched.
+///
+/// Returns the new metadata.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+///
+/// let first = binconf::store_stamped("test-binconf-stamped", None, ConfigType::Bin, Config, 1u32).unwrap();
+/// let second = binconf::store_stamped("test-binconf-stamped", None, ConfigType::Bin, Config, 2u32).unwrap();
+///
+/// assert!(second.is_newer_than(&first));
+/// assert_eq!(second.created_at, first.created_at);
+///
+/// let config = binconf::load_bin::<u32>("test-binconf-stamped", None, Config, false).unwrap();
+/// assert_eq!(config, 2);
+/// ```
+pub fn store_stamped<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: impl AsRef<ConfigType>,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+) -> Result<ConfigMetadata, ConfigError>
+where
+    T: serde::Serialize,
+{
+    let app_name = app_name.as_ref();
+    let config_name = config_name.into();
+    let config_type = config_type.as_ref();
+    let location = location.as_ref();
+
+    let config_file_path =
+        crate::config_location(app_name, config_name, config_type.as_str(), location)?;
+
+    // A damaged previous header only means the history is lost, the new config can still be stored
+    let metadata =
+        ConfigMetadata::next(read_metadata(&config_file_path, config_type).unwrap_or(None));
+
+    match config_type {
+        #[cfg(feature = "binary-conf")]
+        ConfigType::Bin => {
+            let full_data = crate::binary_conf::prepare_stamped_data(
+                &metadata.to_bytes(),
+                &bincode::serialize(&data)?,
+            );
+            crate::save_config_atomic(&config_file_path, &full_data)?;
+            return Ok(metadata);
+        }
+
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => crate::store_toml(app_name, config_name, location, data)?,
+
+        #[cfg(feature = "json-conf")]
+        ConfigType::Json => crate::store_json(app_name, config_name, location, data)?,
+
+        #[cfg(feature = "yaml-conf")]
+        ConfigType::Yaml => crate::store_yaml(app_name, config_name, location, data)?,
+
+        #[cfg(feature = "ron-conf")]
+        ConfigType::Ron => crate::store_ron(app_name, config_name, location, data)?,
+    }
+
+    #[allow(unreachable_code)]
+    {
+        crate::save_config_atomic(&sidecar_path(&config_file_path), &metadata.to_bytes())?;
+        Ok(metadata)
+    }
+}
+
+/// Loads the [`ConfigMetadata`] of a config file stored with [`store_stamped`].
+///
+/// Returns `None` if the config, or its metadata, does not exist.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if the file could not be read,
+/// or if the hash of a binary file does not match.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+///
+/// let metadata = binconf::load_metadata("test-binconf-load-metadata", None, ConfigType::Bin, Config).unwrap();
+/// assert!(metadata.is_none());
+/// ```
+pub fn load_metadata<'a>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: impl AsRef<ConfigType>,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<Option<ConfigMetadata>, ConfigError> {
+    let config_type = config_type.as_ref();
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        config_type.as_str(),
+        location.as_ref(),
+    )?;
+
+    read_metadata(&config_file_path, config_type)
+}
+
+fn read_metadata(
+    config_file_path: &Path,
+    config_type: &ConfigType,
+) -> Result<Option<ConfigMetadata>, ConfigError> {
+    let path = match config_type {
+        #[cfg(feature = "binary-conf")]
+        ConfigType::Bin => config_file_path.to_path_buf(),
+
+        #[allow(unreachable_patterns)]
+        _ => sidecar_path(config_file_path),
+    };
+
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    match config_type {
+        #[cfg(feature = "binary-conf")]
+        ConfigType::Bin => Ok(crate::binary_conf::verified_frame(&data)?
+            .split_metadata()?
+            .0),
+
+        #[allow(unreachable_patterns)]
+        _ => Ok(ConfigMetadata::from_bytes(&data).map(|(metadata, _)| metadata)),
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use ConfigLocation::Config;
+
+    #[test]
+    fn stamped_write_counter_increases() {
+        for config_type in [ConfigType::Bin, ConfigType::Toml] {
+            let app_name = format!(
+                "test-binconf-stamped_write_counter-{}",
+                config_type.as_str()
+            );
+
+            assert_eq!(
+                load_metadata(&app_name, None, config_type, Config).unwrap(),
+                None
+            );
+
+            let first = store_stamped(
+                &app_name,
+                None,
+                config_type,
+                Config,
+                HashMap::from([("a", 1)]),
+            )
+            .unwrap();
+            let second = store_stamped(
+                &app_name,
+                None,
+                config_type,
+                Config,
+                HashMap::from([("a", 2)]),
+            )
+            .unwrap();
+
+            assert_eq!(first.write_count, 1);
+            assert_eq!(second.write_count, 2);
+            assert_eq!(second.created_at, first.created_at);
+            assert!(second.modified_at >= first.modified_at);
+            assert!(second.is_newer_than(&first));
+
+            let metadata = load_metadata(&app_name, None, config_type, Config).unwrap();
+            assert_eq!(metadata.map(|metadata| metadata.write_count), Some(2));
+        }
+
+        let config: HashMap<String, u8> = crate::load_bin(
+            "test-binconf-stamped_write_counter-bin",
+            None,
+            Config,
+            false,
+        )
+        .unwrap();
+        assert_eq!(config.get("a"), Some(&2));
+
+        let config: HashMap<String, u8> = crate::load_toml(
+            "test-binconf-stamped_write_counter-toml",
+            None,
+            Config,
+            false,
+        )
+        .unwrap();
+        assert_eq!(config.get("a"), Some(&2));
+    }
+
+    #[test]
+    fn stamped_files_are_read_by_every_binary_reader() {
+        let app_name = "test-binconf-stamped_files_are_read_by_every_binary_reader";
+        let records = vec![1u32, 2, 3];
+        store_stamped(app_name, None, ConfigType::Bin, Config, &records).unwrap();
+
+        let iterated: Vec<u32> = crate::load_bin_iter(app_name, None, Config)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(iterated, records);
+
+        let path = crate::get_configuration_path(app_name, None, ConfigType::Bin, Config).unwrap();
+        let streamed: Vec<u32> =
+            crate::load_from_reader(std::fs::File::open(path).unwrap(), ConfigType::Bin).unwrap();
+        assert_eq!(streamed, records);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{ConfigError, ConfigLocation, ConfigType, Hasher, Xxh3Hasher};
+use std::path::{Path, PathBuf};
+
+/// Extension appended to the file name of text configs to get their binary mirror.
+const MIRROR_EXTENSION: &str = "mirror";
+
+/// Stores a text config along with a binary mirror of it, which [`load_mirrored`] reads instead of parsing the text.
+///
+/// The text file stays the authoritative, human editable config. The mirror, `<file>.mirror` next to it, holds the
+/// config in `binary` format along with the hash of the text it was made from, so it is only used while the text
+/// file is unchanged. The text file is written first: if the process stops before the mirror is written, the mirror
+/// is outdated and ignored.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if
+/// something went wrong while serializing the config.
+///
+/// Binary configs don't need a mirror and return a [`ConfigError::UnsupportedFormat`] error.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+///
+/// let hosts = std::collections::HashMap::from([(String::from("db"), String::from("10.0.0.2"))]);
+/// binconf::store_mirrored("test-binconf-store-mirrored", None, ConfigType::Toml, Config, &hosts).unwrap();
+///
+/// let loaded = binconf::load_mirrored::<std::collections::HashMap<String, String>>("test-binconf-store-mirrored", None, ConfigType::Toml, Config, false).unwrap();
+/// assert_eq!(loaded, hosts);
+/// ```
+pub fn store_mirrored<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    ensure_text(config_type)?;
+    let app_name = app_name.as_ref();
+    let config_name = config_name.into();
+    let location = location.as_ref();
+
+    crate::store_typed(app_name, config_name, &config_type, location, &data)?;
+
+    let config_file_path =
+        crate::config_location(app_name, config_name, config_type.as_str(), location)?;
+    let source = std::fs::read(&config_file_path)?;
+    crate::save_config_atomic(
+        &mirror_path(&config_file_path),
+        &encode_snapshot(&source, &data)?,
+    )
+}
+
+/// Loads a text config from its binary mirror when it is up to date, or like the `load` function of its [`ConfigType`] otherwise.
+///
+/// The mirror written by [`store_mirrored`] is used if it was made from the current content of the text file, which
+/// spares parsing big configs at startup. Otherwise the text file is parsed, and the mirror is written again for the next
+/// load. Failing to write the mirror does not fail the load, the mirror is only an optimization.
+///
+/// # Errors
+///
+/// This function will return the errors of the `load` function of the format when the mirror is outdated.
+///
+/// Binary configs don't need a mirror and return a [`ConfigError::UnsupportedFormat`] error.
+pub fn load_mirrored<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    ensure_text(config_type)?;
+    let app_name = app_name.as_ref();
+    let config_name = config_name.into();
+    let location = location.as_ref();
+
+    let config_file_path =
+        crate::config_location(app_name, config_name, config_type.as_str(), location)?;
+    let mirror_path = mirror_path(&config_file_path);
+
+    if let (Ok(source), Ok(mirror)) = (
+        std::fs::read(&config_file_path),
+        std::fs::read(&mirror_path),
+    ) {
+        if let Some(config) = decode_snapshot(&mirror, &source) {
+            return Ok(config);
+        }
+    }
+
+    let config = crate::load_typed(
+        app_name,
+        config_name,
+        &config_type,
+        location,
+        reset_conf_on_err,
+    )?;
+
+    // The file may have been created or reset by the load
+    if let Ok(source) = std::fs::read(&config_file_path) {
+        if let Ok(snapshot) = encode_snapshot(&source, &config) {
+            let _ = crate::save_config_atomic(&mirror_path, &snapshot);
+        }
+    }
+
+    Ok(config)
+}
+
+/// Serializes `data` in `binary` format, along with the hash of the `source` text it was parsed from.
+pub(crate) fn encode_snapshot<T>(source: &[u8], data: &T) -> Result<Vec<u8>, ConfigError>
+where
+    T: serde::Serialize,
+{
+    Ok(crate::binary_conf::prepare_framed_data(&[
+        &Xxh3Hasher.digest(source),
+        &bincode::serialize(data)?,
+    ]))
+}
+
+/// Deserializes a snapshot written by [`encode_snapshot`], if it is intact, was made from `source` and holds a `T`.
+pub(crate) fn decode_snapshot<T>(snapshot: &[u8], source: &[u8]) -> Option<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let payload = crate::binary_conf::verified_payload(snapshot).ok()?;
+    let (source_hash, mut data) = payload.split_at_checked(Xxh3Hasher.digest_length())?;
+
+    if source_hash != Xxh3Hasher.digest(source) {
+        return None;
+    }
+
+    // A snapshot of another shape, as after a setting changed type, may decode without consuming all of its data
+    let decoded = bincode::deserialize_from(&mut data).ok()?;
+    data.is_empty().then_some(decoded)
+}
+
+fn ensure_text(config_type: ConfigType) -> Result<(), ConfigError> {
+    if config_type == ConfigType::Bin {
+        return Err(ConfigError::UnsupportedFormat(
+            config_type.as_str().to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn mirror_path(config_file_path: &Path) -> PathBuf {
+    let mut file_name = config_file_path
+        .file_name()
+        .map(std::ffi::OsStr::to_os_string)
+        .unwrap_or_default();
+    file_name.push(".");
+    file_name.push(MIRROR_EXTENSION);
+    config_file_path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Cache;
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Settings {
+        name: String,
+        hosts: Vec<String>,
+    }
+
+    #[test]
+    fn mirror_is_used_until_the_text_changes() {
+        let app = "test-binconf-mirror_is_used_until_the_text_changes";
+        let settings = Settings {
+            name: String::from("mirrored"),
+            hosts: vec![String::from("a"), String::from("b")],
+        };
+        store_mirrored(app, None, ConfigType::Yaml, Cache, &settings).unwrap();
+
+        let path = crate::get_configuration_path(app, None, ConfigType::Yaml, Cache).unwrap();
+        let mirror = std::fs::read(mirror_path(&path)).unwrap();
+        let source = std::fs::read(&path).unwrap();
+        assert_eq!(
+            decode_snapshot::<Settings>(&mirror, &source),
+            Some(settings)
+        );
+
+        // Edited by hand, the mirror is outdated
+        std::fs::write(&path, "name: edited\nhosts: [c]\n").unwrap();
+        let loaded: Settings = load_mirrored(app, None, ConfigType::Yaml, Cache, false).unwrap();
+        assert_eq!(loaded.name, "edited");
+
+        // And written again by the load
+        let mirror = std::fs::read(mirror_path(&path)).unwrap();
+        let source = std::fs::read(&path).unwrap();
+        assert_eq!(decode_snapshot::<Settings>(&mirror, &source), Some(loaded));
+
+        assert!(matches!(
+            store_mirrored(app, None, ConfigType::Bin, Cache, Settings::default()),
+            Err(ConfigError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,348 @@
+use crate::slow_io::IoRecord;
+use crate::{ConfigError, ConfigLocation, ConfigType, IoOperation};
+use std::time::{Duration, Instant};
+
+/// How [`load_with_outcome`] got the config it returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadOutcome {
+    /// The config was read from its file.
+    Loaded,
+    /// There was no config file, the default config was returned and written.
+    Created,
+    /// The config file was empty, as left by `touch` or an interrupted write. The default config was returned,
+    /// and written to the file if `reset_conf_on_err` was `true`. This is not a sign of a damaged file.
+    EmptyFile,
+    /// The config file could not be deserialized and was replaced by the default config, because of `reset_conf_on_err`.
+    Reset,
+}
+
+/// Size and timing of a load, returned by [`load_with_stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LoadStats {
+    /// Number of bytes read from the config file, `0` if there was no file.
+    pub read_bytes: u64,
+    /// Time spent reading the config file, in microseconds.
+    pub read_micros: u64,
+    /// Time spent after reading the config file, in microseconds. That is deserializing the config, and writing the
+    /// default config when the file is reset.
+    pub parse_micros: u64,
+    /// Duration of the whole call, in microseconds.
+    pub total_micros: u64,
+}
+
+/// Size and timing of a store, returned by [`store_with_stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StoreStats {
+    /// Number of bytes written to the config file.
+    pub written_bytes: u64,
+    /// Time spent before writing the config file, in microseconds. That is mostly serializing the config.
+    pub serialize_micros: u64,
+    /// Time spent writing the config file, in microseconds.
+    pub write_micros: u64,
+    /// Duration of the whole call, in microseconds.
+    pub total_micros: u64,
+}
+
+/// Loads a config like the `load` function of its [`ConfigType`], also telling whether it was read, created, empty or reset.
+///
+/// Lets apps warn the user when their config was replaced, without treating an empty file as a damaged one.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created,
+/// or if the config could not be loaded. An empty binary file is an error unless `reset_conf_on_err` is `true`, as
+/// in [`crate::load_bin`].
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigType, LoadOutcome};
+///
+/// let path = binconf::get_configuration_path("test-binconf-load-with-outcome", None, ConfigType::Toml, Config).unwrap();
+/// std::fs::write(&path, "").unwrap();
+///
+/// let (config, outcome) = binconf::load_with_outcome::<Vec<String>>("test-binconf-load-with-outcome", None, ConfigType::Toml, Config, false).unwrap();
+/// assert!(config.is_empty());
+/// assert_eq!(outcome, LoadOutcome::EmptyFile);
+/// ```
+pub fn load_with_outcome<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+) -> Result<(T, LoadOutcome), ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_with_stats(
+        app_name,
+        config_name,
+        config_type,
+        location,
+        reset_conf_on_err,
+    )
+    .map(|(config, outcome, _)| (config, outcome))
+}
+
+/// Loads a config like [`load_with_outcome`], also measuring how long it took and how much was read.
+///
+/// Meant to track the performance of configs in the telemetry of the app, without wrapping every call with timers.
+/// Only the work done on the calling thread is measured.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created,
+/// or if the config could not be loaded.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigType, LoadOutcome};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct TestConfig {
+///    name: String,
+/// }
+///
+/// binconf::store_toml("test-binconf-load-with-stats", None, Config, TestConfig { name: String::from("stats") }).unwrap();
+///
+/// let (config, outcome, stats) = binconf::load_with_stats::<TestConfig>("test-binconf-load-with-stats", None, ConfigType::Toml, Config, false).unwrap();
+/// assert_eq!(config.name, "stats");
+/// assert_eq!(outcome, LoadOutcome::Loaded);
+/// assert!(stats.read_bytes > 0);
+/// println!("Read {} bytes in {}µs, parsed in {}µs", stats.read_bytes, stats.read_micros, stats.parse_micros);
+/// ```
+pub fn load_with_stats<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+) -> Result<(T, LoadOutcome, LoadStats), ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let app_name = app_name.as_ref();
+    let config_name = config_name.into();
+    let location = location.as_ref();
+
+    let config_file_path =
+        crate::config_location(app_name, config_name, config_type.as_str(), location)?;
+    let previous_len = match std::fs::metadata(&config_file_path) {
+        Ok(metadata) => Some(metadata.len()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let resets = crate::reset::reset_count();
+    let started = Instant::now();
+    let (config, records) = crate::slow_io::record(|| {
+        crate::load_typed(
+            app_name,
+            config_name,
+            &config_type,
+            location,
+            reset_conf_on_err,
+        )
+    });
+    let config = config?;
+    let finished = Instant::now();
+
+    let outcome = match previous_len {
+        None => LoadOutcome::Created,
+        Some(0) => LoadOutcome::EmptyFile,
+        Some(_) if crate::reset::reset_count() != resets => LoadOutcome::Reset,
+        Some(_) => LoadOutcome::Loaded,
+    };
+
+    let stats = match records
+        .iter()
+        .find(|record| record.operation == IoOperation::Load)
+    {
+        Some(read) => LoadStats {
+            read_bytes: read.size,
+            read_micros: micros(read.duration),
+            parse_micros: micros(finished.saturating_duration_since(read.started + read.duration)),
+            total_micros: micros(finished - started),
+        },
+        None => LoadStats {
+            total_micros: micros(finished - started),
+            ..LoadStats::default()
+        },
+    };
+
+    Ok((config, outcome, stats))
+}
+
+/// Stores a config like the `store` function of its [`ConfigType`], also measuring how long it took and how much was written.
+///
+/// The counterpart of [`load_with_stats`]. Only the work done on the calling thread is measured.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created,
+/// or if the config could not be stored.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+///
+/// let stats = binconf::store_with_stats("test-binconf-store-with-stats", None, ConfigType::Json, Config, vec![1u8, 2, 3]).unwrap();
+/// assert!(stats.written_bytes > 0);
+/// println!("Wrote {} bytes in {}µs", stats.written_bytes, stats.write_micros);
+/// ```
+pub fn store_with_stats<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+) -> Result<StoreStats, ConfigError>
+where
+    T: serde::Serialize,
+{
+    let started = Instant::now();
+    let (stored, records) = crate::slow_io::record(|| {
+        crate::store_typed(
+            app_name.as_ref(),
+            config_name.into(),
+            &config_type,
+            location.as_ref(),
+            data,
+        )
+    });
+    stored?;
+    let finished = Instant::now();
+
+    let writes: Vec<&IoRecord> = records
+        .iter()
+        .filter(|record| record.operation == IoOperation::Store)
+        .collect();
+
+    Ok(StoreStats {
+        written_bytes: writes.iter().map(|write| write.size).sum(),
+        serialize_micros: micros(
+            writes
+                .first()
+                .map_or(finished, |write| write.started)
+                .saturating_duration_since(started),
+        ),
+        write_micros: writes.iter().map(|write| micros(write.duration)).sum(),
+        total_micros: micros(finished - started),
+    })
+}
+
+fn micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Settings {
+        name: String,
+    }
+
+    #[test]
+    fn empty_files_are_not_corrupt() {
+        let app = "test-binconf-empty_files_are_not_corrupt";
+
+        for config_type in [
+            ConfigType::Toml,
+            ConfigType::Json,
+            ConfigType::Yaml,
+            ConfigType::Ron,
+        ] {
+            let path = crate::get_configuration_path(app, None, config_type, Config).unwrap();
+            let load = |reset| load_with_outcome::<Settings>(app, None, config_type, Config, reset);
+
+            std::fs::write(&path, "").unwrap();
+            assert_eq!(
+                load(false).unwrap(),
+                (Settings::default(), LoadOutcome::EmptyFile)
+            );
+            // Not rewritten without `reset_conf_on_err`
+            assert!(std::fs::read(&path).unwrap().is_empty());
+
+            assert_eq!(
+                load(true).unwrap(),
+                (Settings::default(), LoadOutcome::EmptyFile)
+            );
+            assert_eq!(load(false).unwrap().1, LoadOutcome::Loaded);
+
+            std::fs::write(&path, "{{ invalid").unwrap();
+            assert_eq!(load(true).unwrap().1, LoadOutcome::Reset);
+
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(load(false).unwrap().1, LoadOutcome::Created);
+        }
+
+        let path = crate::get_configuration_path(app, None, ConfigType::Bin, Config).unwrap();
+        let load = |reset| load_with_outcome::<Settings>(app, None, ConfigType::Bin, Config, reset);
+
+        std::fs::write(&path, "").unwrap();
+        assert!(matches!(
+            load(false),
+            Err(ConfigError::TruncatedFile { .. })
+        ));
+        assert_eq!(
+            load(true).unwrap(),
+            (Settings::default(), LoadOutcome::EmptyFile)
+        );
+        assert_eq!(load(false).unwrap().1, LoadOutcome::Loaded);
+
+        std::fs::write(&path, "not a binconf file, long enough for a hash").unwrap();
+        assert_eq!(load(true).unwrap().1, LoadOutcome::Reset);
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(load(false).unwrap().1, LoadOutcome::Created);
+    }
+
+    #[test]
+    fn stats_measure_loads_and_stores() {
+        let app = "test-binconf-stats_measure_loads_and_stores";
+        let settings = Settings {
+            name: String::from("stats"),
+        };
+
+        for config_type in [ConfigType::Toml, ConfigType::Json, ConfigType::Bin] {
+            let path = crate::get_configuration_path(app, None, config_type, Config).unwrap();
+            let _ = std::fs::remove_file(&path);
+
+            let (_, outcome, stats) =
+                load_with_stats::<Settings>(app, None, config_type, Config, false).unwrap();
+            assert_eq!(outcome, LoadOutcome::Created);
+            assert_eq!(stats.read_bytes, 0);
+
+            let stored = store_with_stats(app, None, config_type, Config, &settings).unwrap();
+            let file_len = std::fs::metadata(&path).unwrap().len();
+            assert_eq!(stored.written_bytes, file_len);
+            assert!(stored.write_micros <= stored.total_micros);
+
+            let (config, outcome, stats) =
+                load_with_stats::<Settings>(app, None, config_type, Config, false).unwrap();
+            assert_eq!(
+                (config, outcome),
+                (
+                    Settings {
+                        name: String::from("stats")
+                    },
+                    LoadOutcome::Loaded
+                )
+            );
+            assert_eq!(stats.read_bytes, file_len);
+            assert!(stats.read_micros + stats.parse_micros <= stats.total_micros);
+        }
+    }
+}
@@ -0,0 +1,814 @@
+use std::path::Path;
+use std::sync::RwLock;
+
+static OUTPUT_OPTIONS: RwLock<OutputOptions> = RwLock::new(OutputOptions {
+    canonical: false,
+    key_order: Vec::new(),
+    inline_threshold: None,
+    inline_keys: Vec::new(),
+    compact: false,
+});
+
+/// Output options set with [`set_app_output_options`], by app name.
+static APP_OUTPUT_OPTIONS: RwLock<Vec<(String, OutputOptions)>> = RwLock::new(Vec::new());
+
+/// How text configs are written by the `store` functions. Set with [`set_output_options`] for every app, or with
+/// [`set_app_output_options`] for the files of a single app.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OutputOptions {
+    /// Sort the keys of every map and struct alphabetically when storing `toml`, `json` or `yaml` configs.
+    ///
+    /// The output then only depends on the stored values, never on the iteration order of a `HashMap`,
+    /// so configs checked into git don't produce noisy diffs. Floats are always written in their shortest round-trip form.
+    pub canonical: bool,
+    /// Keys written first, in this order, when storing `toml` or `yaml` configs. Nested keys are addressed with dotted paths (e.g. `"ui.theme"`).
+    ///
+    /// Listing the top level tables gives the order of the sections. Keys that are not listed follow in alphabetical order,
+    /// so setting this implies [`OutputOptions::canonical`] for those formats.
+    pub key_order: Vec<String>,
+    /// Arrays and tables with at most this many entries, none of them nested, are written on a single line when storing
+    /// `toml` or `yaml` configs: as inline arrays and tables in `toml`, in flow style in `yaml`. Larger ones keep the block style.
+    pub inline_threshold: Option<usize>,
+    /// Dotted key paths of arrays and tables always written on a single line, whatever their size.
+    pub inline_keys: Vec<String>,
+    /// Write the smallest representation instead of a human friendly one, for configs that are never edited by hand
+    /// like state or caches: minified `json`, `ron` without indentation and `toml` arrays on a single line.
+    /// `yaml` has no more compact block style and is not affected.
+    ///
+    /// Usually set with [`set_app_output_options`] for the app owning the machine-managed files only.
+    pub compact: bool,
+}
+
+impl OutputOptions {
+    #[cfg_attr(
+        not(any(feature = "toml-conf", feature = "yaml-conf")),
+        allow(dead_code)
+    )]
+    fn is_ordered(&self) -> bool {
+        self.canonical || !self.key_order.is_empty()
+    }
+
+    #[cfg_attr(
+        not(any(feature = "toml-conf", feature = "yaml-conf")),
+        allow(dead_code)
+    )]
+    fn is_inline(&self) -> bool {
+        self.inline_threshold.is_some() || !self.inline_keys.is_empty()
+    }
+
+    /// Whether the array or table at `path`, with `len` entries, should be written on a single line.
+    #[cfg_attr(
+        not(any(feature = "toml-conf", feature = "yaml-conf")),
+        allow(dead_code)
+    )]
+    fn should_inline(&self, path: &str, len: usize, nested: bool) -> bool {
+        self.inline_keys.iter().any(|key| key == path)
+            || (!nested
+                && self
+                    .inline_threshold
+                    .is_some_and(|threshold| len <= threshold))
+    }
+}
+
+/// Returns the position of the key at `path` in the declared order, keys that are not listed sort after all listed ones.
+#[cfg(any(feature = "toml-conf", feature = "yaml-conf"))]
+fn order_rank(key_order: &[String], path: &str) -> usize {
+    key_order
+        .iter()
+        .position(|key| key == path)
+        .unwrap_or(usize::MAX)
+}
+
+#[cfg(any(feature = "toml-conf", feature = "yaml-conf"))]
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Sets the options used by every `store` function writing a text config.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::OutputOptions;
+///
+/// binconf::set_output_options(OutputOptions {
+///     canonical: true,
+///     key_order: vec![String::from("general"), String::from("ui")],
+///     inline_threshold: Some(4),
+///     ..OutputOptions::default()
+/// });
+/// ```
+pub fn set_output_options(options: OutputOptions) {
+    *OUTPUT_OPTIONS
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = options;
+}
+
+/// Sets the options used when storing the text configs of `app_name`, in place of the ones set with [`set_output_options`].
+///
+/// The options apply to the files inside the directories of the app, in every [`ConfigLocation`](crate::ConfigLocation).
+/// `None` goes back to the options of every app.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::OutputOptions;
+///
+/// // A cache never edited by hand, written in its most compact form
+/// binconf::set_app_output_options("test-binconf-app-output-options", Some(OutputOptions {
+///     compact: true,
+///     ..OutputOptions::default()
+/// }));
+/// ```
+pub fn set_app_output_options(app_name: impl AsRef<str>, options: Option<OutputOptions>) {
+    let app_name = app_name.as_ref();
+    let mut all_options = APP_OUTPUT_OPTIONS
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    all_options.retain(|(name, _)| name != app_name);
+    if let Some(options) = options {
+        all_options.push((app_name.to_owned(), options));
+    }
+}
+
+/// Returns the options set with [`set_output_options`], for text that is not written to the file of an app.
+#[cfg_attr(
+    not(any(feature = "toml-conf", feature = "json-conf", feature = "yaml-conf")),
+    allow(dead_code)
+)]
+pub(crate) fn output_options() -> OutputOptions {
+    OUTPUT_OPTIONS
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .clone()
+}
+
+/// Returns the options to write the config file at `path` with: those of the app owning it, if any were set.
+#[cfg_attr(
+    not(any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn output_options_for(path: &Path) -> OutputOptions {
+    let all_options = APP_OUTPUT_OPTIONS
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    // The same directories as `crate::app_dir`, without creating them
+    all_options
+        .iter()
+        .find(|(app_name, _)| {
+            crate::resolution::app_dirs(app_name)
+                .iter()
+                .any(|app_dir| path.starts_with(app_dir))
+        })
+        .map(|(_, options)| options.clone())
+        .unwrap_or_else(output_options)
+}
+
+/// Serializes a toml value with the keys of every table in the order given by [`OutputOptions`].
+///
+/// `toml::Table` always iterates in alphabetical order, so the order is applied while serializing.
+#[cfg(feature = "toml-conf")]
+struct OrderedToml<'a> {
+    value: &'a toml::Value,
+    path: String,
+    key_order: &'a [String],
+}
+
+#[cfg(feature = "toml-conf")]
+impl serde::Serialize for OrderedToml<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::{SerializeMap, SerializeSeq};
+
+        match self.value {
+            toml::Value::Table(table) => {
+                let mut entries: Vec<_> = table
+                    .iter()
+                    .map(|(key, value)| (child_path(&self.path, key), key, value))
+                    .collect();
+                entries.sort_by_key(|(path, key, _)| (order_rank(self.key_order, path), *key));
+
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (path, key, value) in entries {
+                    map.serialize_entry(
+                        key,
+                        &OrderedToml {
+                            value,
+                            path,
+                            key_order: self.key_order,
+                        },
+                    )?;
+                }
+                map.end()
+            }
+            toml::Value::Array(array) => {
+                let mut seq = serializer.serialize_seq(Some(array.len()))?;
+                for value in array {
+                    seq.serialize_element(&OrderedToml {
+                        value,
+                        path: self.path.clone(),
+                        key_order: self.key_order,
+                    })?;
+                }
+                seq.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(feature = "toml-conf")]
+pub(crate) fn to_toml_string<T>(
+    data: &T,
+    options: &OutputOptions,
+) -> Result<String, crate::ConfigError>
+where
+    T: serde::Serialize,
+{
+    fn to_string<T>(data: &T, compact: bool) -> Result<String, toml::ser::Error>
+    where
+        T: serde::Serialize,
+    {
+        if compact {
+            toml::to_string(data)
+        } else {
+            toml::to_string_pretty(data)
+        }
+    }
+
+    let toml = if options.is_ordered() {
+        to_string(
+            &OrderedToml {
+                value: &toml::Value::Table(toml::Table::try_from(data)?),
+                path: String::new(),
+                key_order: &options.key_order,
+            },
+            options.compact,
+        )?
+    } else {
+        to_string(data, options.compact)?
+    };
+
+    if !options.is_inline() {
+        return Ok(toml);
+    }
+
+    // Reparsing keeps the order and lets the style of every array and table be changed
+    let mut document = toml
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    inline_toml_table(document.as_table_mut(), "", options);
+
+    Ok(document.to_string())
+}
+
+#[cfg(feature = "toml-conf")]
+fn inline_toml_table(table: &mut toml_edit::Table, path: &str, options: &OutputOptions) {
+    for (mut key, item) in table.iter_mut() {
+        let path = child_path(path, key.get());
+        match item {
+            toml_edit::Item::Table(child) => {
+                let nested = child
+                    .iter()
+                    .any(|(_, item)| !item.as_value().is_some_and(is_toml_scalar));
+                if options.should_inline(&path, child.len(), nested) {
+                    let inline = std::mem::take(child).into_inline_table();
+                    // The decor of a table header key lacks the spaces around ` = `
+                    key.fmt();
+                    *item = toml_edit::Item::Value(toml_edit::Value::InlineTable(inline));
+                } else {
+                    inline_toml_table(child, &path, options);
+                }
+            }
+            toml_edit::Item::Value(toml_edit::Value::Array(array)) => {
+                let nested = !array.iter().all(is_toml_scalar);
+                if options.should_inline(&path, array.len(), nested) {
+                    array.fmt();
+                }
+            }
+            toml_edit::Item::ArrayOfTables(tables) => {
+                for table in tables.iter_mut() {
+                    inline_toml_table(table, &path, options);
+                }
+            }
+            toml_edit::Item::Value(_) | toml_edit::Item::None => {}
+        }
+    }
+}
+
+#[cfg(feature = "toml-conf")]
+fn is_toml_scalar(value: &toml_edit::Value) -> bool {
+    !matches!(
+        value,
+        toml_edit::Value::Array(_) | toml_edit::Value::InlineTable(_)
+    )
+}
+
+#[cfg(feature = "json-conf")]
+pub(crate) fn to_json_string<T>(
+    data: &T,
+    options: &OutputOptions,
+) -> Result<String, crate::ConfigError>
+where
+    T: serde::Serialize,
+{
+    fn to_string<T>(data: &T, compact: bool) -> Result<String, serde_json::Error>
+    where
+        T: serde::Serialize,
+    {
+        if compact {
+            serde_json::to_string(data)
+        } else {
+            serde_json::to_string_pretty(data)
+        }
+    }
+
+    if !options.canonical {
+        return Ok(to_string(data, options.compact)?);
+    }
+
+    fn sorted(value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(map) => {
+                let mut entries: Vec<_> = map.into_iter().collect();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                serde_json::Value::Object(
+                    entries
+                        .into_iter()
+                        .map(|(key, value)| (key, sorted(value)))
+                        .collect(),
+                )
+            }
+            serde_json::Value::Array(array) => {
+                serde_json::Value::Array(array.into_iter().map(sorted).collect())
+            }
+            value => value,
+        }
+    }
+
+    Ok(to_string(
+        &sorted(serde_json::to_value(data)?),
+        options.compact,
+    )?)
+}
+
+#[cfg(feature = "yaml-conf")]
+pub(crate) fn to_yaml_string<T>(
+    data: &T,
+    options: &OutputOptions,
+) -> Result<String, crate::ConfigError>
+where
+    T: serde::Serialize,
+{
+    if !options.is_ordered() && !options.is_inline() {
+        return Ok(serde_yaml::to_string(data)?);
+    }
+
+    // Mappings keep their insertion order, so they are rebuilt in the wanted order
+    fn ordered(
+        value: serde_yaml::Value,
+        path: &str,
+        key_order: &[String],
+    ) -> Result<serde_yaml::Value, crate::ConfigError> {
+        Ok(match value {
+            serde_yaml::Value::Mapping(mapping) => {
+                let mut entries = mapping
+                    .into_iter()
+                    .map(|(key, value)| {
+                        // Keys are not necessarily strings, compare their serialized form
+                        let name = match key.as_str() {
+                            Some(name) => name.to_owned(),
+                            None => serde_yaml::to_string(&key)?,
+                        };
+                        let path = child_path(path, &name);
+                        let value = ordered(value, &path, key_order)?;
+                        Ok((order_rank(key_order, &path), name, key, value))
+                    })
+                    .collect::<Result<Vec<_>, crate::ConfigError>>()?;
+                entries
+                    .sort_by(|(a_rank, a, _, _), (b_rank, b, _, _)| (a_rank, a).cmp(&(b_rank, b)));
+                serde_yaml::Value::Mapping(
+                    entries
+                        .into_iter()
+                        .map(|(_, _, key, value)| (key, value))
+                        .collect(),
+                )
+            }
+            serde_yaml::Value::Sequence(sequence) => serde_yaml::Value::Sequence(
+                sequence
+                    .into_iter()
+                    .map(|value| ordered(value, path, key_order))
+                    .collect::<Result<_, _>>()?,
+            ),
+            serde_yaml::Value::Tagged(tagged) => {
+                let tagged = *tagged;
+                serde_yaml::Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
+                    tag: tagged.tag,
+                    value: ordered(tagged.value, path, key_order)?,
+                }))
+            }
+            value => value,
+        })
+    }
+
+    let mut value = serde_yaml::to_value(data)?;
+    if options.is_ordered() {
+        value = ordered(value, "", &options.key_order)?;
+    }
+
+    // `serde_yaml` always writes the block style, the sequences and mappings written in flow style are swapped
+    // for placeholders and the flow text is put in their place afterwards
+    if !options.is_inline() || mentions_flow_marker(&value) {
+        return Ok(serde_yaml::to_string(&value)?);
+    }
+
+    let mut flows = Vec::new();
+    let value = flow_yaml(value, "", options, &mut flows)?;
+    let mut yaml = serde_yaml::to_string(&value)?;
+    for (index, flow) in flows.iter().enumerate() {
+        yaml = yaml.replacen(&flow_marker(index), flow, 1);
+    }
+
+    Ok(yaml)
+}
+
+#[cfg(feature = "yaml-conf")]
+const FLOW_MARKER: &str = "__binconf_flow_";
+
+#[cfg(feature = "yaml-conf")]
+fn flow_marker(index: usize) -> String {
+    format!("{FLOW_MARKER}{index}__")
+}
+
+#[cfg(feature = "yaml-conf")]
+fn mentions_flow_marker(value: &serde_yaml::Value) -> bool {
+    match value {
+        serde_yaml::Value::String(string) => string.contains(FLOW_MARKER),
+        serde_yaml::Value::Sequence(sequence) => sequence.iter().any(mentions_flow_marker),
+        serde_yaml::Value::Mapping(mapping) => mapping
+            .iter()
+            .any(|(key, value)| mentions_flow_marker(key) || mentions_flow_marker(value)),
+        serde_yaml::Value::Tagged(tagged) => mentions_flow_marker(&tagged.value),
+        _ => false,
+    }
+}
+
+/// Replaces the sequences and mappings to write in flow style with markers, pushing their flow text to `flows`.
+#[cfg(feature = "yaml-conf")]
+fn flow_yaml(
+    value: serde_yaml::Value,
+    path: &str,
+    options: &OutputOptions,
+    flows: &mut Vec<String>,
+) -> Result<serde_yaml::Value, crate::ConfigError> {
+    let (len, nested) = match &value {
+        serde_yaml::Value::Sequence(sequence) => (
+            sequence.len(),
+            sequence.iter().any(|value| !is_yaml_scalar(value)),
+        ),
+        serde_yaml::Value::Mapping(mapping) => (
+            mapping.len(),
+            mapping.values().any(|value| !is_yaml_scalar(value)),
+        ),
+        _ => return Ok(value),
+    };
+
+    if !path.is_empty() && options.should_inline(path, len, nested) {
+        if let Some(flow) = flow_text(&value)? {
+            flows.push(flow);
+            return Ok(serde_yaml::Value::String(flow_marker(flows.len() - 1)));
+        }
+    }
+
+    Ok(match value {
+        serde_yaml::Value::Sequence(sequence) => serde_yaml::Value::Sequence(
+            sequence
+                .into_iter()
+                .map(|value| flow_yaml(value, path, options, flows))
+                .collect::<Result<_, _>>()?,
+        ),
+        serde_yaml::Value::Mapping(mapping) => serde_yaml::Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = match key.as_str() {
+                        Some(name) => flow_yaml(value, &child_path(path, name), options, flows)?,
+                        None => value,
+                    };
+                    Ok((key, value))
+                })
+                .collect::<Result<_, crate::ConfigError>>()?,
+        ),
+        value => value,
+    })
+}
+
+#[cfg(feature = "yaml-conf")]
+fn is_yaml_scalar(value: &serde_yaml::Value) -> bool {
+    !matches!(
+        value,
+        serde_yaml::Value::Sequence(_)
+            | serde_yaml::Value::Mapping(_)
+            | serde_yaml::Value::Tagged(_)
+    )
+}
+
+/// Returns the flow style text of `value`, or `None` if one of its scalars can't be written in flow style as is.
+#[cfg(feature = "yaml-conf")]
+fn flow_text(value: &serde_yaml::Value) -> Result<Option<String>, crate::ConfigError> {
+    fn join<I>(items: I, open: char, close: char) -> Result<Option<String>, crate::ConfigError>
+    where
+        I: Iterator<Item = Result<Option<String>, crate::ConfigError>>,
+    {
+        let mut parts = Vec::new();
+        for item in items {
+            match item? {
+                Some(part) => parts.push(part),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(format!("{open}{}{close}", parts.join(", "))))
+    }
+
+    match value {
+        serde_yaml::Value::Sequence(sequence) => join(sequence.iter().map(flow_text), '[', ']'),
+        serde_yaml::Value::Mapping(mapping) => join(
+            mapping.iter().map(|(key, value)| {
+                Ok(match (flow_text(key)?, flow_text(value)?) {
+                    (Some(key), Some(value)) => Some(format!("{key}: {value}")),
+                    _ => None,
+                })
+            }),
+            '{',
+            '}',
+        ),
+        serde_yaml::Value::Tagged(_) => Ok(None),
+        scalar => {
+            let text = serde_yaml::to_string(scalar)?;
+            let text = text.trim_end_matches('\n');
+            let quoted = text.starts_with('\'') || text.starts_with('"');
+            // Plain scalars may contain flow indicators, which only have a meaning in flow style
+            if text.contains('\n') || (!quoted && text.contains([',', '[', ']', '{', '}'])) {
+                Ok(None)
+            } else {
+                Ok(Some(text.to_owned()))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ron-conf")]
+pub(crate) fn to_ron_string<T>(
+    data: &T,
+    options: &OutputOptions,
+) -> Result<String, crate::ConfigError>
+where
+    T: serde::Serialize,
+{
+    if options.compact {
+        return Ok(ron::to_string(data)?);
+    }
+
+    let ser_config = ron::ser::PrettyConfig::new()
+        .depth_limit(4)
+        .indentor("\t".to_owned());
+
+    Ok(ron::ser::to_string_pretty(data, ser_config)?)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    #[derive(serde::Serialize)]
+    struct Config {
+        zeta: u8,
+        alpha: HashMap<String, f64>,
+    }
+
+    #[test]
+    fn canonical_output_sorts_keys() {
+        let config = Config {
+            zeta: 1,
+            alpha: HashMap::from([
+                (String::from("d"), 0.1),
+                (String::from("b"), 2.0),
+                (String::from("c"), 1e-7),
+            ]),
+        };
+
+        let options = OutputOptions {
+            canonical: true,
+            ..OutputOptions::default()
+        };
+        let toml = to_toml_string(&config, &options);
+        let json = to_json_string(&config, &options);
+        let yaml = to_yaml_string(&config, &options);
+
+        assert_eq!(
+            toml.unwrap(),
+            "zeta = 1\n\n[alpha]\nb = 2.0\nc = 0.0000001\nd = 0.1\n"
+        );
+        assert_eq!(
+            json.unwrap(),
+            "{\n  \"alpha\": {\n    \"b\": 2.0,\n    \"c\": 1e-7,\n    \"d\": 0.1\n  },\n  \"zeta\": 1\n}"
+        );
+        assert_eq!(
+            yaml.unwrap(),
+            "alpha:\n  b: 2.0\n  c: 1e-7\n  d: 0.1\nzeta: 1\n"
+        );
+    }
+
+    #[derive(serde::Serialize)]
+    struct Sections {
+        general: General,
+        ui: Ui,
+        advanced: General,
+    }
+
+    #[derive(serde::Serialize)]
+    struct General {
+        name: String,
+        id: u8,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Ui {
+        theme: String,
+        font: String,
+        size: u8,
+    }
+
+    #[test]
+    fn key_order_groups_sections() {
+        let config = Sections {
+            general: General {
+                name: String::from("app"),
+                id: 1,
+            },
+            ui: Ui {
+                theme: String::from("dark"),
+                font: String::from("mono"),
+                size: 12,
+            },
+            advanced: General {
+                name: String::from("advanced"),
+                id: 2,
+            },
+        };
+
+        let options = OutputOptions {
+            key_order: vec![
+                String::from("ui"),
+                String::from("ui.size"),
+                String::from("general"),
+            ],
+            ..OutputOptions::default()
+        };
+        let toml = to_toml_string(&config, &options);
+        let yaml = to_yaml_string(&config, &options);
+
+        assert_eq!(
+            toml.unwrap(),
+            "[ui]\nsize = 12\nfont = \"mono\"\ntheme = \"dark\"\n\n[general]\nid = 1\nname = \"app\"\n\n[advanced]\nid = 2\nname = \"advanced\"\n"
+        );
+        assert_eq!(
+            yaml.unwrap(),
+            "ui:\n  size: 12\n  font: mono\n  theme: dark\ngeneral:\n  id: 1\n  name: app\nadvanced:\n  id: 2\n  name: advanced\n"
+        );
+    }
+
+    #[derive(serde::Serialize)]
+    struct Layout {
+        name: String,
+        ports: Vec<u16>,
+        hosts: Vec<String>,
+        window: Window,
+        colors: HashMap<String, String>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Window {
+        width: u32,
+        height: u32,
+    }
+
+    #[test]
+    fn inline_threshold_and_keys() {
+        let config = Layout {
+            name: String::from("app"),
+            ports: vec![80, 443],
+            hosts: vec![
+                String::from("a, b"),
+                String::from("c"),
+                String::from("d"),
+                String::from("e"),
+            ],
+            window: Window {
+                width: 800,
+                height: 600,
+            },
+            colors: HashMap::from([
+                (String::from("fg"), String::from("white")),
+                (String::from("bg"), String::from("black")),
+                (String::from("accent"), String::from("blue")),
+            ]),
+        };
+
+        let options = OutputOptions {
+            canonical: true,
+            inline_threshold: Some(2),
+            inline_keys: vec![String::from("hosts")],
+            ..OutputOptions::default()
+        };
+        let toml = to_toml_string(&config, &options);
+        let yaml = to_yaml_string(&config, &options);
+
+        assert_eq!(
+            toml.unwrap(),
+            "hosts = [\"a, b\", \"c\", \"d\", \"e\"]\nname = \"app\"\nports = [80, 443]\nwindow = { height = 600, width = 800 }\n\n[colors]\naccent = \"blue\"\nbg = \"black\"\nfg = \"white\"\n"
+        );
+        // `a, b` can't be written as a plain scalar in flow style, so `hosts` stays in block style
+        assert_eq!(
+            yaml.unwrap(),
+            "colors:\n  accent: blue\n  bg: black\n  fg: white\nhosts:\n- a, b\n- c\n- d\n- e\nname: app\nports: [80, 443]\nwindow: {height: 600, width: 800}\n"
+        );
+    }
+
+    #[test]
+    fn compact_output_is_minified() {
+        let config = General {
+            name: String::from("app"),
+            id: 1,
+        };
+        let list = HashMap::from([("ids", vec![1, 2, 3])]);
+
+        let options = OutputOptions {
+            compact: true,
+            ..OutputOptions::default()
+        };
+        let toml = to_toml_string(&list, &options);
+        let json = to_json_string(&config, &options);
+        let ron = to_ron_string(&config, &options);
+
+        assert_eq!(toml.unwrap(), "ids = [1, 2, 3]\n");
+        assert_eq!(json.unwrap(), r#"{"name":"app","id":1}"#);
+        assert_eq!(ron.unwrap(), r#"(name:"app",id:1)"#);
+    }
+
+    #[test]
+    fn app_output_options_only_apply_to_its_files() {
+        let app = "test-binconf-app_output_options_only_apply_to_its_files";
+        let other = "test-binconf-app_output_options_only_apply_to_its_files-other";
+        set_app_output_options(
+            app,
+            Some(OutputOptions {
+                compact: true,
+                ..OutputOptions::default()
+            }),
+        );
+
+        let config = General {
+            name: String::from("app"),
+            id: 1,
+        };
+        for app_name in [app, other] {
+            crate::store_json(app_name, None, crate::ConfigLocation::Cache, &config).unwrap();
+        }
+        let stored = |app_name| {
+            let path = crate::get_configuration_path(
+                app_name,
+                None,
+                crate::ConfigType::Json,
+                crate::ConfigLocation::Cache,
+            )
+            .unwrap();
+            std::fs::read_to_string(path).unwrap()
+        };
+
+        assert_eq!(stored(app), r#"{"name":"app","id":1}"#);
+        assert_eq!(stored(other), "{\n  \"name\": \"app\",\n  \"id\": 1\n}");
+
+        set_app_output_options(app, None);
+        crate::store_json(app, None, crate::ConfigLocation::Cache, &config).unwrap();
+        assert_eq!(stored(app), stored(other));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+
+/// Loads a config made of a read-only base document and a user file holding only the overrides, from the config, cache, cwd, or local data directory of the current user. In `toml` format.
+///
+/// The `base` document is the config shipped with the app (embedded with `include_str!` or read from the install directory).
+/// The user file is deep merged over it, so it only needs the values the user changed. If the user file does not exist, the base is used as is
+/// and no file is created.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization of the user file fails, an error will be returned. If it is set to `true`
+/// the user file will be emptied, dropping every override.
+///
+/// An invalid `base` document always returns an error.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///    theme: String,
+///    font_size: u32,
+/// }
+///
+/// const BASE: &str = "theme = \"light\"\nfont_size = 12\n";
+///
+/// let mut config = binconf::load_toml_with_base::<TestConfig>("test-binconf-read-base", None, Config, BASE, false).unwrap();
+/// config.font_size = 14;
+///
+/// // Only `font_size = 14` is written to the user file
+/// binconf::store_toml_overrides("test-binconf-read-base", None, Config, BASE, &config).unwrap();
+/// ```
+pub fn load_toml_with_base<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    base: &str,
+    reset_conf_on_err: bool,
+) -> Result<T, ConfigError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Toml.as_str(),
+        location.as_ref(),
+    )?;
+
+    let mut config = toml::Value::Table(toml::from_str(base)?);
+
+    if config_file_path.try_exists()? {
+        let overrides_str = std::fs::read_to_string(&config_file_path)?;
+        match toml::from_str::<toml::Table>(&overrides_str) {
+            Ok(overrides) => crate::merge::merge_toml(&mut config, toml::Value::Table(overrides)),
+            Err(err) => {
+                if !reset_conf_on_err {
+                    return Err(err.into());
+                }
+                if !crate::freeze::is_frozen_path(&config_file_path) {
+                    crate::reset::back_up_corrupt(&config_file_path);
+                    crate::save_config_str(&config_file_path, "")?;
+                }
+            }
+        }
+    }
+
+    Ok(config.try_into()?)
+}
+
+/// Stores only the values of a config that differ from a read-only base document, in the config, cache, cwd, or local data directory of the current user. In `toml` format.
+///
+/// The counterpart of [`load_toml_with_base`]. Values equal to the ones in `base` are left out of the user file, which keeps it small
+/// and lets app upgrades change the defaults the user never touched.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+///
+/// An invalid `base` document always returns an error.
+pub fn store_toml_overrides<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    base: &str,
+    data: T,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Toml.as_str(),
+        location.as_ref(),
+    )?;
+
+    let base = toml::from_str::<toml::Table>(base)?;
+    let config = toml::Table::try_from(data)?;
+
+    let overrides = crate::merge::diff_toml(&base, &config);
+
+    crate::save_config_atomic(
+        &config_file_path,
+        toml::to_string_pretty(&overrides)?.as_bytes(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::{Deserialize, Serialize};
+    use ConfigLocation::Config;
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Ui {
+        theme: String,
+        size: u32,
+    }
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct TestConfig {
+        name: String,
+        ui: Ui,
+    }
+
+    const BASE: &str = "name = \"base\"\n[ui]\ntheme = \"light\"\nsize = 12\n";
+
+    #[test]
+    fn overrides_store_only_the_diff() {
+        let mut config: TestConfig = load_toml_with_base(
+            "test-binconf-overrides_diff-toml",
+            None,
+            Config,
+            BASE,
+            false,
+        )
+        .unwrap();
+        assert_eq!(config.ui.theme, "light");
+
+        config.ui.size = 16;
+        store_toml_overrides(
+            "test-binconf-overrides_diff-toml",
+            None,
+            Config,
+            BASE,
+            &config,
+        )
+        .unwrap();
+
+        let path = crate::get_configuration_path(
+            "test-binconf-overrides_diff-toml",
+            None,
+            ConfigType::Toml,
+            Config,
+        )
+        .unwrap();
+        let overrides: toml::Table =
+            toml::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides["ui"].as_table().map(toml::Table::len), Some(1));
+        assert_eq!(overrides["ui"]["size"].as_integer(), Some(16));
+
+        let loaded: TestConfig = load_toml_with_base(
+            "test-binconf-overrides_diff-toml",
+            None,
+            Config,
+            BASE,
+            false,
+        )
+        .unwrap();
+        assert_eq!(loaded, config);
+    }
+}
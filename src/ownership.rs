@@ -0,0 +1,135 @@
+use crate::ConfigError;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// The guard set with [`set_ownership_guard`].
+static GUARD: RwLock<Option<OwnershipGuard>> = RwLock::new(None);
+
+/// What to do when an app directory belongs to another user, see [`set_ownership_guard`].
+#[derive(Debug, Clone, Copy)]
+pub enum OwnershipGuard {
+    /// Fail with a [`ConfigError::ForeignOwner`] error.
+    Refuse,
+    /// Call the function, then carry on with the operation.
+    Warn(fn(&OwnershipMismatch)),
+}
+
+/// An app directory owned by another user than the one running the process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnershipMismatch {
+    /// The app directory.
+    pub path: PathBuf,
+    /// The uid owning the app directory.
+    pub owner_uid: u32,
+    /// The effective uid of the process.
+    pub current_uid: u32,
+}
+
+/// Enables or disables the ownership guard used by every `load` and `store` function. Disabled by default.
+///
+/// When enabled, the owner of the app directory is compared with the effective user of the process before any file is
+/// read or written. A mismatch usually means the app runs with `sudo`, or points [`crate::set_base_dir_override`] at
+/// the directory of another account, and would leave files the regular user can't write anymore.
+///
+/// The guard is a no-op on non-unix platforms, and for app directories that don't exist yet.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::OwnershipGuard;
+///
+/// binconf::set_ownership_guard(Some(OwnershipGuard::Warn(|mismatch| {
+///     eprintln!("{} belongs to uid {}", mismatch.path.display(), mismatch.owner_uid);
+/// })));
+///
+/// binconf::store_bin("test-binconf-ownership-guard", None, Cache, 42u32).unwrap();
+///
+/// binconf::set_ownership_guard(None);
+/// ```
+pub fn set_ownership_guard(guard: Option<OwnershipGuard>) {
+    *GUARD
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = guard;
+}
+
+/// Applies the ownership guard to the app directory at `app_dir`, if it is enabled.
+pub(crate) fn ensure_owned(app_dir: &Path) -> Result<(), ConfigError> {
+    let Some(guard) = *GUARD
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+    else {
+        return Ok(());
+    };
+
+    let Some(mismatch) = current_uid().and_then(|uid| mismatch(app_dir, uid)) else {
+        return Ok(());
+    };
+
+    match guard {
+        OwnershipGuard::Refuse => Err(ConfigError::ForeignOwner(mismatch)),
+        OwnershipGuard::Warn(warn) => {
+            warn(&mismatch);
+            Ok(())
+        }
+    }
+}
+
+/// Returns the mismatch if `path` exists and is not owned by `current_uid`.
+#[cfg(unix)]
+fn mismatch(path: &Path, current_uid: u32) -> Option<OwnershipMismatch> {
+    use std::os::unix::fs::MetadataExt;
+
+    let owner_uid = std::fs::metadata(path).ok()?.uid();
+    (owner_uid != current_uid).then(|| OwnershipMismatch {
+        path: path.to_path_buf(),
+        owner_uid,
+        current_uid,
+    })
+}
+
+#[cfg(not(unix))]
+fn mismatch(_path: &Path, _current_uid: u32) -> Option<OwnershipMismatch> {
+    None
+}
+
+#[cfg(unix)]
+fn current_uid() -> Option<u32> {
+    // SAFETY: geteuid has no preconditions and never fails.
+    Some(unsafe { libc::geteuid() })
+}
+
+#[cfg(not(unix))]
+fn current_uid() -> Option<u32> {
+    None
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directories_of_other_users_are_reported() {
+        let app_dir = crate::app_dir(
+            "test-binconf-ownership-mismatch",
+            &crate::ConfigLocation::Cache,
+        )
+        .unwrap();
+        let uid = current_uid().unwrap();
+
+        assert_eq!(mismatch(&app_dir, uid), None);
+        assert_eq!(
+            mismatch(&app_dir, uid.wrapping_add(1)),
+            Some(OwnershipMismatch {
+                path: app_dir.clone(),
+                owner_uid: uid,
+                current_uid: uid.wrapping_add(1),
+            })
+        );
+        assert_eq!(
+            mismatch(&app_dir.join("missing"), uid.wrapping_add(1)),
+            None
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{ConfigLocation, Hasher, Xxh3Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Minimum size, in bytes, of the text configs whose parsed value is cached. `None` disables the cache.
+static MIN_SIZE: Mutex<Option<usize>> = Mutex::new(None);
+
+/// Directory of the snapshots, in the cache directory of the app.
+const CACHE_DIR: &str = "parse-cache";
+
+/// Enables or disables the parse cache used by every `load` function reading a text config.
+///
+/// When enabled, loading a text config of at least `min_size` bytes stores its parsed value in `binary` format in the cache
+/// directory of the app, keyed by the hash of the file. The next loads of the unchanged file read the snapshot instead of
+/// parsing the text again, a significant startup win for configs of several megabytes. Editing the file invalidates its
+/// snapshot, and a snapshot that can't be read is ignored. The `strict` loads always parse the text.
+///
+/// Snapshots are also keyed by the name and the settings of the config type, and by the executable of the app: a rebuilt
+/// app never reads the snapshots of the previous build, so a setting changing type without changing name, like `u32` to
+/// `f32`, is never read from a stale snapshot. A snapshot that can't be decoded is a miss and is deleted. The cache stays
+/// disabled if the executable can't be identified.
+///
+/// The cache is disabled by default.
+///
+/// # Example
+///
+/// ```
+/// // Cache the configs of 64 KiB and more
+/// binconf::set_parse_cache(Some(64 * 1024));
+/// ```
+pub fn set_parse_cache(min_size: Option<usize>) {
+    *MIN_SIZE
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = min_size;
+}
+
+/// Returns the cached value of the config at `config_file_path`, if it was cached from the same `text`.
+pub(crate) fn lookup<T>(app_name: &str, config_file_path: &Path, text: &str) -> Option<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    read_snapshot(
+        &snapshot_path(app_name, config_file_path, text.len())?,
+        text,
+    )
+}
+
+/// Caches the value of the config at `config_file_path`, just parsed from `text`. Errors are ignored, the cache is only an optimization.
+pub(crate) fn store<T>(app_name: &str, config_file_path: &Path, text: &str, config: &T)
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    if let Some(snapshot_path) = snapshot_path(app_name, config_file_path, text.len()) {
+        write_snapshot(&snapshot_path, text, config);
+    }
+}
+
+fn read_snapshot<T>(snapshot_path: &Path, text: &str) -> Option<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let snapshot = std::fs::read(snapshot_path).ok()?;
+    let config = crate::mirror::decode_snapshot(&snapshot, &cache_key::<T>(text));
+
+    // Stale or damaged, it would be missed on every load until the file is stored again
+    if config.is_none() {
+        let _ = std::fs::remove_file(snapshot_path);
+    }
+
+    config
+}
+
+fn write_snapshot<T>(snapshot_path: &Path, text: &str, config: &T)
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    if crate::freeze::is_frozen_path(snapshot_path) {
+        return;
+    }
+    let Ok(snapshot) = crate::mirror::encode_snapshot(&cache_key::<T>(text), config) else {
+        return;
+    };
+
+    if let Some(dir) = snapshot_path.parent() {
+        if std::fs::create_dir_all(dir).is_ok() {
+            let _ = crate::save_config_atomic(snapshot_path, &snapshot);
+        }
+    }
+}
+
+/// Returns the path of the snapshot of the config at `config_file_path`, if the cache is enabled for configs of `len` bytes.
+fn snapshot_path(app_name: &str, config_file_path: &Path, len: usize) -> Option<PathBuf> {
+    let min_size = (*MIN_SIZE
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner))?;
+    if len < min_size {
+        return None;
+    }
+
+    build_identity()?;
+
+    let path_hash = Xxh3Hasher.digest(config_file_path.as_os_str().as_encoded_bytes());
+    let file_name: String = path_hash.iter().map(|byte| format!("{byte:02x}")).collect();
+
+    let app_dir = crate::resolution::resolve_app_dir(app_name, ConfigLocation::Cache).ok()?;
+    Some(app_dir.base_dir.join(CACHE_DIR).join(file_name))
+}
+
+/// Identifies the build of the running executable by its path, size and modification time.
+fn build_identity() -> Option<&'static [u8]> {
+    static IDENTITY: OnceLock<Option<Vec<u8>>> = OnceLock::new();
+
+    IDENTITY
+        .get_or_init(|| {
+            let executable = std::env::current_exe().ok()?;
+            let metadata = std::fs::metadata(&executable).ok()?;
+            let modified = metadata
+                .modified()
+                .ok()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?;
+
+            let mut identity = executable.as_os_str().as_encoded_bytes().to_vec();
+            identity.extend_from_slice(&metadata.len().to_le_bytes());
+            identity.extend_from_slice(&modified.as_nanos().to_le_bytes());
+            Some(identity)
+        })
+        .as_deref()
+}
+
+/// The text of the config along with the name and the settings of `T` and the build of the app, so a snapshot is never
+/// read as another type.
+fn cache_key<T>(text: &str) -> Vec<u8>
+where
+    T: serde::de::DeserializeOwned,
+{
+    cache_key_for_build::<T>(text, build_identity().unwrap_or_default())
+}
+
+fn cache_key_for_build<T>(text: &str, build: &[u8]) -> Vec<u8>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let mut key = build.to_vec();
+    key.push(0);
+    key.extend_from_slice(text.as_bytes());
+    key.extend_from_slice(std::any::type_name::<T>().as_bytes());
+    for setting in crate::config_keys::<T>() {
+        key.push(0);
+        key.extend_from_slice(setting.as_bytes());
+    }
+    key
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Settings {
+        name: String,
+    }
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Renamed {
+        title: String,
+    }
+
+    #[test]
+    fn snapshots_are_keyed_by_text_and_type() {
+        let snapshot_path = std::env::temp_dir()
+            .join("test-binconf-snapshots_are_keyed_by_text_and_type")
+            .join(CACHE_DIR)
+            .join("settings");
+        let text = "name = \"cached\"\n";
+        let settings = Settings {
+            name: String::from("cached"),
+        };
+
+        write_snapshot(&snapshot_path, text, &settings);
+
+        assert_eq!(
+            read_snapshot::<Settings>(&snapshot_path, text),
+            Some(settings)
+        );
+        assert_eq!(
+            read_snapshot::<Settings>(&snapshot_path, "name = \"edited\"\n"),
+            None
+        );
+        assert_eq!(read_snapshot::<Renamed>(&snapshot_path, text), None);
+    }
+
+    #[test]
+    fn snapshots_of_another_shape_are_dropped() {
+        mod v1 {
+            #[derive(serde::Serialize)]
+            pub struct Counter {
+                pub count: u32,
+            }
+        }
+        #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Narrowed {
+            count: u16,
+        }
+        #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Widened {
+            count: u64,
+        }
+
+        let snapshot_path = std::env::temp_dir()
+            .join("test-binconf-snapshots_of_another_shape_are_dropped")
+            .join(CACHE_DIR)
+            .join("counter");
+        std::fs::create_dir_all(snapshot_path.parent().unwrap()).unwrap();
+        let text = "count = 7\n";
+
+        // Stored before the setting changed type, under the key of the new type
+        let stale = |key: &[u8]| {
+            let snapshot = crate::mirror::encode_snapshot(key, &v1::Counter { count: 7 }).unwrap();
+            std::fs::write(&snapshot_path, snapshot).unwrap();
+        };
+
+        stale(&cache_key::<Widened>(text));
+        assert_eq!(read_snapshot::<Widened>(&snapshot_path, text), None);
+        assert!(!snapshot_path.exists());
+
+        // Decodes, but leaves data behind
+        stale(&cache_key::<Narrowed>(text));
+        assert_eq!(read_snapshot::<Narrowed>(&snapshot_path, text), None);
+        assert!(!snapshot_path.exists());
+    }
+
+    #[test]
+    fn snapshots_of_a_previous_build_are_ignored() {
+        // The same setting before and after it changed type, of the same width
+        mod before {
+            #[derive(serde::Serialize)]
+            pub struct Ratio {
+                pub value: u32,
+            }
+        }
+        #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Ratio {
+            value: f32,
+        }
+
+        let snapshot_path = std::env::temp_dir()
+            .join("test-binconf-snapshots_of_a_previous_build_are_ignored")
+            .join(CACHE_DIR)
+            .join("ratio");
+        std::fs::create_dir_all(snapshot_path.parent().unwrap()).unwrap();
+        let text = "value = 0.5\n";
+
+        // Stored by the previous build, where `Ratio` had the same name and settings
+        let key = cache_key_for_build::<Ratio>(text, b"previous build");
+        let snapshot = crate::mirror::encode_snapshot(&key, &before::Ratio { value: 7 }).unwrap();
+        std::fs::write(&snapshot_path, snapshot).unwrap();
+
+        assert_eq!(read_snapshot::<Ratio>(&snapshot_path, text), None);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::{ConfigError, ConfigLocation};
+use std::path::{Path, PathBuf};
+
+/// A change made by [`fix_permissions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PermissionFix {
+    /// The owner of the file or directory was changed. Only done when running as root.
+    Owner {
+        path: PathBuf,
+        from_uid: u32,
+        to_uid: u32,
+    },
+    /// The file, owned by another user, was replaced by a copy owned by the current user.
+    Replaced { path: PathBuf, from_uid: u32 },
+    /// The owner was missing read or write access, or search access to a directory.
+    Mode {
+        path: PathBuf,
+        from_mode: u32,
+        to_mode: u32,
+    },
+}
+
+impl std::fmt::Display for PermissionFix {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PermissionFix::Owner {
+                path,
+                from_uid,
+                to_uid,
+            } => write!(
+                f,
+                "Changed the owner of {} from uid {from_uid} to uid {to_uid}",
+                path.display()
+            ),
+            PermissionFix::Replaced { path, from_uid } => write!(
+                f,
+                "Replaced {}, owned by uid {from_uid}, with a copy",
+                path.display()
+            ),
+            PermissionFix::Mode {
+                path,
+                from_mode,
+                to_mode,
+            } => write!(
+                f,
+                "Changed the mode of {} from {from_mode:o} to {to_mode:o}",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// The result of [`fix_permissions`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermissionsReport {
+    pub app_dir: PathBuf,
+    pub fixed: Vec<PermissionFix>,
+    /// The paths that are still broken, with the reason.
+    pub unfixable: Vec<(PathBuf, String)>,
+}
+
+/// Repairs the ownership and permissions of the files of an app, in the config, cache, cwd, or local data directory of the current user.
+///
+/// Running an app once with `sudo` typically leaves root owned files in the app directory, that the regular user can't
+/// write anymore. Every file and directory in the app directory is checked:
+///
+/// - Files owned by another user are replaced by a copy owned by the current user, if they are readable. When running
+///   as root, files and directories are given back to the owner of the directory holding the app directory instead.
+/// - Files the owner can't read or write, and directories the owner can't list, get the missing permissions.
+///
+/// Symbolic links are neither repaired nor followed, so nothing outside of the app directory is touched.
+///
+/// Meant for a `doctor` command, the app directory is not created if it does not exist. A no-op on non-unix platforms.
+///
+/// # Errors
+///
+/// This function only returns an error if the config, cache or local data directory itself could not be found. Every
+/// path that could not be repaired is reported in [`PermissionsReport::unfixable`].
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// let report = binconf::fix_permissions("test-binconf-fix-permissions", Config).unwrap();
+///
+/// for fix in &report.fixed {
+///     println!("{fix}");
+/// }
+/// for (path, reason) in &report.unfixable {
+///     eprintln!("Could not repair {}: {reason}", path.display());
+/// }
+/// ```
+pub fn fix_permissions(
+    app_name: impl AsRef<str>,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<PermissionsReport, ConfigError> {
+    let app_dir = crate::resolution::resolve_app_dir(app_name, location)?.base_dir;
+
+    let mut report = PermissionsReport {
+        app_dir: app_dir.clone(),
+        fixed: Vec::new(),
+        unfixable: Vec::new(),
+    };
+
+    if app_dir.try_exists()? {
+        fix_tree(&app_dir, &mut report);
+    }
+
+    Ok(report)
+}
+
+#[cfg(unix)]
+fn fix_tree(app_dir: &Path, report: &mut PermissionsReport) {
+    use std::os::unix::fs::MetadataExt;
+
+    // SAFETY: geteuid has no preconditions and never fails.
+    let current_uid = unsafe { libc::geteuid() };
+
+    // Root gives the files back to the user owning the parent directory, like `~/.config`
+    let owner = if current_uid == 0 {
+        app_dir
+            .parent()
+            .and_then(|parent| std::fs::metadata(parent).ok())
+            .map(|metadata| (metadata.uid(), Some(metadata.gid())))
+            .unwrap_or((current_uid, None))
+    } else {
+        (current_uid, None)
+    };
+
+    let mut pending = vec![app_dir.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        if let Err(err) = fix_path(&path, current_uid, owner, report) {
+            report.unfixable.push((path.clone(), err.to_string()));
+            continue;
+        }
+
+        // Never follow a symbolic link, it may point outside of the app directory
+        match std::fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_dir() => {}
+            Ok(_) => continue,
+            Err(err) => {
+                report.unfixable.push((path, err.to_string()));
+                continue;
+            }
+        }
+        let entries = match std::fs::read_dir(&path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                report.unfixable.push((path, err.to_string()));
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            pending.push(entry.path());
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn fix_tree(_app_dir: &Path, _report: &mut PermissionsReport) {}
+
+/// Fixes the owner and then the mode of a single file or directory. Symbolic links are left alone.
+#[cfg(unix)]
+fn fix_path(
+    path: &Path,
+    current_uid: u32,
+    (owner_uid, owner_gid): (u32, Option<u32>),
+    report: &mut PermissionsReport,
+) -> std::io::Result<()> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let metadata = std::fs::symlink_metadata(path)?;
+    if metadata.file_type().is_symlink() {
+        return Ok(());
+    }
+
+    if metadata.uid() != owner_uid {
+        if current_uid == 0 {
+            std::os::unix::fs::chown(path, Some(owner_uid), owner_gid)?;
+            report.fixed.push(PermissionFix::Owner {
+                path: path.to_path_buf(),
+                from_uid: metadata.uid(),
+                to_uid: owner_uid,
+            });
+        } else if metadata.is_file() {
+            replace_with_copy(path, metadata.mode())?;
+            report.fixed.push(PermissionFix::Replaced {
+                path: path.to_path_buf(),
+                from_uid: metadata.uid(),
+            });
+        } else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("owned by uid {}, run as root to repair", metadata.uid()),
+            ));
+        }
+    }
+
+    let required = if metadata.is_dir() { 0o700 } else { 0o600 };
+    // The copy of a replaced file may already have the required mode
+    let mode = std::fs::symlink_metadata(path)?.mode() & 0o7777;
+    if mode & required != required {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode | required))?;
+        report.fixed.push(PermissionFix::Mode {
+            path: path.to_path_buf(),
+            from_mode: mode,
+            to_mode: mode | required,
+        });
+    }
+
+    Ok(())
+}
+
+/// Replaces the file at `path` with a copy created by the current user, keeping its content and mode.
+#[cfg(unix)]
+fn replace_with_copy(path: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let content = std::fs::read(path)?;
+
+    let mut tmp_file_name = path
+        .file_name()
+        .map(std::ffi::OsStr::to_os_string)
+        .unwrap_or_default();
+    tmp_file_name.push(".tmp");
+    let tmp_file_path = path.with_file_name(tmp_file_name);
+
+    std::fs::write(&tmp_file_path, content)?;
+    std::fs::set_permissions(
+        &tmp_file_path,
+        std::fs::Permissions::from_mode((mode & 0o7777) | 0o600),
+    )?;
+    if let Err(err) = std::fs::rename(&tmp_file_path, path) {
+        let _ = std::fs::remove_file(&tmp_file_path);
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::fs::PermissionsExt;
+    use ConfigLocation::Cache;
+
+    #[test]
+    fn missing_permissions_are_added() {
+        let app = "test-binconf-missing_permissions_are_added";
+        let app_dir = crate::app_dir(app, &Cache).unwrap();
+        let file = app_dir.join("settings.toml");
+        let nested = app_dir.join("nested");
+
+        std::fs::write(&file, "a = 1").unwrap();
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o200)).unwrap();
+        std::fs::set_permissions(&nested, std::fs::Permissions::from_mode(0o500)).unwrap();
+
+        let report = fix_permissions(app, Cache).unwrap();
+        assert!(report.unfixable.is_empty(), "{report:?}");
+        assert!(report.fixed.contains(&PermissionFix::Mode {
+            path: file.clone(),
+            from_mode: 0o200,
+            to_mode: 0o600,
+        }));
+        assert!(report.fixed.contains(&PermissionFix::Mode {
+            path: nested.clone(),
+            from_mode: 0o500,
+            to_mode: 0o700,
+        }));
+
+        assert_eq!(
+            std::fs::metadata(&file).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert!(fix_permissions(app, Cache).unwrap().fixed.is_empty());
+    }
+
+    #[test]
+    fn symlinked_directories_are_not_followed() {
+        let app = "test-binconf-symlinked_directories_are_not_followed";
+        let app_dir = crate::app_dir(app, &Cache).unwrap();
+        let outside = std::env::temp_dir().join(format!("{app}-outside"));
+        let outside_file = outside.join("settings.toml");
+        let link = app_dir.join("linked");
+
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(&outside_file, "a = 1").unwrap();
+        std::fs::set_permissions(&outside_file, std::fs::Permissions::from_mode(0o200)).unwrap();
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+
+        let report = fix_permissions(app, Cache).unwrap();
+        assert!(report.fixed.is_empty(), "{report:?}");
+        assert_eq!(
+            std::fs::metadata(&outside_file)
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o777,
+            0o200
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{ConfigError, ConfigLocation};
+use std::path::{Path, PathBuf};
+
+/// A config loaded with a system policy applied on top of it, returned by [`load_toml_with_policy`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyConfig<T> {
+    /// The effective config, with the policy values overriding the user values.
+    pub config: T,
+    /// The dotted key paths set by the policy. The user can't change these values.
+    pub locked_keys: Vec<String>,
+}
+
+impl<T> PolicyConfig<T> {
+    /// Returns `true` if the value at the dotted `key_path` is enforced by the policy.
+    pub fn is_locked(&self, key_path: &str) -> bool {
+        self.locked_keys.iter().any(|locked| {
+            locked == key_path
+                || locked
+                    .strip_prefix(key_path)
+                    .is_some_and(|rest| rest.starts_with('.'))
+                || key_path
+                    .strip_prefix(locked.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+}
+
+/// Returns the conventional path of the system policy file of an app.
+///
+/// - Unix: `/etc/<app_name>/<app_name>.policy.toml`
+/// - Windows: `%ProgramData%\<app_name>\<app_name>.policy.toml`
+///
+/// The file is not required to exist.
+pub fn system_policy_path(app_name: impl AsRef<str>) -> PathBuf {
+    let app_name = app_name.as_ref();
+
+    #[cfg(windows)]
+    let base = std::env::var_os("ProgramData")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"));
+
+    #[cfg(not(windows))]
+    let base = PathBuf::from("/etc");
+
+    base.join(app_name).join(format!("{app_name}.policy.toml"))
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user, with a system policy enforced on top of it. In `toml` format.
+///
+/// The user config is loaded like [`crate::load_toml`] does, then every value of the policy file at `policy_path` is deep merged over it.
+/// The keys set by the policy are reported in [`PolicyConfig::locked_keys`], so the app can show them as managed and refuse to change them.
+///
+/// A missing policy file is treated as an empty policy. Use [`system_policy_path`] for the conventional location.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization of the user config fails, an error will be returned. If it is set to `true` the user config file will be reset to the default config.
+///
+/// An invalid policy file always returns an error, as silently ignoring an enforced policy is never the right call.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///    update_url: String,
+///    auto_update: bool,
+/// }
+///
+/// let policy_path = std::env::temp_dir().join("test-binconf-policy.toml");
+/// std::fs::write(&policy_path, "auto_update = true\n").unwrap();
+///
+/// let loaded = binconf::load_toml_with_policy::<TestConfig>("test-binconf-read-policy", None, Config, &policy_path, false).unwrap();
+/// assert!(loaded.config.auto_update);
+/// assert!(loaded.is_locked("auto_update"));
+/// ```
+pub fn load_toml_with_policy<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    policy_path: impl AsRef<Path>,
+    reset_conf_on_err: bool,
+) -> Result<PolicyConfig<T>, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let user_config: T = crate::load_toml(app_name, config_name, location, reset_conf_on_err)?;
+
+    let policy_path = policy_path.as_ref();
+    if !policy_path.try_exists()? {
+        return Ok(PolicyConfig {
+            config: user_config,
+            locked_keys: Vec::new(),
+        });
+    }
+
+    let policy = toml::from_str::<toml::Table>(&std::fs::read_to_string(policy_path)?)?;
+
+    let mut locked_keys = Vec::new();
+    collect_leaf_keys(&policy, "", &mut locked_keys);
+
+    let mut config = toml::Value::try_from(&user_config)?;
+    crate::merge::merge_toml(&mut config, toml::Value::Table(policy));
+
+    Ok(PolicyConfig {
+        config: config.try_into()?,
+        locked_keys,
+    })
+}
+
+/// Collects the dotted paths of every non-table value in `table`.
+fn collect_leaf_keys(table: &toml::Table, prefix: &str, keys: &mut Vec<String>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+
+        match value {
+            toml::Value::Table(child) => collect_leaf_keys(child, &path, keys),
+            _ => keys.push(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::{Deserialize, Serialize};
+    use ConfigLocation::Config;
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Ui {
+        theme: String,
+        size: u32,
+    }
+
+    #[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct TestConfig {
+        name: String,
+        ui: Ui,
+    }
+
+    #[test]
+    fn policy_overrides_and_locks_keys() {
+        let user = TestConfig {
+            name: String::from("user"),
+            ui: Ui {
+                theme: String::from("light"),
+                size: 12,
+            },
+        };
+        crate::store_toml("test-binconf-policy_overrides-toml", None, Config, &user).unwrap();
+
+        let policy_path = std::env::temp_dir().join("test-binconf-policy_overrides.toml");
+        std::fs::write(&policy_path, "[ui]\ntheme = \"corporate\"\n").unwrap();
+
+        let loaded: PolicyConfig<TestConfig> = load_toml_with_policy(
+            "test-binconf-policy_overrides-toml",
+            None,
+            Config,
+            &policy_path,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(loaded.config.name, "user");
+        assert_eq!(loaded.config.ui.theme, "corporate");
+        assert_eq!(loaded.config.ui.size, 12);
+        assert_eq!(loaded.locked_keys, vec![String::from("ui.theme")]);
+        assert!(loaded.is_locked("ui"));
+        assert!(loaded.is_locked("ui.theme"));
+        assert!(!loaded.is_locked("ui.size"));
+    }
+}
@@ -0,0 +1,31 @@
+//! Re-exports of the enums, options structs and traits used by most apps.
+//!
+//! ```
+//! use binconf::prelude::*;
+//!
+//! let config = binconf::load_bin::<u32>("test-binconf-prelude", None, ConfigLocation::Config, false);
+//!
+//! if let Err(err) = config {
+//!     assert_ne!(err.kind(), ErrorKind::Unsupported);
+//! }
+//! ```
+
+pub use crate::{ConfigError, ConfigLocation, ConfigType, ErrorKind};
+
+pub use crate::{CleanupPolicy, HealthCheckOptions, LayoutPolicy, StaleLockPolicy};
+
+pub use crate::{ConfigFile, ConfigFormat};
+
+pub use crate::{ConfigMetadata, ErrorMessages, GCounter, LwwRegister, Merge, OrSet};
+
+#[cfg(feature = "binary-conf")]
+pub use crate::{ConflictPolicy, HashStatus, Hasher, NoHasher, VerifyMode, Xxh3Hasher};
+
+#[cfg(feature = "toml-conf")]
+pub use crate::{LayerSources, Source};
+
+#[cfg(feature = "watch")]
+pub use crate::{WatchHandle, WatchOptions};
+
+#[cfg(all(feature = "watch", feature = "json-conf"))]
+pub use crate::ChangedKeys;
@@ -0,0 +1,232 @@
+use crate::{ConfigError, ConfigLocation};
+use std::path::{Path, PathBuf};
+
+/// Returns the directory of a profile of an app, in the config, cache, cwd, or local data directory of the current user.
+///
+/// Profiles are subdirectories of the app directory, holding a full set of config files. The directory is not created.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created,
+/// or if `profile` is not a plain directory name.
+pub fn profile_dir(
+    app_name: impl AsRef<str>,
+    location: impl AsRef<ConfigLocation>,
+    profile: &str,
+) -> Result<PathBuf, ConfigError> {
+    if profile.is_empty()
+        || profile.starts_with('.')
+        || Path::new(profile).file_name() != Some(profile.as_ref())
+    {
+        return Err(ConfigError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid profile name `{profile}`"),
+        )));
+    }
+
+    Ok(crate::app_dir(app_name.as_ref(), location.as_ref())?.join(profile))
+}
+
+/// Replaces a whole profile directory with the content of `source_dir`, in the config, cache, cwd, or local data directory of the current user.
+///
+/// The new content is first copied to a staging directory next to the profile, then swapped in. Readers see either the complete
+/// old profile or the complete new one, never a mix of both. On Linux both directories are exchanged at once, elsewhere the
+/// swap takes two renames and the profile directory briefly does not exist. A profile left aside by a swap that was interrupted
+/// between the two renames is put back first.
+///
+/// Useful for tools that regenerate whole config sets (e.g. from templates).
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if `profile` is not a plain
+/// directory name, or if something went wrong while copying or renaming.
+///
+/// If the swap fails, the old profile is put back in place.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// let source_dir = std::env::temp_dir().join("test-binconf-replace-profile-source");
+/// std::fs::create_dir_all(&source_dir).unwrap();
+/// std::fs::write(source_dir.join("settings.toml"), "theme = \"dark\"\n").unwrap();
+///
+/// binconf::replace_profile("test-binconf-replace-profile", Config, "work", &source_dir).unwrap();
+///
+/// let profile_dir = binconf::profile_dir("test-binconf-replace-profile", Config, "work").unwrap();
+/// assert!(profile_dir.join("settings.toml").exists());
+/// ```
+pub fn replace_profile(
+    app_name: impl AsRef<str>,
+    location: impl AsRef<ConfigLocation>,
+    profile: &str,
+    source_dir: impl AsRef<Path>,
+) -> Result<(), ConfigError> {
+    let profile_dir = profile_dir(app_name, location, profile)?;
+    crate::freeze::ensure_not_frozen(&profile_dir)?;
+    crate::project::ensure_inside_project(&profile_dir)?;
+    let staging_dir = profile_dir.with_file_name(format!(".{profile}.staging"));
+    let old_dir = profile_dir.with_file_name(format!(".{profile}.old"));
+
+    // Leftovers of an interrupted swap. The old profile is the only copy left if the swap stopped between its renames
+    restore_interrupted_swap(&profile_dir, &old_dir)?;
+    for leftover in [&staging_dir, &old_dir] {
+        if leftover.try_exists()? {
+            std::fs::remove_dir_all(leftover)?;
+        }
+    }
+
+    copy_dir(source_dir.as_ref(), &staging_dir)?;
+
+    if !profile_dir.try_exists()? {
+        std::fs::rename(&staging_dir, &profile_dir)?;
+        return Ok(());
+    }
+
+    if exchange_dirs(&staging_dir, &profile_dir)? {
+        std::fs::remove_dir_all(&staging_dir)?;
+        return Ok(());
+    }
+
+    std::fs::rename(&profile_dir, &old_dir)?;
+    if let Err(err) = std::fs::rename(&staging_dir, &profile_dir) {
+        std::fs::rename(&old_dir, &profile_dir)?;
+        return Err(err.into());
+    }
+
+    std::fs::remove_dir_all(&old_dir)?;
+
+    Ok(())
+}
+
+/// Renames the `.<profile>.old` directory at `old_dir` back to `profile_dir`, if the profile is missing.
+///
+/// The swap of [`replace_profile`] leaves the old profile there, and no profile at all, when it is interrupted between its two renames.
+pub(crate) fn restore_interrupted_swap(profile_dir: &Path, old_dir: &Path) -> std::io::Result<()> {
+    if !profile_dir.try_exists()? && old_dir.try_exists()? {
+        std::fs::rename(old_dir, profile_dir)?;
+    }
+
+    Ok(())
+}
+
+/// Atomically exchanges the directories at `first` and `second`. Returns `false` if the platform or the file system can't.
+#[cfg(all(target_os = "linux", any(target_env = "gnu", target_env = "musl")))]
+fn exchange_dirs(first: &Path, second: &Path) -> std::io::Result<bool> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let first = CString::new(first.as_os_str().as_bytes())?;
+    let second = CString::new(second.as_os_str().as_bytes())?;
+    // SAFETY: both paths are nul terminated strings that outlive the call, and are resolved from the current directory
+    // when relative, as `AT_FDCWD` is given.
+    let result = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            first.as_ptr(),
+            libc::AT_FDCWD,
+            second.as_ptr(),
+            libc::RENAME_EXCHANGE,
+        )
+    };
+    if result == 0 {
+        return Ok(true);
+    }
+
+    let err = std::io::Error::last_os_error();
+    match err.raw_os_error() {
+        // Older kernels and file systems without support for the exchange
+        Some(libc::EINVAL | libc::ENOSYS) => Ok(false),
+        _ => Err(err),
+    }
+}
+
+#[cfg(not(all(target_os = "linux", any(target_env = "gnu", target_env = "musl"))))]
+fn exchange_dirs(_first: &Path, _second: &Path) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+/// Recursively copies the content of `source` into `destination`, creating it.
+fn copy_dir(source: &Path, destination: &Path) -> Result<(), ConfigError> {
+    std::fs::create_dir_all(destination)?;
+
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let target = destination.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[test]
+    fn replace_profile_swaps_whole_directory() {
+        let source_dir = std::env::temp_dir().join("test-binconf-replace_profile-source");
+        let _ = std::fs::remove_dir_all(&source_dir);
+        std::fs::create_dir_all(source_dir.join("nested")).unwrap();
+        std::fs::write(source_dir.join("a.toml"), "a = 1\n").unwrap();
+        std::fs::write(source_dir.join("nested/b.toml"), "b = 2\n").unwrap();
+
+        let profile_dir =
+            profile_dir("test-binconf-replace_profile-swap", Config, "default").unwrap();
+        std::fs::create_dir_all(&profile_dir).unwrap();
+        std::fs::write(profile_dir.join("stale.toml"), "stale = true\n").unwrap();
+
+        replace_profile(
+            "test-binconf-replace_profile-swap",
+            Config,
+            "default",
+            &source_dir,
+        )
+        .unwrap();
+
+        assert!(!profile_dir.join("stale.toml").exists());
+        assert_eq!(
+            std::fs::read_to_string(profile_dir.join("nested/b.toml")).unwrap(),
+            "b = 2\n"
+        );
+        assert!(!profile_dir.with_file_name(".default.old").exists());
+        assert!(!profile_dir.with_file_name(".default.staging").exists());
+    }
+
+    #[test]
+    fn profile_dir_rejects_paths() {
+        assert!(profile_dir("test-binconf-profile_dir_rejects", Config, "../escape").is_err());
+        assert!(profile_dir("test-binconf-profile_dir_rejects", Config, "").is_err());
+    }
+
+    #[test]
+    fn interrupted_swaps_keep_the_old_profile() {
+        let app = "test-binconf-interrupted_swaps_keep_the_old_profile";
+        let profile_dir = profile_dir(app, Config, "default").unwrap();
+        let old_dir = profile_dir.with_file_name(".default.old");
+        let _ = std::fs::remove_dir_all(&profile_dir);
+        let _ = std::fs::remove_dir_all(&old_dir);
+
+        // Interrupted after the profile was moved aside
+        std::fs::create_dir_all(&old_dir).unwrap();
+        std::fs::write(old_dir.join("a.toml"), "a = 1\n").unwrap();
+
+        let missing_source = std::env::temp_dir().join("test-binconf-interrupted_swaps-missing");
+        let _ = std::fs::remove_dir_all(&missing_source);
+        assert!(replace_profile(app, Config, "default", &missing_source).is_err());
+
+        assert_eq!(
+            std::fs::read_to_string(profile_dir.join("a.toml")).unwrap(),
+            "a = 1\n"
+        );
+        assert!(!old_dir.exists());
+    }
+}
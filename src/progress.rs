@@ -0,0 +1,184 @@
+use crate::{ConfigError, IoOperation};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+type ProgressHook = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+static HOOK: RwLock<Option<(u64, ProgressHook)>> = RwLock::new(None);
+
+/// Files are read and written in chunks of this size while a progress hook is set.
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Progress of a config file read or write, delivered to the hook set with [`set_progress_hook`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressEvent {
+    pub operation: IoOperation,
+    pub path: PathBuf,
+    /// Number of bytes read or written so far.
+    pub processed: u64,
+    /// Size of the file.
+    pub total: u64,
+}
+
+/// Calls `hook` while a `load` or `store` function reads or writes a config file of at least `min_size` bytes.
+///
+/// Meant for progress bars on big state files: the file is then read or written in chunks of 1 MiB, and `hook` is
+/// called after every chunk, the last call having `processed == total`. Serialization is not included. Smaller files
+/// are read and written at once without calling the hook. Replaces the previous hook, if any.
+///
+/// The hook is called from the thread doing the operation, it must return quickly.
+///
+/// # Example
+///
+/// ```
+/// binconf::set_progress_hook(64 * 1024 * 1024, |event| {
+///     let percent = event.processed * 100 / event.total.max(1);
+///     eprintln!("{:?} of {}: {percent}%", event.operation, event.path.display());
+/// });
+///
+/// binconf::clear_progress_hook();
+/// ```
+pub fn set_progress_hook(min_size: u64, hook: impl Fn(&ProgressEvent) + Send + Sync + 'static) {
+    *HOOK
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some((min_size, Arc::new(hook)));
+}
+
+/// Removes the hook set with [`set_progress_hook`].
+pub fn clear_progress_hook() {
+    *HOOK
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = None;
+}
+
+/// Returns the hook, if one is set for files of `size` bytes.
+fn hook_for(size: u64) -> Option<ProgressHook> {
+    match HOOK
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .as_ref()
+    {
+        Some((min_size, hook)) if size >= *min_size => Some(Arc::clone(hook)),
+        _ => None,
+    }
+}
+
+/// Reads a whole file like [`std::fs::read`], reporting the progress to the hook.
+///
+/// Returns a [`ConfigError::Cancelled`] error before reading, and between chunks, if the operation was cancelled.
+pub(crate) fn read(path: impl AsRef<Path>) -> Result<Vec<u8>, ConfigError> {
+    crate::cancel::check()?;
+
+    let path = path.as_ref();
+    let mut file = std::fs::File::open(path)?;
+    let total = file.metadata()?.len();
+
+    let hook = hook_for(total);
+    let chunked = hook.is_some() || (crate::cancel::is_watched() && total > CHUNK_SIZE as u64);
+    if !chunked {
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        return Ok(data);
+    }
+
+    let mut data = Vec::with_capacity(usize::try_from(total).unwrap_or_default());
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        data.extend_from_slice(chunk.get(..read).unwrap_or_default());
+        if let Some(hook) = &hook {
+            hook(&ProgressEvent {
+                operation: IoOperation::Load,
+                path: path.to_path_buf(),
+                processed: data.len() as u64,
+                // The file may grow while it is read
+                total: total.max(data.len() as u64),
+            });
+        }
+        crate::cancel::check()?;
+    }
+
+    Ok(data)
+}
+
+/// Reads a whole file like [`std::fs::read_to_string`], reporting the progress to the hook.
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+pub(crate) fn read_to_string(path: impl AsRef<Path>) -> Result<String, ConfigError> {
+    String::from_utf8(read(path)?)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err).into())
+}
+
+/// Writes `data` to `writer` like [`Write::write_all`], reporting the progress of writing the file at `path` to the hook.
+pub(crate) fn write_all(writer: &mut impl Write, path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let total = data.len() as u64;
+    let Some(hook) = hook_for(total) else {
+        return writer.write_all(data);
+    };
+
+    let mut processed = 0;
+    for chunk in data.chunks(CHUNK_SIZE) {
+        writer.write_all(chunk)?;
+        processed += chunk.len() as u64;
+        hook(&ProgressEvent {
+            operation: IoOperation::Store,
+            path: path.to_path_buf(),
+            processed,
+            total,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_is_reported_per_chunk() {
+        let path = std::env::temp_dir().join("test-binconf-progress_is_reported_per_chunk");
+        let data = vec![7u8; CHUNK_SIZE * 2 + 10];
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = std::sync::Mutex::new(sender);
+        // Other tests read and write configs concurrently, only look at the events of this test
+        let watched = path.clone();
+        set_progress_hook(CHUNK_SIZE as u64, move |event| {
+            if event.path == watched {
+                let _ = sender
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .send((event.operation, event.processed, event.total));
+            }
+        });
+
+        let mut file = std::fs::File::create(&path).unwrap();
+        write_all(&mut file, &path, &data).unwrap();
+        drop(file);
+        assert_eq!(read(&path).unwrap(), data);
+        clear_progress_hook();
+
+        let total = data.len() as u64;
+        let events: Vec<_> = receiver.try_iter().collect();
+        let stores: Vec<_> = events
+            .iter()
+            .filter(|(operation, ..)| *operation == IoOperation::Store)
+            .collect();
+        assert_eq!(stores.len(), 3);
+        assert_eq!(stores.last(), Some(&&(IoOperation::Store, total, total)));
+        assert_eq!(
+            events.last(),
+            Some(&(IoOperation::Load, total, total)),
+            "{events:?}"
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use crate::{ConfigError, ConfigLocation};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Policies set with [`set_cwd_policy`], by app name.
+static CWD_POLICIES: RwLock<Vec<(String, CwdPolicy)>> = RwLock::new(Vec::new());
+
+/// How the configs of an app are found in [`crate::ConfigLocation::Cwd`]. Set with [`set_cwd_policy`].
+///
+/// By default the files are stored in `<cwd>/<app>/`, like in the other locations. Per-project tool configs usually
+/// follow the dot file convention instead: `.myapp.toml` at the root of the project, found from any subdirectory.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CwdPolicy {
+    /// Stores the files directly in the directory, without an app directory, and names the default file `.<app>.<ext>`.
+    /// Files named explicitly with a `config_name` keep their name. The subdirectories of [`crate::LayoutPolicy`] are
+    /// not used.
+    pub dot_files: bool,
+    /// Looks for the config file in the parent directories when it does not exist in the current directory, like
+    /// `.gitignore` files are discovered. The nearest one is used. New files are still created in the current directory.
+    pub search_parents: bool,
+    /// Names of the entries marking the last directory searched, e.g. `.git` to stay inside the repository. Without
+    /// markers, the search goes up to the root of the filesystem.
+    pub stop_markers: Vec<String>,
+    /// Refuses to create or write configs when the current directory is the home directory or the root of the filesystem,
+    /// or is not inside a project holding one of the [`project_markers`](CwdPolicy::project_markers), with a
+    /// [`ConfigError::OutsideProject`] error. Keeps a tool run from an unexpected directory from littering it. Existing
+    /// files are still read, but a `load` that would create the default config fails.
+    pub refuse_outside_project: bool,
+    /// Names of the entries marking the root of a project, like `Cargo.toml` or `.git`, for
+    /// [`refuse_outside_project`](CwdPolicy::refuse_outside_project). Without markers, any other directory is accepted.
+    pub project_markers: Vec<String>,
+}
+
+/// Sets how the configs of `app_name` are found in [`crate::ConfigLocation::Cwd`].
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigType, CwdPolicy};
+///
+/// binconf::set_cwd_policy("test-binconf-cwd-policy", CwdPolicy {
+///     dot_files: true,
+///     search_parents: true,
+///     stop_markers: vec![String::from(".git")],
+///     ..CwdPolicy::default()
+/// });
+///
+/// let path = binconf::get_configuration_path("test-binconf-cwd-policy", None, ConfigType::Bin, Cwd).unwrap();
+/// assert!(path.ends_with(".test-binconf-cwd-policy.bin"));
+/// ```
+pub fn set_cwd_policy(app_name: impl AsRef<str>, policy: CwdPolicy) {
+    let app_name = app_name.as_ref();
+    let mut policies = CWD_POLICIES
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    match policies.iter_mut().find(|(name, _)| name == app_name) {
+        Some((_, cwd_policy)) => *cwd_policy = policy,
+        None => policies.push((app_name.to_owned(), policy)),
+    }
+}
+
+fn policy_for(app_name: &str) -> Option<CwdPolicy> {
+    CWD_POLICIES
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .iter()
+        .find(|(name, _)| name == app_name)
+        .map(|(_, policy)| policy.clone())
+}
+
+/// Returns `true` if the configs of `app_name` are dot files in the current directory.
+pub(crate) fn uses_dot_files(app_name: &str) -> bool {
+    policy_for(app_name).is_some_and(|policy| policy.dot_files)
+}
+
+/// Returns `true` if the app refuses configs outside of a project in `location` and the current directory is not inside
+/// one. No directory is created for its files then.
+pub(crate) fn is_outside_project(app_name: &str, location: &ConfigLocation) -> bool {
+    if *location != ConfigLocation::Cwd {
+        return false;
+    }
+    let Some(policy) = policy_for(app_name).filter(|policy| policy.refuse_outside_project) else {
+        return false;
+    };
+
+    std::env::current_dir().map_or(true, |current_dir| {
+        !is_inside_project(&current_dir, &policy.project_markers)
+    })
+}
+
+/// Fails with [`ConfigError::OutsideProject`] if `path` is a file of an app refusing configs outside of a project in
+/// the current directory, and the current directory is not inside one. Checked before every write, reads are never refused.
+pub(crate) fn ensure_inside_project(path: &Path) -> Result<(), ConfigError> {
+    let policies = CWD_POLICIES
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if !policies
+        .iter()
+        .any(|(_, policy)| policy.refuse_outside_project)
+    {
+        return Ok(());
+    }
+
+    let current_dir = std::env::current_dir()?;
+    if policies
+        .iter()
+        .any(|(app_name, policy)| refuses_write(app_name, policy, &current_dir, path))
+    {
+        return Err(ConfigError::OutsideProject(current_dir));
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if writing `path` from `current_dir` is refused by the policy of `app_name`.
+fn refuses_write(app_name: &str, policy: &CwdPolicy, current_dir: &Path, path: &Path) -> bool {
+    if !policy.refuse_outside_project || is_inside_project(current_dir, &policy.project_markers) {
+        return false;
+    }
+
+    // The directories the files of the app are found in, see `search_parents`
+    let searched = if policy.search_parents { usize::MAX } else { 1 };
+    current_dir.ancestors().take(searched).any(|dir| {
+        if policy.dot_files {
+            path.parent() == Some(dir)
+        } else {
+            path.starts_with(dir.join(app_name))
+        }
+    })
+}
+
+fn is_inside_project(dir: &Path, project_markers: &[String]) -> bool {
+    let is_root = dir.parent().is_none();
+    let is_home = dirs::home_dir().is_some_and(|home| home == dir);
+    if is_root || is_home {
+        return false;
+    }
+
+    project_markers.is_empty()
+        || dir.ancestors().any(|dir| {
+            project_markers
+                .iter()
+                .any(|marker| dir.join(marker).exists())
+        })
+}
+
+/// Returns the config file in the nearest parent directory, if `conf_file` in the current directory does not exist and
+/// the app searches the parents. Returns `conf_file` otherwise.
+pub(crate) fn search_parents(app_name: &str, conf_file: PathBuf) -> Result<PathBuf, ConfigError> {
+    let Some(policy) = policy_for(app_name).filter(|policy| policy.search_parents) else {
+        return Ok(conf_file);
+    };
+    if conf_file.try_exists()? {
+        return Ok(conf_file);
+    }
+
+    let current_dir = std::env::current_dir()?;
+    let Ok(relative) = conf_file.strip_prefix(&current_dir) else {
+        return Ok(conf_file);
+    };
+
+    Ok(find_upward(&current_dir, relative, &policy.stop_markers).unwrap_or(conf_file))
+}
+
+/// Returns the first `<dir>/<relative>` file, for `start` and then its parents, stopping after the first directory
+/// holding one of the `stop_markers`.
+fn find_upward(start: &Path, relative: &Path, stop_markers: &[String]) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let candidate = dir.join(relative);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if stop_markers.iter().any(|marker| dir.join(marker).exists()) {
+            break;
+        }
+    }
+
+    None
+}
+
+/// Returns the root of the project the current directory belongs to: the nearest directory, from the current directory
+/// up, holding one of the `markers`, like `Cargo.toml` or `.git`.
+///
+/// Returns `None` outside of a project.
+///
+/// # Errors
+///
+/// This function will return an error if the current directory could not be read.
+///
+/// # Example
+///
+/// ```
+/// if let Some(root) = binconf::find_project_root(&["Cargo.toml", ".git"]).unwrap() {
+///     println!("Project root: {}", root.display());
+/// }
+/// ```
+pub fn find_project_root(markers: &[&str]) -> Result<Option<PathBuf>, ConfigError> {
+    Ok(project_root(&std::env::current_dir()?, markers))
+}
+
+fn project_root(start: &Path, markers: &[&str]) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| markers.iter().any(|marker| dir.join(marker).exists()))
+        .map(Path::to_path_buf)
+}
+
+/// Loads the config of a tool for the project the current directory belongs to, layered over the config of the user.
+///
+/// The user config is the default file of the app in [`crate::ConfigLocation::Config`], like `~/.config/<app>/<app>.toml`.
+/// The project config is the default file name of the app in the project root found with [`find_project_root`]:
+/// `<root>/<app>.toml`, or `<root>/.<app>.toml` if the app uses dot files (see [`CwdPolicy::dot_files`]). The project
+/// config is deep merged over the user config, so it only needs the settings specific to the project, like the configs
+/// of cargo or rustfmt. Missing files are skipped, and the default config is returned if there are none.
+///
+/// No file is ever created. Only text formats can be merged.
+///
+/// # Errors
+///
+/// This function will return an error if the config directory could not be found, if a file could not be read or
+/// deserialized, or a [`ConfigError::UnsupportedFormat`] error for binary configs.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigType;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// #[serde(default)]
+/// struct Lints {
+///     max_width: u32,
+///     edition: String,
+/// }
+///
+/// let lints = binconf::find_project_config::<Lints>("test-binconf-find-project-config", ConfigType::Toml, &["Cargo.toml", ".git"]).unwrap();
+/// ```
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+pub fn find_project_config<T>(
+    app_name: impl AsRef<str>,
+    config_type: impl AsRef<crate::ConfigType>,
+    markers: &[&str],
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::de::DeserializeOwned,
+{
+    load_project_config(
+        app_name.as_ref(),
+        config_type.as_ref(),
+        &std::env::current_dir()?,
+        markers,
+    )
+}
+
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+fn load_project_config<T>(
+    app_name: &str,
+    config_type: &crate::ConfigType,
+    start: &Path,
+    markers: &[&str],
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::de::DeserializeOwned,
+{
+    #[cfg(feature = "binary-conf")]
+    if matches!(config_type, crate::ConfigType::Bin) {
+        return Err(ConfigError::UnsupportedFormat(
+            config_type.as_str().to_owned(),
+        ));
+    }
+
+    let extension = config_type.as_str();
+    let user_file =
+        crate::existing_config_location(app_name, None, extension, &crate::ConfigLocation::Config)?;
+    let project_file = match project_root(start, markers) {
+        Some(root) => Some(root.join(crate::layout::default_file_name(
+            app_name,
+            extension,
+            &crate::ConfigLocation::Cwd,
+        )?)),
+        None => None,
+    };
+
+    let mut merged: Option<String> = None;
+    for path in [Some(user_file), project_file].into_iter().flatten() {
+        if !path.try_exists()? {
+            continue;
+        }
+        let document = crate::progress::read_to_string(&path)?;
+        merged = Some(match merged {
+            Some(base) => crate::merge::merge_documents(&base, &document, config_type)?,
+            None => document,
+        });
+    }
+
+    match merged {
+        Some(merged) => crate::merge::deserialize_document(&merged, config_type),
+        None => Ok(T::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "binary-conf")]
+    use crate::{ConfigLocation::Cwd, ConfigType};
+
+    #[test]
+    fn configs_are_found_in_parent_directories() {
+        let root =
+            std::env::temp_dir().join("test-binconf-configs_are_found_in_parent_directories");
+        let project = root.join("project");
+        let nested = project.join("src").join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(project.join(".git")).unwrap();
+        std::fs::write(root.join(".tool.toml"), "").unwrap();
+        let _ = std::fs::remove_file(project.join(".tool.toml"));
+
+        let relative = Path::new(".tool.toml");
+        // The marker stops the search at the project root
+        assert_eq!(
+            find_upward(&nested, relative, &[String::from(".git")]),
+            None
+        );
+        assert_eq!(
+            find_upward(&nested, relative, &[]),
+            Some(root.join(".tool.toml"))
+        );
+
+        std::fs::write(project.join(".tool.toml"), "").unwrap();
+        assert_eq!(
+            find_upward(&nested, relative, &[String::from(".git")]),
+            Some(project.join(".tool.toml"))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "full")]
+    fn project_configs_are_layered_over_the_user_config() {
+        #[derive(Default, serde::Deserialize, PartialEq, Debug)]
+        #[serde(default)]
+        struct Lints {
+            max_width: u32,
+            edition: String,
+        }
+
+        let app = "test-binconf-project_configs_are_layered_over_the_user_config";
+        let project = std::env::temp_dir().join(app);
+        let nested = project.join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(project.join("Cargo.toml"), "").unwrap();
+
+        let load =
+            || load_project_config::<Lints>(app, &ConfigType::Toml, &nested, &["Cargo.toml"]);
+
+        let _ = std::fs::remove_file(project.join(format!("{app}.toml")));
+        crate::store_toml(
+            app,
+            None,
+            crate::ConfigLocation::Config,
+            toml::toml! {
+                max_width = 100
+                edition = "2021"
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            load().unwrap(),
+            Lints {
+                max_width: 100,
+                edition: String::from("2021")
+            }
+        );
+
+        std::fs::write(project.join(format!("{app}.toml")), "edition = \"2024\"\n").unwrap();
+        assert_eq!(
+            load().unwrap(),
+            Lints {
+                max_width: 100,
+                edition: String::from("2024")
+            }
+        );
+        assert_eq!(project_root(&nested, &[".missing"]), None);
+    }
+
+    #[test]
+    fn configs_outside_of_projects_are_refused() {
+        let project =
+            std::env::temp_dir().join("test-binconf-configs_outside_of_projects_are_refused");
+        let nested = project.join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(project.join("Cargo.toml"), "").unwrap();
+
+        let markers = [String::from("Cargo.toml")];
+        assert!(is_inside_project(&nested, &markers));
+        assert!(is_inside_project(&nested, &[]));
+        assert!(!is_inside_project(&nested, &[String::from(".missing")]));
+        assert!(!is_inside_project(Path::new("/"), &[]));
+        if let Some(home) = dirs::home_dir() {
+            assert!(!is_inside_project(&home, &[]));
+        }
+    }
+
+    #[test]
+    fn only_writes_outside_of_projects_are_refused() {
+        let project = std::env::temp_dir()
+            .join("test-binconf-only_writes_outside_of_projects_are_refused")
+            .join("project");
+        let _ = std::fs::remove_file(project.join("Cargo.toml"));
+        let policy = CwdPolicy {
+            refuse_outside_project: true,
+            project_markers: vec![String::from("Cargo.toml")],
+            ..CwdPolicy::default()
+        };
+        let dot_files = CwdPolicy {
+            dot_files: true,
+            search_parents: true,
+            ..policy.clone()
+        };
+
+        let app_file = project.join("tool").join("tool.toml");
+        let dot_file = project.join(".tool.toml");
+        assert!(refuses_write("tool", &policy, &project, &app_file));
+        assert!(!refuses_write("tool", &policy, &project, &dot_file));
+        assert!(!refuses_write("other", &policy, &project, &app_file));
+        assert!(refuses_write(
+            "tool",
+            &dot_files,
+            &project.join("src"),
+            &dot_file
+        ));
+        assert!(!refuses_write("tool", &dot_files, &project, &app_file));
+
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("Cargo.toml"), "").unwrap();
+        assert!(!refuses_write("tool", &policy, &project, &app_file));
+    }
+
+    #[test]
+    #[cfg(feature = "full")]
+    fn project_configs_are_found_without_creating_the_user_directory() {
+        let app = "test-binconf-project_configs_are_found_without_creating_the_user_directory";
+        let project = std::env::temp_dir().join(app);
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("Cargo.toml"), "").unwrap();
+        std::fs::write(project.join(format!("{app}.toml")), "level = 2\n").unwrap();
+        let user_dir = crate::resolution::resolve_app_dir(app, crate::ConfigLocation::Config)
+            .unwrap()
+            .base_dir;
+
+        let config: toml::Table =
+            load_project_config(app, &ConfigType::Toml, &project, &["Cargo.toml"]).unwrap();
+        assert_eq!(config.get("level"), Some(&toml::Value::Integer(2)));
+        assert!(!user_dir.exists());
+    }
+
+    #[test]
+    #[cfg(feature = "binary-conf")]
+    fn dot_files_are_stored_in_the_current_directory() {
+        let app = "test-binconf-dot_files_are_stored_in_the_current_directory";
+        set_cwd_policy(
+            app,
+            CwdPolicy {
+                dot_files: true,
+                ..CwdPolicy::default()
+            },
+        );
+
+        let current_dir = std::env::current_dir().unwrap();
+        let path = crate::get_configuration_path(app, None, ConfigType::Bin, Cwd).unwrap();
+        assert_eq!(path, current_dir.join(format!(".{app}.bin")));
+        let path = crate::get_configuration_path(app, "tool.bin", ConfigType::Bin, Cwd).unwrap();
+        assert_eq!(path, current_dir.join("tool.bin"));
+        assert!(!current_dir.join(app).exists());
+    }
+}
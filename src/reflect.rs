@@ -0,0 +1,402 @@
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use std::fmt;
+
+/// Returns the dotted paths of every setting of `T`, found from its `Deserialize` implementation.
+///
+/// Nested structs, also behind an `Option`, are listed field by field. Anything else is a single key, maps and sequences
+/// included, since their keys are only known at runtime. Useful to offer shell completion and validation for
+/// `myapp config set <key>` commands.
+///
+/// Types that are only known once the input is read, like untagged enums or `#[serde(flatten)]` fields,
+/// are listed as a single key and the keys that follow them may be missing.
+///
+/// # Example
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Settings {
+///     name: String,
+///     ui: Ui,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Ui {
+///     theme: String,
+///     size: Option<u8>,
+/// }
+///
+/// assert_eq!(binconf::config_keys::<Settings>(), vec!["name", "ui.theme", "ui.size"]);
+/// ```
+pub fn config_keys<T>() -> Vec<String>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let mut keys = Vec::new();
+    // The tracer stops at the first type it can't drive, the keys found so far are still returned
+    let _ = T::deserialize(KeyTracer {
+        path: String::new(),
+        keys: Some(&mut keys),
+    });
+    keys
+}
+
+#[derive(Debug)]
+struct TraceError(String);
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+impl de::Error for TraceError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        TraceError(msg.to_string())
+    }
+}
+
+/// A deserializer producing the simplest value of every type, recording the path of every value that is not a struct.
+///
+/// `keys` is `None` inside values that are not settings themselves, like the elements of a tuple.
+struct KeyTracer<'a> {
+    path: String,
+    keys: Option<&'a mut Vec<String>>,
+}
+
+impl KeyTracer<'_> {
+    fn record(&mut self) {
+        if let Some(keys) = &mut self.keys {
+            if !self.path.is_empty() {
+                keys.push(self.path.clone());
+            }
+        }
+    }
+
+    fn silent() -> KeyTracer<'static> {
+        KeyTracer {
+            path: String::new(),
+            keys: None,
+        }
+    }
+}
+
+macro_rules! trace_leaf {
+    ($($method:ident => $visit:ident($($value:expr)?)),* $(,)?) => {
+        $(
+            fn $method<V>(mut self, visitor: V) -> Result<V::Value, TraceError>
+            where
+                V: Visitor<'de>,
+            {
+                self.record();
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyTracer<'_> {
+    type Error = TraceError;
+
+    trace_leaf! {
+        deserialize_any => visit_unit(),
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i8(0),
+        deserialize_i16 => visit_i16(0),
+        deserialize_i32 => visit_i32(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_i128 => visit_i128(0),
+        deserialize_u8 => visit_u8(0),
+        deserialize_u16 => visit_u16(0),
+        deserialize_u32 => visit_u32(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_u128 => visit_u128(0),
+        deserialize_f32 => visit_f32(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char(' '),
+        deserialize_str => visit_str(""),
+        deserialize_string => visit_str(""),
+        deserialize_bytes => visit_bytes(&[]),
+        deserialize_byte_buf => visit_bytes(&[]),
+        deserialize_unit => visit_unit(),
+        deserialize_identifier => visit_str(""),
+        deserialize_ignored_any => visit_unit(),
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, TraceError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V>(
+        mut self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, TraceError>
+    where
+        V: Visitor<'de>,
+    {
+        self.record();
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, TraceError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value, TraceError>
+    where
+        V: Visitor<'de>,
+    {
+        self.record();
+        visitor.visit_seq(SilentSeq(0))
+    }
+
+    fn deserialize_tuple<V>(mut self, len: usize, visitor: V) -> Result<V::Value, TraceError>
+    where
+        V: Visitor<'de>,
+    {
+        self.record();
+        visitor.visit_seq(SilentSeq(len))
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, TraceError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value, TraceError>
+    where
+        V: Visitor<'de>,
+    {
+        self.record();
+        visitor.visit_map(Fields {
+            fields: [].iter(),
+            path: String::new(),
+            keys: None,
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, TraceError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(Fields {
+            fields: fields.iter(),
+            path: self.path,
+            keys: self.keys,
+        })
+    }
+
+    fn deserialize_enum<V>(
+        mut self,
+        _: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, TraceError>
+    where
+        V: Visitor<'de>,
+    {
+        self.record();
+        let variant = variants
+            .first()
+            .ok_or_else(|| TraceError(String::from("enum without variants")))?;
+        visitor.visit_enum(FirstVariant(variant))
+    }
+}
+
+/// Gives every field of a struct, in declaration order, tracing their values under the path of the struct.
+struct Fields<'a> {
+    fields: std::slice::Iter<'static, &'static str>,
+    path: String,
+    keys: Option<&'a mut Vec<String>>,
+}
+
+impl<'de> de::MapAccess<'de> for Fields<'_> {
+    type Error = TraceError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, TraceError>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.fields.as_slice().first() {
+            Some(field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, TraceError>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let field = self
+            .fields
+            .next()
+            .ok_or_else(|| TraceError(String::from("value without a field")))?;
+        let path = if self.path.is_empty() {
+            (*field).to_owned()
+        } else {
+            format!("{}.{field}", self.path)
+        };
+
+        seed.deserialize(KeyTracer {
+            path,
+            keys: self.keys.as_deref_mut(),
+        })
+    }
+}
+
+/// Gives `len` untraced elements.
+struct SilentSeq(usize);
+
+impl<'de> de::SeqAccess<'de> for SilentSeq {
+    type Error = TraceError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, TraceError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.0 == 0 {
+            return Ok(None);
+        }
+        self.0 -= 1;
+        seed.deserialize(KeyTracer::silent()).map(Some)
+    }
+}
+
+/// Picks the first variant of an enum, with untraced content.
+struct FirstVariant(&'static str);
+
+impl<'de> de::EnumAccess<'de> for FirstVariant {
+    type Error = TraceError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), TraceError>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.0.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for FirstVariant {
+    type Error = TraceError;
+
+    fn unit_variant(self) -> Result<(), TraceError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, TraceError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(KeyTracer::silent())
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, TraceError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(SilentSeq(len))
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, TraceError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(Fields {
+            fields: fields.iter(),
+            path: String::new(),
+            keys: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    #[derive(serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Settings {
+        name: String,
+        window: Option<Window>,
+        servers: Vec<String>,
+        colors: HashMap<String, String>,
+        position: (i32, i32),
+        mode: Mode,
+        #[serde(rename = "log-level")]
+        log_level: u8,
+        id: Id,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Window {
+        width: u32,
+        height: u32,
+        fullscreen: bool,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[allow(dead_code)]
+    enum Mode {
+        Light { contrast: f32 },
+        Dark,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Id(u64);
+
+    #[test]
+    fn lists_nested_keys() {
+        assert_eq!(
+            config_keys::<Settings>(),
+            vec![
+                "name",
+                "window.width",
+                "window.height",
+                "window.fullscreen",
+                "servers",
+                "colors",
+                "position",
+                "mode",
+                "log-level",
+                "id",
+            ]
+        );
+        assert!(config_keys::<u32>().is_empty());
+    }
+}
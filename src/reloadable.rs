@@ -0,0 +1,159 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A loaded config kept in memory, reloaded on demand or when its file changes. In any format.
+///
+/// [`get`](ReloadableConfig::get) returns the cached config without touching the disk, and
+/// [`reload`](ReloadableConfig::reload) loads it again. With [`auto_reload`](ReloadableConfig::auto_reload), `get`
+/// first compares the modification time and length of the file with the ones of the last load, and reloads the config
+/// if they changed. A reload that fails keeps the previous config, the file is never reset.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigType, ReloadableConfig};
+///
+/// binconf::store_bin("test-binconf-reloadable", None, Config, 10u32).unwrap();
+///
+/// let mut timeout = ReloadableConfig::<u32>::load("test-binconf-reloadable", None, ConfigType::Bin, Config)
+///     .unwrap()
+///     .auto_reload(true);
+/// assert_eq!(*timeout.get(), 10);
+///
+/// binconf::store_bin("test-binconf-reloadable", None, Config, 30u32).unwrap();
+/// assert_eq!(*timeout.reload().unwrap(), 30);
+/// ```
+#[derive(Debug)]
+pub struct ReloadableConfig<T> {
+    app_name: String,
+    config_name: Option<String>,
+    config_type: ConfigType,
+    location: ConfigLocation,
+    path: PathBuf,
+    auto_reload: bool,
+    stamp: Option<(SystemTime, u64)>,
+    current: T,
+}
+
+impl<T> ReloadableConfig<T>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    /// Loads the config like the `load` function of its [`ConfigType`], without resetting it on errors.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config, cache or local data directory could not be found or created,
+    /// or if the config could not be loaded.
+    pub fn load<'a>(
+        app_name: impl AsRef<str>,
+        config_name: impl Into<Option<&'a str>>,
+        config_type: ConfigType,
+        location: impl AsRef<ConfigLocation>,
+    ) -> Result<Self, ConfigError> {
+        let app_name = app_name.as_ref();
+        let config_name = config_name.into();
+        let location = location.as_ref();
+
+        let path = crate::config_location(app_name, config_name, config_type.as_str(), location)?;
+        let stamp = file_stamp(&path);
+        let current = crate::load_typed(app_name, config_name, &config_type, location, false)?;
+
+        Ok(ReloadableConfig {
+            app_name: app_name.to_owned(),
+            config_name: config_name.map(str::to_owned),
+            config_type,
+            location: location.clone(),
+            path,
+            auto_reload: false,
+            stamp,
+            current,
+        })
+    }
+
+    /// Reloads the config from [`get`](ReloadableConfig::get) when its file was modified. Disabled by default.
+    ///
+    /// Checking the file costs a `stat` call on every `get`.
+    #[must_use]
+    pub fn auto_reload(mut self, auto_reload: bool) -> Self {
+        self.auto_reload = auto_reload;
+        self
+    }
+
+    /// Returns the config, reloading it first if auto reload is enabled and its file was modified.
+    pub fn get(&mut self) -> &T {
+        if self.auto_reload {
+            let stamp = file_stamp(&self.path);
+            // The previous config is kept until the file is modified again, rather than parsing a broken file on every call
+            if stamp != self.stamp && self.reload().is_err() {
+                self.stamp = stamp;
+            }
+        }
+
+        &self.current
+    }
+
+    /// Loads the config again and returns it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config could not be loaded, the previous config is then kept.
+    pub fn reload(&mut self) -> Result<&T, ConfigError> {
+        // Taken before reading, so a modification made during the load is seen by the next check
+        let stamp = file_stamp(&self.path);
+        self.current = crate::load_typed(
+            &self.app_name,
+            self.config_name.as_deref(),
+            &self.config_type,
+            &self.location,
+            false,
+        )?;
+        self.stamp = stamp;
+
+        Ok(&self.current)
+    }
+
+    /// Returns the path of the config file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// The modification time and length of a file, used to detect changes without reading it.
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Cache;
+
+    #[test]
+    fn modified_files_are_reloaded_by_get() {
+        let app = "test-binconf-modified_files_are_reloaded_by_get";
+        crate::store_json(app, None, Cache, vec![String::from("a")]).unwrap();
+
+        let mut manual =
+            ReloadableConfig::<Vec<String>>::load(app, None, ConfigType::Json, Cache).unwrap();
+        let mut auto = ReloadableConfig::<Vec<String>>::load(app, None, ConfigType::Json, Cache)
+            .unwrap()
+            .auto_reload(true);
+
+        // A different length is seen even within the resolution of the modification time
+        crate::store_json(app, None, Cache, vec![String::from("a"), String::from("b")]).unwrap();
+        assert_eq!(manual.get().len(), 1);
+        assert_eq!(auto.get().len(), 2);
+
+        // Unparsable edits keep the previous config
+        std::fs::write(manual.path(), "[\"a\", ").unwrap();
+        assert_eq!(auto.get().len(), 2);
+        assert!(manual.reload().is_err());
+        assert_eq!(manual.get().len(), 1);
+    }
+}
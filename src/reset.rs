@@ -0,0 +1,338 @@
+use crate::ConfigError;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+type ResetHook = Arc<dyn Fn(&ResetEvent) + Send + Sync>;
+
+static HOOK: RwLock<Option<ResetHook>> = RwLock::new(None);
+
+thread_local! {
+    /// Number of resets reported on this thread, to tell a reset apart from a regular load.
+    static RESETS: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
+/// Number of characters of the offending line kept in [`ResetEvent::snippet`].
+const SNIPPET_WIDTH: usize = 80;
+
+/// A position in a config file, both counted from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A text config that could not be deserialized and was reset to the default config, reported to the hook set with [`set_reset_hook`].
+#[derive(Debug)]
+pub struct ResetEvent {
+    /// The path of the config file that was replaced.
+    pub path: PathBuf,
+    /// The error that triggered the reset.
+    pub error: ConfigError,
+    /// Where the error is in the replaced file, if the parser reported it.
+    pub location: Option<ErrorLocation>,
+    /// Up to 80 characters of the offending line, centered on the error.
+    pub snippet: Option<String>,
+}
+
+/// Calls `hook` every time a `load` function resets a `toml`, `json`, `yaml` or `ron` config to its default because of `reset_conf_on_err`.
+///
+/// The event holds the parse error and where it was in the file, so the app can tell the user exactly what was wrong
+/// with the file it just replaced. The replaced file itself is kept next to the config, as `<name>.corrupt-<timestamp>`.
+/// Replaces the previous hook, if any.
+///
+/// # Example
+///
+/// ```
+/// binconf::set_reset_hook(|event| {
+///     eprint!("{} was invalid and has been reset: {}", event.path.display(), event.error);
+///     if let (Some(location), Some(snippet)) = (event.location, &event.snippet) {
+///         eprint!(" (line {}, column {}: `{snippet}`)", location.line, location.column);
+///     }
+///     eprintln!();
+/// });
+///
+/// binconf::clear_reset_hook();
+/// ```
+pub fn set_reset_hook(hook: impl Fn(&ResetEvent) + Send + Sync + 'static) {
+    *HOOK
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(Arc::new(hook));
+}
+
+/// Removes the hook set with [`set_reset_hook`].
+pub fn clear_reset_hook() {
+    *HOOK
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = None;
+}
+
+/// Reports that the config at `path`, holding `text`, is being reset because of `error`.
+#[cfg_attr(
+    not(any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn report(path: &Path, text: &str, error: ConfigError) {
+    count();
+
+    let Some(hook) = HOOK
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .as_ref()
+        .map(Arc::clone)
+    else {
+        return;
+    };
+
+    let location = error_location(&error, text);
+    let snippet = location.and_then(|location| snippet(text, location));
+
+    // The lock is released before calling the hook, so the hook can replace itself
+    hook(&ResetEvent {
+        path: path.to_path_buf(),
+        error,
+        location,
+        snippet,
+    });
+}
+
+/// Renames the config file at `path` to `<name>.corrupt-<timestamp>`, right before it is replaced by the default config.
+///
+/// Returns the path of the copy, or `None` if there was nothing worth keeping, a missing or empty file. Failing to
+/// rename the file is not an error, the reset goes on. The copies are trimmed by [`crate::cleanup()`], which looks in the same
+/// directories the configs are written to.
+pub(crate) fn back_up_corrupt(path: &Path) -> Option<PathBuf> {
+    if std::fs::metadata(path).ok()?.len() == 0 {
+        return None;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut file_name = path.file_name()?.to_os_string();
+    // Zero padded, so the copies sort by age
+    file_name.push(format!("{}{timestamp:010}", crate::cleanup::CORRUPT_MARKER));
+
+    // Another reset of the same file within the same second
+    let mut backup_path = path.with_file_name(&file_name);
+    let mut attempt = 1;
+    while backup_path.try_exists().ok()? {
+        let mut numbered = file_name.clone();
+        numbered.push(format!("-{attempt}"));
+        backup_path = path.with_file_name(numbered);
+        attempt += 1;
+    }
+
+    std::fs::rename(path, &backup_path).ok()?;
+
+    Some(backup_path)
+}
+
+/// Counts a reset on the current thread, for [`reset_count`]. Done by [`report`], binary resets are only counted.
+pub(crate) fn count() {
+    RESETS.with(|resets| resets.set(resets.get().wrapping_add(1)));
+}
+
+/// Returns the number of resets reported on the current thread so far.
+#[cfg_attr(
+    not(any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn reset_count() -> u64 {
+    RESETS.with(std::cell::Cell::get)
+}
+
+/// Returns the position of a deserialization error in `text`, if the parser reported it.
+#[cfg_attr(
+    not(any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )),
+    allow(unused_variables)
+)]
+fn error_location(error: &ConfigError, text: &str) -> Option<ErrorLocation> {
+    match error {
+        ConfigError::DuplicateKey { line, .. } => {
+            // Point at the duplicated key, the first character of the line
+            let content = text.lines().nth(line.checked_sub(1)?)?;
+            Some(ErrorLocation {
+                line: *line,
+                column: content
+                    .chars()
+                    .take_while(|char| char.is_whitespace())
+                    .count()
+                    + 1,
+            })
+        }
+        #[cfg(feature = "toml-conf")]
+        ConfigError::TomlDe(err) => {
+            let before = text.get(..err.span()?.start)?;
+            let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+            Some(ErrorLocation {
+                line: before.matches('\n').count() + 1,
+                column: before.get(line_start..)?.chars().count() + 1,
+            })
+        }
+        #[cfg(feature = "json-conf")]
+        ConfigError::Json(err) if err.line() > 0 => Some(ErrorLocation {
+            line: err.line(),
+            column: err.column().max(1),
+        }),
+        #[cfg(feature = "yaml-conf")]
+        ConfigError::Yaml(err) => err.location().map(|location| ErrorLocation {
+            line: location.line(),
+            column: location.column(),
+        }),
+        #[cfg(feature = "ron-conf")]
+        ConfigError::RonDe(err) => Some(ErrorLocation {
+            line: err.position.line,
+            column: err.position.col,
+        }),
+        _ => None,
+    }
+}
+
+/// Returns up to [`SNIPPET_WIDTH`] characters of the line at `location`, centered on its column.
+fn snippet(text: &str, location: ErrorLocation) -> Option<String> {
+    let line = text.lines().nth(location.line.checked_sub(1)?)?;
+    let start = location
+        .column
+        .saturating_sub(1)
+        .saturating_sub(SNIPPET_WIDTH / 2);
+    let snippet: String = line.chars().skip(start).take(SNIPPET_WIDTH).collect();
+
+    Some(snippet.trim().to_owned())
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use crate::ConfigLocation::Config;
+
+    fn reset_events(
+        path: PathBuf,
+        load: impl FnOnce(),
+    ) -> Vec<(Option<ErrorLocation>, Option<String>)> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = std::sync::Mutex::new(sender);
+        // Other tests reset configs concurrently, only look at the events of this test
+        set_reset_hook(move |event| {
+            if event.path == path {
+                let _ = sender
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .send((event.location, event.snippet.clone()));
+            }
+        });
+        load();
+        clear_reset_hook();
+
+        receiver.try_iter().collect()
+    }
+
+    #[test]
+    fn reset_reports_location_and_snippet() {
+        let cases = [
+            (
+                crate::ConfigType::Toml,
+                "name = \"app\"\nsize = 12 13\n",
+                (2, 11, "size = 12 13"),
+            ),
+            (
+                crate::ConfigType::Json,
+                "{\n  \"name\": \"app\",\n  \"size\" 12\n}",
+                (3, 10, "\"size\" 12"),
+            ),
+            (
+                crate::ConfigType::Yaml,
+                "name: app\nsize: [12\n",
+                (2, 7, "size: [12"),
+            ),
+            (
+                crate::ConfigType::Ron,
+                "{\n    \"name\": \"app\",\n    \"size\": ]\n}",
+                (3, 13, "\"size\": ]"),
+            ),
+        ];
+
+        for (config_type, text, (line, column, snippet)) in cases {
+            let app = format!(
+                "test-binconf-reset_reports_location-{}",
+                config_type.as_str()
+            );
+            let path = crate::get_configuration_path(&app, None, config_type, Config).unwrap();
+            std::fs::write(&path, text).unwrap();
+
+            let events = reset_events(path, || {
+                let config: Result<std::collections::HashMap<String, String>, _> = match config_type
+                {
+                    crate::ConfigType::Toml => crate::load_toml(&app, None, Config, true),
+                    crate::ConfigType::Json => crate::load_json(&app, None, Config, true),
+                    crate::ConfigType::Yaml => crate::load_yaml(&app, None, Config, true),
+                    _ => crate::load_ron(&app, None, Config, true),
+                };
+                assert!(config.unwrap().is_empty());
+            });
+
+            assert_eq!(
+                events,
+                vec![(
+                    Some(ErrorLocation { line, column }),
+                    Some(String::from(snippet))
+                )],
+                "{}",
+                config_type.as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn reset_keeps_a_copy_of_the_corrupt_file() {
+        let app = "test-binconf-reset_keeps_a_copy_of_the_corrupt_file";
+        let app_dir = crate::app_dir(app, &Config).unwrap();
+        for entry in std::fs::read_dir(&app_dir).unwrap() {
+            std::fs::remove_file(entry.unwrap().path()).unwrap();
+        }
+
+        let path = app_dir.join("settings.toml");
+        std::fs::write(&path, "name = ").unwrap();
+        let config: toml::Table = crate::load_toml(app, "settings.toml", Config, true).unwrap();
+        assert!(config.is_empty());
+
+        std::fs::write(&path, "name = \"second\" = 1").unwrap();
+        let _: toml::Table = crate::load_toml(app, "settings.toml", Config, true).unwrap();
+
+        let mut copies: Vec<String> = std::fs::read_dir(&app_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|file_name| file_name.starts_with("settings.toml.corrupt-"))
+            .collect();
+        copies.sort();
+        assert_eq!(copies.len(), 2);
+
+        let contents: Vec<String> = copies
+            .iter()
+            .map(|copy| std::fs::read_to_string(app_dir.join(copy)).unwrap())
+            .collect();
+        assert!(contents.contains(&String::from("name = ")));
+        assert!(contents.contains(&String::from("name = \"second\" = 1")));
+
+        // A missing file has nothing to keep
+        assert_eq!(back_up_corrupt(&app_dir.join("missing.toml")), None);
+    }
+}
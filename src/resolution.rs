@@ -0,0 +1,510 @@
+use crate::{ConfigError, ConfigLocation};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, RwLock};
+
+/// Whether [`set_container_resolution`] was enabled.
+static CONTAINER_RESOLUTION: Mutex<bool> = Mutex::new(false);
+
+/// Whether the process runs in a container, detected once.
+static IN_CONTAINER: OnceLock<bool> = OnceLock::new();
+
+/// App directories set with [`set_base_dir_override`], by app name.
+static OVERRIDES: RwLock<Vec<(String, PathBuf)>> = RwLock::new(Vec::new());
+
+/// Whether [`set_path_canonicalization`] was enabled.
+static CANONICAL_PATHS: Mutex<bool> = Mutex::new(false);
+
+/// How the base directory of a [`ConfigLocation`] was chosen. Returned by [`resolve_location`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolutionSource {
+    /// The standard directory of the platform, like `~/.config` for [`ConfigLocation::Config`] on linux.
+    Standard,
+    /// The directory given by the environment variable, like `XDG_CONFIG_HOME`, in a container.
+    EnvVar(String),
+    /// The conventional mount point of the location, like `/config`, in a container.
+    MountPoint,
+    /// The directory set with [`set_base_dir_override`].
+    Override,
+}
+
+/// The base directory of a [`ConfigLocation`], holding the directories of the apps, and how it was chosen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedLocation {
+    pub base_dir: PathBuf,
+    pub source: ResolutionSource,
+}
+
+/// The path of a config file, as built from its location and canonicalized. Returned by [`resolve_config_path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPath {
+    /// The path built from the base directory of the location, the app directory and the file name.
+    pub raw: PathBuf,
+    /// The raw path with the symbolic links resolved, see [`std::fs::canonicalize`]. The file itself may not exist yet.
+    pub canonical: PathBuf,
+}
+
+/// Enables or disables the container aware resolution of the locations, used by every `load` and `store` function.
+///
+/// When enabled and the process runs in a container (see [`is_container`]), the base directory of [`ConfigLocation::Config`],
+/// [`ConfigLocation::Cache`] and [`ConfigLocation::LocalData`] is, in order of preference:
+/// - the directory given by `XDG_CONFIG_HOME`, `XDG_CACHE_HOME` or `XDG_DATA_HOME`, if set to an absolute path.
+/// - the conventional mount point `/config`, `/cache` or `/data`, if it is a writable directory.
+/// - the standard directory of the platform.
+///
+/// [`ConfigLocation::Cwd`] is never affected. Disabled by default, check the chosen directories with [`resolve_location`].
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// binconf::set_container_resolution(true);
+///
+/// let resolved = binconf::resolve_location(Config).unwrap();
+/// println!("Configs are stored in {} ({:?})", resolved.base_dir.display(), resolved.source);
+///
+/// binconf::set_container_resolution(false);
+/// ```
+pub fn set_container_resolution(enabled: bool) {
+    *CONTAINER_RESOLUTION
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = enabled;
+}
+
+/// Enables or disables the canonicalization of the config paths used by every `load` and `store` function.
+///
+/// When enabled, the symbolic links in the path of the config file are resolved, so the paths returned by
+/// [`crate::get_configuration_path`] and reported to the hooks compare equal to the paths given by the OS, like
+/// `/private/var` for `/var` on macOS, or the paths of a file watcher. Disabled by default. Both paths are returned by
+/// [`resolve_config_path`], whatever the setting.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+///
+/// binconf::set_path_canonicalization(true);
+///
+/// let path = binconf::get_configuration_path("test-binconf-path-canonicalization", None, ConfigType::Bin, Config).unwrap();
+/// assert_eq!(path.parent().unwrap(), path.parent().unwrap().canonicalize().unwrap());
+///
+/// binconf::set_path_canonicalization(false);
+/// ```
+pub fn set_path_canonicalization(enabled: bool) {
+    *CANONICAL_PATHS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = enabled;
+}
+
+/// Returns the canonical form of `path` if [`set_path_canonicalization`] is enabled, `path` itself otherwise.
+pub(crate) fn canonicalize_if_enabled(path: PathBuf) -> PathBuf {
+    let enabled = *CANONICAL_PATHS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    if enabled {
+        canonicalize(&path)
+    } else {
+        path
+    }
+}
+
+/// Resolves the symbolic links of `path`, or of its parent directory if the file does not exist.
+///
+/// Returns `path` unchanged if neither exists, like for the configs of a frozen app.
+fn canonicalize(path: &Path) -> PathBuf {
+    if let Ok(canonical) = std::fs::canonicalize(path) {
+        return canonical;
+    }
+
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(file_name)) => std::fs::canonicalize(parent)
+            .map(|parent| parent.join(file_name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Returns the raw and the canonical path of a config file. The app directory is created if it does not exist.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+///
+/// let resolved = binconf::resolve_config_path("test-binconf-resolve-config-path", None, ConfigType::Bin, Cwd).unwrap();
+/// assert!(resolved.raw.ends_with("test-binconf-resolve-config-path/test-binconf-resolve-config-path.bin"));
+/// assert!(resolved.canonical.is_absolute());
+/// ```
+pub fn resolve_config_path<'a>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: impl AsRef<crate::ConfigType>,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<ResolvedPath, ConfigError> {
+    let raw = crate::raw_config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        config_type.as_ref().as_str(),
+        location.as_ref(),
+    )?;
+    let canonical = canonicalize(&raw);
+
+    Ok(ResolvedPath { raw, canonical })
+}
+
+/// Returns `true` if the process looks like it runs in a container: `HOME` is not set, the root filesystem is
+/// mounted read-only, or the marker file of Docker or Podman exists.
+pub fn is_container() -> bool {
+    *IN_CONTAINER.get_or_init(|| {
+        std::env::var_os("HOME").is_none_or(|home| home.is_empty())
+            || crate::disk::is_read_only(Path::new("/")).unwrap_or(false)
+            || Path::new("/.dockerenv").exists()
+            || Path::new("/run/.containerenv").exists()
+    })
+}
+
+/// Returns the base directory used for `location`, holding the directories of the apps, and how it was chosen.
+///
+/// # Errors
+///
+/// This function will return an error if the standard directory of the location could not be found.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ResolutionSource;
+///
+/// let resolved = binconf::resolve_location(Cwd).unwrap();
+/// assert_eq!(resolved.base_dir, std::env::current_dir().unwrap());
+/// assert_eq!(resolved.source, ResolutionSource::Standard);
+/// ```
+pub fn resolve_location(
+    location: impl AsRef<ConfigLocation>,
+) -> Result<ResolvedLocation, ConfigError> {
+    let location = location.as_ref();
+
+    let container_resolution = *CONTAINER_RESOLUTION
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if container_resolution && is_container() {
+        let env = |name: &str| std::env::var_os(name);
+        if let Some(resolved) = container_location(location, &env, Path::new("/")) {
+            return Ok(resolved);
+        }
+    }
+
+    Ok(ResolvedLocation {
+        base_dir: standard_dir(location)?,
+        source: ResolutionSource::Standard,
+    })
+}
+
+/// Relocates the directory of `app_name` to `dir` in every location but [`ConfigLocation::Cwd`], or restores it with `None`.
+///
+/// The files of the app are then read from and written to a subdirectory of `dir` per location, `config`, `cache` or `data`,
+/// instead of `<base dir>/<app>`, so files of the same name in different locations stay apart. Without an override set
+/// in the code, the `BINCONF_<APP>_DIR` environment variable is used, with the app name in upper case and every character
+/// other than a letter or a digit replaced by `_`, like `BINCONF_MY_APP_DIR` for `my-app`. Both take precedence over
+/// [`set_container_resolution`]. Check the chosen directory with [`resolve_app_dir`].
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+///
+/// let dir = std::env::temp_dir().join("test-binconf-base-dir-override");
+/// binconf::set_base_dir_override("test-binconf-base-dir-override", Some(dir.clone()));
+///
+/// let path = binconf::get_configuration_path("test-binconf-base-dir-override", None, ConfigType::Bin, Config).unwrap();
+/// assert_eq!(path, dir.join("config").join("test-binconf-base-dir-override.bin"));
+/// ```
+pub fn set_base_dir_override(app_name: impl AsRef<str>, dir: Option<PathBuf>) {
+    let app_name = app_name.as_ref();
+    let mut overrides = OVERRIDES
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    overrides.retain(|(name, _)| name != app_name);
+    if let Some(dir) = dir {
+        overrides.push((app_name.to_owned(), dir));
+    }
+}
+
+/// Returns the directory holding the files of `app_name` in `location`, and how it was chosen. It is not created.
+///
+/// # Errors
+///
+/// This function will return an error if the standard directory of the location could not be found.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// let resolved = binconf::resolve_app_dir("test-binconf-resolve-app-dir", Cwd).unwrap();
+/// assert_eq!(resolved.base_dir, std::env::current_dir().unwrap().join("test-binconf-resolve-app-dir"));
+/// ```
+pub fn resolve_app_dir(
+    app_name: impl AsRef<str>,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<ResolvedLocation, ConfigError> {
+    let app_name = app_name.as_ref();
+    let location = location.as_ref();
+
+    if let Some(resolved) = app_dir_override(app_name, location) {
+        return Ok(resolved);
+    }
+
+    let resolved = resolve_location(location)?;
+    Ok(ResolvedLocation {
+        base_dir: resolved.base_dir.join(app_name),
+        source: resolved.source,
+    })
+}
+
+/// Returns the relocated directory of `app_name` in `location`, set in the code or in the environment.
+fn app_dir_override(app_name: &str, location: &ConfigLocation) -> Option<ResolvedLocation> {
+    let subdirectory = match location {
+        ConfigLocation::Config => "config",
+        ConfigLocation::Cache => "cache",
+        ConfigLocation::LocalData => "data",
+        ConfigLocation::Cwd => return None,
+    };
+
+    let overridden = OVERRIDES
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .iter()
+        .find(|(name, _)| name == app_name)
+        .map(|(_, dir)| dir.clone());
+    if let Some(dir) = overridden {
+        return Some(ResolvedLocation {
+            base_dir: dir.join(subdirectory),
+            source: ResolutionSource::Override,
+        });
+    }
+
+    let env_var = override_env_var(app_name);
+    std::env::var_os(&env_var)
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| ResolvedLocation {
+            base_dir: PathBuf::from(dir).join(subdirectory),
+            source: ResolutionSource::EnvVar(env_var),
+        })
+}
+
+/// Returns the name of the environment variable relocating the directory of `app_name`.
+fn override_env_var(app_name: &str) -> String {
+    let app_name: String = app_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!("BINCONF_{app_name}_DIR")
+}
+
+/// Returns the directories of `app_name` in every location, skipping the ones that could not be found.
+pub(crate) fn app_dirs(app_name: &str) -> Vec<PathBuf> {
+    [
+        ConfigLocation::Config,
+        ConfigLocation::Cache,
+        ConfigLocation::LocalData,
+        ConfigLocation::Cwd,
+    ]
+    .iter()
+    .filter_map(|location| resolve_app_dir(app_name, location).ok())
+    .map(|resolved| resolved.base_dir)
+    .collect()
+}
+
+/// Returns the base directory of `location` in a container, with the mount points looked up under `root`.
+fn container_location(
+    location: &ConfigLocation,
+    env: &dyn Fn(&str) -> Option<OsString>,
+    root: &Path,
+) -> Option<ResolvedLocation> {
+    let (env_var, mount_point) = match location {
+        ConfigLocation::Config => ("XDG_CONFIG_HOME", "config"),
+        ConfigLocation::Cache => ("XDG_CACHE_HOME", "cache"),
+        ConfigLocation::LocalData => ("XDG_DATA_HOME", "data"),
+        ConfigLocation::Cwd => return None,
+    };
+
+    if let Some(base_dir) = env(env_var)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+    {
+        return Some(ResolvedLocation {
+            base_dir,
+            source: ResolutionSource::EnvVar(env_var.to_owned()),
+        });
+    }
+
+    let base_dir = root.join(mount_point);
+    let writable = base_dir.is_dir() && !crate::disk::is_read_only(&base_dir).unwrap_or(false);
+    writable.then_some(ResolvedLocation {
+        base_dir,
+        source: ResolutionSource::MountPoint,
+    })
+}
+
+fn standard_dir(location: &ConfigLocation) -> Result<PathBuf, ConfigError> {
+    let not_found =
+        |message| ConfigError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, message));
+
+    match location {
+        ConfigLocation::Config => {
+            dirs::config_dir().ok_or_else(|| not_found("Config directory not found"))
+        }
+        ConfigLocation::Cache => {
+            dirs::cache_dir().ok_or_else(|| not_found("Cache directory not found"))
+        }
+        ConfigLocation::LocalData => {
+            dirs::data_local_dir().ok_or_else(|| not_found("Local data directory not found"))
+        }
+        ConfigLocation::Cwd => Ok(std::env::current_dir()?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "binary-conf")]
+    fn app_dirs_can_be_relocated() {
+        let app = "test-binconf-app_dirs_can_be_relocated";
+        assert_eq!(
+            override_env_var(app),
+            "BINCONF_TEST_BINCONF_APP_DIRS_CAN_BE_RELOCATED_DIR"
+        );
+
+        let dir = std::env::temp_dir().join(app);
+        set_base_dir_override(app, Some(dir.clone()));
+        assert_eq!(
+            resolve_app_dir(app, ConfigLocation::Cache).unwrap(),
+            ResolvedLocation {
+                base_dir: dir.join("cache"),
+                source: ResolutionSource::Override,
+            }
+        );
+        // The current directory is never relocated
+        assert_eq!(
+            resolve_app_dir(app, ConfigLocation::Cwd).unwrap().source,
+            ResolutionSource::Standard
+        );
+
+        // Every location keeps its own files
+        crate::store_bin(app, None, ConfigLocation::Config, 42u32).unwrap();
+        crate::store_bin(app, None, ConfigLocation::Cache, 7u32).unwrap();
+        crate::store_bin(app, None, ConfigLocation::LocalData, 1u32).unwrap();
+        assert!(dir.join("config").join(format!("{app}.bin")).is_file());
+        for (location, value) in [
+            (ConfigLocation::Config, 42u32),
+            (ConfigLocation::Cache, 7),
+            (ConfigLocation::LocalData, 1),
+        ] {
+            assert_eq!(
+                crate::load_bin::<u32>(app, None, location, false).unwrap(),
+                value
+            );
+        }
+
+        set_base_dir_override(app, None);
+        assert_eq!(
+            resolve_app_dir(app, ConfigLocation::Cache).unwrap().source,
+            ResolutionSource::Standard
+        );
+    }
+
+    #[test]
+    #[cfg(all(unix, feature = "binary-conf"))]
+    fn config_paths_resolve_symbolic_links() {
+        let app = "test-binconf-config_paths_resolve_symbolic_links";
+        let target = std::env::temp_dir().join(app);
+        let link = std::env::temp_dir().join(format!("{app}-link"));
+        std::fs::create_dir_all(&target).unwrap();
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        set_base_dir_override(app, Some(link.clone()));
+        let resolved = resolve_config_path(
+            app,
+            "settings.bin",
+            crate::ConfigType::Bin,
+            ConfigLocation::Cache,
+        )
+        .unwrap();
+        set_base_dir_override(app, None);
+
+        assert_eq!(resolved.raw, link.join("cache").join("settings.bin"));
+        assert_eq!(
+            resolved.canonical,
+            target
+                .canonicalize()
+                .unwrap()
+                .join("cache")
+                .join("settings.bin")
+        );
+    }
+
+    #[test]
+    fn container_locations_prefer_env_vars_then_mount_points() {
+        let root = std::env::temp_dir().join("test-binconf-container_locations");
+        std::fs::create_dir_all(root.join("config")).unwrap();
+        let _ = std::fs::remove_dir(root.join("cache"));
+
+        let no_env = |_: &str| None;
+        assert_eq!(
+            container_location(&ConfigLocation::Config, &no_env, &root),
+            Some(ResolvedLocation {
+                base_dir: root.join("config"),
+                source: ResolutionSource::MountPoint,
+            })
+        );
+        // No mount point, the standard directory is used
+        assert_eq!(
+            container_location(&ConfigLocation::Cache, &no_env, &root),
+            None
+        );
+        assert_eq!(
+            container_location(&ConfigLocation::Cwd, &no_env, &root),
+            None
+        );
+
+        let env = |name: &str| match name {
+            "XDG_CONFIG_HOME" => Some(OsString::from("/srv/config")),
+            "XDG_CACHE_HOME" => Some(OsString::from("relative/cache")),
+            _ => None,
+        };
+        assert_eq!(
+            container_location(&ConfigLocation::Config, &env, &root),
+            Some(ResolvedLocation {
+                base_dir: PathBuf::from("/srv/config"),
+                source: ResolutionSource::EnvVar(String::from("XDG_CONFIG_HOME")),
+            })
+        );
+        // Relative paths are ignored
+        assert_eq!(
+            container_location(&ConfigLocation::Cache, &env, &root),
+            None
+        );
+    }
+}
@@ -1,5 +1,5 @@
+use crate::progress::read_to_string;
 use crate::{ConfigError, ConfigLocation, ConfigType};
-use std::{fs::read_to_string, io::Write};
 
 /// Loads a config file from the config, cache, cwd, or local data directory of the current user. In `ron` format.
 ///
@@ -8,11 +8,14 @@ use std::{fs::read_to_string, io::Write};
 /// If the flag `reset_conf_on_err` is set to `true`, the config file will be reset to the default config if
 /// the deserialization fails, if set to `false` an error will be returned.
 ///
+/// An empty file, as left by `touch` or an interrupted write, is not an error: the default config is returned,
+/// and written to the file if `reset_conf_on_err` is `true`.
+///
 /// # Errors
 ///
 /// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
 ///
-/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config.
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
 ///
 /// # Example
 ///
@@ -38,41 +41,208 @@ pub fn load_ron<'a, T>(
 where
     T: Default + serde::Serialize + serde::de::DeserializeOwned,
 {
-    let config_file_path = crate::config_location(
+    load_ron_internal(
         app_name.as_ref(),
         config_name.into(),
-        ConfigType::Ron.as_str(),
         location.as_ref(),
-    )?;
+        reset_conf_on_err,
+        false,
+        &T::default,
+    )
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user. **Rejecting trailing data**. In `ron` format.
+///
+/// Works like [`load_ron`], but if anything other than whitespace follows the RON document,
+/// a [`ConfigError::TrailingData`] error is returned instead of a generic parse error.
+///
+/// If the flag `reset_conf_on_err` is set to `true`, the config file will be reset to the default config when trailing data is found.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails or trailing data is found, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///    test: String,
+///    test_vec: Vec<u8>,
+/// }
+///
+/// let config = binconf::load_ron_strict::<TestConfig>("test-binconf-read-strict-ron", None, Config, false).unwrap();
+/// assert_eq!(config, TestConfig::default());
+/// ```
+pub fn load_ron_strict<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_ron_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        reset_conf_on_err,
+        true,
+        &T::default,
+    )
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user, **falling back to a given config**. In `ron` format.
+///
+/// Works like [`load_ron`], but `fallback` takes the place of the `Default` implementation: it is cloned when the file does not exist
+/// or is reset, and left alone when the file is loaded. Meant for configs with big defaults, like lookup tables, that should not be built
+/// on every load just to be thrown away, and for types that can't implement `Default`.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the fallback config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Lookup {
+///    table: Vec<u32>,
+/// }
+///
+/// let fallback = Lookup { table: (0..1024).collect() };
+///
+/// let config = binconf::load_ron_or("test-binconf-read-ron-or", None, Config, false, &fallback).unwrap();
+/// assert_eq!(config, fallback);
+/// ```
+pub fn load_ron_or<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+    fallback: &T,
+) -> Result<T, ConfigError>
+where
+    T: Clone + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_ron_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        reset_conf_on_err,
+        false,
+        &|| fallback.clone(),
+    )
+}
+
+fn load_ron_internal<T>(
+    app_name: &str,
+    config_name: Option<&str>,
+    location: &ConfigLocation,
+    reset_conf_on_err: bool,
+    strict: bool,
+    default: &dyn Fn() -> T,
+) -> Result<T, ConfigError>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let config_file_path =
+        crate::config_location(app_name, config_name, ConfigType::Ron.as_str(), location)?;
 
     let save_default_conf = || {
-        let default_config = T::default();
-        let ser_config = ron::ser::PrettyConfig::new()
-            .depth_limit(4)
-            .indentor("\t".to_owned());
-        let ron_str = ron::ser::to_string_pretty(&default_config, ser_config)?;
-        crate::save_config_str(&config_file_path, &ron_str)?;
+        let default_config = default();
+        let ron_str = crate::output::to_ron_string(
+            &default_config,
+            &crate::output::output_options_for(&config_file_path),
+        )?;
+        if !crate::freeze::is_frozen_path(&config_file_path) {
+            crate::reset::back_up_corrupt(&config_file_path);
+            crate::save_config_str(&config_file_path, &ron_str)?;
+        }
         Ok(default_config)
     };
 
     if !config_file_path.try_exists()? {
-        return save_default_conf();
+        let default_config = default();
+        let ron_str = crate::output::to_ron_string(
+            &default_config,
+            &crate::output::output_options_for(&config_file_path),
+        )?;
+        if crate::freeze::is_frozen_path(&config_file_path)
+            || crate::save_config_new(&config_file_path, ron_str.as_bytes())?
+        {
+            return Ok(default_config);
+        }
+        // Created by another process in the meantime, it is loaded below
     }
 
+    let started = std::time::Instant::now();
     let ron_str = read_to_string(&config_file_path)?;
-    let config = match ron::from_str::<T>(&ron_str) {
+    crate::slow_io::report(
+        crate::IoOperation::Load,
+        &config_file_path,
+        ron_str.len(),
+        started,
+    );
+    // An empty file is left by `touch` or an interrupted write, it holds no config rather than a damaged one
+    if ron_str.is_empty() {
+        return if reset_conf_on_err {
+            save_default_conf()
+        } else {
+            Ok(default())
+        };
+    }
+    #[cfg(feature = "binary-conf")]
+    if !strict {
+        if let Some(config) = crate::parse_cache::lookup(app_name, &config_file_path, &ron_str) {
+            return Ok(config);
+        }
+    }
+    let config = match deserialize_ron::<T>(&ron_str, strict) {
         Ok(config) => config,
         Err(err) => {
             if reset_conf_on_err {
+                crate::reset::report(&config_file_path, &ron_str, err);
                 return save_default_conf();
             }
-            return Err(err.into());
+            return Err(err);
         }
     };
 
+    #[cfg(feature = "binary-conf")]
+    if !strict {
+        crate::parse_cache::store(app_name, &config_file_path, &ron_str, &config);
+    }
+
     Ok(config)
 }
 
+/// Deserializes a `ron` string.
+///
+/// In strict mode a trailing characters error is reported as [`ConfigError::TrailingData`].
+fn deserialize_ron<T>(ron_str: &str, strict: bool) -> Result<T, ConfigError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    match ron::from_str::<T>(ron_str) {
+        Err(err) if strict && err.code == ron::Error::TrailingCharacters => {
+            Err(ConfigError::TrailingData)
+        }
+        result => Ok(result?),
+    }
+}
+
 /// Stores a config file in the config, cache, cwd, or local data directory of the current user. In `ron` format.
 ///
 /// It will store a config file, serializing it with the `serde_ron` crate.
@@ -119,20 +289,76 @@ where
         location.as_ref(),
     )?;
 
-    let mut file = std::io::BufWriter::new(std::fs::File::create(config_file_path)?);
+    let ron_str =
+        crate::output::to_ron_string(&data, &crate::output::output_options_for(&config_file_path))?;
+    crate::save_config_str(&config_file_path, &ron_str)?;
+    #[cfg(feature = "json-conf")]
+    crate::changelog::record(&config_file_path, &data)?;
 
-    let ser_config = ron::ser::PrettyConfig::new()
-        .depth_limit(4)
-        .indentor("\t".to_owned());
-    let ron_str = ron::ser::to_string_pretty(&data, ser_config)?;
+    Ok(())
+}
+
+/// Stores a config file like [`store_ron`], failing if the file already exists. In `ron` format.
+///
+/// Meant for commands like `myapp init` that generate a config and must never overwrite the one of the user.
+/// When several processes store the same config at once, exactly one of them creates the file.
+///
+/// # Errors
+///
+/// This function will return a [`ConfigError::AlreadyExists`] error if the config file already exists. Other errors are returned if the config,
+/// cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigError, ConfigType};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///   test: String,
+/// }
+///
+/// let path = binconf::get_configuration_path("test-binconf-store-ron-new", None, ConfigType::Ron, Config).unwrap();
+/// # let _ = std::fs::remove_file(&path);
+///
+/// binconf::store_ron_new("test-binconf-store-ron-new", None, Config, TestConfig::default()).unwrap();
+///
+/// let result = binconf::store_ron_new("test-binconf-store-ron-new", None, Config, TestConfig::default());
+/// assert!(matches!(result, Err(ConfigError::AlreadyExists(_))));
+/// ```
+pub fn store_ron_new<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Ron.as_str(),
+        location.as_ref(),
+    )?;
 
-    file.write_all(ron_str.as_bytes())?;
+    let ron_str =
+        crate::output::to_ron_string(&data, &crate::output::output_options_for(&config_file_path))?;
+    if !crate::save_config_new(&config_file_path, ron_str.as_bytes())? {
+        return Err(ConfigError::AlreadyExists(config_file_path));
+    }
+    #[cfg(feature = "json-conf")]
+    crate::changelog::record(&config_file_path, &data)?;
 
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+
     use super::*;
 
     use serde::Deserialize;
@@ -324,6 +550,48 @@ mod tests {
         assert_eq!(config, data);
     }
 
+    #[test]
+    fn strict_rejects_trailing_data_ron() {
+        let data = TestConfig {
+            test: String::from("test"),
+            test_vec: vec![1, 2, 3, 4, 5],
+        };
+
+        store_ron(
+            "test-binconf-strict_rejects_trailing_data-ron",
+            None,
+            Config,
+            &data,
+        )
+        .unwrap();
+        let config: TestConfig = load_ron_strict(
+            "test-binconf-strict_rejects_trailing_data-ron",
+            None,
+            Config,
+            false,
+        )
+        .unwrap();
+        assert_eq!(config, data);
+
+        let path = crate::get_configuration_path(
+            "test-binconf-strict_rejects_trailing_data-ron",
+            None,
+            ConfigType::Ron,
+            Config,
+        )
+        .unwrap();
+        let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(b"(test: \"\", test_vec: [])").unwrap();
+
+        let config = load_ron_strict::<TestConfig>(
+            "test-binconf-strict_rejects_trailing_data-ron",
+            None,
+            Config,
+            false,
+        );
+        assert!(matches!(config, Err(ConfigError::TrailingData)));
+    }
+
     #[test]
     fn save_config_user_cwd_ron() {
         let data = TestConfig {
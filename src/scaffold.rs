@@ -0,0 +1,178 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::PathBuf;
+
+/// Writes the default config of an app, for `myapp init` commands, and returns its path for display.
+///
+/// The file holds `T::default()` serialized like the `store` functions do, every setting with its default value. The settings
+/// are not documented in the file: there are no comments describing them, and no template is used.
+///
+/// The only comment is written in `toml` configs, which have no way to write a value that is unset, like an `Option` field set
+/// to `None`: the keys of these settings are listed in a comment at the end of the file, for the user to fill in. `json` and
+/// `yaml` configs write them as `null`, `ron` configs as `None`.
+///
+/// # Errors
+///
+/// This function will return a [`ConfigError::AlreadyExists`] error if the config file already exists, it is never overwritten.
+/// Other errors are returned if the config, cache or local data directory could not be found or created, or if something went
+/// wrong while serializing the config.
+///
+/// Binary configs can't be edited by the user and return a [`ConfigError::UnsupportedFormat`] error.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigError, ConfigType};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct Settings {
+///     name: String,
+///     proxy: Option<String>,
+/// }
+///
+/// # let path = binconf::get_configuration_path("test-binconf-scaffold", None, ConfigType::Toml, Config).unwrap();
+/// # let _ = std::fs::remove_file(&path);
+/// match binconf::scaffold::<Settings>("test-binconf-scaffold", None, ConfigType::Toml, Config) {
+///     Ok(path) => println!("Created {}", path.display()),
+///     Err(ConfigError::AlreadyExists(path)) => eprintln!("{} already exists", path.display()),
+///     Err(err) => eprintln!("{err}"),
+/// }
+/// ```
+pub fn scaffold<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<PathBuf, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        config_type.as_str(),
+        location.as_ref(),
+    )?;
+
+    let default_config = T::default();
+    let options = crate::output::output_options_for(&config_file_path);
+    let (mut text, list_unset) = match config_type {
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => (
+            crate::output::to_toml_string(&default_config, &options)?,
+            true,
+        ),
+
+        #[cfg(feature = "json-conf")]
+        ConfigType::Json => (
+            crate::output::to_json_string(&default_config, &options)?,
+            false,
+        ),
+
+        #[cfg(feature = "yaml-conf")]
+        ConfigType::Yaml => (
+            crate::output::to_yaml_string(&default_config, &options)?,
+            false,
+        ),
+
+        #[cfg(feature = "ron-conf")]
+        ConfigType::Ron => (
+            crate::output::to_ron_string(&default_config, &options)?,
+            false,
+        ),
+
+        #[allow(unreachable_patterns)]
+        _ => {
+            return Err(ConfigError::UnsupportedFormat(
+                config_type.as_str().to_owned(),
+            ))
+        }
+    };
+
+    if list_unset {
+        let unset: Vec<_> = crate::config_keys::<T>()
+            .into_iter()
+            .filter(|key| {
+                !matches!(
+                    crate::keys::get_key::<serde::de::IgnoredAny>(&text, &config_type, key),
+                    Ok(Some(_))
+                )
+            })
+            .collect();
+
+        if !unset.is_empty() {
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str("\n# Unset by default:\n");
+            for key in unset {
+                text.push_str(&format!("# {key}\n"));
+            }
+        }
+    }
+
+    if !crate::save_config_new(&config_file_path, text.as_bytes())? {
+        return Err(ConfigError::AlreadyExists(config_file_path));
+    }
+
+    Ok(config_file_path)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Settings {
+        name: String,
+        proxy: Option<String>,
+        ui: Ui,
+    }
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Ui {
+        theme: String,
+        scale: Option<f32>,
+    }
+
+    #[test]
+    fn scaffold_lists_unset_settings() {
+        let app = "test-binconf-scaffold_lists_unset_settings";
+
+        for config_type in [ConfigType::Toml, ConfigType::Yaml, ConfigType::Ron] {
+            let path = crate::get_configuration_path(app, None, config_type, Config).unwrap();
+            let _ = std::fs::remove_file(&path);
+
+            assert_eq!(
+                scaffold::<Settings>(app, None, config_type, Config).unwrap(),
+                path
+            );
+            let text = std::fs::read_to_string(&path).unwrap();
+            let unset: Vec<_> = text
+                .lines()
+                .skip_while(|line| !line.ends_with("Unset by default:"))
+                .skip(1)
+                .collect();
+            if config_type == ConfigType::Toml {
+                assert_eq!(unset, vec!["# proxy", "# ui.scale"]);
+            } else {
+                // `null` and `None` can be written
+                assert!(unset.is_empty(), "{text}");
+            }
+
+            // The scaffold is a valid config
+            let config: Settings =
+                crate::load_typed(app, None, &config_type, &Config, false).unwrap();
+            assert_eq!(config, Settings::default());
+
+            assert!(matches!(
+                scaffold::<Settings>(app, None, config_type, Config),
+                Err(ConfigError::AlreadyExists(_))
+            ));
+        }
+    }
+}
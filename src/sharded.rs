@@ -0,0 +1,208 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::{collections::HashMap, hash::Hash, io::Write, path::PathBuf};
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Number of bucket files a sharded config is split into.
+const SHARD_COUNT: u64 = 64;
+
+/// Returns the directory holding the bucket files of a sharded config, creating it if needed.
+fn shard_dir(
+    app_name: &str,
+    config_name: &str,
+    location: &ConfigLocation,
+) -> Result<PathBuf, ConfigError> {
+    let shard_dir = crate::config_location(
+        app_name,
+        Some(&format!("{config_name}.shards")),
+        ConfigType::Bin.as_str(),
+        location,
+    )?;
+
+    if !shard_dir.try_exists()? {
+        std::fs::create_dir_all(&shard_dir)?;
+    }
+
+    Ok(shard_dir)
+}
+
+/// Returns the bucket a key belongs to.
+///
+/// The bucket is derived from the `xxh3_64` hash of the serialized key, so it is stable across runs and platforms,
+/// unlike the standard library hasher.
+fn shard_index<K>(key: &K) -> Result<u64, ConfigError>
+where
+    K: serde::Serialize,
+{
+    Ok(xxh3_64(&bincode::serialize(key)?) % SHARD_COUNT)
+}
+
+fn shard_file_name(index: u64) -> String {
+    format!("{index:02x}.{}", ConfigType::Bin.as_str())
+}
+
+/// Stores a map-like config split into several bucket files, in the config, cache, cwd, or local data directory of the current user. In `binary` format.
+///
+/// Each entry is written to a bucket chosen by the hash of its key, so a single entry can later be read with [`load_shard`]
+/// without deserializing the whole map. Every bucket file carries its own `xxh3_128` hash, just like [`crate::store_bin`].
+///
+/// The buckets are stored in the `<config_name>.shards` directory inside the app directory. All buckets are rewritten, so entries
+/// removed from the map do not linger on disk.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use std::collections::HashMap;
+///
+/// let mut index = HashMap::new();
+/// index.insert(String::from("project-a"), vec![1u8, 2, 3]);
+/// index.insert(String::from("project-b"), vec![4, 5, 6]);
+///
+/// binconf::store_sharded("test-binconf-store-sharded", "index", Config, &index).unwrap();
+///
+/// let entry = binconf::load_shard::<String, Vec<u8>>("test-binconf-store-sharded", "index", Config, &String::from("project-b")).unwrap();
+/// assert_eq!(entry, Some(vec![4, 5, 6]));
+/// ```
+pub fn store_sharded<K, V>(
+    app_name: impl AsRef<str>,
+    config_name: &str,
+    location: impl AsRef<ConfigLocation>,
+    data: &HashMap<K, V>,
+) -> Result<(), ConfigError>
+where
+    K: serde::Serialize + Eq + Hash,
+    V: serde::Serialize,
+{
+    let shard_dir = shard_dir(app_name.as_ref(), config_name, location.as_ref())?;
+
+    let mut shards: Vec<Vec<(&K, &V)>> = (0..SHARD_COUNT).map(|_| Vec::new()).collect();
+    for (key, value) in data {
+        if let Some(shard) = shards.get_mut(shard_index(key)? as usize) {
+            shard.push((key, value));
+        }
+    }
+
+    for (index, shard) in (0..SHARD_COUNT).zip(shards) {
+        let full_data = crate::binary_conf::prepare_serialized_data(&shard)?;
+        let shard_path = shard_dir.join(shard_file_name(index));
+
+        crate::freeze::ensure_not_frozen(&shard_path)?;
+        crate::project::ensure_inside_project(&shard_path)?;
+        crate::disk::ensure_free_space(&shard_path, full_data.len())?;
+
+        crate::attributes::before_write(&shard_path);
+        let started = std::time::Instant::now();
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&shard_path)?);
+        crate::progress::write_all(&mut file, &shard_path, &full_data)?;
+        file.flush()?;
+        drop(file);
+        crate::attributes::after_write(&shard_path);
+        crate::slow_io::report(
+            crate::IoOperation::Store,
+            &shard_path,
+            full_data.len(),
+            started,
+        );
+    }
+
+    Ok(())
+}
+
+/// Loads a single entry of a sharded config stored with [`store_sharded`]. In `binary` format.
+///
+/// Only the bucket the key belongs to is read and deserialized.
+///
+/// Returns `None` if the key, or the sharded config itself, does not exist.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found, or if something went wrong while deserializing the bucket.
+/// No directory is created when the sharded config does not exist.
+///
+/// If the bucket is corrupted a [`ConfigError::HashMismatch`] or [`ConfigError::CorruptedHashSector`] error is returned.
+pub fn load_shard<K, V>(
+    app_name: impl AsRef<str>,
+    config_name: &str,
+    location: impl AsRef<ConfigLocation>,
+    key: &K,
+) -> Result<Option<V>, ConfigError>
+where
+    K: serde::Serialize + serde::de::DeserializeOwned + PartialEq,
+    V: serde::de::DeserializeOwned,
+{
+    // Looked up without creating anything, a sharded config that was never stored has no entries
+    let shard_dir = crate::existing_config_location(
+        app_name.as_ref(),
+        Some(&format!("{config_name}.shards")),
+        ConfigType::Bin.as_str(),
+        location.as_ref(),
+    )?;
+    let shard_file_path = shard_dir.join(shard_file_name(shard_index(key)?));
+
+    if !shard_file_path.try_exists()? {
+        return Ok(None);
+    }
+
+    let data = std::fs::read(shard_file_path)?;
+    let shard: Vec<(K, V)> = crate::binary_conf::deserialize_verified(&data)?;
+
+    Ok(shard
+        .into_iter()
+        .find_map(|(shard_key, value)| (shard_key == *key).then_some(value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[test]
+    fn sharded_store_and_load() {
+        let data: HashMap<u32, String> = (0..500).map(|i| (i, format!("value-{i}"))).collect();
+
+        store_sharded(
+            "test-binconf-sharded_store_and_load-bin",
+            "index",
+            Config,
+            &data,
+        )
+        .unwrap();
+
+        for key in [0, 42, 499] {
+            let value: Option<String> = load_shard(
+                "test-binconf-sharded_store_and_load-bin",
+                "index",
+                Config,
+                &key,
+            )
+            .unwrap();
+            assert_eq!(value, Some(format!("value-{key}")));
+        }
+
+        let missing: Option<String> = load_shard(
+            "test-binconf-sharded_store_and_load-bin",
+            "index",
+            Config,
+            &500,
+        )
+        .unwrap();
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn loading_a_missing_shard_creates_nothing() {
+        let app = "test-binconf-loading_a_missing_shard_creates_nothing";
+        let app_dir = crate::resolution::resolve_app_dir(app, Config)
+            .unwrap()
+            .base_dir;
+
+        let missing: Option<String> = load_shard(app, "index", Config, &0u32).unwrap();
+        assert_eq!(missing, None);
+        assert!(!app_dir.exists());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{ConfigError, ConfigLocation};
+
+/// Name of the shared config file, in the config directory of the vendor.
+const SHARED_FILE: &str = "shared.toml";
+
+/// Loads the config shared by the apps of `vendor`, from `<config dir>/<vendor>/shared.toml`.
+///
+/// Meant for a suite of tools sharing settings like authentication endpoints or proxies. The shared config is a regular
+/// `toml` config, the default config is written if it does not exist. Change it with [`update_shared`].
+///
+/// # Errors
+///
+/// This function will return an error if the config directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// # Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Shared {
+///     proxy: Option<String>,
+/// }
+///
+/// let shared = binconf::load_shared::<Shared>("test-binconf-load-shared").unwrap();
+/// assert_eq!(shared.proxy, None);
+/// ```
+pub fn load_shared<T>(vendor: impl AsRef<str>) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    crate::load_toml(vendor, SHARED_FILE, ConfigLocation::Config, false)
+}
+
+/// Changes the config shared by the apps of `vendor` with `update`, and returns the stored config.
+///
+/// The config is loaded, updated and stored while holding a lock, so concurrent updates from other apps of the suite
+/// are never lost, like [`update_toml`](crate::update_toml) does. A lock left behind by a crashed process is broken. See [`load_shared`].
+///
+/// # Errors
+///
+/// This function will return an error if the config directory could not be found or created, if the config could not be
+/// loaded or stored, or a [`ConfigError::LockHeldBy`] error if another app held the lock for more than 5 seconds.
+///
+/// # Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Shared {
+///     proxy: Option<String>,
+/// }
+///
+/// let shared = binconf::update_shared("test-binconf-update-shared", |shared: &mut Shared| {
+///     shared.proxy = Some(String::from("http://proxy:3128"));
+/// })
+/// .unwrap();
+///
+/// assert_eq!(binconf::load_shared::<Shared>("test-binconf-update-shared").unwrap(), shared);
+/// ```
+pub fn update_shared<T, F>(vendor: impl AsRef<str>, update: F) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    F: FnOnce(&mut T),
+{
+    // Same lock and atomic replace as any other update, readers never see a half-written shared config
+    crate::update_toml(vendor, SHARED_FILE, ConfigLocation::Config, update)
+}
+
+/// Watches the config shared by the apps of `vendor`, calling `callback` with the reloaded config every time it changes.
+///
+/// See [`watch_config`](crate::watch_config) and [`load_shared`].
+///
+/// # Errors
+///
+/// This function will return an error if the config directory could not be found or created.
+///
+/// # Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, Debug)]
+/// struct Shared {
+///     proxy: Option<String>,
+/// }
+///
+/// let handle = binconf::watch_shared::<Shared, _>("test-binconf-watch-shared", |shared| {
+///     println!("The proxy is now {:?}", shared.proxy);
+/// })
+/// .unwrap();
+///
+/// handle.stop();
+/// ```
+#[cfg(feature = "watch")]
+pub fn watch_shared<T, F>(
+    vendor: impl AsRef<str>,
+    callback: F,
+) -> Result<crate::WatchHandle, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    F: FnMut(T) + Send + 'static,
+{
+    crate::watch_config(
+        vendor,
+        SHARED_FILE,
+        crate::ConfigType::Toml,
+        ConfigLocation::Config,
+        callback,
+    )
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        let vendor = "test-binconf-concurrent_updates_are_not_lost";
+        crate::store_toml(
+            vendor,
+            SHARED_FILE,
+            ConfigLocation::Config,
+            HashMap::<String, u32>::new(),
+        )
+        .unwrap();
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    for _ in 0..5 {
+                        update_shared(vendor, |shared: &mut HashMap<String, u32>| {
+                            *shared.entry(String::from("count")).or_default() += 1;
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let shared: HashMap<String, u32> = load_shared(vendor).unwrap();
+        assert_eq!(shared.get("count"), Some(&40));
+    }
+}
@@ -0,0 +1,171 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+type SlowIoHook = Arc<dyn Fn(&SlowIoEvent) + Send + Sync>;
+
+static HOOK: RwLock<Option<(Duration, SlowIoHook)>> = RwLock::new(None);
+
+thread_local! {
+    /// The file accesses made on this thread while [`record`] runs.
+    static RECORDS: RefCell<Option<Vec<IoRecord>>> = const { RefCell::new(None) };
+}
+
+/// The kind of operation reported in a [`SlowIoEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoOperation {
+    Load,
+    Store,
+}
+
+/// A config file read or write that took longer than the threshold given to [`set_slow_io_hook`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlowIoEvent {
+    pub operation: IoOperation,
+    pub path: PathBuf,
+    /// Number of bytes read or written.
+    pub size: u64,
+    pub duration: Duration,
+}
+
+/// A config file read or write made while [`record`] runs.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    not(any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )),
+    allow(dead_code)
+)]
+pub(crate) struct IoRecord {
+    pub(crate) operation: IoOperation,
+    pub(crate) size: u64,
+    pub(crate) started: Instant,
+    pub(crate) duration: Duration,
+}
+
+/// Runs `operation` and returns its result, with the file accesses it made on the current thread in order.
+#[cfg_attr(
+    not(any(
+        feature = "toml-conf",
+        feature = "json-conf",
+        feature = "yaml-conf",
+        feature = "ron-conf"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn record<R>(operation: impl FnOnce() -> R) -> (R, Vec<IoRecord>) {
+    // The records of an enclosing call are put back afterwards
+    let outer = RECORDS.with(|records| records.replace(Some(Vec::new())));
+    let result = operation();
+    let records = RECORDS.with(|records| records.replace(outer));
+
+    (result, records.unwrap_or_default())
+}
+
+/// Calls `hook` every time a config file takes longer than `threshold` to be read or written by a `load` or `store` function.
+///
+/// Useful to tell users that their config lives on a slow network share. Only the file access itself is measured,
+/// serialization is not included. Replaces the previous hook, if any.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// binconf::set_slow_io_hook(Duration::from_millis(250), |event| {
+///     eprintln!(
+///         "{:?} of {} ({} bytes) took {:?}",
+///         event.operation,
+///         event.path.display(),
+///         event.size,
+///         event.duration
+///     );
+/// });
+///
+/// binconf::clear_slow_io_hook();
+/// ```
+pub fn set_slow_io_hook(threshold: Duration, hook: impl Fn(&SlowIoEvent) + Send + Sync + 'static) {
+    *HOOK
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some((threshold, Arc::new(hook)));
+}
+
+/// Removes the hook set with [`set_slow_io_hook`].
+pub fn clear_slow_io_hook() {
+    *HOOK
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = None;
+}
+
+/// Reports an operation on a config file that started at `started`, if it was slower than the threshold of the hook.
+pub(crate) fn report(operation: IoOperation, path: &Path, size: usize, started: Instant) {
+    let duration = started.elapsed();
+
+    RECORDS.with(|records| {
+        if let Some(records) = records.borrow_mut().as_mut() {
+            records.push(IoRecord {
+                operation,
+                size: size as u64,
+                started,
+                duration,
+            });
+        }
+    });
+
+    let hook = match HOOK
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .as_ref()
+    {
+        Some((threshold, hook)) if duration > *threshold => Arc::clone(hook),
+        _ => return,
+    };
+
+    // The lock is released before calling the hook, so the hook can replace itself
+    hook(&SlowIoEvent {
+        operation,
+        path: path.to_path_buf(),
+        size: size as u64,
+        duration,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_only_calls_hook_above_threshold() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = std::sync::Mutex::new(sender);
+        // Other tests store configs concurrently, only look at the events of this test
+        set_slow_io_hook(Duration::from_millis(50), move |event| {
+            if event.path == Path::new("test-binconf-slow_io") {
+                let _ = sender
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .send(event.clone());
+            }
+        });
+
+        let path = Path::new("test-binconf-slow_io");
+        report(IoOperation::Load, path, 1, Instant::now());
+        report(
+            IoOperation::Store,
+            path,
+            2,
+            Instant::now() - Duration::from_millis(100),
+        );
+        clear_slow_io_hook();
+
+        let events: Vec<_> = receiver.try_iter().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].operation, IoOperation::Store);
+        assert_eq!(events[0].size, 2);
+        assert!(events[0].duration >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// Several independent typed sections stored in a single `toml` file, like `[window]`, `[recent]` and `[updates]`.
+///
+/// Meant for the small pieces of state an app keeps around, which would otherwise each need their own file.
+/// Sections are read with [`get`](StateFile::get) and changed in memory with [`set`](StateFile::set), then
+/// [`save`](StateFile::save) writes all the changed sections at once, with a single atomic write.
+///
+/// Saving reads the file again and only replaces the changed sections, so sections written by other instances
+/// of the app in the meantime are kept. The file is read and written under the same `<file>.lock` lock as the
+/// [`update_toml`](crate::update_toml) function, so concurrent saves never drop each other's sections.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::StateFile;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Window {
+///     width: u32,
+///     height: u32,
+/// }
+///
+/// let mut state = StateFile::open("test-binconf-state-file", Some("state.toml"), Cache).unwrap();
+///
+/// state.set("window", &Window { width: 800, height: 600 }).unwrap();
+/// state.set("recent", &vec!["notes.txt"]).unwrap();
+/// assert!(state.is_dirty());
+/// state.save().unwrap();
+///
+/// let state = StateFile::open("test-binconf-state-file", Some("state.toml"), Cache).unwrap();
+/// assert_eq!(state.get::<Window>("window").unwrap(), Window { width: 800, height: 600 });
+/// assert_eq!(state.get::<Vec<String>>("recent").unwrap(), vec!["notes.txt"]);
+/// ```
+#[derive(Debug)]
+pub struct StateFile {
+    path: PathBuf,
+    sections: toml::Table,
+    dirty: BTreeSet<String>,
+}
+
+impl StateFile {
+    /// Opens the state file, reading its sections if it exists.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config, cache or local data directory could not be found or created,
+    /// or if the file exists but could not be read or parsed.
+    pub fn open<'a>(
+        app_name: impl AsRef<str>,
+        config_name: impl Into<Option<&'a str>>,
+        location: impl AsRef<ConfigLocation>,
+    ) -> Result<Self, ConfigError> {
+        let path = crate::config_location(
+            app_name.as_ref(),
+            config_name.into(),
+            ConfigType::Toml.as_str(),
+            location.as_ref(),
+        )?;
+
+        Ok(StateFile {
+            sections: read_sections(&path)?,
+            path,
+            dirty: BTreeSet::new(),
+        })
+    }
+
+    /// Returns the section `name`, or the default value if the file has no such section.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the section could not be deserialized into `T`.
+    pub fn get<T>(&self, name: &str) -> Result<T, ConfigError>
+    where
+        T: Default + serde::de::DeserializeOwned,
+    {
+        match self.sections.get(name) {
+            Some(section) => Ok(section.clone().try_into()?),
+            None => Ok(T::default()),
+        }
+    }
+
+    /// Replaces the section `name` in memory. It is written by the next [`save`](StateFile::save), if its value changed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `value` could not be serialized.
+    pub fn set<T>(&mut self, name: &str, value: &T) -> Result<(), ConfigError>
+    where
+        T: serde::Serialize,
+    {
+        let value = toml::Value::try_from(value)?;
+        if self.sections.get(name) != Some(&value) {
+            self.sections.insert(name.to_owned(), value);
+            self.dirty.insert(name.to_owned());
+        }
+
+        Ok(())
+    }
+
+    /// Removes the section `name` in memory, returns `false` if there was no such section.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let removed = self.sections.remove(name).is_some();
+        if removed {
+            self.dirty.insert(name.to_owned());
+        }
+        removed
+    }
+
+    /// Returns `true` if some sections were changed since the file was opened or last saved.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Writes the changed sections to the file, does nothing if none changed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file could not be read, parsed or written, or a [`ConfigError::LockHeldBy`]
+    /// error if another process held the lock for more than 5 seconds. The changes are then kept for the next call.
+    pub fn save(&mut self) -> Result<(), ConfigError> {
+        if self.dirty.is_empty() {
+            return Ok(());
+        }
+
+        let lock = crate::update::lock_config(&self.path)?;
+
+        let mut sections = read_sections(&self.path)?;
+        for name in &self.dirty {
+            match self.sections.get(name) {
+                Some(section) => sections.insert(name.clone(), section.clone()),
+                None => sections.remove(name),
+            };
+        }
+
+        let text = crate::output::to_toml_string(
+            &sections,
+            &crate::output::output_options_for(&self.path),
+        )?;
+        crate::save_config_atomic(&self.path, text.as_bytes())?;
+        lock.release()?;
+
+        self.sections = sections;
+        self.dirty.clear();
+        Ok(())
+    }
+}
+
+fn read_sections(path: &std::path::Path) -> Result<toml::Table, ConfigError> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(toml::from_str(&text)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(toml::Table::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Cache;
+
+    #[test]
+    fn save_only_writes_changed_sections() {
+        let app = "test-binconf-save_only_writes_changed_sections";
+        let mut first = StateFile::open(app, None, Cache).unwrap();
+        let mut second = StateFile::open(app, None, Cache).unwrap();
+
+        first.set("window", &[800, 600]).unwrap();
+        first.set("theme", &"dark").unwrap();
+        first.save().unwrap();
+        assert!(!first.is_dirty());
+
+        // Setting the same value again does not make the file dirty
+        first.set("theme", &"dark").unwrap();
+        assert!(!first.is_dirty());
+
+        second.set("recent", &vec!["a.txt"]).unwrap();
+        second.save().unwrap();
+
+        let state = StateFile::open(app, None, Cache).unwrap();
+        assert_eq!(state.get::<Vec<u32>>("window").unwrap(), vec![800, 600]);
+        assert_eq!(state.get::<String>("theme").unwrap(), "dark");
+        assert_eq!(state.get::<Vec<String>>("recent").unwrap(), vec!["a.txt"]);
+        assert_eq!(state.get::<u32>("missing").unwrap(), 0);
+
+        first.remove("theme");
+        first.save().unwrap();
+        let state = StateFile::open(app, None, Cache).unwrap();
+        assert!(state.get::<String>("theme").unwrap().is_empty());
+        assert_eq!(state.get::<Vec<String>>("recent").unwrap(), vec!["a.txt"]);
+    }
+
+    #[test]
+    fn concurrent_saves_keep_every_section() {
+        let app = "test-binconf-concurrent_saves_keep_every_section";
+        let path = crate::get_configuration_path(app, None, ConfigType::Toml, Cache).unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    let mut state = StateFile::open(app, None, Cache).unwrap();
+                    state.set(&format!("section-{i}"), &i).unwrap();
+                    state.save().unwrap();
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let state = StateFile::open(app, None, Cache).unwrap();
+        for i in 0..8 {
+            assert_eq!(state.get::<u32>(&format!("section-{i}")).unwrap(), i);
+        }
+    }
+}
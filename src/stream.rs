@@ -0,0 +1,141 @@
+use crate::{ConfigError, ConfigType};
+use std::io::Read;
+
+/// Loads a config from `reader` instead of a config file, like a pipe or a socket. In any format.
+///
+/// Meant for helper processes receiving their config from a parent process, without touching the disk. The whole
+/// input is read and parsed like the `load` function of `config_type` parses a file: binary configs must match their
+/// hash, and an empty input holds no config, the default config is returned. Nothing is ever written.
+///
+/// # Errors
+///
+/// This function will return an error if `reader` could not be read or if something went wrong while deserializing the config.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+///
+/// binconf::store_bin("test-binconf-load-from-reader", None, Config, vec![1u8, 2, 3]).unwrap();
+/// let path = binconf::get_configuration_path("test-binconf-load-from-reader", None, ConfigType::Bin, Config).unwrap();
+///
+/// let reader = std::fs::File::open(path).unwrap();
+/// let config = binconf::load_from_reader::<Vec<u8>>(reader, ConfigType::Bin).unwrap();
+/// assert_eq!(config, vec![1, 2, 3]);
+/// ```
+pub fn load_from_reader<T>(
+    mut reader: impl Read,
+    config_type: impl AsRef<ConfigType>,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::de::DeserializeOwned,
+{
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    crate::deserialize_typed(&data, config_type.as_ref())
+}
+
+/// Loads a config from the standard input of the process. In any format. See [`load_from_reader`].
+///
+/// # Errors
+///
+/// This function will return an error if the standard input could not be read or if something went wrong while deserializing the config.
+///
+/// # Example
+///
+/// ```no_run
+/// use binconf::ConfigType;
+///
+/// // parent | my-helper
+/// let config = binconf::load_from_stdin::<Vec<String>>(ConfigType::Bin).unwrap();
+/// ```
+pub fn load_from_stdin<T>(config_type: impl AsRef<ConfigType>) -> Result<T, ConfigError>
+where
+    T: Default + serde::de::DeserializeOwned,
+{
+    load_from_reader(std::io::stdin().lock(), config_type)
+}
+
+/// Loads a config from a file descriptor inherited from the parent process, which is closed afterwards. In any format.
+/// See [`load_from_reader`].
+///
+/// # Errors
+///
+/// This function will return an error if the file descriptor could not be read or if something went wrong while deserializing the config.
+///
+/// # Example
+///
+/// ```no_run
+/// use binconf::ConfigType;
+/// use std::os::fd::{FromRawFd, OwnedFd};
+///
+/// // The parent process passed the config on file descriptor 3
+/// // SAFETY: file descriptor 3 is open and owned by nothing else in this process.
+/// let fd = unsafe { OwnedFd::from_raw_fd(3) };
+/// let config = binconf::load_from_fd::<Vec<String>>(fd, ConfigType::Bin).unwrap();
+/// ```
+#[cfg(unix)]
+pub fn load_from_fd<T>(
+    fd: std::os::fd::OwnedFd,
+    config_type: impl AsRef<ConfigType>,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::de::DeserializeOwned,
+{
+    load_from_reader(std::fs::File::from(fd), config_type)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    #[derive(Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Job {
+        id: u32,
+        args: Vec<String>,
+    }
+
+    #[test]
+    fn configs_are_loaded_from_streams() {
+        let job = Job {
+            id: 7,
+            args: vec![String::from("--fast")],
+        };
+
+        let bin = crate::binary_conf::prepare_serialized_data(&job).unwrap();
+        assert_eq!(
+            load_from_reader::<Job>(&bin[..], ConfigType::Bin).unwrap(),
+            job
+        );
+        let toml = "id = 7\nargs = [\"--fast\"]\n";
+        assert_eq!(
+            load_from_reader::<Job>(toml.as_bytes(), ConfigType::Toml).unwrap(),
+            job
+        );
+        assert_eq!(
+            load_from_reader::<Job>(&b""[..], ConfigType::Yaml).unwrap(),
+            Job::default()
+        );
+
+        let mut tampered = bin;
+        if let Some(last) = tampered.last_mut() {
+            *last ^= 1;
+        }
+        assert!(matches!(
+            load_from_reader::<Job>(&tampered[..], ConfigType::Bin),
+            Err(ConfigError::HashMismatch)
+        ));
+
+        #[cfg(unix)]
+        {
+            let path =
+                std::env::temp_dir().join("test-binconf-configs_are_loaded_from_streams.ron");
+            std::fs::write(&path, "(id: 7, args: [\"--fast\"])").unwrap();
+            let fd = std::os::fd::OwnedFd::from(std::fs::File::open(&path).unwrap());
+            assert_eq!(load_from_fd::<Job>(fd, ConfigType::Ron).unwrap(), job);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::io::{BufReader, Read};
+
+/// Length of the `u64` summary length field that follows the hash.
+const SUMMARY_LENGTH_BYTES: usize = 8;
+
+/// Stores a config file with a summary section in the config, cache, cwd, or local data directory of the current user. In `binary` format.
+///
+/// The summary is a small, user defined value (record count, version, a few selected fields, ...) stored in front of the data,
+/// so it can be read back with [`load_bin_summary`] without deserializing the full payload. Useful for listing many saved sessions quickly.
+///
+/// The file layout is the header and the `xxh3_128` hash of every binary file, followed by the summary length as a little endian `u64`, the serialized summary and the serialized data.
+/// The hash covers everything after it. Files stored with this function must be loaded with [`load_bin_with_summary`], not [`crate::load_bin`].
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+///
+/// A [`ConfigError::Frozen`] error is returned if the app was frozen with [`crate::freeze`]. If the free space guard is enabled with
+/// [`crate::set_free_space_guard`] and there is not enough space, [`ConfigError::InsufficientSpace`] is returned before the file is touched.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct SessionSummary {
+///    title: String,
+///    record_count: usize,
+/// }
+///
+/// let records: Vec<u64> = (0..10_000).collect();
+/// let summary = SessionSummary { title: String::from("session"), record_count: records.len() };
+///
+/// binconf::store_bin_with_summary("test-binconf-store-summary-bin", None, Config, &summary, &records).unwrap();
+///
+/// let stored = binconf::load_bin_summary::<SessionSummary>("test-binconf-store-summary-bin", None, Config).unwrap();
+/// assert_eq!(stored, summary);
+/// ```
+pub fn store_bin_with_summary<'a, S, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    summary: S,
+    data: T,
+) -> Result<(), ConfigError>
+where
+    S: serde::Serialize,
+    T: serde::Serialize,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Bin.as_str(),
+        location.as_ref(),
+    )?;
+
+    let summary = bincode::serialize(&summary)?;
+    let sections = (summary.len() as u64).to_le_bytes();
+    let full_data = crate::binary_conf::prepare_framed_data(&[
+        &sections,
+        &summary,
+        &bincode::serialize(&data)?,
+    ]);
+
+    crate::binary_conf::write_bin_file(&config_file_path, &full_data)
+}
+
+/// Loads only the summary section of a config file stored with [`store_bin_with_summary`]. In `binary` format.
+///
+/// Only the beginning of the file is read, the data section is never touched.
+///
+/// **The summary is not verified against the hash**, as that would require reading the whole file. Use [`load_bin_with_summary`]
+/// when the integrity of the data matters.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if the file does not exist,
+/// or if something went wrong while deserializing the summary.
+pub fn load_bin_summary<'a, S>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<S, ConfigError>
+where
+    S: serde::de::DeserializeOwned,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Bin.as_str(),
+        location.as_ref(),
+    )?;
+
+    let mut reader = BufReader::new(std::fs::File::open(config_file_path)?);
+
+    let (_, flags) = crate::binary_conf::read_hash(&mut reader)?;
+    crate::binary_conf::skip_metadata(flags, &mut reader)?;
+    let mut length_bytes = [0; SUMMARY_LENGTH_BYTES];
+    reader
+        .read_exact(&mut length_bytes)
+        .map_err(|_| ConfigError::CorruptedHashSector)?;
+
+    let summary_length = read_summary_length(&length_bytes)?;
+
+    let mut summary = Vec::new();
+    reader.take(summary_length).read_to_end(&mut summary)?;
+
+    Ok(bincode::deserialize(&summary)?)
+}
+
+/// Loads the summary and the data of a config file stored with [`store_bin_with_summary`]. In `binary` format.
+///
+/// The whole file is verified against its `xxh3_128` hash.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if the file does not exist,
+/// or if something went wrong while deserializing the config.
+///
+/// If the data is corrupted a [`ConfigError::HashMismatch`] or [`ConfigError::CorruptedHashSector`] error is returned.
+pub fn load_bin_with_summary<'a, S, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+) -> Result<(S, T), ConfigError>
+where
+    S: serde::de::DeserializeOwned,
+    T: serde::de::DeserializeOwned,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Bin.as_str(),
+        location.as_ref(),
+    )?;
+
+    let data = std::fs::read(config_file_path)?;
+    let sections = crate::binary_conf::verified_payload(&data)?;
+
+    if sections.len() < SUMMARY_LENGTH_BYTES {
+        return Err(ConfigError::CorruptedHashSector);
+    }
+    let (length_bytes, sections) = sections.split_at(SUMMARY_LENGTH_BYTES);
+    let summary_length = usize::try_from(read_summary_length(length_bytes)?)
+        .ok()
+        .filter(|length| *length <= sections.len())
+        .ok_or(ConfigError::CorruptedHashSector)?;
+
+    let (summary, data) = sections.split_at(summary_length);
+
+    Ok((bincode::deserialize(summary)?, bincode::deserialize(data)?))
+}
+
+fn read_summary_length(bytes: &[u8]) -> Result<u64, ConfigError> {
+    let bytes: [u8; SUMMARY_LENGTH_BYTES] = bytes
+        .try_into()
+        .map_err(|_| ConfigError::CorruptedHashSector)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::{Deserialize, Serialize};
+    use ConfigLocation::Config;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Summary {
+        version: u32,
+        count: usize,
+    }
+
+    #[test]
+    fn summary_round_trip() {
+        let data: Vec<String> = (0..50).map(|i| format!("entry-{i}")).collect();
+        let summary = Summary {
+            version: 3,
+            count: data.len(),
+        };
+
+        store_bin_with_summary(
+            "test-binconf-summary_round_trip-bin",
+            None,
+            Config,
+            &summary,
+            &data,
+        )
+        .unwrap();
+
+        let stored: Summary =
+            load_bin_summary("test-binconf-summary_round_trip-bin", None, Config).unwrap();
+        assert_eq!(stored, summary);
+
+        let (stored_summary, stored_data): (Summary, Vec<String>) =
+            load_bin_with_summary("test-binconf-summary_round_trip-bin", None, Config).unwrap();
+        assert_eq!(stored_summary, summary);
+        assert_eq!(stored_data, data);
+    }
+}
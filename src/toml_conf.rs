@@ -1,5 +1,5 @@
+use crate::progress::read_to_string;
 use crate::{ConfigError, ConfigLocation, ConfigType};
-use std::{fs::read_to_string, io::Write};
 
 /// Loads a config file from the config, cache, cwd, or local data directory of the current user. In `toml` format.
 ///
@@ -8,11 +8,14 @@ use std::{fs::read_to_string, io::Write};
 /// If the flag `reset_conf_on_err` is set to `true`, the config file will be reset to the default config if
 /// the deserialization fails, if set to `false` an error will be returned.
 ///
+/// An empty file, as left by `touch` or an interrupted write, is not an error: the default config is returned,
+/// and written to the file if `reset_conf_on_err` is `true`.
+///
 /// # Errors
 ///
 /// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
 ///
-/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config.
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
 ///
 /// # Example
 ///
@@ -38,35 +41,137 @@ pub fn load_toml<'a, T>(
 where
     T: Default + serde::Serialize + serde::de::DeserializeOwned,
 {
-    let config_file_path = crate::config_location(
+    load_toml_internal(
         app_name.as_ref(),
         config_name.into(),
-        ConfigType::Toml.as_str(),
         location.as_ref(),
-    )?;
+        reset_conf_on_err,
+        &T::default,
+    )
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user, **falling back to a given config**. In `toml` format.
+///
+/// Works like [`load_toml`], but `fallback` takes the place of the `Default` implementation: it is cloned when the file does not exist
+/// or is reset, and left alone when the file is loaded. Meant for configs with big defaults, like lookup tables, that should not be built
+/// on every load just to be thrown away, and for types that can't implement `Default`.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the fallback config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Lookup {
+///    table: Vec<u32>,
+/// }
+///
+/// let fallback = Lookup { table: (0..1024).collect() };
+///
+/// let config = binconf::load_toml_or("test-binconf-read-toml-or", None, Config, false, &fallback).unwrap();
+/// assert_eq!(config, fallback);
+/// ```
+pub fn load_toml_or<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+    fallback: &T,
+) -> Result<T, ConfigError>
+where
+    T: Clone + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_toml_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        reset_conf_on_err,
+        &|| fallback.clone(),
+    )
+}
+
+fn load_toml_internal<T>(
+    app_name: &str,
+    config_name: Option<&str>,
+    location: &ConfigLocation,
+    reset_conf_on_err: bool,
+    default: &dyn Fn() -> T,
+) -> Result<T, ConfigError>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let config_file_path =
+        crate::config_location(app_name, config_name, ConfigType::Toml.as_str(), location)?;
 
     let save_default_conf = || {
-        let default_config = T::default();
-        let toml_str = toml::to_string_pretty(&default_config)?;
-        crate::save_config_str(&config_file_path, &toml_str)?;
+        let default_config = default();
+        let toml_str = crate::output::to_toml_string(
+            &default_config,
+            &crate::output::output_options_for(&config_file_path),
+        )?;
+        if !crate::freeze::is_frozen_path(&config_file_path) {
+            crate::reset::back_up_corrupt(&config_file_path);
+            crate::save_config_str(&config_file_path, &toml_str)?;
+        }
         Ok(default_config)
     };
 
     if !config_file_path.try_exists()? {
-        return save_default_conf();
+        let default_config = default();
+        let toml_str = crate::output::to_toml_string(
+            &default_config,
+            &crate::output::output_options_for(&config_file_path),
+        )?;
+        if crate::freeze::is_frozen_path(&config_file_path)
+            || crate::save_config_new(&config_file_path, toml_str.as_bytes())?
+        {
+            return Ok(default_config);
+        }
+        // Created by another process in the meantime, it is loaded below
     }
 
+    let started = std::time::Instant::now();
     let toml_str = read_to_string(&config_file_path)?;
+    crate::slow_io::report(
+        crate::IoOperation::Load,
+        &config_file_path,
+        toml_str.len(),
+        started,
+    );
+    // An empty file is left by `touch` or an interrupted write, it holds no config rather than a damaged one
+    if toml_str.is_empty() {
+        return if reset_conf_on_err {
+            save_default_conf()
+        } else {
+            Ok(default())
+        };
+    }
+    #[cfg(feature = "binary-conf")]
+    if let Some(config) = crate::parse_cache::lookup(app_name, &config_file_path, &toml_str) {
+        return Ok(config);
+    }
     let config = match toml::from_str::<T>(&toml_str) {
         Ok(config) => config,
         Err(err) => {
+            let err = crate::duplicates::toml_error(err, &toml_str);
             if reset_conf_on_err {
+                crate::reset::report(&config_file_path, &toml_str, err);
                 return save_default_conf();
             }
-            return Err(err.into());
+            return Err(err);
         }
     };
 
+    #[cfg(feature = "binary-conf")]
+    crate::parse_cache::store(app_name, &config_file_path, &toml_str, &config);
+
     Ok(config)
 }
 
@@ -116,11 +221,72 @@ where
         location.as_ref(),
     )?;
 
-    let mut file = std::io::BufWriter::new(std::fs::File::create(config_file_path)?);
+    let toml_str = crate::output::to_toml_string(
+        &data,
+        &crate::output::output_options_for(&config_file_path),
+    )?;
+    crate::save_config_str(&config_file_path, &toml_str)?;
+    #[cfg(feature = "json-conf")]
+    crate::changelog::record(&config_file_path, &data)?;
 
-    let toml_str = toml::to_string_pretty(&data)?;
+    Ok(())
+}
 
-    file.write_all(toml_str.as_bytes())?;
+/// Stores a config file like [`store_toml`], failing if the file already exists. In `toml` format.
+///
+/// Meant for commands like `myapp init` that generate a config and must never overwrite the one of the user.
+/// When several processes store the same config at once, exactly one of them creates the file.
+///
+/// # Errors
+///
+/// This function will return a [`ConfigError::AlreadyExists`] error if the config file already exists. Other errors are returned if the config,
+/// cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigError, ConfigType};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///   test: String,
+/// }
+///
+/// let path = binconf::get_configuration_path("test-binconf-store-toml-new", None, ConfigType::Toml, Config).unwrap();
+/// # let _ = std::fs::remove_file(&path);
+///
+/// binconf::store_toml_new("test-binconf-store-toml-new", None, Config, TestConfig::default()).unwrap();
+///
+/// let result = binconf::store_toml_new("test-binconf-store-toml-new", None, Config, TestConfig::default());
+/// assert!(matches!(result, Err(ConfigError::AlreadyExists(_))));
+/// ```
+pub fn store_toml_new<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Toml.as_str(),
+        location.as_ref(),
+    )?;
+
+    let toml_str = crate::output::to_toml_string(
+        &data,
+        &crate::output::output_options_for(&config_file_path),
+    )?;
+    if !crate::save_config_new(&config_file_path, toml_str.as_bytes())? {
+        return Err(ConfigError::AlreadyExists(config_file_path));
+    }
+    #[cfg(feature = "json-conf")]
+    crate::changelog::record(&config_file_path, &data)?;
 
     Ok(())
 }
@@ -330,4 +496,28 @@ mod tests {
             load_toml("test-binconf-save_config_user_cwd-toml", None, Cwd, false).unwrap();
         assert_eq!(config, data);
     }
+
+    #[test]
+    fn rejects_duplicate_keys_toml() {
+        let path = crate::get_configuration_path(
+            "test-binconf-rejects_duplicate_keys-toml",
+            None,
+            ConfigType::Toml,
+            Config,
+        )
+        .unwrap();
+        std::fs::write(path, "[ui]\ntheme = 1\nsize = 2\ntheme = 3\n").unwrap();
+
+        let config =
+            load_toml::<std::collections::HashMap<String, std::collections::HashMap<String, u8>>>(
+                "test-binconf-rejects_duplicate_keys-toml",
+                None,
+                Config,
+                false,
+            );
+        assert!(matches!(
+            config,
+            Err(ConfigError::DuplicateKey { path, line: 4 }) if path == "ui.theme"
+        ));
+    }
 }
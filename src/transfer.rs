@@ -0,0 +1,162 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::Path;
+
+/// Checks that the raw bytes of a config file are a valid document of the given type.
+///
+/// Binary files must match their `xxh3_128` hash, text files must parse. The typed structure is not checked,
+/// as the files are copied without knowing it.
+pub(crate) fn validate_raw(data: &[u8], config_type: &ConfigType) -> Result<(), ConfigError> {
+    #[cfg(any(feature = "toml-conf", feature = "ron-conf"))]
+    let text = || {
+        std::str::from_utf8(data)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    };
+
+    match config_type {
+        #[cfg(feature = "toml-conf")]
+        ConfigType::Toml => {
+            toml::from_str::<toml::Table>(text()?)?;
+        }
+
+        #[cfg(feature = "json-conf")]
+        ConfigType::Json => {
+            serde_json::from_slice::<serde_json::Value>(data)?;
+        }
+
+        #[cfg(feature = "yaml-conf")]
+        ConfigType::Yaml => {
+            serde_yaml::from_slice::<serde_yaml::Value>(data)?;
+        }
+
+        #[cfg(feature = "ron-conf")]
+        ConfigType::Ron => {
+            ron::from_str::<ron::Value>(text()?)?;
+        }
+
+        #[cfg(feature = "binary-conf")]
+        ConfigType::Bin => {
+            crate::binary_conf::verified_payload(data)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies a config file out of the config, cache, cwd, or local data directory of the current user to `destination`.
+///
+/// The file is verified before being copied: binary files must match their `xxh3_128` hash and text files must be valid documents
+/// of their format. Useful to build support bundles or to transfer settings between machines.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if the config file does not exist,
+/// or if it could not be written to `destination`.
+///
+/// If the config file is corrupted or is not a valid document of the given type, the error from the verification is returned and nothing is copied.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+///
+/// binconf::store_bin("test-binconf-export-raw", None, Config, &String::from("exported")).unwrap();
+///
+/// let destination = std::env::temp_dir().join("test-binconf-export-raw.bin");
+/// binconf::export_raw("test-binconf-export-raw", None, ConfigType::Bin, Config, &destination).unwrap();
+///
+/// binconf::import_raw("test-binconf-import-raw", None, ConfigType::Bin, Config, &destination).unwrap();
+/// let imported = binconf::load_bin::<String>("test-binconf-import-raw", None, Config, false).unwrap();
+/// assert_eq!(imported, "exported");
+/// ```
+pub fn export_raw<'a>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: impl AsRef<ConfigType>,
+    location: impl AsRef<ConfigLocation>,
+    destination: impl AsRef<Path>,
+) -> Result<(), ConfigError> {
+    let config_type = config_type.as_ref();
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        config_type.as_str(),
+        location.as_ref(),
+    )?;
+
+    let data = crate::progress::read(config_file_path)?;
+    validate_raw(&data, config_type)?;
+
+    crate::save_config_atomic(destination.as_ref(), &data)
+}
+
+/// Copies a config file from `source` into the config, cache, cwd, or local data directory of the current user.
+///
+/// The file is verified before being copied: binary files must match their `xxh3_128` hash and text files must be valid documents
+/// of their format, so foreign or corrupted files are refused. The existing config file is replaced atomically.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if `source` could not be read,
+/// or if the config file could not be written.
+///
+/// If `source` is corrupted or is not a valid document of the given type, the error from the verification is returned and the existing config is left untouched.
+pub fn import_raw<'a>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: impl AsRef<ConfigType>,
+    location: impl AsRef<ConfigLocation>,
+    source: impl AsRef<Path>,
+) -> Result<(), ConfigError> {
+    let config_type = config_type.as_ref();
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        config_type.as_str(),
+        location.as_ref(),
+    )?;
+
+    let data = crate::progress::read(source)?;
+    validate_raw(&data, config_type)?;
+
+    crate::save_config_atomic(&config_file_path, &data)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[test]
+    fn import_raw_refuses_corrupted_files() {
+        let source = std::env::temp_dir().join("test-binconf-import_raw_refuses.bin");
+        std::fs::write(&source, b"definitely not a binconf file").unwrap();
+
+        crate::store_bin("test-binconf-import_raw_refuses-bin", None, Config, 42u32).unwrap();
+
+        let result = import_raw(
+            "test-binconf-import_raw_refuses-bin",
+            None,
+            ConfigType::Bin,
+            Config,
+            &source,
+        );
+        assert!(matches!(result, Err(ConfigError::HashMismatch)));
+
+        let config: u32 =
+            crate::load_bin("test-binconf-import_raw_refuses-bin", None, Config, false).unwrap();
+        assert_eq!(config, 42);
+
+        std::fs::write(&source, b"{ not json").unwrap();
+        let result = import_raw(
+            "test-binconf-import_raw_refuses-json",
+            None,
+            ConfigType::Json,
+            Config,
+            &source,
+        );
+        assert!(matches!(result, Err(ConfigError::Json(_))));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::path::{Path, PathBuf};
+
+/// Extension appended to the file name of a config to get the file holding its undo and redo stacks.
+const UNDO_EXTENSION: &str = "undo";
+
+/// The undo file: the stacks, and the config they were saved for, to detect changes made by someone else.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct UndoState {
+    current: serde_json::Value,
+    undo: Vec<serde_json::Value>,
+    redo: Vec<serde_json::Value>,
+}
+
+/// A config with undo and redo, for settings dialogs.
+///
+/// Every [`apply`](UndoableConfig::apply), [`undo`](UndoableConfig::undo) and [`redo`](UndoableConfig::redo) stores the
+/// config with the `store` function of its [`ConfigType`], so it is recorded by the change log when it is enabled with
+/// [`set_change_log`](crate::set_change_log). The stacks are kept in a `<config file>.undo` file next to the config,
+/// so the history survives restarts. It is dropped if the config was changed by anything else in the meantime.
+///
+/// At most `max_history` previous configs are kept, the oldest ones are dropped first.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigType, UndoableConfig};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Settings {
+///     theme: String,
+/// }
+///
+/// let mut settings = UndoableConfig::<Settings>::load("test-binconf-undoable", None, ConfigType::Json, Config, 10).unwrap();
+///
+/// settings.apply(Settings::default()).unwrap();
+/// settings.apply(Settings { theme: String::from("dark") }).unwrap();
+/// assert_eq!(settings.get().theme, "dark");
+///
+/// assert!(settings.undo().unwrap());
+/// assert_eq!(settings.get(), &Settings::default());
+///
+/// assert!(settings.redo().unwrap());
+/// assert_eq!(settings.get().theme, "dark");
+/// ```
+#[derive(Debug)]
+pub struct UndoableConfig<T> {
+    app_name: String,
+    config_name: Option<String>,
+    config_type: ConfigType,
+    location: ConfigLocation,
+    max_history: usize,
+    current: T,
+    undo: Vec<T>,
+    redo: Vec<T>,
+}
+
+impl<T> UndoableConfig<T>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    /// Loads the config and its undo and redo stacks.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config, cache or local data directory could not be found or created,
+    /// or if the config could not be loaded. An unreadable undo file only means the history is lost.
+    pub fn load<'a>(
+        app_name: impl AsRef<str>,
+        config_name: impl Into<Option<&'a str>>,
+        config_type: ConfigType,
+        location: impl AsRef<ConfigLocation>,
+        max_history: usize,
+    ) -> Result<Self, ConfigError> {
+        let app_name = app_name.as_ref();
+        let config_name = config_name.into();
+        let location = location.as_ref();
+
+        let current: T = crate::load_typed(app_name, config_name, &config_type, location, false)?;
+
+        let config_file_path =
+            crate::config_location(app_name, config_name, config_type.as_str(), location)?;
+        let (undo, redo) = read_stacks(&undo_path(&config_file_path), &current).unwrap_or_default();
+
+        Ok(UndoableConfig {
+            app_name: app_name.to_owned(),
+            config_name: config_name.map(str::to_owned),
+            config_type,
+            location: location.clone(),
+            max_history,
+            current,
+            undo,
+            redo,
+        })
+    }
+
+    /// Returns the current config.
+    pub fn get(&self) -> &T {
+        &self.current
+    }
+
+    /// Stores `config`, making the current one undoable. Clears the redo stack.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config could not be stored, the history is then left untouched.
+    pub fn apply(&mut self, config: T) -> Result<(), ConfigError> {
+        self.store(&config)?;
+
+        let previous = std::mem::replace(&mut self.current, config);
+        self.undo.push(previous);
+        let excess = self.undo.len().saturating_sub(self.max_history);
+        self.undo.drain(..excess);
+        self.redo.clear();
+
+        self.save_stacks()
+    }
+
+    /// Stores the previous config back. Returns `false` if there is nothing to undo.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config could not be stored, the history is then left untouched.
+    pub fn undo(&mut self) -> Result<bool, ConfigError> {
+        let Some(previous) = self.undo.last() else {
+            return Ok(false);
+        };
+        self.store(previous)?;
+
+        if let Some(previous) = self.undo.pop() {
+            let undone = std::mem::replace(&mut self.current, previous);
+            self.redo.push(undone);
+        }
+
+        self.save_stacks()?;
+        Ok(true)
+    }
+
+    /// Stores the last undone config again. Returns `false` if there is nothing to redo.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config could not be stored, the history is then left untouched.
+    pub fn redo(&mut self) -> Result<bool, ConfigError> {
+        let Some(next) = self.redo.last() else {
+            return Ok(false);
+        };
+        self.store(next)?;
+
+        if let Some(next) = self.redo.pop() {
+            let redone = std::mem::replace(&mut self.current, next);
+            self.undo.push(redone);
+        }
+
+        self.save_stacks()?;
+        Ok(true)
+    }
+
+    /// Returns `true` if [`undo`](UndoableConfig::undo) would change the config.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns `true` if [`redo`](UndoableConfig::redo) would change the config.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    fn store(&self, config: &T) -> Result<(), ConfigError> {
+        crate::store_typed(
+            &self.app_name,
+            self.config_name.as_deref(),
+            &self.config_type,
+            &self.location,
+            config,
+        )
+    }
+
+    fn save_stacks(&self) -> Result<(), ConfigError> {
+        let to_values = |configs: &[T]| {
+            configs
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let state = UndoState {
+            current: serde_json::to_value(&self.current)?,
+            undo: to_values(&self.undo)?,
+            redo: to_values(&self.redo)?,
+        };
+
+        let config_file_path = crate::config_location(
+            &self.app_name,
+            self.config_name.as_deref(),
+            self.config_type.as_str(),
+            &self.location,
+        )?;
+
+        crate::save_config_atomic(&undo_path(&config_file_path), &serde_json::to_vec(&state)?)
+    }
+}
+
+/// Reads the undo and redo stacks saved for `current`, `None` if there are none or they were saved for another config.
+fn read_stacks<T>(path: &Path, current: &T) -> Option<(Vec<T>, Vec<T>)>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let state: UndoState = serde_json::from_slice(&std::fs::read(path).ok()?).ok()?;
+    if state.current != serde_json::to_value(current).ok()? {
+        return None;
+    }
+
+    let from_values = |values: Vec<serde_json::Value>| {
+        values
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<Vec<T>, _>>()
+            .ok()
+    };
+
+    Some((from_values(state.undo)?, from_values(state.redo)?))
+}
+
+fn undo_path(config_file_path: &Path) -> PathBuf {
+    let mut file_name = config_file_path
+        .file_name()
+        .map(std::ffi::OsStr::to_os_string)
+        .unwrap_or_default();
+    file_name.push(".");
+    file_name.push(UNDO_EXTENSION);
+    config_file_path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[test]
+    fn history_is_bounded_and_persisted() {
+        let app = "test-binconf-history_is_bounded_and_persisted";
+        let load = || UndoableConfig::<u32>::load(app, None, ConfigType::Bin, Config, 2).unwrap();
+
+        let mut config = load();
+        assert!(!config.can_undo());
+        for value in 1..=3 {
+            config.apply(value).unwrap();
+        }
+
+        // Only the 2 previous configs are kept, 0 was dropped
+        let mut config = load();
+        assert_eq!(*config.get(), 3);
+        assert!(config.undo().unwrap());
+        assert!(config.undo().unwrap());
+        assert!(!config.undo().unwrap());
+        assert_eq!(*config.get(), 1);
+        assert_eq!(crate::load_bin::<u32>(app, None, Config, false).unwrap(), 1);
+
+        // Applying drops the redo stack
+        assert!(config.can_redo());
+        config.apply(10).unwrap();
+        assert!(!config.can_redo());
+
+        // A config changed by someone else makes the history meaningless
+        crate::store_bin(app, None, Config, 20u32).unwrap();
+        let config = load();
+        assert_eq!(*config.get(), 20);
+        assert!(!config.can_undo());
+    }
+}
@@ -0,0 +1,370 @@
+use crate::{ConfigError, ConfigLocation, ConfigType, StaleLockPolicy};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long the `update` functions wait for another process to finish its update of the same config.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Changes a config with `update` and returns the stored config. In `binary` format.
+///
+/// The config is loaded, passed to `update` and stored atomically while holding the lock file `<file>.lock`, so an update
+/// made by another process between the load and the store is never lost, which a `load` followed by a `store` can't
+/// guarantee. A missing config starts from the default config. A lock left behind by a crashed process is broken.
+///
+/// A config that can't be deserialized is never reset, its error is returned and the file is left untouched.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if the
+/// config could not be loaded or stored, or a [`ConfigError::LockHeldBy`] error if another process held the lock for more
+/// than 5 seconds.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// let launches = binconf::update_bin("test-binconf-update-bin", None, Config, |launches: &mut u32| {
+///     *launches += 1;
+/// })
+/// .unwrap();
+///
+/// assert_eq!(binconf::load_bin::<u32>("test-binconf-update-bin", None, Config, false).unwrap(), launches);
+/// ```
+#[cfg(feature = "binary-conf")]
+pub fn update_bin<'a, T, F>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    update: F,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    F: FnOnce(&mut T),
+{
+    update_typed(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Bin,
+        location.as_ref(),
+        update,
+    )
+}
+
+/// Changes a config with `update` and returns the stored config, under a lock. In `toml` format.
+///
+/// See [`update_bin`](crate::update_bin) for how concurrent updates are handled.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if the
+/// config could not be loaded or stored, or a [`ConfigError::LockHeldBy`] error if another process held the lock for more
+/// than 5 seconds.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Recent {
+///     files: Vec<String>,
+/// }
+///
+/// let recent = binconf::update_toml("test-binconf-update-toml", None, Config, |recent: &mut Recent| {
+///     recent.files.push(String::from("notes.txt"));
+/// })
+/// .unwrap();
+///
+/// assert_eq!(binconf::load_toml::<Recent>("test-binconf-update-toml", None, Config, false).unwrap(), recent);
+/// ```
+#[cfg(feature = "toml-conf")]
+pub fn update_toml<'a, T, F>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    update: F,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    F: FnOnce(&mut T),
+{
+    update_typed(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Toml,
+        location.as_ref(),
+        update,
+    )
+}
+
+/// Changes a config with `update` and returns the stored config, under a lock. In `json` format.
+///
+/// See [`update_bin`](crate::update_bin) for how concurrent updates are handled.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if the
+/// config could not be loaded or stored, or a [`ConfigError::LockHeldBy`] error if another process held the lock for more
+/// than 5 seconds.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// let volume = binconf::update_json("test-binconf-update-json", None, Config, |volume: &mut u8| {
+///     *volume = 80;
+/// })
+/// .unwrap();
+///
+/// assert_eq!(volume, 80);
+/// ```
+#[cfg(feature = "json-conf")]
+pub fn update_json<'a, T, F>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    update: F,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    F: FnOnce(&mut T),
+{
+    update_typed(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Json,
+        location.as_ref(),
+        update,
+    )
+}
+
+/// Changes a config with `update` and returns the stored config, under a lock. In `yaml` format.
+///
+/// See [`update_bin`](crate::update_bin) for how concurrent updates are handled.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if the
+/// config could not be loaded or stored, or a [`ConfigError::LockHeldBy`] error if another process held the lock for more
+/// than 5 seconds.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// let theme = binconf::update_yaml("test-binconf-update-yaml", None, Config, |theme: &mut String| {
+///     *theme = String::from("dark");
+/// })
+/// .unwrap();
+///
+/// assert_eq!(theme, "dark");
+/// ```
+#[cfg(feature = "yaml-conf")]
+pub fn update_yaml<'a, T, F>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    update: F,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    F: FnOnce(&mut T),
+{
+    update_typed(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Yaml,
+        location.as_ref(),
+        update,
+    )
+}
+
+/// Changes a config with `update` and returns the stored config, under a lock. In `ron` format.
+///
+/// See [`update_bin`](crate::update_bin) for how concurrent updates are handled.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, if the
+/// config could not be loaded or stored, or a [`ConfigError::LockHeldBy`] error if another process held the lock for more
+/// than 5 seconds.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+///
+/// let zoom = binconf::update_ron("test-binconf-update-ron", None, Config, |zoom: &mut f32| {
+///     *zoom = 1.5;
+/// })
+/// .unwrap();
+///
+/// assert_eq!(zoom, 1.5);
+/// ```
+#[cfg(feature = "ron-conf")]
+pub fn update_ron<'a, T, F>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    update: F,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    F: FnOnce(&mut T),
+{
+    update_typed(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Ron,
+        location.as_ref(),
+        update,
+    )
+}
+
+/// Loads, updates and stores a config in any format while holding its lock file.
+fn update_typed<T, F>(
+    app_name: &str,
+    config_name: Option<&str>,
+    config_type: ConfigType,
+    location: &ConfigLocation,
+    update: F,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    F: FnOnce(&mut T),
+{
+    // Checked before the lock file is created in the directory of the app
+    if crate::freeze::is_frozen(app_name) {
+        return Err(ConfigError::Frozen);
+    }
+
+    let config_file_path =
+        crate::config_location(app_name, config_name, config_type.as_str(), location)?;
+    crate::project::ensure_inside_project(&config_file_path)?;
+    let lock = lock_config(&config_file_path)?;
+
+    let mut config = crate::load_typed(app_name, config_name, &config_type, location, false)?;
+    update(&mut config);
+
+    // Renamed over the config, readers that don't take the lock never see a partially written file
+    let config_bytes = crate::serialize_typed(&config, &config_type, &config_file_path)?;
+    crate::save_config_atomic(&config_file_path, &config_bytes)?;
+    #[cfg(feature = "json-conf")]
+    crate::changelog::record(&config_file_path, &config)?;
+
+    lock.release()?;
+    Ok(config)
+}
+
+/// Takes the `<file>.lock` lock of a config, held by the `update` functions around their load and store.
+pub(crate) fn lock_config(
+    config_file_path: &std::path::Path,
+) -> Result<crate::lock::SessionLock, ConfigError> {
+    crate::lock::lock_file_waiting(
+        lock_path(config_file_path),
+        &StaleLockPolicy::default(),
+        LOCK_TIMEOUT,
+    )
+}
+
+fn lock_path(config_file_path: &std::path::Path) -> PathBuf {
+    let mut file_name = config_file_path
+        .file_name()
+        .map(std::ffi::OsStr::to_os_string)
+        .unwrap_or_default();
+    file_name.push(".lock");
+    config_file_path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Cache;
+
+    #[test]
+    fn concurrent_typed_updates_are_not_lost() {
+        let app = "test-binconf-concurrent_typed_updates_are_not_lost";
+        crate::store_json(app, None, Cache, 0u32).unwrap();
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    for _ in 0..10 {
+                        update_json(app, None, Cache, |count: &mut u32| *count += 1).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(
+            crate::load_json::<u32>(app, None, Cache, false).unwrap(),
+            80
+        );
+
+        let path = crate::get_configuration_path(app, None, ConfigType::Json, Cache).unwrap();
+        assert!(!lock_path(&path).exists());
+    }
+
+    #[test]
+    fn corrupt_configs_are_not_overwritten() {
+        let app = "test-binconf-corrupt_configs_are_not_overwritten";
+        let path = crate::get_configuration_path(app, None, ConfigType::Toml, Cache).unwrap();
+        std::fs::write(&path, "count = [").unwrap();
+
+        assert!(update_toml(app, None, Cache, |count: &mut u32| *count += 1).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "count = [");
+        assert!(!lock_path(&path).exists());
+    }
+
+    #[test]
+    fn readers_never_see_a_partial_update() {
+        let app = "test-binconf-readers_never_see_a_partial_update";
+        let names: Vec<String> = (0..2000).map(|index| format!("name-{index}")).collect();
+        crate::store_toml(
+            app,
+            None,
+            Cache,
+            Names {
+                names: names.clone(),
+            },
+        )
+        .unwrap();
+        let path = crate::get_configuration_path(app, None, ConfigType::Toml, Cache).unwrap();
+
+        let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let reader = {
+            let done = std::sync::Arc::clone(&done);
+            let path = path.clone();
+            std::thread::spawn(move || {
+                while !done.load(std::sync::atomic::Ordering::SeqCst) {
+                    let text = std::fs::read_to_string(&path).unwrap();
+                    let config: Names = toml::from_str(&text).unwrap();
+                    assert!(config.names.len() >= 2000, "partial read");
+                }
+            })
+        };
+
+        for _ in 0..20 {
+            update_toml(app, None, Cache, |config: &mut Names| {
+                config.names.push(String::from("more"));
+            })
+            .unwrap();
+        }
+        done.store(true, std::sync::atomic::Ordering::SeqCst);
+        reader.join().unwrap();
+    }
+
+    #[derive(Default, serde::Serialize, serde::Deserialize)]
+    struct Names {
+        names: Vec<String>,
+    }
+}
@@ -0,0 +1,574 @@
+use crate::{ConfigError, ConfigLocation, ConfigType};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How often the watched file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+static WATCH_OPTIONS: RwLock<WatchOptions> = RwLock::new(WatchOptions {
+    debounce: Duration::ZERO,
+    parse_retry: Duration::ZERO,
+});
+
+/// How watchers turn file modifications into updates. Set with [`set_watch_options`].
+///
+/// Editors often save a file in several steps (truncate, write, rename). By default every step the watcher sees is
+/// loaded, and the steps leaving the file unparsable are ignored.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WatchOptions {
+    /// Wait until the file was left untouched for this long before loading it, so a burst of writes gives a single update.
+    pub debounce: Duration,
+    /// When the modified file can't be parsed, keep loading it on every poll for this long, in case the rest of the edit
+    /// lands without changing the modification time or length the watcher relies on.
+    pub parse_retry: Duration,
+}
+
+/// Sets the options of the watchers created afterwards, running watchers keep the options they were created with.
+///
+/// # Example
+///
+/// ```
+/// use binconf::WatchOptions;
+/// use std::time::Duration;
+///
+/// binconf::set_watch_options(WatchOptions {
+///     debounce: Duration::from_millis(300),
+///     parse_retry: Duration::from_secs(2),
+/// });
+/// ```
+pub fn set_watch_options(options: WatchOptions) {
+    *WATCH_OPTIONS
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = options;
+}
+
+/// Handle to a running config watcher.
+///
+/// The watcher runs on a background thread, it is stopped when the handle is dropped or when [`WatchHandle::stop`] is called.
+pub struct WatchHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WatchHandle {
+    /// Stops the watcher and waits for its thread to finish.
+    pub fn stop(self) {}
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The modification time and length of a file, used to detect changes without reading it.
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Reads a watched config without ever writing it, `None` until it holds a config that parses.
+///
+/// A missing or empty file is the middle of a replace or an edit rather than an empty config, it is retried like a parse error.
+fn reload<T>(path: &Path, config_type: &ConfigType) -> Option<T>
+where
+    T: Default + serde::de::DeserializeOwned,
+{
+    let data = std::fs::read(path).ok()?;
+    if data.is_empty() {
+        return None;
+    }
+    crate::deserialize_typed(&data, config_type).ok()
+}
+
+/// Spawns the polling thread with the current [`WatchOptions`].
+fn spawn_watcher<F>(config_file_path: PathBuf, on_change: F) -> WatchHandle
+where
+    F: FnMut(&Path) -> bool + Send + 'static,
+{
+    let options = *WATCH_OPTIONS
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    spawn_watcher_with(config_file_path, options, on_change)
+}
+
+/// Spawns the polling thread, calling `on_change` with the file path every time the file is modified.
+///
+/// `on_change` returns `false` if the file could not be parsed, it is then called again until [`WatchOptions::parse_retry`] runs out.
+fn spawn_watcher_with<F>(
+    config_file_path: PathBuf,
+    options: WatchOptions,
+    mut on_change: F,
+) -> WatchHandle
+where
+    F: FnMut(&Path) -> bool + Send + 'static,
+{
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+
+    // Taken before spawning, so modifications made right after the watcher is created are not missed
+    let mut last_stamp = file_stamp(&config_file_path);
+
+    let thread = std::thread::spawn(move || {
+        // When the last unhandled modification was seen, and until when a file that failed to parse is loaded again
+        let mut modified_at: Option<Instant> = None;
+        let mut retry_until: Option<Instant> = None;
+
+        while !thread_stop.load(Ordering::Relaxed) {
+            std::thread::park_timeout(POLL_INTERVAL);
+
+            let stamp = file_stamp(&config_file_path);
+            if stamp != last_stamp {
+                last_stamp = stamp;
+                modified_at = Some(Instant::now());
+            }
+
+            if let Some(at) = modified_at {
+                if at.elapsed() < options.debounce {
+                    continue;
+                }
+                modified_at = None;
+                retry_until =
+                    (!on_change(&config_file_path)).then(|| Instant::now() + options.parse_retry);
+            } else if let Some(until) = retry_until {
+                retry_until =
+                    (Instant::now() < until && !on_change(&config_file_path)).then_some(until);
+            }
+        }
+    });
+
+    WatchHandle {
+        stop,
+        thread: Some(thread),
+    }
+}
+
+/// Watches a single key of a config file, calling `callback` only when its value actually changes. In `toml`, `json`, `yaml` or `ron` format.
+///
+/// The file is polled in the background, and on every modification the value at `key_path` is read and compared
+/// with the previous one. Nested keys are separated by dots (e.g. `"ui.theme"`).
+///
+/// The callback receives `None` if the key was removed from the file. Modifications that leave the file unparsable are ignored.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created.
+///
+/// The binary format does not carry key names, so it returns a [`ConfigError::UnsupportedFormat`] error.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+///
+/// let handle = binconf::watch_key::<String, _>("test-binconf-watch-key", None, ConfigType::Toml, Config, "ui.theme", |theme| {
+///     println!("The theme is now {theme:?}");
+/// })
+/// .unwrap();
+///
+/// handle.stop();
+/// ```
+#[cfg(any(
+    feature = "toml-conf",
+    feature = "json-conf",
+    feature = "yaml-conf",
+    feature = "ron-conf"
+))]
+pub fn watch_key<'a, V, F>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+    key_path: &str,
+    mut callback: F,
+) -> Result<WatchHandle, ConfigError>
+where
+    V: serde::de::DeserializeOwned + PartialEq + Clone + Send + 'static,
+    F: FnMut(Option<V>) + Send + 'static,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        config_type.as_str(),
+        location.as_ref(),
+    )?;
+
+    let key_path = key_path.to_owned();
+    let read_key = move |path: &Path| -> Result<Option<V>, ConfigError> {
+        let document = std::fs::read_to_string(path)?;
+        crate::keys::get_key(&document, &config_type, &key_path)
+    };
+
+    // Fail fast for formats that do not support key lookups, other errors just mean there is no value yet
+    let mut last_value = match read_key(&config_file_path) {
+        Err(ConfigError::UnsupportedFormat(format)) => {
+            return Err(ConfigError::UnsupportedFormat(format))
+        }
+        result => result.ok().flatten(),
+    };
+
+    Ok(spawn_watcher(config_file_path, move |path| {
+        let Ok(value) = read_key(path) else {
+            return false;
+        };
+
+        if value != last_value {
+            last_value = value.clone();
+            callback(value);
+        }
+        true
+    }))
+}
+
+/// Watches a whole config file, calling `callback` with the reloaded config every time the file is modified. In any format.
+///
+/// The file is polled in the background and only ever read, it is never created or reset by the watcher.
+/// Modifications that leave the file unparsable, empty or missing are ignored. Available with the `watch` feature alone, for binary configs.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, Debug)]
+/// struct TestConfig {
+///    theme: String,
+/// }
+///
+/// let handle = binconf::watch_config::<TestConfig, _>("test-binconf-watch-config", None, ConfigType::Bin, Config, |config| {
+///     println!("The config is now {config:?}");
+/// })
+/// .unwrap();
+///
+/// handle.stop();
+/// ```
+pub fn watch_config<'a, T, F>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+    mut callback: F,
+) -> Result<WatchHandle, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    F: FnMut(T) + Send + 'static,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        config_type.as_str(),
+        location.as_ref(),
+    )?;
+
+    Ok(spawn_watcher(config_file_path, move |path| {
+        match reload(path, &config_type) {
+            Some(config) => {
+                callback(config);
+                true
+            }
+            None => false,
+        }
+    }))
+}
+
+/// The keys whose value changed between two versions of a config, delivered by [`watch_config_changes`].
+///
+/// Keys are dotted paths (e.g. `"log.path"`), nested structs and maps are compared key by key, anything else as a whole.
+#[cfg(feature = "json-conf")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangedKeys(std::collections::BTreeSet<String>);
+
+#[cfg(feature = "json-conf")]
+impl ChangedKeys {
+    /// Returns `true` if the value at `key`, or any key nested in it, changed.
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.iter().any(|changed| {
+            changed == key
+                || changed
+                    .strip_prefix(key)
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+
+    /// Iterates over the changed keys, in alphabetical order.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Flattens a config into its values by dotted key, `None` if `json` can't represent it.
+#[cfg(feature = "json-conf")]
+fn config_values<T>(config: &T) -> Option<std::collections::BTreeMap<String, String>>
+where
+    T: serde::Serialize,
+{
+    let mut values = std::collections::BTreeMap::new();
+    crate::changelog::flatten(
+        serde_json::to_value(config).ok()?,
+        String::new(),
+        &mut values,
+    );
+    Some(values)
+}
+
+/// Watches a whole config file like [`watch_config`], also passing the keys that changed since the previous version to `callback`.
+///
+/// Lets apps react only to the settings they care about, e.g. re-open the log file only when `log.path` changed.
+/// Modifications that leave every value untouched, like reformatting the file, do not call `callback`.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::ConfigType;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, Debug)]
+/// struct TestConfig {
+///    log: Log,
+/// }
+///
+/// #[derive(Default, Serialize, Deserialize, Debug)]
+/// struct Log {
+///    path: String,
+///    level: u8,
+/// }
+///
+/// let handle = binconf::watch_config_changes::<TestConfig, _>("test-binconf-watch-config-changes", None, ConfigType::Toml, Config, |config, changed| {
+///     if changed.contains("log.path") {
+///         println!("Re-opening the log file at {}", config.log.path);
+///     }
+/// })
+/// .unwrap();
+///
+/// handle.stop();
+/// ```
+#[cfg(feature = "json-conf")]
+pub fn watch_config_changes<'a, T, F>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    config_type: ConfigType,
+    location: impl AsRef<ConfigLocation>,
+    mut callback: F,
+) -> Result<WatchHandle, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+    F: FnMut(T, ChangedKeys) + Send + 'static,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        config_type.as_str(),
+        location.as_ref(),
+    )?;
+
+    // An unreadable config has no values yet, all the keys of the next valid one are reported as changed
+    let mut last_values = reload::<T>(&config_file_path, &config_type)
+        .and_then(|config| config_values(&config))
+        .unwrap_or_default();
+
+    Ok(spawn_watcher(config_file_path, move |path| {
+        let Some(config) = reload::<T>(path, &config_type) else {
+            return false;
+        };
+        let Some(values) = config_values(&config) else {
+            return true;
+        };
+
+        let mut changed: std::collections::BTreeSet<String> = values
+            .iter()
+            .filter(|(key, value)| last_values.get(*key) != Some(*value))
+            .map(|(key, _)| key.clone())
+            .collect();
+        changed.extend(
+            last_values
+                .keys()
+                .filter(|key| !values.contains_key(*key))
+                .cloned(),
+        );
+
+        last_values = values;
+        if !changed.is_empty() {
+            callback(config, ChangedKeys(changed));
+        }
+        true
+    }))
+}
+
+#[cfg(test)]
+#[cfg(feature = "full")]
+mod tests {
+    use super::*;
+
+    use ConfigLocation::Config;
+
+    #[test]
+    fn watch_key_only_notifies_on_value_change() {
+        let path = crate::get_configuration_path(
+            "test-binconf-watch_key_only_notifies_on_value_change",
+            None,
+            ConfigType::Toml,
+            Config,
+        )
+        .unwrap();
+        std::fs::write(&path, "[ui]\ntheme = \"light\"\nsize = 1\n").unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let handle = watch_key::<String, _>(
+            "test-binconf-watch_key_only_notifies_on_value_change",
+            None,
+            ConfigType::Toml,
+            Config,
+            "ui.theme",
+            move |theme| sender.send(theme).unwrap(),
+        )
+        .unwrap();
+
+        // Writes replace the file atomically, so the watcher never sees a truncated document
+        crate::save_config_atomic(&path, b"[ui]\ntheme = \"light\"\nsize = 22\n").unwrap();
+        assert!(receiver.recv_timeout(POLL_INTERVAL * 4).is_err());
+
+        crate::save_config_atomic(&path, b"[ui]\ntheme = \"dark\"\nsize = 22\n").unwrap();
+        assert_eq!(
+            receiver.recv_timeout(POLL_INTERVAL * 10).unwrap(),
+            Some(String::from("dark"))
+        );
+
+        handle.stop();
+    }
+
+    #[test]
+    fn watch_config_changes_reports_changed_keys() {
+        let app = "test-binconf-watch_config_changes_reports_changed_keys";
+        let path = crate::get_configuration_path(app, None, ConfigType::Json, Config).unwrap();
+        std::fs::write(&path, r#"{"log":{"path":"a.log","level":1},"name":"app"}"#).unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let handle = watch_config_changes::<serde_json::Value, _>(
+            app,
+            None,
+            ConfigType::Json,
+            Config,
+            move |_, changed| sender.send(changed).unwrap(),
+        )
+        .unwrap();
+
+        // Same values, only formatted differently
+        crate::save_config_atomic(
+            &path,
+            br#"{ "name": "app", "log": { "level": 1, "path": "a.log" } }"#,
+        )
+        .unwrap();
+        assert!(receiver.recv_timeout(POLL_INTERVAL * 4).is_err());
+
+        crate::save_config_atomic(&path, br#"{"log":{"path":"b.log","level":1},"name":"app"}"#)
+            .unwrap();
+        let changed = receiver.recv_timeout(POLL_INTERVAL * 10).unwrap();
+        assert_eq!(changed.iter().collect::<Vec<_>>(), vec!["log.path"]);
+        assert!(changed.contains("log"));
+        assert!(!changed.contains("log.level"));
+        assert!(!changed.contains("name"));
+
+        handle.stop();
+    }
+
+    #[test]
+    fn watch_config_never_writes_and_retries_missing_or_empty_files() {
+        let app = "test-binconf-watch_config_never_writes_and_retries_missing_or_empty_files";
+        let path = crate::get_configuration_path(app, None, ConfigType::Json, Config).unwrap();
+        std::fs::write(&path, "1").unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let handle = watch_config::<u32, _>(app, None, ConfigType::Json, Config, move |config| {
+            sender.send(config).unwrap();
+        })
+        .unwrap();
+
+        // Neither is delivered as a default config, and the watcher does not create the file again
+        std::fs::remove_file(&path).unwrap();
+        assert!(receiver.recv_timeout(POLL_INTERVAL * 4).is_err());
+        assert!(!path.exists());
+
+        std::fs::write(&path, "").unwrap();
+        assert!(receiver.recv_timeout(POLL_INTERVAL * 4).is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"");
+
+        // Still retried, so the config written right after is delivered
+        std::fs::write(&path, "2").unwrap();
+        assert_eq!(receiver.recv_timeout(POLL_INTERVAL * 10).unwrap(), 2);
+
+        handle.stop();
+    }
+
+    #[test]
+    fn watcher_debounces_and_retries_parsing() {
+        let path = crate::get_configuration_path(
+            "test-binconf-watcher_debounces_and_retries_parsing",
+            None,
+            ConfigType::Json,
+            Config,
+        )
+        .unwrap();
+        std::fs::write(&path, "0").unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let options = WatchOptions {
+            debounce: POLL_INTERVAL * 3,
+            parse_retry: POLL_INTERVAL * 4,
+        };
+        // Fails to parse twice, like a file caught in the middle of an edit
+        let mut calls = 0;
+        let handle = spawn_watcher_with(path.clone(), options, move |_| {
+            calls += 1;
+            sender.send(calls).unwrap();
+            calls > 2
+        });
+
+        // A burst of writes, each within the debounce delay of the previous one
+        for value in 1..=4 {
+            std::fs::write(&path, "1".repeat(value)).unwrap();
+            std::thread::sleep(POLL_INTERVAL);
+        }
+
+        let calls: Vec<_> = (0..3)
+            .map(|_| receiver.recv_timeout(POLL_INTERVAL * 10).unwrap())
+            .collect();
+        assert_eq!(calls, vec![1, 2, 3]);
+        // Parsed successfully, not called again until the next modification
+        assert!(receiver.recv_timeout(POLL_INTERVAL * 4).is_err());
+
+        handle.stop();
+    }
+}
@@ -1,5 +1,5 @@
+use crate::progress::read_to_string;
 use crate::{ConfigError, ConfigLocation, ConfigType};
-use std::{fs::read_to_string, io::Write};
 
 /// Loads a config file from the config, cache, cwd, or local data directory of the current user. In `yaml` format.
 ///
@@ -8,11 +8,14 @@ use std::{fs::read_to_string, io::Write};
 /// If the flag `reset_conf_on_err` is set to `true`, the config file will be reset to the default config if
 /// the deserialization fails, if set to `false` an error will be returned.
 ///
+/// An empty file, as left by `touch` or an interrupted write, is not an error: the default config is returned,
+/// and written to the file if `reset_conf_on_err` is `true`.
+///
 /// # Errors
 ///
 /// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
 ///
-/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config.
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
 ///
 /// # Example
 ///
@@ -38,35 +41,217 @@ pub fn load_yaml<'a, T>(
 where
     T: Default + serde::Serialize + serde::de::DeserializeOwned,
 {
-    let config_file_path = crate::config_location(
+    load_yaml_internal(
         app_name.as_ref(),
         config_name.into(),
-        ConfigType::Yaml.as_str(),
         location.as_ref(),
-    )?;
+        reset_conf_on_err,
+        false,
+        &T::default,
+    )
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user. **Rejecting trailing data and duplicate keys**. In `yaml` format.
+///
+/// Works like [`load_yaml`], but if anything other than whitespace follows the YAML document (for example a second document),
+/// a [`ConfigError::TrailingData`] error is returned instead of a generic parse error.
+///
+/// If the flag `reset_conf_on_err` is set to `true`, the config file will be reset to the default config when trailing data or a duplicate key is found.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, trailing data or a duplicate key is found, an error will be returned. If it is set to `true` the config file will be reset to the default config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///    test: String,
+///    test_vec: Vec<u8>,
+/// }
+///
+/// let config = binconf::load_yaml_strict::<TestConfig>("test-binconf-read-strict-yaml", None, Config, false).unwrap();
+/// assert_eq!(config, TestConfig::default());
+/// ```
+pub fn load_yaml_strict<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+) -> Result<T, ConfigError>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_yaml_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        reset_conf_on_err,
+        true,
+        &T::default,
+    )
+}
+
+/// Loads a config file from the config, cache, cwd, or local data directory of the current user, **falling back to a given config**. In `yaml` format.
+///
+/// Works like [`load_yaml`], but `fallback` takes the place of the `Default` implementation: it is cloned when the file does not exist
+/// or is reset, and left alone when the file is loaded. Meant for configs with big defaults, like lookup tables, that should not be built
+/// on every load just to be thrown away, and for types that can't implement `Default`.
+///
+/// # Errors
+///
+/// This function will return an error if the config, cache or local data directory could not be found or created, or if something went wrong while deserializing the config.
+///
+/// If the flag `reset_conf_on_err` is set to `false` and the deserialization fails, an error will be returned. If it is set to `true` the config file will be reset to the fallback config, after renaming the damaged file to `<name>.corrupt-<timestamp>`.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+/// struct Lookup {
+///    table: Vec<u32>,
+/// }
+///
+/// let fallback = Lookup { table: (0..1024).collect() };
+///
+/// let config = binconf::load_yaml_or("test-binconf-read-yaml-or", None, Config, false, &fallback).unwrap();
+/// assert_eq!(config, fallback);
+/// ```
+pub fn load_yaml_or<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    reset_conf_on_err: bool,
+    fallback: &T,
+) -> Result<T, ConfigError>
+where
+    T: Clone + serde::Serialize + serde::de::DeserializeOwned,
+{
+    load_yaml_internal(
+        app_name.as_ref(),
+        config_name.into(),
+        location.as_ref(),
+        reset_conf_on_err,
+        false,
+        &|| fallback.clone(),
+    )
+}
+
+fn load_yaml_internal<T>(
+    app_name: &str,
+    config_name: Option<&str>,
+    location: &ConfigLocation,
+    reset_conf_on_err: bool,
+    strict: bool,
+    default: &dyn Fn() -> T,
+) -> Result<T, ConfigError>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let config_file_path =
+        crate::config_location(app_name, config_name, ConfigType::Yaml.as_str(), location)?;
 
     let save_default_conf = || {
-        let default_config = T::default();
-        let yaml_str = serde_yaml::to_string(&default_config)?;
-        crate::save_config_str(&config_file_path, &yaml_str)?;
+        let default_config = default();
+        let yaml_str = crate::output::to_yaml_string(
+            &default_config,
+            &crate::output::output_options_for(&config_file_path),
+        )?;
+        if !crate::freeze::is_frozen_path(&config_file_path) {
+            crate::reset::back_up_corrupt(&config_file_path);
+            crate::save_config_str(&config_file_path, &yaml_str)?;
+        }
         Ok(default_config)
     };
 
     if !config_file_path.try_exists()? {
-        return save_default_conf();
+        let default_config = default();
+        let yaml_str = crate::output::to_yaml_string(
+            &default_config,
+            &crate::output::output_options_for(&config_file_path),
+        )?;
+        if crate::freeze::is_frozen_path(&config_file_path)
+            || crate::save_config_new(&config_file_path, yaml_str.as_bytes())?
+        {
+            return Ok(default_config);
+        }
+        // Created by another process in the meantime, it is loaded below
     }
 
+    let started = std::time::Instant::now();
     let yaml_str = read_to_string(&config_file_path)?;
-    let config = match serde_yaml::from_str::<T>(&yaml_str) {
+    crate::slow_io::report(
+        crate::IoOperation::Load,
+        &config_file_path,
+        yaml_str.len(),
+        started,
+    );
+    // An empty file is left by `touch` or an interrupted write, it holds no config rather than a damaged one
+    if yaml_str.is_empty() {
+        return if reset_conf_on_err {
+            save_default_conf()
+        } else {
+            Ok(default())
+        };
+    }
+    #[cfg(feature = "binary-conf")]
+    if !strict {
+        if let Some(config) = crate::parse_cache::lookup(app_name, &config_file_path, &yaml_str) {
+            return Ok(config);
+        }
+    }
+    let config = match deserialize_yaml::<T>(&yaml_str, strict) {
         Ok(config) => config,
         Err(err) => {
             if reset_conf_on_err {
+                crate::reset::report(&config_file_path, &yaml_str, err);
                 return save_default_conf();
             }
-            return Err(err.into());
+            return Err(err);
         }
     };
 
+    #[cfg(feature = "binary-conf")]
+    if !strict {
+        crate::parse_cache::store(app_name, &config_file_path, &yaml_str, &config);
+    }
+
+    Ok(config)
+}
+
+/// Deserializes a `yaml` string.
+///
+/// In strict mode the input must contain a single document, otherwise a [`ConfigError::TrailingData`] error is returned.
+/// A key appearing twice in the same mapping returns a [`ConfigError::DuplicateKey`] error.
+fn deserialize_yaml<T>(yaml_str: &str, strict: bool) -> Result<T, ConfigError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    if !strict {
+        return Ok(serde_yaml::from_str::<T>(yaml_str)?);
+    }
+
+    crate::duplicates::check_yaml(yaml_str)?;
+
+    let mut documents = serde_yaml::Deserializer::from_str(yaml_str);
+    let config = match documents.next() {
+        Some(document) => T::deserialize(document)?,
+        None => serde_yaml::from_str::<T>(yaml_str)?,
+    };
+
+    if documents.next().is_some() {
+        return Err(ConfigError::TrailingData);
+    }
+
     Ok(config)
 }
 
@@ -116,17 +301,80 @@ where
         location.as_ref(),
     )?;
 
-    let mut file = std::io::BufWriter::new(std::fs::File::create(config_file_path)?);
+    let yaml_str = crate::output::to_yaml_string(
+        &data,
+        &crate::output::output_options_for(&config_file_path),
+    )?;
+    crate::save_config_str(&config_file_path, &yaml_str)?;
+    #[cfg(feature = "json-conf")]
+    crate::changelog::record(&config_file_path, &data)?;
 
-    let yaml_str = serde_yaml::to_string(&data)?;
+    Ok(())
+}
+
+/// Stores a config file like [`store_yaml`], failing if the file already exists. In `yaml` format.
+///
+/// Meant for commands like `myapp init` that generate a config and must never overwrite the one of the user.
+/// When several processes store the same config at once, exactly one of them creates the file.
+///
+/// # Errors
+///
+/// This function will return a [`ConfigError::AlreadyExists`] error if the config file already exists. Other errors are returned if the config,
+/// cache or local data directory could not be found or created, or if something went wrong while serializing the config.
+///
+/// # Example
+///
+/// ```
+/// use binconf::ConfigLocation::{Cache, Config, LocalData, Cwd};
+/// use binconf::{ConfigError, ConfigType};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
+/// struct TestConfig {
+///   test: String,
+/// }
+///
+/// let path = binconf::get_configuration_path("test-binconf-store-yaml-new", None, ConfigType::Yaml, Config).unwrap();
+/// # let _ = std::fs::remove_file(&path);
+///
+/// binconf::store_yaml_new("test-binconf-store-yaml-new", None, Config, TestConfig::default()).unwrap();
+///
+/// let result = binconf::store_yaml_new("test-binconf-store-yaml-new", None, Config, TestConfig::default());
+/// assert!(matches!(result, Err(ConfigError::AlreadyExists(_))));
+/// ```
+pub fn store_yaml_new<'a, T>(
+    app_name: impl AsRef<str>,
+    config_name: impl Into<Option<&'a str>>,
+    location: impl AsRef<ConfigLocation>,
+    data: T,
+) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let config_file_path = crate::config_location(
+        app_name.as_ref(),
+        config_name.into(),
+        ConfigType::Yaml.as_str(),
+        location.as_ref(),
+    )?;
 
-    file.write_all(yaml_str.as_bytes())?;
+    let yaml_str = crate::output::to_yaml_string(
+        &data,
+        &crate::output::output_options_for(&config_file_path),
+    )?;
+    if !crate::save_config_new(&config_file_path, yaml_str.as_bytes())? {
+        return Err(ConfigError::AlreadyExists(config_file_path));
+    }
+    #[cfg(feature = "json-conf")]
+    crate::changelog::record(&config_file_path, &data)?;
 
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+
     use super::*;
 
     use serde::Deserialize;
@@ -318,6 +566,48 @@ mod tests {
         assert_eq!(config, data);
     }
 
+    #[test]
+    fn strict_rejects_trailing_data_yaml() {
+        let data = TestConfig {
+            test: String::from("test"),
+            test_vec: vec![1, 2, 3, 4, 5],
+        };
+
+        store_yaml(
+            "test-binconf-strict_rejects_trailing_data-yaml",
+            None,
+            Config,
+            &data,
+        )
+        .unwrap();
+        let config: TestConfig = load_yaml_strict(
+            "test-binconf-strict_rejects_trailing_data-yaml",
+            None,
+            Config,
+            false,
+        )
+        .unwrap();
+        assert_eq!(config, data);
+
+        let path = crate::get_configuration_path(
+            "test-binconf-strict_rejects_trailing_data-yaml",
+            None,
+            ConfigType::Yaml,
+            Config,
+        )
+        .unwrap();
+        let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(b"---\ntest: other\ntest_vec: []\n").unwrap();
+
+        let config = load_yaml_strict::<TestConfig>(
+            "test-binconf-strict_rejects_trailing_data-yaml",
+            None,
+            Config,
+            false,
+        );
+        assert!(matches!(config, Err(ConfigError::TrailingData)));
+    }
+
     #[test]
     fn save_config_user_cwd_yaml() {
         let data = TestConfig {
@@ -330,4 +620,29 @@ mod tests {
             load_yaml("test-binconf-save_config_user_cwd-yaml", None, Cwd, false).unwrap();
         assert_eq!(config, data);
     }
+
+    #[test]
+    fn rejects_duplicate_keys_yaml() {
+        let path = crate::get_configuration_path(
+            "test-binconf-rejects_duplicate_keys-yaml",
+            None,
+            ConfigType::Yaml,
+            Config,
+        )
+        .unwrap();
+        std::fs::write(path, "ui:\n  theme: 1\n  size: 2\n  theme: 3\n").unwrap();
+
+        let config = load_yaml_strict::<
+            std::collections::HashMap<String, std::collections::HashMap<String, u8>>,
+        >(
+            "test-binconf-rejects_duplicate_keys-yaml",
+            None,
+            Config,
+            false,
+        );
+        assert!(matches!(
+            config,
+            Err(ConfigError::DuplicateKey { path, line: 4 }) if path == "ui.theme"
+        ));
+    }
 }